@@ -1,6 +1,7 @@
-use crate::config::AiProfile;
+use crate::config::{AiProfile, AsteroidArchetype, SimTuning};
 use crate::controllers::{ControlIntent, Controller};
-use macroquad::prelude::{Vec2, screen_height, screen_width};
+use crate::simulation::EntityId;
+use macroquad::prelude::{Vec2, vec2};
 use std::f32::consts::{FRAC_PI_2, PI};
 
 /// Snapshot that controllers can use to reason about nearby asteroids.
@@ -8,9 +9,35 @@ use std::f32::consts::{FRAC_PI_2, PI};
 /// NOTE: Keep this allocation and size bounded upstream (sensor radius + attention cap).
 #[derive(Clone, Debug)]
 pub struct AsteroidSnapshot {
+    /// Stays with the rock for as long as it exists, so a controller can follow one rock.
+    pub id: EntityId,
     pub position: Vec2,
     pub velocity: Vec2,
     pub radius: f32,
+    pub archetype: AsteroidArchetype,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HazardKind {
+    GravityWell,
+    Nebula,
+}
+
+/// Static environmental hazard visible to controllers.
+#[derive(Clone, Debug)]
+pub struct HazardSnapshot {
+    pub kind: HazardKind,
+    pub position: Vec2,
+    /// Area the hazard affects: pull range for wells, cloud extent for nebulae.
+    pub radius: f32,
+}
+
+/// Another player's ship, as seen by a controller.
+#[derive(Clone, Debug)]
+pub struct ShipSnapshot {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub angle: f32,
 }
 
 /// Read-only snapshot exposed to controllers.
@@ -21,7 +48,19 @@ pub struct WorldSnapshot {
     pub ship_position: Vec2,
     pub ship_velocity: Vec2,
     pub ship_angle: f32,
+    /// Size of the playfield, so controllers need no window to know where the edges are.
+    pub world_size: Vec2,
     pub asteroids: Vec<AsteroidSnapshot>,
+    pub hazards: Vec<HazardSnapshot>,
+    pub solar_flare_active: bool,
+    /// Co-op partners still in play; empty in solo runs.
+    pub allies: Vec<ShipSnapshot>,
+    /// Whether this ship's bullets can hit `allies`.
+    pub friendly_fire: bool,
+    /// Versus rivals still in play and not hidden; empty outside versus.
+    pub opponents: Vec<ShipSnapshot>,
+    /// Muzzle speed of this ship's primary shots, for leading moving targets.
+    pub bullet_speed: f32,
 }
 
 impl Default for WorldSnapshot {
@@ -30,7 +69,15 @@ impl Default for WorldSnapshot {
             ship_position: Vec2::ZERO,
             ship_velocity: Vec2::ZERO,
             ship_angle: -FRAC_PI_2,
+            // Macroquad's default window.
+            world_size: vec2(800.0, 600.0),
             asteroids: Vec::new(),
+            hazards: Vec::new(),
+            solar_flare_active: false,
+            allies: Vec::new(),
+            friendly_fire: false,
+            opponents: Vec::new(),
+            bullet_speed: SimTuning::classic().bullet_speed,
         }
     }
 }
@@ -71,6 +118,14 @@ struct AiTuning {
     rel_speed_min_sq: f32,
     primary_range: f32,
     primary_arc: f32,
+    /// Seconds ahead an ally's position is projected when checking the firing line.
+    ally_lookahead: f32,
+    /// Versus: how far away a rival is worth hunting instead of rocks.
+    rival_range: f32,
+    /// Versus: how tightly the lead point must sit in the nose before firing at a rival.
+    rival_fire_arc: f32,
+    /// Versus: a rival whose nose points within this angle of us is about to shoot.
+    rival_aim_arc: f32,
     secondary_cluster_range: f32,
     secondary_cluster_count: i32,
     target_max_range: f32,
@@ -97,6 +152,10 @@ const TUNING: AiTuning = AiTuning {
     rel_speed_min_sq: 8.0 * 8.0,
     primary_range: 440.0,
     primary_arc: 0.72,
+    ally_lookahead: 0.25,
+    rival_range: 480.0,
+    rival_fire_arc: 0.16,
+    rival_aim_arc: 0.2,
     secondary_cluster_range: 320.0,
     secondary_cluster_count: 2,
     target_max_range: 520.0,
@@ -126,8 +185,8 @@ fn clamp01(x: f32) -> f32 {
     x.clamp(0.0, 1.0)
 }
 
-fn center() -> Vec2 {
-    Vec2::new(screen_width() * 0.5, screen_height() * 0.5)
+fn center(world: &WorldSnapshot) -> Vec2 {
+    world.world_size * 0.5
 }
 
 fn forward(angle: f32) -> Vec2 {
@@ -193,6 +252,8 @@ pub struct AiController {
 
     // Target commitment
     target: Option<Vec2>,
+    /// The rock `target` is on, followed by ID; `None` when aiming at a rival.
+    target_rock: Option<EntityId>,
     target_timer: f32,
 
     // Phase timing
@@ -216,6 +277,7 @@ impl AiController {
             profile,
             decision_timer: 0.0,
             target: None,
+            target_rock: None,
             target_timer: 0.0,
             phase: Phase::Engage,
             phase_timer: 0.0,
@@ -267,6 +329,16 @@ fn asteroid_size_weight(radius: f32) -> f32 {
     }
 }
 
+/// Rocks that chase the ship or explode deserve an earlier dodge than inert ones.
+fn archetype_threat_weight(archetype: AsteroidArchetype) -> f32 {
+    match archetype {
+        AsteroidArchetype::Rock | AsteroidArchetype::Splitter => 1.0,
+        AsteroidArchetype::Armoured => 1.1,
+        AsteroidArchetype::Volatile => 1.2,
+        AsteroidArchetype::Magnetic => 1.3,
+    }
+}
+
 fn detect_threat(world: &WorldSnapshot) -> Option<Threat> {
     let mut best: Option<Threat> = None;
     for ast in world.iter_asteroids() {
@@ -289,7 +361,7 @@ fn detect_threat(world: &WorldSnapshot) -> Option<Threat> {
         }
 
         // Severity ramps with smaller TTC and closer distance, weighted by size and relative speed.
-        let size_w = asteroid_size_weight(ast.radius);
+        let size_w = asteroid_size_weight(ast.radius) * archetype_threat_weight(ast.archetype);
         let speed_w = 1.0 + (rel_vel.length() / 140.0).clamp(0.0, 1.4);
         let dist_w = (220.0 / distance.max(50.0)).clamp(0.25, 4.0);
         let ttc_w = (1.05 / (ttc + 0.08)).clamp(0.0, 9.0);
@@ -318,6 +390,40 @@ fn detect_threat(world: &WorldSnapshot) -> Option<Threat> {
     best
 }
 
+/// Seconds until the nearest rock on a collision course reaches the ship, using the same
+/// closing test as `detect_threat` but only counting rocks whose closest approach is a hit.
+/// `None` when nothing in threat range is on course.
+pub fn time_to_impact(world: &WorldSnapshot, ship_radius: f32) -> Option<f32> {
+    let mut soonest: Option<f32> = None;
+    for ast in world.iter_asteroids() {
+        let rel = ast.position - world.ship_position;
+        if rel.length() > TUNING.threat_range {
+            continue;
+        }
+        let reach = ast.radius + ship_radius;
+        let rel_vel = ast.velocity - world.ship_velocity;
+        let rel_speed_sq = rel_vel.length_squared();
+        let impact = if rel.length_squared() <= reach * reach {
+            0.0
+        } else {
+            if rel_speed_sq < TUNING.rel_speed_min_sq {
+                continue;
+            }
+            let ttc = -rel.dot(rel_vel) / rel_speed_sq;
+            let miss_sq = (rel + rel_vel * ttc).length_squared();
+            if ttc <= 0.0 || miss_sq > reach * reach {
+                continue;
+            }
+            // Contact comes before the closest approach by the overlap along the path.
+            (ttc - ((reach * reach - miss_sq) / rel_speed_sq).sqrt()).max(0.0)
+        };
+        if soonest.is_none_or(|best| impact < best) {
+            soonest = Some(impact);
+        }
+    }
+    soonest
+}
+
 fn safe_direction(world: &WorldSnapshot, base: Vec2) -> Vec2 {
     // Evaluate a small set of candidate headings and choose the lowest risk.
     let offsets = [
@@ -380,14 +486,14 @@ fn direction_risk(world: &WorldSnapshot, dir: Vec2) -> f32 {
 // Target selection (lane clearing)
 // -------------------------
 
-fn select_target_lane_clearing(world: &WorldSnapshot) -> Option<Vec2> {
+fn select_target_lane_clearing(world: &WorldSnapshot) -> Option<&AsteroidSnapshot> {
     // Prefer targets in the forward cone that are likely to become collision-probable soon.
     // If none, fall back to a near-ish target that does not drag us to edges.
 
     let ship_fwd = forward(world.ship_angle);
     let ship_pos = world.ship_position;
 
-    let mut best: Option<(Vec2, f32)> = None;
+    let mut best: Option<(&AsteroidSnapshot, f32)> = None;
 
     for ast in world.iter_asteroids() {
         let rel = ast.position - ship_pos;
@@ -422,7 +528,7 @@ fn select_target_lane_clearing(world: &WorldSnapshot) -> Option<Vec2> {
         let dist_bonus = (260.0 / distance).clamp(0.2, 3.5);
 
         // Edge penalty: avoid targets that pull the ship toward edges/spawn lanes.
-        let edge_penalty = edge_proximity(ast.position) * 1.8;
+        let edge_penalty = edge_proximity(world, ast.position) * 1.8;
 
         // Alignment bonus (within cone).
         let align_bonus = (1.0 - (angle / TUNING.target_arc)).clamp(0.0, 1.0) * 2.2;
@@ -430,17 +536,16 @@ fn select_target_lane_clearing(world: &WorldSnapshot) -> Option<Vec2> {
         let score = (align_bonus + dist_bonus + ttc_bonus) * size_w - edge_penalty;
 
         if best.as_ref().map_or(true, |(_, s)| score > *s) {
-            best = Some((ast.position, score));
+            best = Some((ast, score));
         }
     }
 
-    best.map(|(p, _)| p)
+    best.map(|(ast, _)| ast)
 }
 
-fn edge_proximity(pos: Vec2) -> f32 {
+fn edge_proximity(world: &WorldSnapshot, pos: Vec2) -> f32 {
     // Returns 0 in center-ish regions and increases near edges.
-    let w = screen_width();
-    let h = screen_height();
+    let Vec2 { x: w, y: h } = world.world_size;
 
     let left = pos.x;
     let right = w - pos.x;
@@ -457,8 +562,7 @@ fn edge_proximity(pos: Vec2) -> f32 {
 
 fn edge_repulsion(world: &WorldSnapshot) -> Vec2 {
     // Repel from edges strongly to avoid spawn lanes.
-    let w = screen_width();
-    let h = screen_height();
+    let Vec2 { x: w, y: h } = world.world_size;
     let p = world.ship_position;
 
     let mut rep = Vec2::ZERO;
@@ -488,6 +592,24 @@ fn edge_repulsion(world: &WorldSnapshot) -> Vec2 {
     }
 }
 
+fn hazard_repulsion(world: &WorldSnapshot) -> Vec2 {
+    // Steer clear of gravity wells. Nebulae are left alone: they slow the ship but also hide it.
+    let mut rep = Vec2::ZERO;
+    for hazard in &world.hazards {
+        if hazard.kind != HazardKind::GravityWell {
+            continue;
+        }
+        let away = world.ship_position - hazard.position;
+        let distance = away.length();
+        if distance < hazard.radius && distance > 1e-3 {
+            // Without shields a flare makes any mistake fatal, so give wells a wider berth.
+            let gain = if world.solar_flare_active { 3.2 } else { 2.4 };
+            rep += away / distance * ((hazard.radius - distance) / hazard.radius) * gain;
+        }
+    }
+    rep
+}
+
 // -------------------------
 // Fire policy
 // -------------------------
@@ -522,6 +644,69 @@ fn compute_fire_policy(world: &WorldSnapshot) -> (bool, bool, i32, i32) {
     (fire_primary, fire_secondary, forward_hits, cluster_hits)
 }
 
+/// With friendly fire on, an ally inside the firing arc (where it will be shortly) blocks the shot.
+fn ally_in_line_of_fire(world: &WorldSnapshot) -> bool {
+    if !world.friendly_fire {
+        return false;
+    }
+    let ship_fwd = forward(world.ship_angle);
+    world.allies.iter().any(|ally| {
+        let rel = ally.position + ally.velocity * TUNING.ally_lookahead - world.ship_position;
+        let distance = rel.length();
+        distance <= TUNING.primary_range
+            && ship_fwd.dot(rel.normalize_or_zero()).clamp(-1.0, 1.0).acos() < TUNING.primary_arc
+    })
+}
+
+// -------------------------
+// Versus rivals
+// -------------------------
+
+/// Where to shoot to hit the nearest rival in range: its position led by the bullet's flight time.
+fn rival_aim_point(world: &WorldSnapshot) -> Option<Vec2> {
+    let rival = world
+        .opponents
+        .iter()
+        .map(|rival| (rival, rival.position.distance(world.ship_position)))
+        .filter(|&(_, distance)| distance <= TUNING.rival_range)
+        .min_by(|a, b| a.1.total_cmp(&b.1))?
+        .0;
+    let flight = rival.position.distance(world.ship_position) / world.bullet_speed.max(1.0);
+    Some(rival.position + (rival.velocity - world.ship_velocity) * flight)
+}
+
+fn rival_in_sights(world: &WorldSnapshot) -> bool {
+    rival_aim_point(world).is_some_and(|aim| {
+        let dir = (aim - world.ship_position).normalize_or_zero();
+        forward(world.ship_angle).dot(dir).clamp(-1.0, 1.0).acos() < TUNING.rival_fire_arc
+    })
+}
+
+/// A rival in range with its nose on us: sidestep across its line of fire.
+fn rival_threat(world: &WorldSnapshot) -> Option<Threat> {
+    world
+        .opponents
+        .iter()
+        .filter_map(|rival| {
+            let rel = world.ship_position - rival.position;
+            let distance = rel.length();
+            if !(1.0..=TUNING.rival_range).contains(&distance) {
+                return None;
+            }
+            let line = forward(rival.angle);
+            if line.dot(rel / distance).clamp(-1.0, 1.0).acos() > TUNING.rival_aim_arc {
+                return None;
+            }
+            // Step off the line on whichever side we already lean toward.
+            let side = if line.perp_dot(rel) >= 0.0 { line.perp() } else { -line.perp() };
+            Some(Threat {
+                avoid_dir: safe_direction(world, side),
+                severity: (TUNING.rival_range / distance.max(60.0)).clamp(1.2, 4.0),
+            })
+        })
+        .max_by(|a, b| a.severity.total_cmp(&b.severity))
+}
+
 // -------------------------
 // Controller implementation
 // -------------------------
@@ -561,6 +746,36 @@ mod tests {
         assert!(value >= 0.0 && value < 1.0);
     }
 
+    #[test]
+    fn time_to_impact_counts_only_rocks_on_course() {
+        let rock = |position: Vec2, velocity: Vec2| AsteroidSnapshot {
+            id: EntityId { index: 0, generation: 0 },
+            position,
+            velocity,
+            radius: 20.0,
+            archetype: AsteroidArchetype::Rock,
+        };
+        let mut world = WorldSnapshot {
+            ship_position: Vec2::new(400.0, 300.0),
+            ..WorldSnapshot::default()
+        };
+        // Head-on at 100 px/s from 130 px away: contact once the 30 px reach is closed.
+        world.asteroids = vec![rock(Vec2::new(530.0, 300.0), Vec2::new(-100.0, 0.0))];
+        let impact = time_to_impact(&world, 10.0).unwrap();
+        assert!((impact - 1.0).abs() < 1e-4);
+
+        // Passing wide, or moving away, is no impact.
+        world.asteroids = vec![
+            rock(Vec2::new(530.0, 360.0), Vec2::new(-100.0, 0.0)),
+            rock(Vec2::new(530.0, 300.0), Vec2::new(100.0, 0.0)),
+        ];
+        assert!(time_to_impact(&world, 10.0).is_none());
+
+        // Already touching.
+        world.asteroids.push(rock(Vec2::new(420.0, 300.0), Vec2::ZERO));
+        assert_eq!(time_to_impact(&world, 10.0), Some(0.0));
+    }
+
     #[test]
     fn asteroid_size_weight_prefers_large() {
         let small = asteroid_size_weight(5.0);
@@ -570,6 +785,43 @@ mod tests {
         assert!(large > medium);
     }
 
+    #[test]
+    fn committed_target_follows_its_rock_by_id() {
+        let rock = |index: u32, position: Vec2| AsteroidSnapshot {
+            id: EntityId { index, generation: 0 },
+            position,
+            velocity: Vec2::ZERO,
+            radius: 20.0,
+            archetype: AsteroidArchetype::Rock,
+        };
+        let mut world = WorldSnapshot {
+            ship_position: Vec2::new(400.0, 300.0),
+            ..WorldSnapshot::default()
+        };
+        let mut ai = AiController::new(AiProfile::Balanced);
+        ai.target = Some(Vec2::new(400.0, 150.0));
+        ai.target_rock = Some(EntityId { index: 1, generation: 0 });
+        ai.target_timer = 10.0;
+
+        // Another rock has drifted to where the locked one was; the lock stays on its rock.
+        world.asteroids = vec![rock(1, Vec2::new(520.0, 200.0)), rock(2, Vec2::new(400.0, 150.0))];
+        ai.tick(&world, 1.0 / 60.0);
+        assert_eq!(ai.target, Some(Vec2::new(520.0, 200.0)));
+
+        // Its slot reused by a new rock is a different ID, so the lock moves to the nearest rock.
+        ai.decision_timer = 0.0;
+        world.asteroids = vec![
+            AsteroidSnapshot {
+                id: EntityId { index: 1, generation: 1 },
+                ..rock(1, Vec2::new(200.0, 500.0))
+            },
+            rock(2, Vec2::new(400.0, 150.0)),
+        ];
+        ai.tick(&world, 1.0 / 60.0);
+        assert_eq!(ai.target, Some(Vec2::new(400.0, 150.0)));
+        assert_eq!(ai.target_rock, Some(EntityId { index: 2, generation: 0 }));
+    }
+
     #[test]
     fn world_snapshot_iterates_asteroids() {
         let snapshot = WorldSnapshot {
@@ -578,19 +830,87 @@ mod tests {
             ship_angle: -FRAC_PI_2,
             asteroids: vec![
                 AsteroidSnapshot {
+                    id: EntityId { index: 0, generation: 0 },
                     position: Vec2::new(20.0, 0.0),
                     velocity: Vec2::ZERO,
                     radius: 3.0,
+                    archetype: AsteroidArchetype::Rock,
                 },
                 AsteroidSnapshot {
+                    id: EntityId { index: 1, generation: 0 },
                     position: Vec2::new(-10.0, 5.0),
                     velocity: Vec2::ZERO,
                     radius: 5.0,
+                    archetype: AsteroidArchetype::Magnetic,
                 },
             ],
+            hazards: Vec::new(),
+            solar_flare_active: false,
+            ..WorldSnapshot::default()
         };
         assert_eq!(snapshot.iter_asteroids().count(), 2);
     }
+
+    #[test]
+    fn allies_block_fire_only_with_friendly_fire() {
+        // Facing up (negative y) with a partner straight ahead.
+        let mut snapshot = WorldSnapshot::default();
+        snapshot.allies.push(ShipSnapshot {
+            position: Vec2::new(0.0, -120.0),
+            velocity: Vec2::ZERO,
+            angle: 0.0,
+        });
+        assert!(!ally_in_line_of_fire(&snapshot));
+        snapshot.friendly_fire = true;
+        assert!(ally_in_line_of_fire(&snapshot));
+        snapshot.allies[0].position = Vec2::new(0.0, 120.0);
+        assert!(!ally_in_line_of_fire(&snapshot));
+    }
+
+    #[test]
+    fn rivals_are_led_and_dodged() {
+        // Facing up with a rival straight ahead, drifting right and aiming back at us.
+        let mut snapshot = WorldSnapshot::default();
+        snapshot.opponents.push(ShipSnapshot {
+            position: Vec2::new(0.0, -260.0),
+            velocity: Vec2::new(100.0, 0.0),
+            angle: FRAC_PI_2,
+        });
+        let aim = rival_aim_point(&snapshot).unwrap();
+        assert!(aim.x > 0.0, "aim should lead the rival's drift");
+        assert!(!rival_in_sights(&snapshot));
+        snapshot.ship_angle = (aim - snapshot.ship_position).to_angle();
+        assert!(rival_in_sights(&snapshot));
+
+        let threat = rival_threat(&snapshot).unwrap();
+        assert!(threat.avoid_dir.x.abs() > threat.avoid_dir.y.abs(), "sidestep, not retreat");
+
+        snapshot.opponents[0].angle = 0.0;
+        assert!(rival_threat(&snapshot).is_none());
+        snapshot.opponents[0].position = Vec2::new(0.0, -2000.0);
+        assert!(rival_aim_point(&snapshot).is_none());
+    }
+
+    #[test]
+    fn hazard_repulsion_pushes_away_from_wells_only() {
+        let mut snapshot = WorldSnapshot {
+            ship_position: Vec2::new(100.0, 0.0),
+            ..WorldSnapshot::default()
+        };
+        snapshot.hazards.push(HazardSnapshot {
+            kind: HazardKind::Nebula,
+            position: Vec2::ZERO,
+            radius: 200.0,
+        });
+        assert_eq!(hazard_repulsion(&snapshot), Vec2::ZERO);
+
+        snapshot.hazards.push(HazardSnapshot {
+            kind: HazardKind::GravityWell,
+            position: Vec2::ZERO,
+            radius: 200.0,
+        });
+        assert!(hazard_repulsion(&snapshot).x > 0.0);
+    }
 }
 
 impl Controller for AiController {
@@ -600,8 +920,12 @@ impl Controller for AiController {
         self.target_timer = (self.target_timer - dt).max(0.0);
         self.phase_timer = (self.phase_timer - dt).max(0.0);
 
-        // Threat detection (continuous)
-        let threat = detect_threat(world);
+        // Threat detection (continuous); in versus a rival lining up a shot counts too.
+        let threat = match (detect_threat(world), rival_threat(world)) {
+            (Some(rock), Some(rival)) if rival.severity > rock.severity => Some(rival),
+            (None, rival) => rival,
+            (rock, _) => rock,
+        };
 
         // Phase selection with hysteresis
         if let Some(t) = threat {
@@ -621,7 +945,7 @@ impl Controller for AiController {
         }
 
         // Recenter if far from center ring and not actively evading.
-        let to_center = center() - world.ship_position;
+        let to_center = center(world) - world.ship_position;
         let center_dist = to_center.length();
         let outside_ring = center_dist > TUNING.center_ring_max;
         let inside_ring = center_dist < TUNING.center_ring_min;
@@ -640,27 +964,38 @@ impl Controller for AiController {
         if self.decision_timer <= 0.0 {
             self.decision_timer = self.decision_interval();
 
-            // Refresh target if commitment expired or target is missing.
-            if self.target.is_none() || self.target_timer <= 0.0 {
-                self.target = select_target_lane_clearing(world);
+            // A rival in range always beats a rock; re-aim every decision since it moves.
+            if let Some(aim) = rival_aim_point(world) {
+                self.target = Some(aim);
+                self.target_rock = None;
+                self.target_timer = 0.0;
+            } else if self.target.is_none() || self.target_timer <= 0.0 {
+                let rock = select_target_lane_clearing(world);
+                self.target = rock.map(|ast| ast.position);
+                self.target_rock = rock.map(|ast| ast.id);
                 self.target_timer = self.choose_commit_duration();
-            } else {
-                // If we have a target, occasionally re-anchor to the nearest asteroid to that target
-                // (prevents staleness when the target disappears).
-                if let Some(tp) = self.target {
-                    let mut best = None;
+            } else if let Some(tp) = self.target {
+                // Follow the committed rock while it exists; once it is gone, re-anchor to the
+                // nearest asteroid to where it was (its fragments, usually).
+                let locked = world
+                    .iter_asteroids()
+                    .find(|ast| Some(ast.id) == self.target_rock);
+                let mut best = locked.map(|ast| (ast, 0.0));
+                if best.is_none() {
                     for ast in world.iter_asteroids() {
                         let d = (ast.position - tp).length_squared();
                         if best.as_ref().map_or(true, |(_, bd)| d < *bd) {
-                            best = Some((ast.position, d));
+                            best = Some((ast, d));
                         }
                     }
-                    if let Some((p, _)) = best {
-                        self.target = Some(p);
-                    } else {
-                        self.target = None;
-                        self.target_timer = 0.0;
-                    }
+                }
+                if let Some((ast, _)) = best {
+                    self.target = Some(ast.position);
+                    self.target_rock = Some(ast.id);
+                } else {
+                    self.target = None;
+                    self.target_rock = None;
+                    self.target_timer = 0.0;
                 }
             }
         }
@@ -670,6 +1005,7 @@ impl Controller for AiController {
 
         // Edge repulsion is always active to avoid spawn lanes.
         desired_heading += edge_repulsion(world);
+        desired_heading += hazard_repulsion(world);
 
         // Center control: maintain a ring, avoid jitter by only applying when outside the ring.
         if outside_ring {
@@ -737,7 +1073,7 @@ impl Controller for AiController {
         };
 
         // If we are near edges, reduce thrust slightly to maintain turn authority.
-        let edge_p = edge_proximity(world.ship_position);
+        let edge_p = edge_proximity(world, world.ship_position);
         if edge_p > 0.0 {
             thrust *= 1.0 - 0.22 * edge_p;
         }
@@ -764,13 +1100,29 @@ impl Controller for AiController {
             true
         };
 
-        fire_primary = fire_primary && align_ok;
+        fire_primary = (fire_primary && align_ok) || rival_in_sights(world);
+        if ally_in_line_of_fire(world) {
+            fire_primary = false;
+            fire_secondary = false;
+        }
 
         let mut intent = ControlIntent::default();
         intent.turn = smooth_turn.clamp(-1.0, 1.0);
         intent.thrust = clamp01(thrust).clamp(0.15, 0.90);
         intent.fire_primary = fire_primary;
         intent.fire_secondary = fire_secondary;
+        // Drop a sentinel to cover the retreat when evading through a cluster.
+        intent.deploy_sentinel =
+            self.phase == Phase::Evade && cluster_hits >= TUNING.secondary_cluster_count;
         intent
     }
+
+    fn phase(&self) -> Option<&'static str> {
+        Some(match self.phase {
+            Phase::Engage => "engage",
+            Phase::Evade => "evade",
+            Phase::Recover => "recover",
+            Phase::Recenter => "recenter",
+        })
+    }
 }
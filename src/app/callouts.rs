@@ -0,0 +1,81 @@
+use crate::config::PickupKind;
+use crate::simulation::SimEvent;
+
+/// Seconds a callout stays up, fading over the last half.
+const CALLOUT_SECONDS: f32 = 1.6;
+const MAX_CALLOUTS: usize = 4;
+
+/// Brief words in the middle of the screen for moments that are easy to miss in a busy field:
+/// an extra life, a pickup, a shield soaking a hit. Fed from the simulation's events.
+pub(super) struct Callouts {
+    /// Newest last.
+    active: Vec<Callout>,
+}
+
+struct Callout {
+    text: String,
+    /// The seat it is about, for its colour; `None` for the whole field.
+    seat: Option<usize>,
+    remaining: f32,
+}
+
+impl Callouts {
+    pub(super) fn new() -> Self {
+        Self { active: Vec::new() }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    /// Adds a callout for the events worth one. With more than one ship, it says whose.
+    pub(super) fn observe(&mut self, event: &SimEvent, seats: usize) {
+        let (text, seat) = match event {
+            SimEvent::ExtraLife { seat } => ("EXTRA LIFE", Some(*seat)),
+            SimEvent::PickupCollected { seat, kind } => (pickup_text(*kind), Some(*seat)),
+            SimEvent::ShipHit { seat, shielded: true, .. } => ("SHIELD HELD", Some(*seat)),
+            SimEvent::CarrierSpawned { .. } => ("CARRIER INBOUND", None),
+            _ => return,
+        };
+        let text = match seat {
+            Some(seat) if seats > 1 => format!("P{} {text}", seat + 1),
+            _ => text.to_string(),
+        };
+        if self.active.len() == MAX_CALLOUTS {
+            self.active.remove(0);
+        }
+        self.active.push(Callout {
+            text,
+            seat,
+            remaining: CALLOUT_SECONDS,
+        });
+    }
+
+    pub(super) fn tick(&mut self, dt: f32) {
+        self.active.retain_mut(|callout| {
+            callout.remaining -= dt;
+            callout.remaining > 0.0
+        });
+    }
+
+    /// Text, seat and opacity of each callout still up, oldest first.
+    pub(super) fn visible(&self) -> Vec<(&str, Option<usize>, f32)> {
+        self.active
+            .iter()
+            .map(|callout| {
+                let alpha = (callout.remaining / (CALLOUT_SECONDS * 0.5)).min(1.0);
+                (callout.text.as_str(), callout.seat, alpha)
+            })
+            .collect()
+    }
+}
+
+fn pickup_text(kind: PickupKind) -> &'static str {
+    match kind {
+        PickupKind::RapidFire => "RAPID FIRE",
+        PickupKind::SpreadReset => "SPREAD READY",
+        PickupKind::ExtraLife => "EXTRA LIFE",
+        PickupKind::Shield => "SHIELD",
+        PickupKind::ScoreMultiplier => "SCORE MULTIPLIER",
+    }
+}
@@ -0,0 +1,143 @@
+/// Speeds the faster/slower keys step through; the simulation always advances in whole fixed
+/// frames, so a replay or checksum never depends on the speed it was played at.
+const TIME_SCALES: [f32; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+pub const MIN_TIME_SCALE: f32 = TIME_SCALES[0];
+pub const MAX_TIME_SCALE: f32 = TIME_SCALES[TIME_SCALES.len() - 1];
+
+/// Bullet time: how slow, how close a hit has to be (seconds of game time) to start it, and
+/// how many frames it lasts after the last close call.
+const BULLET_TIME_SCALE: f32 = 0.35;
+pub(super) const BULLET_TIME_TTC: f32 = 0.5;
+const BULLET_TIME_FRAMES: u32 = 30;
+
+/// How many simulation frames each rendered frame plays: the chosen time scale, or the
+/// bullet-time assist's while it is slowing things down.
+pub(super) struct GameClock {
+    scale: f32,
+    /// Fractions of a frame carried over to the next rendered frame.
+    owed: f32,
+    /// Frames of bullet time left.
+    bullet_time: u32,
+}
+
+impl GameClock {
+    pub(super) fn new(scale: f32) -> Self {
+        Self {
+            scale: scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE),
+            owed: 0.0,
+            bullet_time: 0,
+        }
+    }
+
+    /// Forgets part-played frames and any bullet time; the chosen speed carries over.
+    pub(super) fn restart(&mut self) {
+        self.owed = 0.0;
+        self.bullet_time = 0;
+    }
+
+    pub(super) fn faster(&mut self) {
+        if let Some(&next) = TIME_SCALES.iter().find(|&&scale| scale > self.scale) {
+            self.scale = next;
+        }
+    }
+
+    pub(super) fn slower(&mut self) {
+        if let Some(&next) = TIME_SCALES.iter().rev().find(|&&scale| scale < self.scale) {
+            self.scale = next;
+        }
+    }
+
+    /// The speed the game is running at right now.
+    pub(super) fn scale(&self) -> f32 {
+        if self.bullet_time > 0 {
+            self.scale.min(BULLET_TIME_SCALE)
+        } else {
+            self.scale
+        }
+    }
+
+    pub(super) fn in_bullet_time(&self) -> bool {
+        self.bullet_time > 0
+    }
+
+    /// Whole frames to play this rendered frame.
+    pub(super) fn frames_due(&mut self) -> u32 {
+        self.owed += self.scale();
+        let due = self.owed.floor();
+        self.owed -= due;
+        due as u32
+    }
+
+    /// Drops the rest of this rendered frame's frames, e.g. when a life is lost part-way.
+    pub(super) fn forfeit(&mut self) {
+        self.owed = 0.0;
+    }
+
+    /// Starts or extends bullet time after a close call.
+    pub(super) fn close_call(&mut self) {
+        self.bullet_time = BULLET_TIME_FRAMES;
+    }
+
+    /// Counts down bullet time once a frame has been played.
+    pub(super) fn frame_played(&mut self) {
+        self.bullet_time = self.bullet_time.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(clock: &mut GameClock, calls: usize) -> Vec<u32> {
+        (0..calls).map(|_| clock.frames_due()).collect()
+    }
+
+    #[test]
+    fn fractions_of_a_frame_carry_over() {
+        let mut slow = GameClock::new(0.25);
+        assert_eq!(frames(&mut slow, 8), [0, 0, 0, 1, 0, 0, 0, 1]);
+        let mut fast = GameClock::new(4.0);
+        assert_eq!(frames(&mut fast, 2), [4, 4]);
+    }
+
+    #[test]
+    fn speeds_stop_at_the_ends_of_the_range() {
+        let mut clock = GameClock::new(1.0);
+        for _ in 0..TIME_SCALES.len() {
+            clock.faster();
+        }
+        assert_eq!(clock.scale(), MAX_TIME_SCALE);
+        for _ in 0..TIME_SCALES.len() {
+            clock.slower();
+        }
+        assert_eq!(clock.scale(), MIN_TIME_SCALE);
+        assert_eq!(GameClock::new(100.0).scale(), MAX_TIME_SCALE);
+    }
+
+    #[test]
+    fn bullet_time_caps_the_speed_until_it_runs_out() {
+        let mut clock = GameClock::new(2.0);
+        clock.close_call();
+        assert!(clock.in_bullet_time());
+        assert_eq!(clock.scale(), BULLET_TIME_SCALE);
+        for _ in 1..BULLET_TIME_FRAMES {
+            clock.frame_played();
+        }
+        assert!(clock.in_bullet_time(), "bullet time ended early");
+        clock.frame_played();
+        assert!(!clock.in_bullet_time());
+        assert_eq!(clock.scale(), 2.0);
+
+        let mut slow = GameClock::new(MIN_TIME_SCALE);
+        slow.close_call();
+        assert_eq!(slow.scale(), MIN_TIME_SCALE, "bullet time sped a slow game up");
+    }
+
+    #[test]
+    fn forfeiting_drops_owed_frames() {
+        let mut clock = GameClock::new(0.5);
+        assert_eq!(clock.frames_due(), 0);
+        clock.forfeit();
+        assert_eq!(frames(&mut clock, 2), [0, 1]);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::cli::CliOptions;
+use crate::config::{self, GameConfig, Preset, Settings, SimTuning};
+use crate::keymap::Keymap;
+use crate::netplay::{self, Agreement, NetOptions, Netplay, Role};
+use crate::replay::Replay;
+use crate::telemetry::{Detail, TelemetryLog};
+use macroquad::window::Conf;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Everything resolved before the window opens: the settings file, tuning overrides,
+/// command-line flags and the netplay handshake. `--list-presets` and `--print-config` stop here.
+pub struct Launch {
+    pub(super) config: GameConfig,
+    /// Built-in presets followed by the player's saved presets.
+    pub(super) presets: Vec<Preset>,
+    pub(super) preset_index: usize,
+    pub(super) preset_label: String,
+    pub(super) notices: Vec<String>,
+    pub(super) keymap: Keymap,
+    pub(super) settings_path: PathBuf,
+    pub(super) seed: Option<u64>,
+    pub(super) replay: Option<Replay>,
+    pub(super) record: Option<PathBuf>,
+    pub(super) start_run: bool,
+    pub(super) time_scale: f32,
+    /// `--telemetry`: runs are logged only when asked.
+    pub(super) telemetry: Option<Detail>,
+    /// A connected netplay peer and the run agreed with it.
+    pub(super) netplay: Option<(Netplay, Agreement)>,
+    window: Option<(u32, u32)>,
+}
+
+impl Launch {
+    pub fn resolve(cli: &CliOptions) -> Result<Self, String> {
+        let settings_path = cli.config.clone().unwrap_or_else(Settings::path);
+        let mut config = GameConfig::default();
+        let mut presets = config::presets::default_presets();
+        let mut preset_label = presets[0].name.clone();
+        let mut notices = Vec::new();
+        let mut keymap = Keymap::default();
+
+        match Settings::load(&settings_path) {
+            Ok(Some(settings)) => {
+                config = settings.config;
+                preset_label = settings.preset_label;
+                presets.extend(settings.user_presets);
+                keymap = settings.keymap;
+            }
+            Ok(None) if cli.config.is_some() => {
+                return Err(format!("{}: no such settings file", settings_path.display()));
+            }
+            Ok(None) => {}
+            // A file named on the command line is never moved aside; the user asked for it.
+            Err(err) if cli.config.is_some() => {
+                return Err(format!("{}: {err}", settings_path.display()));
+            }
+            Err(err) => {
+                let kept = Settings::backup_broken(&settings_path)
+                    .map(|backup| format!(" Old file kept as {}.", backup.display()))
+                    .unwrap_or_default();
+                notices.push(format!("Settings file error: {err}. Using defaults.{kept}"));
+            }
+        }
+        notices.extend(keymap.conflicts());
+        notices.extend(load_tuning_overrides(&mut config, &mut presets));
+
+        let mut preset_index = presets
+            .iter()
+            .position(|preset| preset.name == preset_label)
+            .unwrap_or(0);
+
+        if let Some(name) = &cli.preset {
+            let wanted = preset_key(name);
+            preset_index = presets
+                .iter()
+                .position(|preset| preset_key(&preset.name) == wanted)
+                .ok_or_else(|| format!("unknown preset `{name}` (see --list-presets)"))?;
+            config = presets[preset_index].config.clone();
+            preset_label = presets[preset_index].name.clone();
+        }
+
+        if let Some(name) = &cli.controller {
+            let before = config.player_controller.clone();
+            config::apply_setting(&mut config, "controller", name)
+                .map_err(|err| format!("--controller: {err}"))?;
+            if config.player_controller != before {
+                preset_label = "Custom".to_string();
+            }
+        }
+
+        let replay = cli.replay.as_deref().map(Replay::load).transpose()?;
+        let mut window = cli.window.or(replay.as_ref().map(|replay| replay.window));
+
+        let netplay = match cli.netplay() {
+            Some(options) if !cli.list_presets && !cli.print_config => {
+                let (netplay, agreement) = connect(&options, &config, cli.seed, window)?;
+                window = Some(agreement.window);
+                Some((netplay, agreement))
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            config,
+            presets,
+            preset_index,
+            preset_label,
+            notices,
+            keymap,
+            settings_path,
+            seed: cli.seed,
+            replay,
+            record: cli.record.clone(),
+            start_run: cli.starts_run(),
+            time_scale: cli.time_scale.filter(|scale| scale.is_finite()).unwrap_or(1.0),
+            telemetry: cli.telemetry,
+            netplay,
+            window,
+        })
+    }
+
+    pub fn presets(&self) -> &[Preset] {
+        &self.presets
+    }
+
+    /// The configuration the first run will use: a replay's recorded one, the one agreed with a
+    /// netplay peer, else the resolved one.
+    pub fn run_config(&self) -> &GameConfig {
+        match (&self.replay, &self.netplay) {
+            (Some(replay), _) => &replay.config,
+            (None, Some((_, agreement))) => &agreement.config,
+            (None, None) => &self.config,
+        }
+    }
+
+    /// The resolved configuration in settings-file format, loadable again with `--config`.
+    pub fn config_text(&self) -> String {
+        let label = if self.replay.is_some() || self.netplay.is_some() {
+            "Custom".to_string()
+        } else {
+            self.preset_label.clone()
+        };
+        Settings {
+            config: self.run_config().clone(),
+            preset_label: label,
+            user_presets: Vec::new(),
+            keymap: self.keymap.clone(),
+        }
+        .serialize()
+    }
+
+    pub fn window(&self) -> Option<(u32, u32)> {
+        self.window
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// The preset the resolved options came from, or "Custom".
+    pub fn preset_label(&self) -> &str {
+        &self.preset_label
+    }
+
+    /// Where `--telemetry` logs runs, if it was given.
+    pub fn telemetry(&self) -> Option<TelemetryLog> {
+        self.telemetry.map(TelemetryLog::in_data_dir)
+    }
+}
+
+/// Blocks until the peer is there. The host offers its own configuration, made networked, with
+/// its seed and window; the joiner takes whatever the host offers.
+fn connect(
+    options: &NetOptions,
+    config: &GameConfig,
+    seed: Option<u64>,
+    window: Option<(u32, u32)>,
+) -> Result<(Netplay, Agreement), String> {
+    let link = options.open_link()?;
+    match &options.role {
+        Role::Host { port } => {
+            let mut config = config.clone();
+            config.make_networked();
+            let window = window.unwrap_or_else(|| {
+                let conf = Conf::default();
+                (conf.window_width as u32, conf.window_height as u32)
+            });
+            let agreement = Agreement {
+                seed: seed.unwrap_or_else(clock_seed),
+                window,
+                input_delay: options.input_delay,
+                config,
+            };
+            eprintln!("asteroids: waiting for a netplay partner on UDP port {port}...");
+            let netplay = Netplay::host(link, &agreement, netplay::HOST_WAIT)?;
+            Ok((netplay, agreement))
+        }
+        Role::Join { address } => {
+            eprintln!("asteroids: joining {address}...");
+            Netplay::join(link, netplay::JOIN_WAIT)
+        }
+    }
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Case-, space- and dash-insensitive preset matching, so `arcade-upgrades` finds "Arcade Upgrades".
+fn preset_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Applies `tuning.txt` (if present) on top of every preset.
+///
+/// A file with any invalid line is ignored as a whole and the presets keep their own values.
+fn load_tuning_overrides(config: &mut GameConfig, presets: &mut [Preset]) -> Option<String> {
+    let path = SimTuning::path();
+    let text = std::fs::read_to_string(&path).ok()?;
+    let mut tuned = Vec::with_capacity(presets.len() + 1);
+    for base in std::iter::once(&*config).chain(presets.iter().map(|preset| &preset.config)) {
+        let mut tuning = base.tuning.clone();
+        if let Err(errors) = tuning.apply_overrides(&text) {
+            let extra = match errors.len() {
+                1 => String::new(),
+                n => format!(" (+{} more)", n - 1),
+            };
+            return Some(format!("Tuning file ignored: {}{}", errors[0], extra));
+        }
+        tuned.push(tuning);
+    }
+
+    let mut tuned = tuned.into_iter();
+    config.tuning = tuned.next().unwrap_or_default();
+    for (preset, tuning) in presets.iter_mut().zip(tuned) {
+        preset.config.tuning = tuning;
+    }
+    Some(format!("Tuning: {}", path.display()))
+}
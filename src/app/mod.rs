@@ -1,74 +1,153 @@
+mod callouts;
+mod clock;
+mod launch;
+mod rewind;
 mod session;
 mod state;
 
+pub use clock::{MAX_TIME_SCALE, MIN_TIME_SCALE};
+pub use launch::Launch;
 pub use state::AppState;
 
-use crate::config::{self, AiProfile, GameConfig};
+use crate::config::advanced::{self, AdvancedField};
+use crate::config::{AiProfile, GameConfig, PlayerControllerMode, Preset, Settings};
+use crate::keymap::{self, Action, Keymap};
+use crate::netplay::Closed;
+use crate::savegame::SavedRun;
+use crate::simulation::{SimEvent, SimulationStatus};
+use crate::telemetry::TelemetryLog;
 use crate::ui::menu;
 use macroquad::prelude::*;
+use std::path::PathBuf;
 
+use self::callouts::Callouts;
 use self::session::Session;
 
-const PRESET_LABELS: [&str; 3] = ["Classic", "Arcade Upgrades", "AI Autopilot"];
 const GAME_OVER_NAME_MAX: usize = 12;
 
 pub struct App {
     config: GameConfig,
-    presets: Vec<GameConfig>,
+    /// Built-in presets followed by the player's saved presets.
+    presets: Vec<Preset>,
     preset_index: usize,
     preset_label: String,
+    /// Problems and status from the settings and tuning files, shown on the options screen.
+    notices: Vec<String>,
+    settings_path: PathBuf,
+    advanced_fields: Vec<AdvancedField>,
+    advanced_cursor: usize,
+    /// Why the last Advanced panel edit was refused, if it was.
+    advanced_message: Option<String>,
+    keymap: Keymap,
+    /// Row on the Controls screen; one past the last action is "Reset all to defaults".
+    controls_cursor: usize,
+    controls_slot: usize,
+    /// Waiting for the next key press to bind to the selected slot.
+    controls_capturing: bool,
+    controls_message: Option<String>,
     session: Session,
+    /// Pickups, extra lives and the like, called out over the field for a moment.
+    callouts: Callouts,
     state: AppState,
     stats_visible: bool,
+    /// The step timing panel; off until asked for.
+    profiler_visible: bool,
     autopilot_engaged: bool,
     autopilot_profile: AiProfile,
     game_over_name: String,
     name_cursor: usize,
+    /// Final status of each player whose name is being entered, and whose turn it is to type.
+    game_over_results: Vec<SimulationStatus>,
+    game_over_player: usize,
+    /// Whether a run put aside with Save and quit is waiting for Continue.
+    saved_run: bool,
+    /// How the last save or continue went, shown on the pause screen and the main menu.
+    run_message: Option<String>,
 }
 
 impl App {
-    pub fn new() -> Self {
-        let config = GameConfig::default();
-        let presets = config::presets::default_presets();
-        debug_assert_eq!(presets.len(), PRESET_LABELS.len());
-        let mut session = Session::new(&config);
-        session.set_controller(false, AiProfile::Balanced);
+    pub fn new(launch: Launch) -> Self {
+        let config = launch.config;
+        // Flags that start a run straight away also pick its controller: an AI controller
+        // from `--controller` or the preset engages autopilot with that profile.
+        let (autopilot_engaged, autopilot_profile) = match &config.player_controller {
+            PlayerControllerMode::Ai { profile } if launch.start_run && launch.replay.is_none() => {
+                (true, *profile)
+            }
+            _ => (false, AiProfile::Balanced),
+        };
+        let mut session = Session::new(
+            &config,
+            launch.seed,
+            launch.record,
+            launch.keymap.clone(),
+            launch.time_scale,
+            launch.telemetry.map(TelemetryLog::in_data_dir),
+        );
+        session.set_controller(autopilot_engaged, autopilot_profile);
+        let mut state = AppState::MainMenu;
+        if launch.start_run {
+            match (launch.replay, launch.netplay) {
+                (Some(replay), _) => session.play_replay(replay),
+                (None, Some((netplay, agreement))) => {
+                    session.start_netplay(netplay, &agreement, autopilot_engaged, autopilot_profile)
+                }
+                (None, None) => session.reset(&config, autopilot_engaged, autopilot_profile),
+            }
+            state = AppState::Playing;
+        }
         Self {
             config,
-            presets,
-            preset_index: 0,
-            preset_label: PRESET_LABELS[0].to_string(),
+            presets: launch.presets,
+            preset_index: launch.preset_index,
+            preset_label: launch.preset_label,
+            notices: launch.notices,
+            settings_path: launch.settings_path,
+            advanced_fields: advanced::fields(),
+            advanced_cursor: 0,
+            advanced_message: None,
+            keymap: launch.keymap,
+            controls_cursor: 0,
+            controls_slot: 0,
+            controls_capturing: false,
+            controls_message: None,
             session,
-            state: AppState::MainMenu,
+            callouts: Callouts::new(),
+            state,
             stats_visible: true,
-            autopilot_engaged: false,
-            autopilot_profile: AiProfile::Balanced,
+            profiler_visible: false,
+            autopilot_engaged,
+            autopilot_profile,
             game_over_name: String::new(),
             name_cursor: 0,
+            game_over_results: Vec::new(),
+            game_over_player: 0,
+            saved_run: SavedRun::path().exists(),
+            run_message: None,
         }
     }
 
     pub async fn tick(&mut self) -> bool {
         clear_background(BLACK);
         let mut continue_running = true;
+        if self.state != AppState::Playing {
+            self.session.keep_netplay_alive();
+        }
+        if !matches!(self.state, AppState::Playing | AppState::Paused) {
+            self.callouts.clear();
+        }
 
         match self.state {
             AppState::Playing => {
-                let snapshot = self.session.simulation().snapshot();
-                let dt = self.session.simulation().dt();
-                let intent = self.session.controller().tick(&snapshot, dt);
-
-                {
-                    let simulation = self.session.simulation_mut();
-                    simulation.apply_intent(intent);
-                    simulation.step();
-                }
+                self.session.advance();
+                let events = self.observe_events();
+                self.callouts.tick(get_frame_time());
 
                 let status = self.session.simulation().status();
-                let fps = status.fps;
-                let should_degrade = {
+                // Degrading changes the simulation, which a netplay peer would not do in step.
+                let should_degrade = !self.session.networked() && {
                     let guard = self.session.performance_guard();
-                    guard.observe(fps);
+                    guard.observe(status.fps, status.active_bodies);
                     guard.should_degrade()
                 };
 
@@ -76,56 +155,73 @@ impl App {
                     self.session.simulation_mut().policy().degrade();
                 }
 
-                self.session.simulation().draw_debug();
+                self.session.draw();
                 menu::draw_score_display(&status);
                 if self.stats_visible {
-                    menu::draw_stats_overlay(&self.config, &status);
+                    menu::draw_stats_overlay(
+                        &self.config,
+                        &status,
+                        self.session.time_scale(),
+                        self.session.in_bullet_time(),
+                    );
                 }
                 menu::draw_autopilot_status(self.autopilot_engaged, profile_label(&self.autopilot_profile));
+                if self.session.players() > 1 {
+                    menu::draw_hot_seat_turn(self.session.turn());
+                }
+                menu::draw_callouts(&self.callouts.visible());
+                if self.profiler_visible {
+                    menu::draw_profiler(self.session.profiler());
+                }
+                if let Some(net) = self.session.netplay_status() {
+                    menu::draw_netplay_status(&net, self.session.desync_note());
+                }
 
-                if status.game_over {
-                    self.game_over_name = default_game_over_name(
-                        self.autopilot_engaged,
-                        self.autopilot_profile,
-                    );
-                    self.name_cursor = self.game_over_name.len();
-                    drain_char_input();
-                    self.state = AppState::GameOver;
+                // Hot-seat hands over on every death, as long as the other player is still in.
+                if self.session.turn_ends(&events) {
+                    self.state = AppState::HotSeatReady;
+                } else if status.game_over && self.session.settled() {
+                    self.enter_game_over();
+                } else if let Some(closed) = self.session.netplay_closed() {
+                    self.notices.push(match closed {
+                        Closed::PeerLeft => "Netplay: the other player left".to_string(),
+                        Closed::TimedOut => "Netplay: lost contact with the other player".to_string(),
+                    });
+                    self.finish_run();
+                    self.state = AppState::MainMenu;
                 } else {
                     if is_key_pressed(KeyCode::Escape) {
                         self.finish_run();
                         self.state = AppState::MainMenu;
                     }
-                    if is_key_pressed(KeyCode::P) {
-                        if self.autopilot_engaged {
-                            self.autopilot_profile = cycle_profile(self.autopilot_profile);
-                            self.session.set_controller(true, self.autopilot_profile);
-                        } else {
-                            self.state = AppState::Paused;
-                        }
+                    // The peer keeps playing, so a netplay game cannot be paused.
+                    if self.keymap.pressed(Action::Pause) && !self.session.networked() {
+                        self.run_message = None;
+                        self.state = AppState::Paused;
                     }
-                    if is_key_pressed(KeyCode::I) {
-                        self.session.simulation_mut().toggle_invulnerability();
-                    }
-                    if is_key_pressed(KeyCode::T) {
-                        self.stats_visible = !self.stats_visible;
-                    }
-                    if is_key_pressed(KeyCode::U) {
-                        self.autopilot_engaged = !self.autopilot_engaged;
-                        self.session.set_controller(self.autopilot_engaged, self.autopilot_profile);
+                    // Stepping back pauses too, for catching what just happened.
+                    if self.keymap.pressed(Action::StepBack) && !self.session.networked() {
+                        self.run_message = None;
+                        self.session.step_back();
+                        self.state = AppState::Paused;
                     }
+                    self.handle_flight_keys();
                 }
             }
             AppState::MainMenu => {
-                menu::draw_main_menu();
-                if is_key_pressed(KeyCode::P) {
+                menu::draw_main_menu(&self.keymap, self.saved_run, self.run_message.as_deref());
+                if self.keymap.pressed(Action::Play) {
+                    self.run_message = None;
                     self.session.reset(&self.config, self.autopilot_engaged, self.autopilot_profile);
                     self.state = AppState::Playing;
                 }
-                if is_key_pressed(KeyCode::O) {
+                if self.keymap.pressed(Action::ContinueRun) && self.saved_run {
+                    self.continue_run();
+                }
+                if self.keymap.pressed(Action::OpenOptions) {
                     self.state = AppState::Options;
                 }
-                if is_key_pressed(KeyCode::L) {
+                if self.keymap.pressed(Action::OpenLeaderboard) {
                     self.state = AppState::Leaderboard;
                 }
                 if is_key_pressed(KeyCode::Escape) {
@@ -133,36 +229,148 @@ impl App {
                 }
             }
             AppState::Options => {
-                menu::draw_options_menu(&self.config, &self.preset_label);
-                if is_key_pressed(KeyCode::C) {
+                menu::draw_options_menu(&self.config, self.selected_preset(), &self.notices, &self.keymap);
+                if self.keymap.pressed(Action::CycleCollision) {
                     self.config.cycle_collision_policy();
                     self.preset_label = "Custom".to_string();
                 }
-                if is_key_pressed(KeyCode::K) {
+                if self.keymap.pressed(Action::CyclePhysics) {
                     self.config.cycle_physics_mode();
                     self.preset_label = "Custom".to_string();
                 }
-                if is_key_pressed(KeyCode::F) {
+                if self.keymap.pressed(Action::CycleFragmentation) {
                     self.config.cycle_fragmentation_mode();
                     self.preset_label = "Custom".to_string();
                 }
-                if is_key_pressed(KeyCode::L) {
+                if self.keymap.pressed(Action::CycleLeaderboard) {
                     self.config.cycle_leaderboard_mode();
                     self.preset_label = "Custom".to_string();
                 }
-                if is_key_pressed(KeyCode::G) {
+                if self.keymap.pressed(Action::ToggleUpgrades) {
                     self.config.toggle_upgrades();
                     self.preset_label = "Custom".to_string();
                 }
-                if is_key_pressed(KeyCode::Y) {
-                    self.preset_index = (self.preset_index + 1) % self.presets.len();
-                    self.config = self.presets[self.preset_index].clone();
-                    self.preset_label = PRESET_LABELS[self.preset_index].to_string();
+                if self.keymap.pressed(Action::ToggleEnemies) {
+                    self.config.toggle_enemies();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::ToggleSentinels) {
+                    self.config.toggle_sentinels();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::CycleAlienBehaviour) {
+                    self.config.cycle_alien_behaviour();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::ToggleHazards) {
+                    self.config.toggle_hazards();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::ToggleAimMode) {
+                    // Aim is the player's preference, not part of the preset being edited.
+                    self.config.toggle_aim_mode();
+                }
+                if self.keymap.pressed(Action::ToggleBulletTime) {
+                    self.config.toggle_bullet_time();
+                }
+                if self.keymap.pressed(Action::ToggleCoop) {
+                    self.config.toggle_coop();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::CycleCoopPartner) {
+                    self.config.cycle_coop_partner();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::ToggleFriendlyFire) {
+                    self.config.toggle_friendly_fire();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::ToggleHotSeat) {
+                    self.config.toggle_hot_seat();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::ToggleVersus) {
+                    self.config.toggle_versus();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::CycleVersusLineup) {
+                    self.config.cycle_versus_lineup();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::CycleMatchLength) {
+                    self.config.cycle_match_length();
+                    self.preset_label = "Custom".to_string();
+                }
+                if self.keymap.pressed(Action::NextPreset) {
+                    self.select_preset((self.preset_index + 1) % self.presets.len());
+                }
+                if self.keymap.pressed(Action::AdvancedSettings) {
+                    self.advanced_message = None;
+                    self.state = AppState::AdvancedOptions;
+                }
+                if self.keymap.pressed(Action::Controls) {
+                    self.controls_message = None;
+                    self.state = AppState::Controls;
+                }
+                if self.keymap.pressed(Action::SavePreset) {
+                    self.save_user_preset();
+                }
+                if self.keymap.pressed(Action::DeletePreset) {
+                    self.delete_user_preset();
+                }
+                if self.keymap.pressed(Action::ResetOptions) {
+                    self.select_preset(0);
                 }
                 if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
+                    self.save_settings();
                     self.state = AppState::MainMenu;
                 }
             }
+            AppState::AdvancedOptions => {
+                menu::draw_advanced_options(
+                    &self.config,
+                    &self.advanced_fields,
+                    self.advanced_cursor,
+                    self.advanced_message.as_deref(),
+                );
+                let count = self.advanced_fields.len();
+                if is_key_pressed(KeyCode::Down) {
+                    self.advanced_cursor = (self.advanced_cursor + 1) % count;
+                }
+                if is_key_pressed(KeyCode::Up) {
+                    self.advanced_cursor = (self.advanced_cursor + count - 1) % count;
+                }
+                let direction = if is_key_pressed(KeyCode::Right) {
+                    1
+                } else if is_key_pressed(KeyCode::Left) {
+                    -1
+                } else {
+                    0
+                };
+                if direction != 0 {
+                    let fast = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+                    let steps = if fast { direction * 10 } else { direction };
+                    let field = self.advanced_fields[self.advanced_cursor];
+                    let before = field.value(&self.config);
+                    self.advanced_message = field.step(&mut self.config, steps).err();
+                    if field.value(&self.config) != before {
+                        self.preset_label = "Custom".to_string();
+                    }
+                }
+                if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
+                    self.state = AppState::Options;
+                }
+            }
+            AppState::Controls => {
+                menu::draw_controls(
+                    &self.keymap,
+                    self.controls_cursor,
+                    self.controls_slot,
+                    self.controls_capturing,
+                    self.controls_message.as_deref(),
+                );
+                self.handle_controls_keys();
+            }
             AppState::Leaderboard => {
                 menu::draw_leaderboard_menu(self.session.leaderboard());
                 if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
@@ -170,70 +378,139 @@ impl App {
                 }
             }
             AppState::Paused => {
-                self.session.simulation().draw_debug();
+                // Frames stepped through while paused still have their say.
+                self.observe_events();
+                self.session.draw();
                 let status = self.session.simulation().status();
                 menu::draw_score_display(&status);
                 if self.stats_visible {
-                    menu::draw_stats_overlay(&self.config, &status);
+                    menu::draw_stats_overlay(
+                        &self.config,
+                        &status,
+                        self.session.time_scale(),
+                        self.session.in_bullet_time(),
+                    );
                 }
                 menu::draw_autopilot_status(self.autopilot_engaged, profile_label(&self.autopilot_profile));
+                menu::draw_callouts(&self.callouts.visible());
+                if self.profiler_visible {
+                    menu::draw_profiler(self.session.profiler());
+                }
                 if status.game_over {
-                    self.game_over_name = default_game_over_name(
-                        self.autopilot_engaged,
-                        self.autopilot_profile,
-                    );
-                    self.name_cursor = self.game_over_name.len();
-                    drain_char_input();
-                    self.state = AppState::GameOver;
+                    self.enter_game_over();
                 } else {
                     draw_text(
-                        "PAUSED — press P to resume, Esc to end run",
+                        &format!(
+                            "PAUSED — press {} to resume, Esc to end run",
+                            self.keymap.describe(Action::Pause)
+                        ),
                         screen_width() / 2.0 - 220.0,
                         screen_height() / 2.0,
                         30.0,
                         WHITE,
                     );
                     draw_text(
-                        "(P also cycles AI profile when autopilot is enabled)",
+                        &format!(
+                            "({} cycles the AI profile while autopilot is enabled)",
+                            self.keymap.describe(Action::CycleAiProfile)
+                        ),
                         screen_width() / 2.0 - 260.0,
                         screen_height() / 2.0 + 34.0,
                         20.0,
                         WHITE,
                     );
+                    let save_line = match &self.run_message {
+                        Some(message) => Some(message.clone()),
+                        None => self.session.can_save().then(|| {
+                            format!(
+                                "{} saves the run and quits; Continue it from the main menu",
+                                self.keymap.describe(Action::SaveAndQuit)
+                            )
+                        }),
+                    };
+                    if let Some(line) = save_line {
+                        draw_text(
+                            &line,
+                            screen_width() / 2.0 - 260.0,
+                            screen_height() / 2.0 + 62.0,
+                            20.0,
+                            WHITE,
+                        );
+                    }
+                    draw_text(
+                        &format!(
+                            "{} / {} step back / forward, hold {} / {} to scrub; play resumes from here",
+                            self.keymap.describe(Action::StepBack),
+                            self.keymap.describe(Action::StepForward),
+                            self.keymap.describe(Action::ScrubBack),
+                            self.keymap.describe(Action::ScrubForward)
+                        ),
+                        screen_width() / 2.0 - 260.0,
+                        screen_height() / 2.0 + 90.0,
+                        20.0,
+                        WHITE,
+                    );
+                    let (behind, span) = self.session.rewind_position();
+                    menu::draw_rewind_status(behind, span, status.frame_time);
 
-                    if is_key_pressed(KeyCode::P) {
-                        if self.autopilot_engaged {
-                            self.autopilot_profile = cycle_profile(self.autopilot_profile);
-                            self.session.set_controller(true, self.autopilot_profile);
-                        } else {
-                            self.state = AppState::Playing;
-                        }
+                    if self.keymap.pressed(Action::Pause) {
+                        self.session.resume_from_rewind();
+                        self.state = AppState::Playing;
                     }
-                    if is_key_pressed(KeyCode::I) {
-                        self.session.simulation_mut().toggle_invulnerability();
+                    if self.keymap.pressed(Action::SaveAndQuit) && self.session.can_save() {
+                        self.save_and_quit();
                     }
                     if is_key_pressed(KeyCode::Escape) {
                         self.finish_run();
                         self.state = AppState::MainMenu;
                     }
-                    if is_key_pressed(KeyCode::T) {
-                        self.stats_visible = !self.stats_visible;
-                    }
-                    if is_key_pressed(KeyCode::U) {
-                        self.autopilot_engaged = !self.autopilot_engaged;
-                        self.session.set_controller(self.autopilot_engaged, self.autopilot_profile);
+                    self.handle_flight_keys();
+                    if self.state == AppState::Paused {
+                        self.handle_rewind_keys();
                     }
                 }
             }
-            AppState::GameOver => {
-                self.session.simulation().draw_debug();
+            AppState::HotSeatReady => {
+                self.session.draw();
                 let status = self.session.simulation().status();
                 menu::draw_score_display(&status);
+                let turn = self.session.turn();
+                menu::draw_hot_seat_ready(1 - turn, status.game_over.then_some(turn));
+                if is_key_pressed(KeyCode::Enter) {
+                    self.session
+                        .pass_turn(self.autopilot_engaged, self.autopilot_profile);
+                    self.state = AppState::Playing;
+                }
+                if is_key_pressed(KeyCode::Escape) {
+                    self.finish_run();
+                    self.state = AppState::MainMenu;
+                }
+            }
+            AppState::MatchOver => {
+                self.session.draw();
+                let status = self.session.simulation().status();
+                menu::draw_score_display(&status);
+                menu::draw_match_results(&status);
+                if is_key_pressed(KeyCode::Enter) || is_key_pressed(KeyCode::Escape) {
+                    self.finish_run();
+                    self.state = AppState::MainMenu;
+                }
+            }
+            AppState::GameOver => {
+                self.session.draw();
+                let status = self.game_over_results[self.game_over_player].clone();
+                menu::draw_score_display(&status);
                 if self.stats_visible {
-                    menu::draw_stats_overlay(&self.config, &status);
+                    menu::draw_stats_overlay(&self.config, &status, self.session.time_scale(), false);
                 }
                 menu::draw_autopilot_status(self.autopilot_engaged, profile_label(&self.autopilot_profile));
+                let heading = if self.game_over_results.len() > 1 {
+                    format!("GAME OVER - PLAYER {}", self.game_over_player + 1)
+                } else {
+                    "GAME OVER".to_string()
+                };
                 menu::draw_game_over(
+                    &heading,
                     status.score,
                     &self.game_over_name,
                     self.name_cursor,
@@ -256,28 +533,27 @@ impl App {
 
                 if is_key_pressed(KeyCode::Enter) {
                     if status.score > 0 {
-                        let name = sanitize_run_name(
-                            &self.game_over_name,
-                            self.autopilot_engaged,
-                            self.autopilot_profile,
+                        // A co-op run goes on the board once, as a team, with the combined score.
+                        let team = status.pilots.len() > 1;
+                        let name = sanitize_run_name(&self.game_over_name, self.default_run_name());
+                        self.session.leaderboard_mut().submit(
+                            &name,
+                            status.score,
+                            status.run_stats.clone(),
+                            team,
+                            status.assisted,
                         );
-                        self.session
-                            .leaderboard_mut()
-                            .submit(&name, status.score, status.run_stats.clone());
                         self.session.leaderboard_mut().save();
                     }
-                    self.finish_run();
-                    self.state = AppState::MainMenu;
-                }
-                if is_key_pressed(KeyCode::Escape) {
-                    self.finish_run();
-                    self.state = AppState::MainMenu;
+                    self.next_game_over_entry();
+                } else if is_key_pressed(KeyCode::Escape) {
+                    self.next_game_over_entry();
                 }
-                if is_key_pressed(KeyCode::U) {
+                if self.keymap.pressed(Action::ToggleAutopilot) {
                     self.autopilot_engaged = !self.autopilot_engaged;
                     self.session.set_controller(self.autopilot_engaged, self.autopilot_profile);
                 }
-                if is_key_pressed(KeyCode::P) && self.autopilot_engaged {
+                if self.keymap.pressed(Action::CycleAiProfile) && self.autopilot_engaged {
                     self.autopilot_profile = cycle_profile(self.autopilot_profile);
                     self.session.set_controller(true, self.autopilot_profile);
                 }
@@ -288,10 +564,258 @@ impl App {
         continue_running
     }
 
+    fn enter_game_over(&mut self) {
+        // Versus is ranked on frags, not score, so it skips the leaderboard.
+        if self.session.simulation().status().match_remaining.is_some() {
+            self.state = AppState::MatchOver;
+            return;
+        }
+        self.game_over_results = self.session.results();
+        self.game_over_player = 0;
+        self.game_over_name = self.default_run_name();
+        self.name_cursor = self.game_over_name.len();
+        drain_char_input();
+        self.state = AppState::GameOver;
+    }
+
+    /// Moves name entry on to the next hot-seat player, or back to the menu after the last.
+    fn next_game_over_entry(&mut self) {
+        self.game_over_player += 1;
+        if self.game_over_player < self.game_over_results.len() {
+            self.game_over_name = self.default_run_name();
+            self.name_cursor = self.game_over_name.len();
+            drain_char_input();
+        } else {
+            self.finish_run();
+            self.state = AppState::MainMenu;
+        }
+    }
+
+    /// The name offered at game over: the team in co-op, the player number in hot-seat.
+    fn default_run_name(&self) -> String {
+        let team = self
+            .game_over_results
+            .first()
+            .is_some_and(|status| status.pilots.len() > 1);
+        if team {
+            "TEAM".to_string()
+        } else if self.game_over_results.len() > 1 {
+            format!("PLAYER {}", self.game_over_player + 1)
+        } else {
+            default_game_over_name(self.autopilot_engaged, self.autopilot_profile)
+        }
+    }
+
+    /// Takes what the simulation reported since the last rendered frame, and hands it back for
+    /// the caller to act on.
+    fn observe_events(&mut self) -> Vec<SimEvent> {
+        let seats = self.session.simulation().seats();
+        let events = self.session.drain_events();
+        for event in &events {
+            self.callouts.observe(event, seats);
+        }
+        events
+    }
+
+    /// Keys shared by the playing and paused states.
+    fn handle_flight_keys(&mut self) {
+        if self.keymap.pressed(Action::CycleAiProfile) && self.autopilot_engaged {
+            self.autopilot_profile = cycle_profile(self.autopilot_profile);
+            self.session.set_controller(true, self.autopilot_profile);
+        }
+        if self.keymap.pressed(Action::ToggleInvulnerability) && !self.session.networked() {
+            self.session.simulation_mut().toggle_invulnerability();
+        }
+        if self.keymap.pressed(Action::ToggleStats) {
+            self.stats_visible = !self.stats_visible;
+        }
+        if self.keymap.pressed(Action::ToggleProfiler) {
+            self.profiler_visible = !self.profiler_visible;
+        }
+        if self.keymap.pressed(Action::SlowDown) {
+            self.session.change_time_scale(false);
+        }
+        if self.keymap.pressed(Action::SpeedUp) {
+            self.session.change_time_scale(true);
+        }
+        if self.keymap.pressed(Action::ToggleAutopilot) && !self.session.replaying() {
+            self.autopilot_engaged = !self.autopilot_engaged;
+            self.session.set_controller(self.autopilot_engaged, self.autopilot_profile);
+        }
+    }
+
+    /// Paused only: stepping and scrubbing through the recent frames. Stepping forward from the
+    /// newest frame plays a new one, which can end a hot-seat turn like any other.
+    fn handle_rewind_keys(&mut self) {
+        if self.keymap.pressed(Action::StepBack) || self.keymap.down(Action::ScrubBack) {
+            self.session.step_back();
+        }
+        let rewound = self.session.rewind_position().0 > 0;
+        if self.keymap.pressed(Action::StepForward) || (self.keymap.down(Action::ScrubForward) && rewound) {
+            self.session.step_forward();
+        }
+        let events = self.observe_events();
+        if self.session.turn_ends(&events) {
+            self.state = AppState::HotSeatReady;
+        }
+    }
+
+    /// Navigation keys on the Controls screen are fixed so a bad binding can always be undone.
+    fn handle_controls_keys(&mut self) {
+        let actions: Vec<Action> = Action::all().collect();
+        if self.controls_capturing {
+            if is_key_pressed(KeyCode::Escape) {
+                self.controls_capturing = false;
+                self.controls_message = None;
+            } else if let Some(key) = get_last_key_pressed() {
+                let action = actions[self.controls_cursor];
+                self.controls_message = self.keymap.bind(action, self.controls_slot, key).err();
+                self.controls_capturing = false;
+                self.session.set_keymap(self.keymap.clone());
+            }
+            return;
+        }
+
+        let rows = actions.len() + 1;
+        if is_key_pressed(KeyCode::Down) {
+            self.controls_cursor = (self.controls_cursor + 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Up) {
+            self.controls_cursor = (self.controls_cursor + rows - 1) % rows;
+        }
+        if is_key_pressed(KeyCode::Left) || is_key_pressed(KeyCode::Right) {
+            self.controls_slot = (self.controls_slot + 1) % keymap::SLOTS;
+        }
+        let action = actions.get(self.controls_cursor).copied();
+        if is_key_pressed(KeyCode::Enter) {
+            match action {
+                Some(action) => {
+                    self.controls_message =
+                        Some(format!("Press a key for {} (Esc cancels)", action.label()));
+                    self.controls_capturing = true;
+                }
+                None => {
+                    self.keymap = Keymap::default();
+                    self.controls_message = Some("All keys reset to defaults".to_string());
+                    self.session.set_keymap(self.keymap.clone());
+                }
+            }
+        }
+        if let (Some(action), true) = (action, is_key_pressed(KeyCode::Backspace)) {
+            self.controls_message = self.keymap.clear(action, self.controls_slot).err();
+            self.session.set_keymap(self.keymap.clone());
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            self.save_settings();
+            self.state = AppState::Options;
+        }
+    }
+
+    /// Puts the paused run aside in the save slot and returns to the menu. On failure the game
+    /// stays paused with the reason on screen.
+    fn save_and_quit(&mut self) {
+        match self.session.save_run(&SavedRun::path()) {
+            Ok(()) => {
+                self.saved_run = true;
+                self.run_message = Some("Run saved".to_string());
+                self.finish_run();
+                self.state = AppState::MainMenu;
+            }
+            Err(err) => self.run_message = Some(format!("Could not save the run: {err}")),
+        }
+    }
+
+    /// Takes the saved run out of the slot and plays on from it. A file this build cannot read
+    /// is left alone, in case a newer build wrote it.
+    fn continue_run(&mut self) {
+        let path = SavedRun::path();
+        self.saved_run = false;
+        match SavedRun::load(&path) {
+            Ok(Some(saved)) => {
+                if let Err(err) = SavedRun::remove(&path) {
+                    self.notices.push(format!("Could not remove the saved run: {err}"));
+                }
+                self.run_message = None;
+                self.session
+                    .resume(saved, self.autopilot_engaged, self.autopilot_profile);
+                self.state = AppState::Playing;
+            }
+            Ok(None) => self.run_message = Some("The saved run is gone".to_string()),
+            Err(err) => {
+                self.run_message = Some(format!("Saved run not loaded: {err}"));
+            }
+        }
+    }
+
     fn finish_run(&mut self) {
+        if let Some(err) = self.session.log_runs(&self.preset_label) {
+            self.notices.push(err);
+        }
+        self.session.leave_netplay();
+        match self.session.save_recording() {
+            Ok(Some(path)) => self.notices.push(format!("Replay saved to {}", path.display())),
+            Ok(None) => {}
+            Err(err) => self.notices.push(format!("Could not save replay: {err}")),
+        }
         self.session
             .reset(&self.config, self.autopilot_engaged, self.autopilot_profile);
     }
+
+    /// The preset the current options came from, or `None` once they have been edited.
+    fn selected_preset(&self) -> Option<&Preset> {
+        (self.preset_label != "Custom").then(|| &self.presets[self.preset_index])
+    }
+
+    /// Loads a preset's options, keeping the player's aim mode and bullet-time assist.
+    fn select_preset(&mut self, index: usize) {
+        let (aim_mode, bullet_time) = (self.config.aim_mode, self.config.bullet_time);
+        self.preset_index = index;
+        self.config = self.presets[index].config.clone();
+        self.config.aim_mode = aim_mode;
+        self.config.bullet_time = bullet_time;
+        self.preset_label = self.presets[index].name.clone();
+    }
+
+    /// Stores the current options as a new player preset named "User N".
+    fn save_user_preset(&mut self) {
+        let name = (1..)
+            .map(|n| format!("User {n}"))
+            .find(|name| self.presets.iter().all(|preset| preset.name != *name))
+            .unwrap_or_default();
+        let description = format!("Saved from {}.", self.preset_label);
+        self.presets
+            .push(Preset::user(name, description, self.config.clone()));
+        self.select_preset(self.presets.len() - 1);
+    }
+
+    /// Removes the selected preset if it is one of the player's; built-ins cannot be deleted.
+    fn delete_user_preset(&mut self) {
+        if self.presets[self.preset_index].builtin || self.preset_label == "Custom" {
+            return;
+        }
+        self.presets.remove(self.preset_index);
+        self.select_preset(0);
+    }
+
+    fn save_settings(&mut self) {
+        let settings = Settings {
+            config: self.config.clone(),
+            preset_label: self.preset_label.clone(),
+            user_presets: self
+                .presets
+                .iter()
+                .filter(|preset| !preset.builtin)
+                .cloned()
+                .collect(),
+            keymap: self.keymap.clone(),
+        };
+        if let Err(err) = settings.save(&self.settings_path) {
+            let notice = format!("Could not save settings: {err}");
+            if !self.notices.contains(&notice) {
+                self.notices.push(notice);
+            }
+        }
+    }
 }
 
 fn cycle_profile(current: AiProfile) -> AiProfile {
@@ -310,10 +834,10 @@ fn profile_label(profile: &AiProfile) -> &'static str {
     }
 }
 
-fn sanitize_run_name(input: &str, autopilot: bool, profile: AiProfile) -> String {
+fn sanitize_run_name(input: &str, fallback: String) -> String {
     let trimmed = input.trim();
     if trimmed.is_empty() {
-        return default_game_over_name(autopilot, profile);
+        return fallback;
     }
 
     trimmed
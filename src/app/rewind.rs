@@ -0,0 +1,133 @@
+use crate::simulation::SimulationState;
+use std::collections::VecDeque;
+
+/// Seconds of play kept for stepping and scrubbing back.
+pub const REWIND_SECONDS: usize = 10;
+const REWIND_FRAMES: usize = REWIND_SECONDS * 60;
+
+/// The last few seconds of a run, one saved state per frame, newest last. The simulation can be
+/// moved back and forth through them while paused; playing on from a rewound point drops the
+/// frames after it. Generic over the saved state so it can be tested without a simulation.
+pub(super) struct Rewind<S = SimulationState> {
+    states: VecDeque<S>,
+    /// How many frames back from the newest state the simulation has been moved.
+    behind: usize,
+}
+
+impl<S> Rewind<S> {
+    pub(super) fn new() -> Self {
+        Self {
+            states: VecDeque::with_capacity(REWIND_FRAMES),
+            behind: 0,
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.states.clear();
+        self.behind = 0;
+    }
+
+    /// Keeps the state after a frame that was just played.
+    pub(super) fn push(&mut self, state: S) {
+        self.cut();
+        if self.states.len() == REWIND_FRAMES {
+            self.states.pop_front();
+        }
+        self.states.push_back(state);
+    }
+
+    /// One frame further back, if there is one.
+    pub(super) fn back(&mut self) -> Option<&S> {
+        if self.behind + 1 >= self.states.len() {
+            return None;
+        }
+        self.behind += 1;
+        self.current()
+    }
+
+    /// One frame towards the newest, if the simulation has been moved back.
+    pub(super) fn forward(&mut self) -> Option<&S> {
+        if self.behind == 0 {
+            return None;
+        }
+        self.behind -= 1;
+        self.current()
+    }
+
+    /// Forgets the frames after the rewound point, so play continues from there.
+    pub(super) fn cut(&mut self) {
+        let kept = self.states.len() - self.behind;
+        self.states.truncate(kept);
+        self.behind = 0;
+    }
+
+    pub(super) fn behind(&self) -> usize {
+        self.behind
+    }
+
+    /// Frames that can be stepped back through from the newest.
+    pub(super) fn span(&self) -> usize {
+        self.states.len().saturating_sub(1)
+    }
+
+    fn current(&self) -> Option<&S> {
+        self.states.get(self.states.len() - 1 - self.behind)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(frames: usize) -> Rewind<usize> {
+        let mut rewind = Rewind::new();
+        for frame in 1..=frames {
+            rewind.push(frame);
+        }
+        rewind
+    }
+
+    #[test]
+    fn steps_stop_at_both_ends() {
+        let mut rewind = filled(3);
+        assert_eq!(rewind.span(), 2);
+        assert_eq!(rewind.forward(), None);
+        assert_eq!(rewind.back(), Some(&2));
+        assert_eq!(rewind.back(), Some(&1));
+        assert_eq!(rewind.back(), None);
+        assert_eq!(rewind.behind(), 2);
+        assert_eq!(rewind.forward(), Some(&2));
+        assert_eq!(rewind.forward(), Some(&3));
+        assert_eq!(rewind.forward(), None);
+        assert_eq!(rewind.behind(), 0);
+
+        let mut empty = Rewind::<usize>::new();
+        assert_eq!(empty.back(), None);
+        assert_eq!(empty.span(), 0);
+    }
+
+    #[test]
+    fn cut_drops_the_frames_after_the_rewound_point() {
+        let mut rewind = filled(5);
+        rewind.back();
+        rewind.back();
+        rewind.cut();
+        assert_eq!((rewind.behind(), rewind.span()), (0, 2));
+        assert_eq!(rewind.forward(), None);
+        assert_eq!(rewind.back(), Some(&2));
+
+        // Playing on from a rewound point cuts too.
+        rewind.push(9);
+        assert_eq!(rewind.span(), 2);
+        assert_eq!(rewind.back(), Some(&2));
+    }
+
+    #[test]
+    fn oldest_frames_fall_off_at_capacity() {
+        let mut rewind = filled(REWIND_FRAMES + 25);
+        assert_eq!(rewind.span(), REWIND_FRAMES - 1);
+        while rewind.back().is_some() {}
+        assert_eq!(rewind.forward(), Some(&27));
+        assert_eq!(rewind.behind(), REWIND_FRAMES - 2);
+    }
+}
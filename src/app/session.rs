@@ -1,38 +1,637 @@
 use crate::ai::AiController;
-use crate::config::{AiProfile, GameConfig};
-use crate::controllers::Controller;
+use crate::config::{self, AiProfile, AimMode, GameConfig, PlayerControllerMode};
+use crate::controllers::{ControlIntent, Controller};
 use crate::controllers::human::HumanController;
-use crate::eval::PerformanceGuard;
+use crate::eval::{HUD_WINDOW, PerformanceGuard, Profiler};
+use crate::keymap::Keymap;
+use crate::netplay::{Agreement, Closed, FrameEvents, NetStatus, Netplay, Tracked};
+use crate::render::viewport::Viewport;
+use crate::replay::{Replay, ReplayController};
+use crate::savegame::SavedRun;
 use crate::scoreboard::Leaderboard;
-use crate::simulation::Simulation;
+use crate::simulation::{SimEvent, Simulation, SimulationStatus};
+use crate::telemetry::{self, Detail, Record, RunTrace, Sample, TelemetryLog};
+use super::clock::{BULLET_TIME_TTC, GameClock};
+use super::rewind::Rewind;
+use macroquad::prelude::{
+    Vec2, get_fps, get_frame_time, screen_height, screen_width, set_camera, set_default_camera,
+    vec2,
+};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
 pub struct Session {
     simulation: Simulation,
+    /// The configuration the current run was started with; a continued run brings its own.
+    config: GameConfig,
+    /// In hot-seat, the waiting player's game, frozen until their turn comes round again.
+    benched: Option<Simulation>,
+    /// Which hot-seat player is flying `simulation`; always 0 outside hot-seat.
+    turn: usize,
     performance_guard: PerformanceGuard,
+    /// Recent step, draw and frame timings for the profiler panel; carries across runs.
+    profiler: Profiler,
     leaderboard: Leaderboard,
+    /// Seed for every run from `--seed`; otherwise each run draws a fresh one from the clock.
+    seed: Option<u64>,
+    /// Where `--record` writes the current run when it ends.
+    record_path: Option<PathBuf>,
+    recording: Option<Replay>,
+    /// The replay being watched, kept to restart its controllers after a rewind.
+    replaying: Option<Replay>,
+    /// Recent states for stepping back while paused; empty in netplay.
+    rewind: Rewind,
+    /// Game speed and bullet time; netplay always runs at normal speed.
+    clock: GameClock,
+    /// What the frames played since the app last asked produced, oldest first.
+    events: Vec<SimEvent>,
+    /// In netplay, the events of frames that a rollback may still re-simulate.
+    unconfirmed: FrameEvents,
+    keymap: Keymap,
+    /// Steering scheme and turn rate for human controllers, taken from the run's configuration.
+    aim: AimMode,
+    rotation_speed: f32,
+    /// Who flies the ships after player one's: the co-op partner or the versus rivals.
+    other_seats: Vec<PlayerControllerMode>,
+    /// The connection to the other peer in a netplay game; its seat is fed from there.
+    netplay: Option<Netplay>,
+    /// Where our state went when the peers desynced, or why it could not be written.
+    desync_note: Option<String>,
+    /// Where `--telemetry` logs runs; `None` when it was not asked for.
+    telemetry: Option<TelemetryLog>,
+    /// Each hot-seat player's run so far, in player order; empty for replays and without a log.
+    traces: Vec<RunTrace>,
+    /// The first failed write to the log since the last run was logged.
+    telemetry_error: Option<String>,
 }
 
 impl Session {
-    pub fn new(config: &GameConfig) -> Self {
+    pub fn new(
+        config: &GameConfig,
+        seed: Option<u64>,
+        record_path: Option<PathBuf>,
+        keymap: Keymap,
+        time_scale: f32,
+        telemetry: Option<TelemetryLog>,
+    ) -> Self {
+        let simulation = Simulation::new(config.clone(), seed.unwrap_or_default());
+        Self::around(simulation, config, seed, record_path, keymap, time_scale, telemetry)
+    }
+
+    /// A session starting out on `simulation`, which need not have a window behind it.
+    fn around(
+        simulation: Simulation,
+        config: &GameConfig,
+        seed: Option<u64>,
+        record_path: Option<PathBuf>,
+        keymap: Keymap,
+        time_scale: f32,
+        telemetry: Option<TelemetryLog>,
+    ) -> Self {
         Self {
-            simulation: Simulation::new(config.clone()),
-            performance_guard: PerformanceGuard::new(),
+            simulation,
+            config: config.clone(),
+            benched: None,
+            turn: 0,
+            performance_guard: PerformanceGuard::new(&config.guard, config.budgets.max_bodies),
+            profiler: Profiler::new(HUD_WINDOW),
             leaderboard: Leaderboard::load(),
+            seed,
+            record_path,
+            recording: None,
+            replaying: None,
+            rewind: Rewind::new(),
+            clock: GameClock::new(time_scale),
+            events: Vec::new(),
+            unconfirmed: FrameEvents::default(),
+            keymap,
+            aim: config.aim_mode,
+            rotation_speed: config.tuning.ship_rotation_speed,
+            other_seats: Vec::new(),
+            netplay: None,
+            desync_note: None,
+            telemetry,
+            traces: Vec::new(),
+            telemetry_error: None,
         }
     }
 
     pub fn reset(&mut self, config: &GameConfig, autopilot: bool, profile: AiProfile) {
-        self.simulation = Simulation::new(config.clone());
-        self.performance_guard = PerformanceGuard::new();
+        self.leave_netplay();
+        let seed = self.seed.unwrap_or_else(clock_seed);
+        // A replay holds a single game, so hot-seat runs are not recorded.
+        let window = (screen_width() as u32, screen_height() as u32);
+        self.recording = self
+            .record_path
+            .as_ref()
+            .filter(|_| config.players() == 1)
+            .map(|_| Replay::new(seed, window, config.clone()));
+        // A recorded run keeps the playfield it started on, the one its replay will be played
+        // and verified on.
+        self.simulation = match self.recording {
+            Some(_) => Simulation::with_world(config.clone(), seed, world_of(window)),
+            None => Simulation::new(config.clone(), seed),
+        };
+        self.config = config.clone();
+        // Both hot-seat players get the same seed, so neither draws an easier field.
+        self.benched = (config.players() > 1).then(|| Simulation::new(config.clone(), seed));
+        self.turn = 0;
+        self.performance_guard =
+            PerformanceGuard::new(&config.guard, config.budgets.max_bodies);
+        self.replaying = None;
+        self.rewind.clear();
+        self.clock.restart();
+        self.events.clear();
+        self.aim = config.aim_mode;
+        self.rotation_speed = config.tuning.ship_rotation_speed;
+        self.other_seats = config.seat_controllers()[1..].to_vec();
+        self.start_traces(Some(seed));
+        self.set_controller(autopilot, profile);
+        self.install_other_seats();
+    }
+
+    /// Starts a netplay game on the agreed seed and configuration. Only our own seat gets a
+    /// controller; the peer's input arrives over the network.
+    pub fn start_netplay(
+        &mut self,
+        netplay: Netplay,
+        agreement: &Agreement,
+        autopilot: bool,
+        profile: AiProfile,
+    ) {
+        let config = &agreement.config;
+        // Both peers play on the agreed playfield whatever their windows do, or they desync.
+        let world = world_of(agreement.window);
+        self.simulation = Simulation::with_world(config.clone(), agreement.seed, world);
+        self.config = config.clone();
+        self.benched = None;
+        self.turn = 0;
+        self.performance_guard =
+            PerformanceGuard::new(&config.guard, config.budgets.max_bodies);
+        self.recording = None;
+        self.replaying = None;
+        self.rewind.clear();
+        self.clock.restart();
+        self.events.clear();
+        self.unconfirmed.clear();
+        self.aim = config.aim_mode;
+        self.rotation_speed = config.tuning.ship_rotation_speed;
+        self.other_seats = Vec::new();
+        self.netplay = Some(netplay);
+        self.desync_note = None;
+        self.start_traces(Some(agreement.seed));
         self.set_controller(autopilot, profile);
     }
 
+    /// Runs the next rendered frame's worth of simulation: as many fixed frames as the game
+    /// speed calls for, possibly none. In netplay it is always one, after trading input with the
+    /// peer, and it may re-simulate earlier frames or wait instead.
+    pub fn advance(&mut self) {
+        self.profiler.record_frame(get_frame_time());
+        let Some(netplay) = &mut self.netplay else {
+            for _ in 0..self.clock.frames_due() {
+                self.play_frame();
+                // A lost ship may hand over to the other hot-seat player, so stop there.
+                let ship_lost = self.simulation.events().iter().any(SimEvent::ship_lost);
+                if ship_lost || self.simulation.status().game_over {
+                    self.clock.forfeit();
+                    break;
+                }
+            }
+            return;
+        };
+        let seat = netplay.local_seat();
+        let frame = self.simulation.status().frame;
+        let mut tracked = Tracked {
+            simulation: &mut self.simulation,
+            events: &mut self.unconfirmed,
+        };
+        netplay.tick(&mut tracked, |tracked| tracked.simulation.poll_seat(seat));
+        if self.simulation.status().frame > frame {
+            self.profiler.record_step(self.simulation.timings());
+        }
+        // Events are reported once their frame is confirmed, as the final re-simulation of it
+        // produced them; a mispredicted frame's are never seen.
+        let confirmed = self.unconfirmed.take_through(netplay.confirmed_frame());
+        if let (Some(desync), None) = (netplay.desync(), &self.desync_note) {
+            let path = PathBuf::from(format!("desync-frame{}-p{}.txt", desync.frame, seat + 1));
+            let header = format!(
+                "# frame {}: our checksum {:016x}, peer's {:016x}\n",
+                desync.frame, desync.local, desync.remote
+            );
+            self.desync_note = Some(match fs::write(&path, header + &desync.state.dump().to_string()) {
+                Ok(()) => format!("state written to {}", path.display()),
+                Err(err) => format!("could not write state: {err}"),
+            });
+        }
+        for (frame, events) in confirmed {
+            let from = self.events.len();
+            self.events.extend(events);
+            self.trace_frame(frame, from);
+        }
+    }
+
+    /// Plays one fixed frame outside netplay.
+    fn play_frame(&mut self) {
+        // The playfield follows the window, except where a replay needs the recorded one or a
+        // netplay peer the agreed one.
+        if self.recording.is_none() && self.replaying.is_none() && self.netplay.is_none() {
+            self.simulation
+                .set_world(vec2(screen_width(), screen_height()));
+        }
+        let intents = self.simulation.poll_controllers();
+        self.simulation.step();
+        let from = self.events.len();
+        self.events.extend_from_slice(self.simulation.events());
+        self.profiler.record_step(self.simulation.timings());
+        self.trace_frame(self.simulation.status().frame, from);
+        self.record(&intents);
+        self.rewind.push(self.simulation.save_state());
+        // Watching a replay slowly is not playing it slowly.
+        if self.replaying.is_none() {
+            if self.clock.scale() < 1.0 {
+                self.simulation.mark_assisted();
+            }
+            let close_call = self
+                .simulation
+                .time_to_impact()
+                .is_some_and(|seconds| seconds < BULLET_TIME_TTC);
+            if self.config.bullet_time && close_call {
+                self.clock.close_call();
+            }
+        }
+        self.clock.frame_played();
+    }
+
+    /// Hands over the events of the frames played since the last call.
+    pub fn drain_events(&mut self) -> Vec<SimEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// The speed the game runs at right now; always normal in netplay.
+    pub fn time_scale(&self) -> f32 {
+        if self.networked() { 1.0 } else { self.clock.scale() }
+    }
+
+    pub fn in_bullet_time(&self) -> bool {
+        !self.networked() && self.clock.in_bullet_time()
+    }
+
+    /// Steps the chosen game speed up or down; ignored in netplay, where the peer sets the pace
+    /// as much as we do.
+    pub fn change_time_scale(&mut self, faster: bool) {
+        if self.networked() {
+            return;
+        }
+        if faster {
+            self.clock.faster();
+        } else {
+            self.clock.slower();
+        }
+    }
+
+    pub fn networked(&self) -> bool {
+        self.netplay.is_some()
+    }
+
+    pub fn netplay_status(&self) -> Option<NetStatus> {
+        self.netplay.as_ref().map(Netplay::status)
+    }
+
+    pub fn desync_note(&self) -> Option<&str> {
+        self.desync_note.as_deref()
+    }
+
+    /// Whether what is on screen is final: always outside netplay, and in netplay once every
+    /// simulated frame used the peer's real input rather than a guess.
+    pub fn settled(&self) -> bool {
+        self.netplay.as_ref().is_none_or(Netplay::settled)
+    }
+
+    pub fn netplay_closed(&self) -> Option<Closed> {
+        self.netplay.as_ref().and_then(|netplay| netplay.status().closed)
+    }
+
+    /// Keeps answering the peer between frames we do not simulate (results screens), so a peer
+    /// still catching up is not left waiting for our last inputs.
+    pub fn keep_netplay_alive(&mut self) {
+        if let Some(netplay) = &mut self.netplay {
+            netplay.keep_alive();
+        }
+    }
+
+    /// Says goodbye to the peer, if there is one.
+    pub fn leave_netplay(&mut self) {
+        if let Some(mut netplay) = self.netplay.take() {
+            netplay.leave();
+        }
+    }
+
+    /// Starts a run that feeds the replay's intents through its own seed and configuration.
+    pub fn play_replay(&mut self, replay: Replay) {
+        self.leave_netplay();
+        let config = &replay.config;
+        let world = world_of(replay.window);
+        self.simulation = Simulation::with_world(config.clone(), replay.seed, world);
+        self.config = config.clone();
+        self.benched = None;
+        self.turn = 0;
+        self.performance_guard =
+            PerformanceGuard::new(&config.guard, config.budgets.max_bodies);
+        self.recording = None;
+        self.replaying = Some(replay);
+        self.rewind.clear();
+        self.clock.restart();
+        self.events.clear();
+        self.traces.clear();
+        self.install_replay();
+    }
+
+    pub fn replaying(&self) -> bool {
+        self.replaying.is_some()
+    }
+
+    /// Points every seat at the watched replay's intents from the simulation's current frame.
+    fn install_replay(&mut self) {
+        let Some(replay) = &self.replaying else {
+            return;
+        };
+        let frame = self.simulation.status().frame as usize;
+        for seat in 0..self.simulation.seats() {
+            let intents = replay.seat_intents(seat).split_off(frame.min(replay.frames.len()));
+            self.simulation
+                .set_controller(seat, Box::new(ReplayController::new(intents)));
+        }
+    }
+
+    /// Moves the paused simulation one frame back through the recent states. Returns false at
+    /// the oldest one kept.
+    pub fn step_back(&mut self) -> bool {
+        match self.rewind.back() {
+            Some(state) => {
+                self.simulation.restore_state(state);
+                // Going back can undo a lost life, so the run counts as assisted from here on,
+                // like one played slowed down; restoring keeps the flag.
+                if self.replaying.is_none() {
+                    self.simulation.mark_assisted();
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the paused simulation one frame forward: through the recent states after a
+    /// rewind, otherwise by playing a new frame.
+    pub fn step_forward(&mut self) {
+        match self.rewind.forward() {
+            Some(state) => self.simulation.restore_state(state),
+            None => self.play_frame(),
+        }
+    }
+
+    /// Frames the simulation has been moved back, and how many it could be.
+    pub fn rewind_position(&self) -> (usize, usize) {
+        (self.rewind.behind(), self.rewind.span())
+    }
+
+    /// Makes a rewound point the present before play resumes: later frames are dropped from
+    /// the recent states and the recording, and a watched replay carries on from there.
+    pub fn resume_from_rewind(&mut self) {
+        if self.rewind.behind() == 0 {
+            return;
+        }
+        self.rewind.cut();
+        let frame = self.simulation.status().frame as usize;
+        if let Some(recording) = &mut self.recording {
+            recording.truncate(frame);
+        }
+        if let Some(trace) = self.traces.get_mut(self.turn) {
+            trace.rewind_to(frame as f32 * self.simulation.status().frame_time);
+        }
+        self.install_replay();
+    }
+
+    /// Whether Save and quit can put this run aside. Not in netplay, where the peer plays on;
+    /// not for a replay, which can simply be watched again; and not in hot-seat, which has two
+    /// games in flight.
+    pub fn can_save(&self) -> bool {
+        !self.networked() && self.replaying.is_none() && self.benched.is_none()
+    }
+
+    /// Puts the run aside. It is not logged as finished; continuing it starts a new trace.
+    pub fn save_run(&mut self, path: &Path) -> io::Result<()> {
+        SavedRun::save(path, &self.config, &self.simulation)?;
+        self.traces.clear();
+        Ok(())
+    }
+
+    /// Picks a saved run up where it was left. It is not recorded: a replay has to start from
+    /// the seed.
+    pub fn resume(&mut self, saved: SavedRun, autopilot: bool, profile: AiProfile) {
+        self.leave_netplay();
+        let config = saved.config;
+        self.simulation = saved.simulation;
+        self.benched = None;
+        self.turn = 0;
+        self.performance_guard =
+            PerformanceGuard::new(&config.guard, config.budgets.max_bodies);
+        self.recording = None;
+        self.replaying = None;
+        self.rewind.clear();
+        self.clock.restart();
+        self.events.clear();
+        self.aim = config.aim_mode;
+        self.rotation_speed = config.tuning.ship_rotation_speed;
+        self.other_seats = config.seat_controllers()[1..].to_vec();
+        self.config = config;
+        // The seed it was started on is not kept in the save.
+        self.start_traces(None);
+        self.set_controller(autopilot, profile);
+        self.install_other_seats();
+    }
+
+    /// Players taking turns in this run: two in hot-seat, otherwise one.
+    pub fn players(&self) -> usize {
+        if self.benched.is_some() { 2 } else { 1 }
+    }
+
+    pub fn turn(&self) -> usize {
+        self.turn
+    }
+
+    /// Whether the other hot-seat player still has a game to go back to.
+    pub fn turn_waiting(&self) -> bool {
+        self.benched
+            .as_ref()
+            .is_some_and(|benched| !benched.status().game_over)
+    }
+
+    /// Whether the frames that produced `events` end the current hot-seat turn: a ship was lost
+    /// and the other player still has a game to go back to.
+    pub fn turn_ends(&self, events: &[SimEvent]) -> bool {
+        self.turn_waiting() && events.iter().any(SimEvent::ship_lost)
+    }
+
+    /// Benches the current game and brings back the other player's, flown with the current
+    /// autopilot setting.
+    pub fn pass_turn(&mut self, autopilot: bool, profile: AiProfile) {
+        let Some(benched) = &mut self.benched else {
+            return;
+        };
+        std::mem::swap(&mut self.simulation, benched);
+        self.turn = 1 - self.turn;
+        // Each player only rewinds through their own game.
+        self.rewind.clear();
+        self.clock.restart();
+        self.events.clear();
+        self.set_controller(autopilot, profile);
+    }
+
+    /// Each player's final status, in player order.
+    pub fn results(&self) -> Vec<SimulationStatus> {
+        let mut results = vec![self.simulation.status()];
+        if let Some(benched) = &self.benched {
+            results.insert(1 - self.turn, benched.status());
+        }
+        results
+    }
+
+    /// Keeps a step's intents and the state they led to.
+    fn record(&mut self, intents: &[ControlIntent]) {
+        if let Some(recording) = &mut self.recording {
+            recording.record_frame(intents, &self.simulation);
+        }
+    }
+
+    /// Writes the finished run if `--record` asked for it; returns where it went.
+    pub fn save_recording(&mut self) -> io::Result<Option<PathBuf>> {
+        let (Some(recording), Some(path)) = (self.recording.take(), &self.record_path) else {
+            return Ok(None);
+        };
+        if recording.frames.is_empty() {
+            return Ok(None);
+        }
+        recording.save(path)?;
+        Ok(Some(path.clone()))
+    }
+
+    /// Installs player one's controller, or in netplay our own seat's. Autopilot and AI profile
+    /// only ever apply to that seat.
     pub fn set_controller(&mut self, autopilot: bool, profile: AiProfile) {
+        let seat = self.netplay.as_ref().map_or(0, Netplay::local_seat);
+        if let Some(trace) = self.traces.get_mut(self.turn) {
+            let mode = match autopilot {
+                true => PlayerControllerMode::Ai { profile },
+                false => PlayerControllerMode::Human,
+            };
+            let started = self.simulation.status().frame > 0;
+            trace.flown_by(config::controller_name(&mode), started);
+        }
         if autopilot {
-            self.simulation.set_controller(Box::new(AiController::new(profile)));
+            self.simulation.set_controller(seat, Box::new(AiController::new(profile)));
         } else {
-            self.simulation.set_controller(Box::new(HumanController::default()));
+            // With a second human on the keyboard, player one gives up player two's keys.
+            let keymap = if self.other_seats.contains(&PlayerControllerMode::Human) {
+                self.keymap.without_partner_keys()
+            } else {
+                self.keymap.clone()
+            };
+            self.simulation.set_controller(
+                seat,
+                Box::new(HumanController::new(keymap, self.aim, self.rotation_speed)),
+            );
+        }
+    }
+
+    fn install_other_seats(&mut self) {
+        for (seat, mode) in self.other_seats.iter().enumerate().map(|(i, mode)| (i + 1, mode)) {
+            let controller: Box<dyn Controller> = match mode {
+                PlayerControllerMode::Human => Box::new(HumanController::partner(self.keymap.clone())),
+                PlayerControllerMode::Ai { profile } => Box::new(AiController::new(*profile)),
+            };
+            self.simulation.set_controller(seat, controller);
+        }
+    }
+
+    /// Starts tracing a run for the log, one trace per hot-seat player.
+    fn start_traces(&mut self, seed: Option<u64>) {
+        self.traces.clear();
+        if self.telemetry.is_some() {
+            let run = telemetry::run_id();
+            self.traces = (0..self.players())
+                .map(|player| RunTrace::new(run, player, seed))
+                .collect();
+        }
+    }
+
+    /// Feeds a frame played, whose events start at `from` in the pending events, to the current
+    /// player's trace, and writes a sample when one is due. Samples describe the field as it is
+    /// now, which in netplay may be a few predicted frames past `frame`.
+    fn trace_frame(&mut self, frame: u64, from: usize) {
+        let (Some(log), Some(trace)) = (&self.telemetry, self.traces.get_mut(self.turn)) else {
+            return;
+        };
+        let status = self.simulation.status();
+        let time = frame as f32 * status.frame_time;
+        for event in &self.events[from..] {
+            trace.observe(event, time);
+        }
+        if log.detail() == Detail::Samples && trace.sample_due(time) {
+            let seat = self.netplay.as_ref().map_or(0, Netplay::local_seat);
+            let sample = Sample {
+                run: trace.run,
+                player: trace.player,
+                time,
+                bodies: status.active_bodies,
+                asteroids: status.asteroid_count,
+                bullets: status.bullet_count,
+                enemies: status.enemy_count,
+                fps: get_fps() as f32,
+                ai_phase: self.simulation.controller_phase(seat).map(str::to_string),
+            };
+            if let Err(err) = log.append(&Record::Sample(sample)) {
+                self.telemetry_error.get_or_insert_with(|| err.to_string());
+            }
+        }
+    }
+
+    /// Logs each player's run that got under way, on `preset`, and forgets them. Returns why
+    /// the log could not be written, if it could not.
+    pub fn log_runs(&mut self, preset: &str) -> Option<String> {
+        let log = self.telemetry.as_ref()?;
+        let results = self.results();
+        for (trace, status) in std::mem::take(&mut self.traces).iter().zip(&results) {
+            if status.frame == 0 {
+                continue;
+            }
+            if let Err(err) = log.append(&Record::Run(trace.finish(preset, status))) {
+                self.telemetry_error.get_or_insert_with(|| err.to_string());
+            }
         }
+        self.telemetry_error
+            .take()
+            .map(|err| format!("Could not write telemetry to {}: {err}", log.dir().display()))
+    }
+
+    /// Takes effect the next time a human controller is installed.
+    pub fn set_keymap(&mut self, keymap: Keymap) {
+        self.keymap = keymap;
+    }
+
+    /// Draws the playfield, timing it for the profiler.
+    pub fn draw(&mut self) {
+        let start = Instant::now();
+        set_camera(&Viewport::current(self.simulation.world()).camera());
+        self.simulation.draw_debug();
+        set_default_camera();
+        self.profiler.record_draw(start.elapsed());
+    }
+
+    pub fn profiler(&self) -> &Profiler {
+        &self.profiler
     }
 
     pub fn simulation(&self) -> &Simulation {
@@ -43,10 +642,6 @@ impl Session {
         &mut self.simulation
     }
 
-    pub fn controller(&mut self) -> &mut dyn Controller {
-        self.simulation.controller()
-    }
-
     pub fn performance_guard(&mut self) -> &mut PerformanceGuard {
         &mut self.performance_guard
     }
@@ -59,3 +654,72 @@ impl Session {
         &mut self.leaderboard
     }
 }
+
+fn world_of(window: (u32, u32)) -> Vec2 {
+    vec2(window.0 as f32, window.1 as f32)
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::HitCause;
+
+    fn hot_seat() -> Session {
+        let config = GameConfig {
+            hot_seat: true,
+            ..GameConfig::default()
+        };
+        let game = || Simulation::with_world(config.clone(), 11, vec2(800.0, 600.0));
+        let mut session =
+            Session::around(game(), &config, Some(11), None, Keymap::default(), 1.0, None);
+        session.benched = Some(game());
+        session
+    }
+
+    fn ship_hit(shielded: bool) -> SimEvent {
+        SimEvent::ShipHit {
+            seat: 0,
+            position: Vec2::ZERO,
+            shielded,
+            cause: HitCause::Asteroid,
+        }
+    }
+
+    #[test]
+    fn passing_the_turn_swaps_the_two_games() {
+        let mut session = hot_seat();
+        for _ in 0..5 {
+            session.simulation_mut().step();
+        }
+
+        session.pass_turn(false, AiProfile::Balanced);
+        assert_eq!(session.turn(), 1);
+        assert_eq!(session.simulation().status().frame, 0, "player two got player one's game");
+        session.simulation_mut().step();
+        let frames: Vec<u64> = session.results().iter().map(|status| status.frame).collect();
+        assert_eq!(frames, [5, 1], "results not in player order");
+
+        session.pass_turn(false, AiProfile::Balanced);
+        assert_eq!(session.turn(), 0);
+        assert_eq!(session.simulation().status().frame, 5);
+    }
+
+    #[test]
+    fn turns_end_on_a_lost_ship_while_the_other_game_is_on() {
+        let mut session = hot_seat();
+        // A death and an extra life in the same frames leave the life count where it was.
+        assert!(session.turn_ends(&[ship_hit(false), SimEvent::ExtraLife { seat: 0 }]));
+        assert!(!session.turn_ends(&[ship_hit(true)]));
+        assert!(!session.turn_ends(&[]));
+
+        session.benched = None;
+        assert!(!session.turn_ends(&[ship_hit(false)]), "handed over with no one waiting");
+    }
+}
@@ -2,8 +2,14 @@
 pub enum AppState {
     MainMenu,
     Options,
+    AdvancedOptions,
+    Controls,
     Leaderboard,
     Playing,
     Paused,
+    /// Between hot-seat turns, waiting for the next player.
+    HotSeatReady,
+    /// A versus match has ended; shows the standings instead of taking a leaderboard name.
+    MatchOver,
     GameOver,
 }
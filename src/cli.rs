@@ -0,0 +1,346 @@
+use crate::app::{MAX_TIME_SCALE, MIN_TIME_SCALE};
+use crate::netplay::{self, NetOptions, Role};
+use crate::replay::parse_window;
+use crate::telemetry::Detail;
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Usage: asteroids [OPTIONS]
+
+Starting any of --preset, --seed, --controller, --replay, --host or --join skips the menus and
+begins a run.
+
+Options:
+  --preset NAME        Start with a built-in or saved preset (e.g. classic, \"Arcade Upgrades\")
+  --seed N             Seed the simulation for a reproducible run
+  --controller NAME    human, ai-casual, ai-balanced or ai-veteran
+  --window WxH         Window size, e.g. 1280x720
+  --replay FILE        Play back a recorded replay
+  --record FILE        Record the next run to FILE when it ends
+  --verify FILE        Re-run a replay without a window and report where it first diverges
+  --soak SECONDS       Run the AI through SECONDS of game time without a window and report
+  --time-scale SCALE   Game speed, 0.25-4 (default 1); a soak also takes `uncapped`
+  --telemetry MODE     Log each run as JSON Lines in the data directory: `runs`, or `samples`
+                       to add a record per second of play
+  --telemetry-report   Summarize the telemetry logged so far and exit
+  --config FILE        Read and save settings at FILE instead of the data directory
+  --host PORT          Wait for a netplay partner on UDP PORT, then play with them
+  --join ADDR          Join a netplay host, e.g. 192.168.1.20:7000
+  --input-delay N      Netplay input delay in frames, 0-10 (default 2; the host's is used)
+  --net-loss PCT       Drop PCT% of outgoing netplay packets, for testing
+  --net-latency MS     Hold outgoing netplay packets back MS milliseconds, for testing
+  --list-presets       Print the available presets and exit
+  --print-config       Print the resolved configuration in settings-file format and exit
+  -h, --help           Print this help and exit
+";
+
+/// Command-line flags. Values are kept raw here; `app::Launch` resolves them against the
+/// settings file and presets.
+#[derive(Default, Debug)]
+pub struct CliOptions {
+    pub preset: Option<String>,
+    pub seed: Option<u64>,
+    pub controller: Option<String>,
+    pub window: Option<(u32, u32)>,
+    pub replay: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub verify: Option<PathBuf>,
+    pub soak: Option<u32>,
+    /// `f32::INFINITY` for `uncapped`, which only a soak accepts.
+    pub time_scale: Option<f32>,
+    pub telemetry: Option<Detail>,
+    pub telemetry_report: bool,
+    pub config: Option<PathBuf>,
+    pub host: Option<u16>,
+    pub join: Option<String>,
+    pub input_delay: Option<usize>,
+    pub net_loss: Option<f32>,
+    pub net_latency: Option<u64>,
+    pub list_presets: bool,
+    pub print_config: bool,
+    pub help: bool,
+}
+
+impl CliOptions {
+    /// Parses arguments (without the program name). Accepts `--flag value` and `--flag=value`.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = CliOptions::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg, None),
+            };
+            let mut value = || {
+                inline
+                    .clone()
+                    .or_else(|| args.next())
+                    .ok_or_else(|| format!("{flag} needs a value"))
+            };
+            match flag.as_str() {
+                "--preset" => options.preset = Some(value()?),
+                "--seed" => {
+                    let raw = value()?;
+                    let seed = raw
+                        .parse()
+                        .map_err(|_| format!("--seed: `{raw}` is not a whole number"))?;
+                    options.seed = Some(seed);
+                }
+                "--controller" => options.controller = Some(value()?),
+                "--window" => {
+                    options.window =
+                        Some(parse_window(&value()?).map_err(|err| format!("--window: {err}"))?)
+                }
+                "--replay" => options.replay = Some(PathBuf::from(value()?)),
+                "--record" => options.record = Some(PathBuf::from(value()?)),
+                "--verify" => options.verify = Some(PathBuf::from(value()?)),
+                "--soak" => {
+                    let raw = value()?;
+                    let seconds = raw
+                        .parse()
+                        .ok()
+                        .filter(|seconds| *seconds > 0)
+                        .ok_or_else(|| format!("--soak: `{raw}` is not a number of seconds"))?;
+                    options.soak = Some(seconds);
+                }
+                "--time-scale" => {
+                    let raw = value()?;
+                    let scale = match raw.as_str() {
+                        "uncapped" => Some(f32::INFINITY),
+                        _ => raw
+                            .parse()
+                            .ok()
+                            .filter(|scale| (MIN_TIME_SCALE..=MAX_TIME_SCALE).contains(scale)),
+                    };
+                    options.time_scale = Some(scale.ok_or_else(|| {
+                        format!(
+                            "--time-scale: `{raw}` is not a speed from {MIN_TIME_SCALE} to {MAX_TIME_SCALE}"
+                        )
+                    })?);
+                }
+                "--telemetry" => {
+                    let raw = value()?;
+                    let detail = Detail::parse(&raw).ok_or_else(|| {
+                        format!("--telemetry: `{raw}` is not `runs` or `samples`")
+                    })?;
+                    options.telemetry = Some(detail);
+                }
+                "--config" => options.config = Some(PathBuf::from(value()?)),
+                "--host" => {
+                    let raw = value()?;
+                    let port = raw
+                        .parse()
+                        .map_err(|_| format!("--host: `{raw}` is not a port number"))?;
+                    options.host = Some(port);
+                }
+                "--join" => options.join = Some(value()?),
+                "--input-delay" => {
+                    let raw = value()?;
+                    let delay = raw
+                        .parse()
+                        .ok()
+                        .filter(|delay| *delay <= netplay::MAX_INPUT_DELAY)
+                        .ok_or_else(|| {
+                            format!(
+                                "--input-delay: `{raw}` is not a frame count from 0 to {}",
+                                netplay::MAX_INPUT_DELAY
+                            )
+                        })?;
+                    options.input_delay = Some(delay);
+                }
+                "--net-loss" => {
+                    let raw = value()?;
+                    let loss = raw
+                        .parse()
+                        .ok()
+                        .filter(|loss| (0.0..=100.0).contains(loss))
+                        .ok_or_else(|| format!("--net-loss: `{raw}` is not a percentage"))?;
+                    options.net_loss = Some(loss);
+                }
+                "--net-latency" => {
+                    let raw = value()?;
+                    let latency = raw
+                        .parse()
+                        .map_err(|_| format!("--net-latency: `{raw}` is not a whole number"))?;
+                    options.net_latency = Some(latency);
+                }
+                "--list-presets" => options.list_presets = true,
+                "--print-config" => options.print_config = true,
+                "--telemetry-report" => options.telemetry_report = true,
+                "-h" | "--help" => options.help = true,
+                _ => return Err(format!("unknown option `{flag}`")),
+            }
+        }
+        if options.replay.is_some() && (options.preset.is_some() || options.controller.is_some()) {
+            return Err("--replay uses the recorded configuration; drop --preset/--controller"
+                .to_string());
+        }
+        if options.verify.is_some() && (options.replay.is_some() || options.record.is_some()) {
+            return Err("--verify runs on its own; drop --replay/--record".to_string());
+        }
+        let networked = options.host.is_some() || options.join.is_some();
+        if options.soak.is_some()
+            && (options.replay.is_some()
+                || options.record.is_some()
+                || options.verify.is_some()
+                || networked)
+        {
+            return Err("--soak runs on its own; drop --replay/--record/--verify/--host/--join"
+                .to_string());
+        }
+        if options.time_scale == Some(f32::INFINITY) && options.soak.is_none() {
+            return Err("--time-scale uncapped only applies to --soak".to_string());
+        }
+        if options.time_scale.is_some() && networked {
+            return Err("netplay always runs at normal speed; drop --time-scale".to_string());
+        }
+        if options.host.is_some() && options.join.is_some() {
+            return Err("--host and --join are alternatives; pick one".to_string());
+        }
+        if networked && (options.replay.is_some() || options.record.is_some()) {
+            return Err("netplay games are not recorded or replayed; drop --replay/--record"
+                .to_string());
+        }
+        let tuned = options.input_delay.is_some()
+            || options.net_loss.is_some()
+            || options.net_latency.is_some();
+        if tuned && !networked {
+            return Err("--input-delay and --net-* only apply with --host or --join".to_string());
+        }
+        Ok(options)
+    }
+
+    /// The netplay settings, if `--host` or `--join` was given.
+    pub fn netplay(&self) -> Option<NetOptions> {
+        let role = match (self.host, &self.join) {
+            (Some(port), _) => Role::Host { port },
+            (None, Some(address)) => Role::Join {
+                address: address.clone(),
+            },
+            (None, None) => return None,
+        };
+        Some(NetOptions {
+            role,
+            input_delay: self.input_delay.unwrap_or(netplay::DEFAULT_INPUT_DELAY),
+            loss: self.net_loss.unwrap_or_default() / 100.0,
+            latency: Duration::from_millis(self.net_latency.unwrap_or_default()),
+        })
+    }
+
+    /// Whether the flags ask to skip the menus and start playing immediately.
+    pub fn starts_run(&self) -> bool {
+        self.preset.is_some()
+            || self.seed.is_some()
+            || self.controller.is_some()
+            || self.replay.is_some()
+            || self.netplay().is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CliOptions, String> {
+        CliOptions::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    #[test]
+    fn flags_accept_separate_and_inline_values() {
+        let options = parse(&[
+            "--preset",
+            "Arcade Upgrades",
+            "--seed=7",
+            "--controller",
+            "ai-veteran",
+            "--window=800x600",
+            "--print-config",
+        ])
+        .unwrap();
+        assert_eq!(options.preset.as_deref(), Some("Arcade Upgrades"));
+        assert_eq!(options.seed, Some(7));
+        assert_eq!(options.controller.as_deref(), Some("ai-veteran"));
+        assert_eq!(options.window, Some((800, 600)));
+        assert!(options.print_config);
+        assert!(options.starts_run());
+        assert!(!parse(&["--window", "640x480"]).unwrap().starts_run());
+    }
+
+    #[test]
+    fn bad_flags_are_reported() {
+        assert!(parse(&["--warp"]).is_err());
+        assert!(parse(&["--seed"]).is_err());
+        assert!(parse(&["--seed", "soon"]).is_err());
+        assert!(parse(&["--window", "big"]).is_err());
+        assert!(parse(&["--replay", "run.txt", "--preset", "classic"]).is_err());
+        assert!(parse(&["--verify", "run.txt", "--replay", "run.txt"]).is_err());
+    }
+
+    #[test]
+    fn verify_takes_a_replay_file() {
+        let options = parse(&["--verify=runs/long.txt"]).unwrap();
+        assert_eq!(options.verify, Some(PathBuf::from("runs/long.txt")));
+        assert!(!options.starts_run());
+        assert!(parse(&["--verify"]).is_err());
+    }
+
+    #[test]
+    fn soaks_and_time_scales() {
+        let options = parse(&["--soak", "600", "--time-scale=uncapped", "--preset", "horde"]).unwrap();
+        assert_eq!(options.soak, Some(600));
+        assert_eq!(options.time_scale, Some(f32::INFINITY));
+        assert_eq!(parse(&["--time-scale", "0.5"]).unwrap().time_scale, Some(0.5));
+
+        assert!(parse(&["--time-scale", "uncapped"]).is_err());
+        assert!(parse(&["--time-scale", "8"]).is_err());
+        assert!(parse(&["--time-scale", "fast"]).is_err());
+        assert!(parse(&["--soak", "0"]).is_err());
+        assert!(parse(&["--soak", "60", "--record", "run.txt"]).is_err());
+        assert!(parse(&["--host", "7000", "--time-scale", "2"]).is_err());
+    }
+
+    #[test]
+    fn telemetry_takes_a_detail_level() {
+        assert_eq!(parse(&[]).unwrap().telemetry, None);
+        assert_eq!(parse(&["--telemetry", "runs"]).unwrap().telemetry, Some(Detail::Runs));
+        let options = parse(&["--telemetry=samples", "--soak", "60"]).unwrap();
+        assert_eq!(options.telemetry, Some(Detail::Samples));
+        assert!(!parse(&["--telemetry=runs"]).unwrap().starts_run());
+        assert!(parse(&["--telemetry", "everything"]).is_err());
+        assert!(parse(&["--telemetry"]).is_err());
+        assert!(parse(&["--telemetry-report"]).unwrap().telemetry_report);
+    }
+
+    #[test]
+    fn netplay_flags_build_net_options() {
+        let options = parse(&["--join=localhost:7000", "--input-delay", "4", "--net-loss=12.5"])
+            .unwrap();
+        let net = options.netplay().unwrap();
+        assert_eq!(
+            net.role,
+            Role::Join {
+                address: "localhost:7000".to_string()
+            }
+        );
+        assert_eq!(net.input_delay, 4);
+        assert_eq!(net.loss, 0.125);
+        assert_eq!(net.latency, Duration::ZERO);
+        assert!(options.starts_run());
+
+        let host = parse(&["--host", "7000", "--net-latency", "80"]).unwrap().netplay().unwrap();
+        assert_eq!(host.role, Role::Host { port: 7000 });
+        assert_eq!(host.input_delay, netplay::DEFAULT_INPUT_DELAY);
+        assert_eq!(host.latency, Duration::from_millis(80));
+        assert!(parse(&["--seed", "3"]).unwrap().netplay().is_none());
+
+        assert!(parse(&["--host", "7000", "--join", "localhost:7000"]).is_err());
+        assert!(parse(&["--host", "70000"]).is_err());
+        assert!(parse(&["--host", "7000", "--input-delay", "11"]).is_err());
+        assert!(parse(&["--host", "7000", "--net-loss", "150"]).is_err());
+        assert!(parse(&["--host", "7000", "--record", "run.txt"]).is_err());
+        assert!(parse(&["--input-delay", "3"]).is_err());
+    }
+}
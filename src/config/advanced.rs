@@ -0,0 +1,322 @@
+use super::tuning::{SimTuning, TuningError};
+use super::{CollisionPolicy, FragmentationMode, GameConfig};
+
+/// Above this many bodies, `CollisionPolicy::Full` pair checks start to cost whole frames.
+const FULL_COLLISION_BODY_LIMIT: usize = 1000;
+/// Fragment cap above which explosive fragmentation fills the body budget within seconds.
+const EXPLOSIVE_FRAGMENT_LIMIT: usize = 12;
+/// Debris lifetime above which high body caps stay saturated.
+const LONG_DEBRIS_TTL_MS: u64 = 1500;
+const HIGH_BODY_CAP: usize = 1500;
+/// `BigOnly` radii below this make almost every body "big".
+const SMALL_BIG_RADIUS: f32 = 16.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldGroup {
+    Budgets,
+    Guard,
+    Tuning,
+}
+
+impl FieldGroup {
+    /// Prefix of the field's key in the settings file.
+    pub fn prefix(self) -> &'static str {
+        match self {
+            FieldGroup::Budgets => "budgets",
+            FieldGroup::Guard => "guard",
+            FieldGroup::Tuning => "tuning",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Access {
+    Config {
+        get: fn(&GameConfig) -> f64,
+        set: fn(&mut GameConfig, f64),
+    },
+    Tuning,
+}
+
+/// One numeric stepper on the Advanced panel, with the range it accepts.
+#[derive(Clone, Copy)]
+pub struct AdvancedField {
+    pub group: FieldGroup,
+    pub name: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub integer: bool,
+    access: Access,
+}
+
+impl AdvancedField {
+    fn config(
+        group: FieldGroup,
+        name: &'static str,
+        (min, max, integer): (f64, f64, bool),
+        get: fn(&GameConfig) -> f64,
+        set: fn(&mut GameConfig, f64),
+    ) -> Self {
+        Self {
+            group,
+            name,
+            min,
+            max,
+            integer,
+            access: Access::Config { get, set },
+        }
+    }
+
+    /// The key this field uses in the settings file, e.g. `budgets.max_bodies`.
+    pub fn key(&self) -> String {
+        format!("{}.{}", self.group.prefix(), self.name)
+    }
+
+    pub fn value(&self, config: &GameConfig) -> f64 {
+        match self.access {
+            Access::Config { get, .. } => get(config),
+            Access::Tuning => config.tuning.get(self.name).unwrap_or_default(),
+        }
+    }
+
+    /// Sets the field, rejecting values outside its range or fractions on whole-number fields.
+    pub fn set(&self, config: &mut GameConfig, value: f64) -> Result<(), String> {
+        match self.access {
+            Access::Config { set, .. } => {
+                if !(value >= self.min && value <= self.max) {
+                    return Err(format!(
+                        "{} = {value} is outside {}..={}",
+                        self.key(),
+                        self.min,
+                        self.max
+                    ));
+                }
+                if self.integer && value.fract() != 0.0 {
+                    return Err(format!("{} must be a whole number", self.key()));
+                }
+                set(config, value);
+            }
+            Access::Tuning => {
+                let mut tuning = config.tuning.clone();
+                tuning
+                    .set(self.name, &value.to_string())
+                    .and_then(|()| tuning.check_consistency())
+                    .map_err(|kind| TuningError { line: 0, kind }.to_string())?;
+                config.tuning = tuning;
+            }
+        }
+        Ok(())
+    }
+
+    /// Moves the value by `steps` increments of roughly a tenth of its magnitude, clamped to
+    /// the field's range.
+    pub fn step(&self, config: &mut GameConfig, steps: i32) -> Result<(), String> {
+        let value = self.value(config);
+        let increment = self.increment(value);
+        let next = ((value / increment).round() + steps as f64) * increment;
+        let next = next.clamp(self.min, self.max);
+        self.set(config, if self.integer { next.round() } else { next })
+    }
+
+    fn increment(&self, value: f64) -> f64 {
+        let magnitude = if value != 0.0 {
+            value.abs()
+        } else {
+            (self.max.abs() / 10.0).max(f64::EPSILON)
+        };
+        let increment = 10f64.powf(magnitude.log10().floor() - 1.0);
+        if self.integer {
+            increment.round().max(1.0)
+        } else {
+            increment
+        }
+    }
+}
+
+/// Every field on the Advanced panel: budgets, then guard thresholds, then tuning values.
+pub fn fields() -> Vec<AdvancedField> {
+    use FieldGroup::{Budgets, Guard};
+
+    let mut fields = vec![
+        AdvancedField::config(
+            Budgets,
+            "max_bodies",
+            (100.0, 5000.0, true),
+            |c| c.budgets.max_bodies as f64,
+            |c, v| c.budgets.max_bodies = v as usize,
+        ),
+        AdvancedField::config(
+            Budgets,
+            "frag_event_cap",
+            (0.0, 32.0, true),
+            |c| c.budgets.frag_event_cap as f64,
+            |c, v| c.budgets.frag_event_cap = v as usize,
+        ),
+        AdvancedField::config(
+            Budgets,
+            "debris_ttl_ms",
+            (0.0, 5000.0, true),
+            |c| c.budgets.debris_ttl_ms as f64,
+            |c, v| c.budgets.debris_ttl_ms = v as u64,
+        ),
+        AdvancedField::config(
+            Budgets,
+            "big_collision_radius",
+            (4.0, 200.0, false),
+            |c| c.budgets.big_collision_radius as f64,
+            |c, v| c.budgets.big_collision_radius = v as f32,
+        ),
+        AdvancedField::config(
+            Budgets,
+            "v_max",
+            (3.0, 64.0, true),
+            |c| c.budgets.v_max as f64,
+            |c, v| c.budgets.v_max = v as usize,
+        ),
+        AdvancedField::config(
+            Budgets,
+            "enemy_cap",
+            (0.0, 200.0, true),
+            |c| c.budgets.enemy_cap as f64,
+            |c, v| c.budgets.enemy_cap = v as usize,
+        ),
+        AdvancedField::config(
+            Guard,
+            "degrade_fps",
+            (0.0, 240.0, false),
+            |c| c.guard.degrade_fps as f64,
+            |c, v| c.guard.degrade_fps = v as f32,
+        ),
+        AdvancedField::config(
+            Guard,
+            "body_ratio",
+            (0.1, 1.0, false),
+            |c| c.guard.body_ratio as f64,
+            |c, v| c.guard.body_ratio = v as f32,
+        ),
+    ];
+    fields.extend(
+        SimTuning::RANGES
+            .iter()
+            .map(|&(name, min, max, integer)| AdvancedField {
+                group: FieldGroup::Tuning,
+                name,
+                min,
+                max,
+                integer,
+                access: Access::Tuning,
+            }),
+    );
+    fields
+}
+
+/// Looks a field up by its settings-file key.
+pub fn find(key: &str) -> Option<AdvancedField> {
+    fields().into_iter().find(|field| field.key() == key)
+}
+
+/// Combinations likely to cost frames, worded for the Advanced panel.
+pub fn performance_warnings(config: &GameConfig) -> Vec<String> {
+    let budgets = &config.budgets;
+    let mut warnings = Vec::new();
+    if config.collision_policy == CollisionPolicy::Full
+        && budgets.max_bodies > FULL_COLLISION_BODY_LIMIT
+    {
+        warnings.push(format!(
+            "Full collisions with over {FULL_COLLISION_BODY_LIMIT} bodies: pair checks grow quadratically"
+        ));
+    }
+    if matches!(
+        config.fragmentation_mode,
+        FragmentationMode::Explode | FragmentationMode::Full
+    ) && budgets.frag_event_cap > EXPLOSIVE_FRAGMENT_LIMIT
+    {
+        warnings.push(format!(
+            "Explosive fragmentation with more than {EXPLOSIVE_FRAGMENT_LIMIT} fragments per event"
+        ));
+    }
+    if budgets.debris_ttl_ms > LONG_DEBRIS_TTL_MS && budgets.max_bodies > HIGH_BODY_CAP {
+        warnings.push("Long-lived debris keeps a high body cap saturated".to_string());
+    }
+    if config.collision_policy == CollisionPolicy::BigOnly
+        && budgets.big_collision_radius < SMALL_BIG_RADIUS
+    {
+        warnings.push("BigOnly with a small radius checks nearly every body".to_string());
+    }
+    if config.guard.degrade_fps <= 0.0 && config.guard.body_ratio >= 1.0 {
+        warnings.push("Performance guard is effectively disabled".to_string());
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::presets;
+
+    #[test]
+    fn every_field_reads_back_what_it_sets() {
+        for field in fields() {
+            let mut config = GameConfig::default();
+            let target = if field.key() == "tuning.asteroid_min_speed" {
+                field.min
+            } else {
+                field.max
+            };
+            field.set(&mut config, target).unwrap();
+            assert!(
+                (field.value(&config) - target).abs() <= target.abs() * 1e-6,
+                "{} did not round-trip",
+                field.key()
+            );
+            assert_eq!(find(&field.key()).map(|found| found.key()), Some(field.key()));
+        }
+    }
+
+    #[test]
+    fn steps_stay_in_range_and_whole() {
+        let mut config = GameConfig::default();
+        let bodies = find("budgets.max_bodies").unwrap();
+        bodies.step(&mut config, 1).unwrap();
+        assert_eq!(config.budgets.max_bodies, 810);
+        bodies.step(&mut config, 10_000).unwrap();
+        assert_eq!(config.budgets.max_bodies, 5000);
+
+        let drag = find("tuning.ship_drag").unwrap();
+        drag.step(&mut config, -1).unwrap();
+        assert!((config.tuning.ship_drag - 0.24).abs() < 1e-6);
+        drag.step(&mut config, -10_000).unwrap();
+        assert_eq!(config.tuning.ship_drag, 0.0);
+    }
+
+    #[test]
+    fn out_of_range_values_are_rejected() {
+        let mut config = GameConfig::default();
+        let ratio = find("guard.body_ratio").unwrap();
+        assert!(ratio.set(&mut config, 1.5).is_err());
+        assert!(ratio.set(&mut config, f64::NAN).is_err());
+        assert!(find("budgets.frag_event_cap").unwrap().set(&mut config, 2.5).is_err());
+        let count = find("tuning.secondary_count").unwrap();
+        assert!(count.set(&mut config, 2.5).is_err());
+        assert!(find("tuning.ship_drag").unwrap().set(&mut config, f64::NAN).is_err());
+        let min_speed = find("tuning.asteroid_min_speed").unwrap();
+        assert!(min_speed.set(&mut config, 500.0).is_err());
+        assert_eq!(config.tuning, SimTuning::classic());
+    }
+
+    #[test]
+    fn full_collision_with_high_body_cap_warns() {
+        let mut config = GameConfig::default();
+        assert!(performance_warnings(&config).is_empty());
+        config.collision_policy = CollisionPolicy::Full;
+        config.budgets.max_bodies = 2000;
+        assert_eq!(performance_warnings(&config).len(), 1);
+        for preset in presets::default_presets() {
+            assert!(
+                performance_warnings(&preset.config).is_empty(),
+                "{} trips its own warnings",
+                preset.name
+            );
+        }
+    }
+}
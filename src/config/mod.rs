@@ -1,48 +1,268 @@
+pub mod advanced;
+mod settings;
+mod tuning;
+
+pub use settings::{Settings, apply_setting, controller_name, data_file, write_config};
+pub use tuning::SimTuning;
+
 pub mod presets {
     use super::{
-        AiProfile, Budgets, CollisionPolicy, FragmentationMode, GameConfig, LeaderboardMode,
-        PhysicsMode, PlayerControllerMode,
+        AiProfile, AimMode, AlienBehaviour, ArchetypeWeights, Budgets, CollisionPolicy, CoopSettings,
+        DropTable,
+        FragmentationMode, GameConfig, GuardThresholds, HazardSettings, LeaderboardMode,
+        PerformanceWarning, PhysicsMode, PlayerControllerMode, Preset, SimTuning, VersusSettings,
     };
 
-    pub fn default_presets() -> Vec<GameConfig> {
-        vec![classic(), arcade_upgrades(), ai_autopilot()]
+    /// Built-in presets in the order the Options screen cycles through them.
+    pub fn default_presets() -> Vec<Preset> {
+        vec![
+            classic(),
+            arcade_upgrades(),
+            ai_autopilot(),
+            fracture(),
+            horde(),
+            simulation(),
+        ]
     }
 
-    fn classic() -> GameConfig {
-        GameConfig {
-            player_controller: PlayerControllerMode::Human,
-            leaderboard_mode: LeaderboardMode::LocalTop10,
-            budgets: Budgets::classic(),
-            physics_mode: PhysicsMode::Arcade,
-            fragmentation_mode: FragmentationMode::ClassicSplit,
-            upgrades_enabled: false,
-            collision_policy: CollisionPolicy::PlayerOnly,
+    fn classic() -> Preset {
+        Preset::builtin(
+            "Classic",
+            "Faithful baseline: plain rocks, no upgrades.",
+            PerformanceWarning::None,
+            GameConfig {
+                player_controller: PlayerControllerMode::Human,
+                aim_mode: AimMode::Keyboard,
+                bullet_time: false,
+                coop: CoopSettings::off(),
+                hot_seat: false,
+                versus: VersusSettings::off(),
+                leaderboard_mode: LeaderboardMode::LocalTop10,
+                budgets: Budgets::classic(),
+                guard: GuardThresholds::default(),
+                physics_mode: PhysicsMode::Arcade,
+                fragmentation_mode: FragmentationMode::ClassicSplit,
+                upgrades_enabled: false,
+                enemies_enabled: false,
+                sentinels_enabled: false,
+                collision_policy: CollisionPolicy::PlayerOnly,
+                alien_behaviour: AlienBehaviour::Classic,
+                asteroid_weights: ArchetypeWeights::rocks_only(),
+                drop_table: DropTable::none(),
+                hazards: HazardSettings::off(),
+                tuning: SimTuning::classic(),
+            },
+        )
+    }
+
+    fn arcade_upgrades() -> Preset {
+        Preset::builtin(
+            "Arcade Upgrades",
+            "Baseline plus builds: pickups and mixed asteroid archetypes.",
+            PerformanceWarning::None,
+            GameConfig {
+                player_controller: PlayerControllerMode::Human,
+                aim_mode: AimMode::Keyboard,
+                bullet_time: false,
+                coop: CoopSettings::off(),
+                hot_seat: false,
+                versus: VersusSettings::off(),
+                leaderboard_mode: LeaderboardMode::LocalTop10,
+                budgets: Budgets::arcade(),
+                guard: GuardThresholds::default(),
+                physics_mode: PhysicsMode::Arcade,
+                fragmentation_mode: FragmentationMode::ClassicSplit,
+                upgrades_enabled: true,
+                enemies_enabled: false,
+                sentinels_enabled: false,
+                collision_policy: CollisionPolicy::PlayerOnly,
+                alien_behaviour: AlienBehaviour::Classic,
+                asteroid_weights: ArchetypeWeights::mixed(),
+                drop_table: DropTable::arcade(),
+                hazards: HazardSettings::off(),
+                tuning: SimTuning::classic(),
+            },
+        )
+    }
+
+    fn ai_autopilot() -> Preset {
+        Preset::builtin(
+            "AI Autopilot",
+            "Classic rules flown by the Balanced AI, for evaluation runs.",
+            PerformanceWarning::None,
+            GameConfig {
+                player_controller: PlayerControllerMode::Ai {
+                    profile: AiProfile::Balanced,
+                },
+                aim_mode: AimMode::Keyboard,
+                bullet_time: false,
+                coop: CoopSettings::off(),
+                hot_seat: false,
+                versus: VersusSettings::off(),
+                leaderboard_mode: LeaderboardMode::LocalTop10,
+                budgets: Budgets::classic(),
+                guard: GuardThresholds::default(),
+                physics_mode: PhysicsMode::Arcade,
+                fragmentation_mode: FragmentationMode::ClassicSplit,
+                upgrades_enabled: false,
+                enemies_enabled: false,
+                sentinels_enabled: false,
+                collision_policy: CollisionPolicy::PlayerOnly,
+                alien_behaviour: AlienBehaviour::Classic,
+                asteroid_weights: ArchetypeWeights::rocks_only(),
+                drop_table: DropTable::none(),
+                hazards: HazardSettings::off(),
+                tuning: SimTuning::classic(),
+            },
+        )
+    }
+
+    fn fracture() -> Preset {
+        Preset::builtin(
+            "Fracture",
+            "Crowded fields: mixed archetypes and pickups under a raised body budget.",
+            PerformanceWarning::Elevated,
+            GameConfig {
+                player_controller: PlayerControllerMode::Human,
+                aim_mode: AimMode::Keyboard,
+                bullet_time: false,
+                coop: CoopSettings::off(),
+                hot_seat: false,
+                versus: VersusSettings::off(),
+                leaderboard_mode: LeaderboardMode::LocalTop10,
+                budgets: Budgets::fracture(),
+                guard: GuardThresholds::default(),
+                physics_mode: PhysicsMode::Lite,
+                fragmentation_mode: FragmentationMode::SliceOnly,
+                upgrades_enabled: true,
+                enemies_enabled: false,
+                sentinels_enabled: false,
+                collision_policy: CollisionPolicy::PlayerOnly,
+                alien_behaviour: AlienBehaviour::Classic,
+                asteroid_weights: ArchetypeWeights::mixed(),
+                drop_table: DropTable::arcade(),
+                hazards: HazardSettings::off(),
+                tuning: SimTuning::classic(),
+            },
+        )
+    }
+
+    fn horde() -> Preset {
+        Preset::builtin(
+            "Horde",
+            "Dense targets and enemy swarms; only big bodies collide with each other.",
+            PerformanceWarning::Elevated,
+            GameConfig {
+                player_controller: PlayerControllerMode::Human,
+                aim_mode: AimMode::Keyboard,
+                bullet_time: false,
+                coop: CoopSettings::off(),
+                hot_seat: false,
+                versus: VersusSettings::off(),
+                leaderboard_mode: LeaderboardMode::LocalTop10,
+                budgets: Budgets::horde(),
+                guard: GuardThresholds::default(),
+                physics_mode: PhysicsMode::Lite,
+                fragmentation_mode: FragmentationMode::Explode,
+                upgrades_enabled: true,
+                enemies_enabled: true,
+                sentinels_enabled: true,
+                collision_policy: CollisionPolicy::BigOnly,
+                alien_behaviour: AlienBehaviour::Hunter,
+                asteroid_weights: ArchetypeWeights::mixed(),
+                drop_table: DropTable::arcade(),
+                hazards: HazardSettings::off(),
+                tuning: SimTuning {
+                    asteroid_spawn_interval: 1.2,
+                    ..SimTuning::classic()
+                },
+            },
+        )
+    }
+
+    fn simulation() -> Preset {
+        Preset::builtin(
+            "Simulation",
+            "More physical: full fragmentation, full collisions and environmental hazards.",
+            PerformanceWarning::High,
+            GameConfig {
+                player_controller: PlayerControllerMode::Human,
+                aim_mode: AimMode::Keyboard,
+                bullet_time: false,
+                coop: CoopSettings::off(),
+                hot_seat: false,
+                versus: VersusSettings::off(),
+                leaderboard_mode: LeaderboardMode::LocalTop10,
+                budgets: Budgets::simulation(),
+                guard: GuardThresholds::default(),
+                physics_mode: PhysicsMode::Lite,
+                fragmentation_mode: FragmentationMode::Full,
+                upgrades_enabled: true,
+                enemies_enabled: true,
+                sentinels_enabled: false,
+                collision_policy: CollisionPolicy::Full,
+                alien_behaviour: AlienBehaviour::Classic,
+                asteroid_weights: ArchetypeWeights::mixed(),
+                drop_table: DropTable::arcade(),
+                hazards: HazardSettings::all(),
+                tuning: SimTuning::classic(),
+            },
+        )
+    }
+}
+
+/// How much a preset is expected to stress the frame budget; shown next to its name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PerformanceWarning {
+    None,
+    Elevated,
+    High,
+}
+
+impl PerformanceWarning {
+    pub fn label(self) -> &'static str {
+        match self {
+            PerformanceWarning::None => "normal",
+            PerformanceWarning::Elevated => "elevated",
+            PerformanceWarning::High => "high",
         }
     }
+}
 
-    fn arcade_upgrades() -> GameConfig {
-        GameConfig {
-            player_controller: PlayerControllerMode::Human,
-            leaderboard_mode: LeaderboardMode::LocalTop10,
-            budgets: Budgets::arcade(),
-            physics_mode: PhysicsMode::Arcade,
-            fragmentation_mode: FragmentationMode::ClassicSplit,
-            upgrades_enabled: true,
-            collision_policy: CollisionPolicy::PlayerOnly,
+/// A named, self-describing configuration the Options screen can switch to in one step.
+#[derive(Clone)]
+pub struct Preset {
+    pub name: String,
+    pub description: String,
+    pub warning: PerformanceWarning,
+    pub builtin: bool,
+    pub config: GameConfig,
+}
+
+impl Preset {
+    fn builtin(
+        name: &str,
+        description: &str,
+        warning: PerformanceWarning,
+        config: GameConfig,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            warning,
+            builtin: true,
+            config,
         }
     }
 
-    fn ai_autopilot() -> GameConfig {
-        GameConfig {
-            player_controller: PlayerControllerMode::Ai {
-                profile: AiProfile::Balanced,
-            },
-            leaderboard_mode: LeaderboardMode::LocalTop10,
-            budgets: Budgets::classic(),
-            physics_mode: PhysicsMode::Arcade,
-            fragmentation_mode: FragmentationMode::ClassicSplit,
-            upgrades_enabled: false,
-            collision_policy: CollisionPolicy::PlayerOnly,
+    /// A preset the player saved; user presets carry no warning of their own.
+    pub fn user(name: String, description: String, config: GameConfig) -> Self {
+        Self {
+            name,
+            description,
+            warning: PerformanceWarning::None,
+            builtin: false,
+            config,
         }
     }
 }
@@ -50,12 +270,28 @@ pub mod presets {
 #[derive(Clone)]
 pub struct GameConfig {
     pub player_controller: PlayerControllerMode,
+    pub aim_mode: AimMode,
+    /// Accessibility assist: the game slows briefly when a rock is about to hit the ship. Like
+    /// aim, a player preference rather than part of a preset.
+    pub bullet_time: bool,
+    pub coop: CoopSettings,
+    /// Two players take turns on one ship, each with their own saved game, swapping on every death.
+    pub hot_seat: bool,
+    pub versus: VersusSettings,
     pub leaderboard_mode: LeaderboardMode,
     pub budgets: Budgets,
+    pub guard: GuardThresholds,
     pub physics_mode: PhysicsMode,
     pub fragmentation_mode: FragmentationMode,
     pub upgrades_enabled: bool,
+    pub enemies_enabled: bool,
+    pub sentinels_enabled: bool,
     pub collision_policy: CollisionPolicy,
+    pub alien_behaviour: AlienBehaviour,
+    pub asteroid_weights: ArchetypeWeights,
+    pub drop_table: DropTable,
+    pub hazards: HazardSettings,
+    pub tuning: SimTuning,
 }
 
 #[derive(Clone)]
@@ -65,6 +301,10 @@ pub struct Budgets {
     pub debris_ttl_ms: u64,
     pub big_collision_radius: f32,
     pub v_max: usize,
+    /// Cap on live carriers plus drones; drone launches stall while at the cap. This is the only
+    /// bound on enemy cost: there is no time budget, but drone separation is quadratic in the
+    /// drone count, so the cap is what keeps the enemy stage cheap.
+    pub enemy_cap: usize,
 }
 
 impl Budgets {
@@ -75,6 +315,7 @@ impl Budgets {
             debris_ttl_ms: 900,
             big_collision_radius: 32.0,
             v_max: 24,
+            enemy_cap: 24,
         }
     }
 
@@ -85,37 +326,334 @@ impl Budgets {
             debris_ttl_ms: 900,
             big_collision_radius: 32.0,
             v_max: 24,
+            enemy_cap: 24,
+        }
+    }
+
+    pub fn fracture() -> Self {
+        Self {
+            max_bodies: 1200,
+            frag_event_cap: 10,
+            debris_ttl_ms: 800,
+            big_collision_radius: 32.0,
+            v_max: 24,
+            enemy_cap: 24,
+        }
+    }
+
+    pub fn horde() -> Self {
+        Self {
+            max_bodies: 2000,
+            frag_event_cap: 12,
+            debris_ttl_ms: 600,
+            big_collision_radius: 40.0,
+            v_max: 24,
+            enemy_cap: 48,
+        }
+    }
+
+    pub fn simulation() -> Self {
+        Self {
+            max_bodies: 1000,
+            frag_event_cap: 8,
+            debris_ttl_ms: 600,
+            big_collision_radius: 32.0,
+            v_max: 24,
+            enemy_cap: 24,
         }
     }
 }
 
-#[derive(Clone)]
+/// When the performance guard starts shedding expensive work.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GuardThresholds {
+    /// Degrade once the frame rate drops below this.
+    pub degrade_fps: f32,
+    /// Degrade once live bodies exceed this fraction of `Budgets::max_bodies`.
+    pub body_ratio: f32,
+}
+
+impl Default for GuardThresholds {
+    fn default() -> Self {
+        Self {
+            degrade_fps: 55.0,
+            body_ratio: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq)]
 pub enum PlayerControllerMode {
     Human,
     Ai { profile: AiProfile },
 }
 
-#[derive(Clone, Copy, Debug)]
+/// How a human player steers. Both schemes produce the same `ControlIntent`s, so runs and
+/// replays do not depend on it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AimMode {
+    /// Rotate with the turn keys, fire with the fire keys.
+    Keyboard,
+    /// Rotate toward the mouse cursor; left button fires primary, right button secondary.
+    Mouse,
+}
+
+/// A second ship sharing the asteroid field. Each ship keeps its own lives and score.
+#[derive(Clone, PartialEq)]
+pub struct CoopSettings {
+    pub enabled: bool,
+    /// Who flies the second ship: a human on the split keyboard layout, or an AI profile.
+    pub partner: PlayerControllerMode,
+    /// Whether the two ships' bullets can hit each other.
+    pub friendly_fire: bool,
+}
+
+impl CoopSettings {
+    pub fn off() -> Self {
+        Self {
+            enabled: false,
+            partner: PlayerControllerMode::Human,
+            friendly_fire: false,
+        }
+    }
+}
+
+/// A deathmatch: player one against one or more rival ships in a shared asteroid field.
+#[derive(Clone, PartialEq)]
+pub struct VersusSettings {
+    pub enabled: bool,
+    /// Who flies each rival ship, in seat order after player one. At most one can be human,
+    /// since there is only one spare set of keys.
+    pub rivals: Vec<PlayerControllerMode>,
+    /// Length of a match; the most frags when the clock runs out wins.
+    pub match_seconds: u32,
+    /// How long a destroyed ship sits out before respawning.
+    pub respawn_seconds: f32,
+}
+
+impl VersusSettings {
+    pub const MATCH_LENGTHS: [u32; 4] = [60, 120, 180, 300];
+
+    pub fn off() -> Self {
+        Self {
+            enabled: false,
+            rivals: vec![PlayerControllerMode::Ai {
+                profile: AiProfile::Balanced,
+            }],
+            match_seconds: 180,
+            respawn_seconds: 2.0,
+        }
+    }
+
+    /// The rival line-ups the Options screen steps through.
+    pub fn lineups() -> Vec<Vec<PlayerControllerMode>> {
+        let ai = |profile| PlayerControllerMode::Ai { profile };
+        vec![
+            vec![ai(AiProfile::Balanced)],
+            vec![ai(AiProfile::Veteran)],
+            vec![PlayerControllerMode::Human],
+            vec![ai(AiProfile::Casual), ai(AiProfile::Balanced)],
+            vec![PlayerControllerMode::Human, ai(AiProfile::Balanced)],
+            vec![ai(AiProfile::Balanced), ai(AiProfile::Veteran), ai(AiProfile::Veteran)],
+        ]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum AiProfile {
     Casual,
     Balanced,
     Veteran,
 }
 
-#[derive(Clone)]
+/// Behavioural family of an asteroid. Per-archetype numbers live in the simulation's table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AsteroidArchetype {
+    Rock,
+    Armoured,
+    Volatile,
+    Splitter,
+    Magnetic,
+}
+
+/// Relative spawn weights for new large asteroids. All-zero falls back to plain rocks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ArchetypeWeights {
+    pub rock: u32,
+    pub armoured: u32,
+    pub volatile: u32,
+    pub splitter: u32,
+    pub magnetic: u32,
+}
+
+impl ArchetypeWeights {
+    pub fn rocks_only() -> Self {
+        Self {
+            rock: 1,
+            armoured: 0,
+            volatile: 0,
+            splitter: 0,
+            magnetic: 0,
+        }
+    }
+
+    pub fn mixed() -> Self {
+        Self {
+            rock: 10,
+            armoured: 2,
+            volatile: 1,
+            splitter: 2,
+            magnetic: 1,
+        }
+    }
+
+    /// Maps a roll in `[0, total)` to an archetype.
+    pub fn pick(&self, roll: u32) -> AsteroidArchetype {
+        let table = [
+            (AsteroidArchetype::Rock, self.rock),
+            (AsteroidArchetype::Armoured, self.armoured),
+            (AsteroidArchetype::Volatile, self.volatile),
+            (AsteroidArchetype::Splitter, self.splitter),
+            (AsteroidArchetype::Magnetic, self.magnetic),
+        ];
+        let mut remaining = roll;
+        for (archetype, weight) in table {
+            if remaining < weight {
+                return archetype;
+            }
+            remaining -= weight;
+        }
+        AsteroidArchetype::Rock
+    }
+
+    pub fn total(&self) -> u32 {
+        self.rock + self.armoured + self.volatile + self.splitter + self.magnetic
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PickupKind {
+    RapidFire,
+    SpreadReset,
+    ExtraLife,
+    Shield,
+    ScoreMultiplier,
+}
+
+/// Chance that a destroyed asteroid or enemy drops a pickup, and the relative odds of each kind.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DropTable {
+    pub chance: f32,
+    pub rapid_fire: u32,
+    pub spread_reset: u32,
+    pub extra_life: u32,
+    pub shield: u32,
+    pub score_multiplier: u32,
+}
+
+impl DropTable {
+    pub fn none() -> Self {
+        Self {
+            chance: 0.0,
+            rapid_fire: 0,
+            spread_reset: 0,
+            extra_life: 0,
+            shield: 0,
+            score_multiplier: 0,
+        }
+    }
+
+    pub fn arcade() -> Self {
+        Self {
+            chance: 0.06,
+            rapid_fire: 4,
+            spread_reset: 4,
+            extra_life: 1,
+            shield: 3,
+            score_multiplier: 2,
+        }
+    }
+
+    /// Maps a roll in `[0, total)` to a pickup kind; `None` when every weight is zero.
+    pub fn pick(&self, roll: u32) -> Option<PickupKind> {
+        let table = [
+            (PickupKind::RapidFire, self.rapid_fire),
+            (PickupKind::SpreadReset, self.spread_reset),
+            (PickupKind::ExtraLife, self.extra_life),
+            (PickupKind::Shield, self.shield),
+            (PickupKind::ScoreMultiplier, self.score_multiplier),
+        ];
+        let mut remaining = roll;
+        for (kind, weight) in table {
+            if remaining < weight {
+                return Some(kind);
+            }
+            remaining -= weight;
+        }
+        None
+    }
+
+    pub fn total(&self) -> u32 {
+        self.rapid_fire + self.spread_reset + self.extra_life + self.shield + self.score_multiplier
+    }
+}
+
+/// Environmental hazards placed in the field at the start of a run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HazardSettings {
+    /// Gravity wells bend ship, bullet and asteroid paths and swallow anything that crosses the core.
+    pub gravity_wells: bool,
+    /// Nebulae slow the ship and hide it from saucers.
+    pub nebulae: bool,
+    /// Solar flares periodically disable shield charges.
+    pub solar_flares: bool,
+}
+
+impl HazardSettings {
+    pub fn off() -> Self {
+        Self {
+            gravity_wells: false,
+            nebulae: false,
+            solar_flares: false,
+        }
+    }
+
+    pub fn all() -> Self {
+        Self {
+            gravity_wells: true,
+            nebulae: true,
+            solar_flares: true,
+        }
+    }
+
+    pub fn any(&self) -> bool {
+        self.gravity_wells || self.nebulae || self.solar_flares
+    }
+}
+
+/// Which saucer controller drives newly spawned aliens.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlienBehaviour {
+    Classic,
+    Hunter,
+    Sniper,
+    Kamikaze,
+}
+
+#[derive(Clone, PartialEq)]
 pub enum LeaderboardMode {
     Off,
     LocalTop10,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum PhysicsMode {
     Off,
     Arcade,
     Lite,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum FragmentationMode {
     Off,
     ClassicSplit,
@@ -124,7 +662,7 @@ pub enum FragmentationMode {
     Full,
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum CollisionPolicy {
     PlayerOnly,
     BigOnly,
@@ -135,12 +673,25 @@ impl Default for GameConfig {
     fn default() -> Self {
         GameConfig {
             player_controller: PlayerControllerMode::Human,
+            aim_mode: AimMode::Keyboard,
+            bullet_time: false,
+            coop: CoopSettings::off(),
+            hot_seat: false,
+            versus: VersusSettings::off(),
             leaderboard_mode: LeaderboardMode::LocalTop10,
             budgets: Budgets::classic(),
+            guard: GuardThresholds::default(),
             physics_mode: PhysicsMode::Arcade,
             fragmentation_mode: FragmentationMode::ClassicSplit,
             upgrades_enabled: false,
+            enemies_enabled: false,
+            sentinels_enabled: false,
             collision_policy: CollisionPolicy::PlayerOnly,
+            alien_behaviour: AlienBehaviour::Classic,
+            asteroid_weights: ArchetypeWeights::rocks_only(),
+            drop_table: DropTable::none(),
+            hazards: HazardSettings::off(),
+            tuning: SimTuning::classic(),
         }
     }
 }
@@ -181,9 +732,150 @@ impl GameConfig {
         self.leaderboard_mode = next;
     }
 
+    pub fn cycle_alien_behaviour(&mut self) {
+        self.alien_behaviour = match self.alien_behaviour {
+            AlienBehaviour::Classic => AlienBehaviour::Hunter,
+            AlienBehaviour::Hunter => AlienBehaviour::Sniper,
+            AlienBehaviour::Sniper => AlienBehaviour::Kamikaze,
+            AlienBehaviour::Kamikaze => AlienBehaviour::Classic,
+        };
+    }
+
     pub fn toggle_upgrades(&mut self) {
         self.upgrades_enabled = !self.upgrades_enabled;
     }
+
+    pub fn toggle_enemies(&mut self) {
+        self.enemies_enabled = !self.enemies_enabled;
+    }
+
+    pub fn toggle_hazards(&mut self) {
+        self.hazards = if self.hazards.any() {
+            HazardSettings::off()
+        } else {
+            HazardSettings::all()
+        };
+    }
+
+    pub fn toggle_sentinels(&mut self) {
+        self.sentinels_enabled = !self.sentinels_enabled;
+    }
+
+    /// Ships in play: one, two in co-op, or player one plus the rivals in versus.
+    pub fn seats(&self) -> usize {
+        self.seat_controllers().len()
+    }
+
+    /// Who flies each ship, in seat order; seat 0 is always player one.
+    pub fn seat_controllers(&self) -> Vec<PlayerControllerMode> {
+        let mut seats = vec![self.player_controller.clone()];
+        if self.coop.enabled {
+            seats.push(self.coop.partner.clone());
+        } else if self.versus.enabled {
+            seats.extend(self.versus.rivals.iter().cloned());
+        }
+        seats
+    }
+
+    /// Players taking turns: two in hot-seat, otherwise one. Co-op takes precedence if a
+    /// hand-edited file turns both on.
+    pub fn players(&self) -> usize {
+        if self.hot_seat && self.seats() == 1 { 2 } else { 1 }
+    }
+
+    /// Co-op, hot-seat and versus are alternative multiplayer modes; turning one on turns the
+    /// others off.
+    pub fn toggle_coop(&mut self) {
+        let enabled = !self.coop.enabled;
+        self.clear_multiplayer();
+        self.coop.enabled = enabled;
+    }
+
+    pub fn toggle_hot_seat(&mut self) {
+        let enabled = !self.hot_seat;
+        self.clear_multiplayer();
+        self.hot_seat = enabled;
+    }
+
+    pub fn toggle_versus(&mut self) {
+        let enabled = !self.versus.enabled;
+        self.clear_multiplayer();
+        self.versus.enabled = enabled;
+    }
+
+    /// Two human seats, one per netplay peer: a one-on-one match if versus is on, otherwise co-op.
+    pub fn make_networked(&mut self) {
+        let versus = self.versus.enabled;
+        self.clear_multiplayer();
+        self.player_controller = PlayerControllerMode::Human;
+        if versus {
+            self.versus.enabled = true;
+            self.versus.rivals = vec![PlayerControllerMode::Human];
+        } else {
+            self.coop.enabled = true;
+            self.coop.partner = PlayerControllerMode::Human;
+        }
+    }
+
+    fn clear_multiplayer(&mut self) {
+        self.coop.enabled = false;
+        self.hot_seat = false;
+        self.versus.enabled = false;
+    }
+
+    pub fn cycle_versus_lineup(&mut self) {
+        let lineups = VersusSettings::lineups();
+        let next = lineups
+            .iter()
+            .position(|lineup| *lineup == self.versus.rivals)
+            .map_or(0, |index| (index + 1) % lineups.len());
+        self.versus.rivals = lineups[next].clone();
+    }
+
+    pub fn cycle_match_length(&mut self) {
+        let lengths = VersusSettings::MATCH_LENGTHS;
+        let next = lengths
+            .iter()
+            .position(|&seconds| seconds == self.versus.match_seconds)
+            .map_or(0, |index| (index + 1) % lengths.len());
+        self.versus.match_seconds = lengths[next];
+    }
+
+    pub fn cycle_coop_partner(&mut self) {
+        self.coop.partner = match self.coop.partner {
+            PlayerControllerMode::Human => PlayerControllerMode::Ai {
+                profile: AiProfile::Casual,
+            },
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Casual,
+            } => PlayerControllerMode::Ai {
+                profile: AiProfile::Balanced,
+            },
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Balanced,
+            } => PlayerControllerMode::Ai {
+                profile: AiProfile::Veteran,
+            },
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Veteran,
+            } => PlayerControllerMode::Human,
+        };
+    }
+
+    pub fn toggle_friendly_fire(&mut self) {
+        self.coop.friendly_fire = !self.coop.friendly_fire;
+    }
+
+    pub fn toggle_bullet_time(&mut self) {
+        self.bullet_time = !self.bullet_time;
+    }
+
+    pub fn toggle_aim_mode(&mut self) {
+        self.aim_mode = match self.aim_mode {
+            AimMode::Keyboard => AimMode::Mouse,
+            AimMode::Mouse => AimMode::Keyboard,
+        };
+    }
 }
 
 #[cfg(test)]
@@ -193,19 +885,81 @@ mod tests {
     #[test]
     fn default_presets_cover_play_modes() {
         let presets = presets::default_presets();
-        assert_eq!(presets.len(), 3);
+        let names: Vec<&str> = presets.iter().map(|preset| preset.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Classic",
+                "Arcade Upgrades",
+                "AI Autopilot",
+                "Fracture",
+                "Horde",
+                "Simulation"
+            ]
+        );
         assert!(matches!(
-            presets[0].player_controller,
+            presets[0].config.player_controller,
             PlayerControllerMode::Human
         ));
         assert!(matches!(
-            presets[2].player_controller,
+            presets[2].config.player_controller,
             PlayerControllerMode::Ai {
                 profile: AiProfile::Balanced
             }
         ));
     }
 
+    #[test]
+    fn preset_labels_are_unique_and_described() {
+        let presets = presets::default_presets();
+        for (index, preset) in presets.iter().enumerate() {
+            assert!(preset.builtin, "{} should be built in", preset.name);
+            assert!(!preset.name.is_empty());
+            assert!(!preset.description.is_empty(), "{} lacks a description", preset.name);
+            // "Custom" marks edited options and must never select a preset.
+            assert_ne!(preset.name, "Custom");
+            assert!(
+                presets[index + 1..]
+                    .iter()
+                    .all(|other| other.name != preset.name),
+                "duplicate preset {}",
+                preset.name
+            );
+        }
+    }
+
+    #[test]
+    fn new_presets_match_the_matrix() {
+        let presets = presets::default_presets();
+        let find = |name: &str| {
+            presets
+                .iter()
+                .find(|preset| preset.name == name)
+                .unwrap_or_else(|| panic!("missing preset {name}"))
+        };
+
+        let fracture = find("Fracture");
+        assert!(fracture.config.fragmentation_mode == FragmentationMode::SliceOnly);
+        assert!(fracture.config.collision_policy == CollisionPolicy::PlayerOnly);
+        assert_eq!(fracture.warning, PerformanceWarning::Elevated);
+
+        let horde = find("Horde");
+        assert!(horde.config.fragmentation_mode == FragmentationMode::Explode);
+        assert!(horde.config.collision_policy == CollisionPolicy::BigOnly);
+        assert!(horde.config.enemies_enabled);
+        assert_eq!(horde.config.budgets.enemy_cap, 48);
+
+        let simulation = find("Simulation");
+        assert!(simulation.config.fragmentation_mode == FragmentationMode::Full);
+        assert!(simulation.config.collision_policy == CollisionPolicy::Full);
+        assert!(simulation.config.physics_mode == PhysicsMode::Lite);
+        assert_eq!(simulation.warning, PerformanceWarning::High);
+
+        for classic in ["Classic", "Arcade Upgrades", "AI Autopilot"] {
+            assert_eq!(find(classic).warning, PerformanceWarning::None);
+        }
+    }
+
     #[test]
     fn budgets_have_expected_values() {
         let classic = Budgets::classic();
@@ -233,4 +987,84 @@ mod tests {
             CollisionPolicy::PlayerOnly
         ));
     }
+
+    #[test]
+    fn archetype_weights_pick_by_cumulative_roll() {
+        let weights = ArchetypeWeights::mixed();
+        assert_eq!(weights.total(), 16);
+        assert_eq!(weights.pick(0), AsteroidArchetype::Rock);
+        assert_eq!(weights.pick(10), AsteroidArchetype::Armoured);
+        assert_eq!(weights.pick(12), AsteroidArchetype::Volatile);
+        assert_eq!(weights.pick(15), AsteroidArchetype::Magnetic);
+        assert_eq!(ArchetypeWeights::rocks_only().pick(0), AsteroidArchetype::Rock);
+    }
+
+    #[test]
+    fn drop_table_pick_respects_weights() {
+        let table = DropTable::arcade();
+        assert_eq!(table.pick(0), Some(PickupKind::RapidFire));
+        assert_eq!(table.pick(8), Some(PickupKind::ExtraLife));
+        assert_eq!(table.pick(table.total() - 1), Some(PickupKind::ScoreMultiplier));
+        assert_eq!(table.pick(table.total()), None);
+        assert_eq!(DropTable::none().pick(0), None);
+    }
+
+    #[test]
+    fn alien_behaviour_cycle_wraps() {
+        let mut config = GameConfig::default();
+        assert_eq!(config.alien_behaviour, AlienBehaviour::Classic);
+        for _ in 0..4 {
+            config.cycle_alien_behaviour();
+        }
+        assert_eq!(config.alien_behaviour, AlienBehaviour::Classic);
+    }
+
+    #[test]
+    fn two_player_modes_are_exclusive() {
+        let mut config = GameConfig::default();
+        config.toggle_coop();
+        config.toggle_hot_seat();
+        assert!(config.hot_seat && !config.coop.enabled);
+        assert_eq!((config.seats(), config.players()), (1, 2));
+        config.toggle_coop();
+        assert!(!config.hot_seat);
+        assert_eq!((config.seats(), config.players()), (2, 1));
+        config.toggle_versus();
+        assert!(!config.coop.enabled);
+        assert_eq!(config.seats(), 1 + config.versus.rivals.len());
+    }
+
+    #[test]
+    fn versus_lineups_cycle_and_keep_one_human_rival_at_most() {
+        let mut config = GameConfig::default();
+        let lineups = VersusSettings::lineups();
+        for lineup in &lineups {
+            let humans = lineup
+                .iter()
+                .filter(|mode| **mode == PlayerControllerMode::Human)
+                .count();
+            assert!(!lineup.is_empty() && humans <= 1);
+        }
+        for _ in 0..lineups.len() {
+            config.cycle_versus_lineup();
+        }
+        assert!(config.versus.rivals == VersusSettings::off().rivals);
+        config.cycle_match_length();
+        assert_eq!(config.versus.match_seconds, 300);
+    }
+
+    #[test]
+    fn networked_runs_have_two_human_seats() {
+        let mut config = GameConfig::default();
+        config.toggle_hot_seat();
+        config.make_networked();
+        assert!(config.coop.enabled && !config.hot_seat);
+        assert!(config.seat_controllers() == [PlayerControllerMode::Human, PlayerControllerMode::Human]);
+
+        config.toggle_versus();
+        config.versus.rivals = vec![PlayerControllerMode::Ai { profile: AiProfile::Veteran }; 3];
+        config.make_networked();
+        assert!(config.versus.enabled && !config.coop.enabled);
+        assert_eq!(config.seats(), 2);
+    }
 }
@@ -0,0 +1,598 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::keymap::{Action, Keymap};
+
+use super::advanced;
+use super::tuning::{SimTuning, TuningError};
+use super::{
+    AiProfile, AimMode, AlienBehaviour, CollisionPolicy, FragmentationMode, GameConfig, LeaderboardMode,
+    PhysicsMode, PlayerControllerMode, Preset,
+};
+
+const APP_DIR: &str = "asteroids";
+const SETTINGS_FILENAME: &str = "settings.txt";
+const BACKUP_SUFFIX: &str = ".bak";
+/// Largest accepted value for any one `weights.*` or `drops.*` entry.
+const MAX_WEIGHT: u32 = 1000;
+
+const CONTROLLERS: &[(&str, PlayerControllerMode)] = &[
+    ("human", PlayerControllerMode::Human),
+    (
+        "ai-casual",
+        PlayerControllerMode::Ai {
+            profile: AiProfile::Casual,
+        },
+    ),
+    (
+        "ai-balanced",
+        PlayerControllerMode::Ai {
+            profile: AiProfile::Balanced,
+        },
+    ),
+    (
+        "ai-veteran",
+        PlayerControllerMode::Ai {
+            profile: AiProfile::Veteran,
+        },
+    ),
+];
+const AIM_MODES: &[(&str, AimMode)] = &[
+    ("keyboard", AimMode::Keyboard),
+    ("mouse", AimMode::Mouse),
+];
+const LEADERBOARD_MODES: &[(&str, LeaderboardMode)] = &[
+    ("off", LeaderboardMode::Off),
+    ("local-top10", LeaderboardMode::LocalTop10),
+];
+const PHYSICS_MODES: &[(&str, PhysicsMode)] = &[
+    ("off", PhysicsMode::Off),
+    ("arcade", PhysicsMode::Arcade),
+    ("lite", PhysicsMode::Lite),
+];
+const FRAGMENTATION_MODES: &[(&str, FragmentationMode)] = &[
+    ("off", FragmentationMode::Off),
+    ("classic-split", FragmentationMode::ClassicSplit),
+    ("slice-only", FragmentationMode::SliceOnly),
+    ("explode", FragmentationMode::Explode),
+    ("full", FragmentationMode::Full),
+];
+const COLLISION_POLICIES: &[(&str, CollisionPolicy)] = &[
+    ("player-only", CollisionPolicy::PlayerOnly),
+    ("big-only", CollisionPolicy::BigOnly),
+    ("full", CollisionPolicy::Full),
+];
+const ALIEN_BEHAVIOURS: &[(&str, AlienBehaviour)] = &[
+    ("classic", AlienBehaviour::Classic),
+    ("hunter", AlienBehaviour::Hunter),
+    ("sniper", AlienBehaviour::Sniper),
+    ("kamikaze", AlienBehaviour::Kamikaze),
+];
+
+/// Everything persisted between runs: the active configuration and the player's own presets.
+///
+/// Stored as a plain `key = value` file with `[current]`, `[keys]` and `[preset <name>]` sections
+/// so it can be edited by hand. Keys that are missing keep their defaults; unknown keys are errors.
+#[derive(Clone)]
+pub struct Settings {
+    pub config: GameConfig,
+    pub preset_label: String,
+    pub user_presets: Vec<Preset>,
+    pub keymap: Keymap,
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::Io(err) => write!(f, "{err}"),
+            SettingsError::Parse { line, message } => write!(f, "line {line}: {message}"),
+        }
+    }
+}
+
+enum Section {
+    Current,
+    Keys,
+    Preset(usize),
+}
+
+impl Settings {
+    /// Reads the settings file at `path`. `Ok(None)` means there is no file yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(SettingsError::Io(err)),
+        }
+    }
+
+    /// Writes through a temporary file so a crash mid-save never leaves a truncated file.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, self.serialize())?;
+        fs::rename(temp, path)
+    }
+
+    /// Moves an unreadable settings file aside so the next save does not destroy it.
+    pub fn backup_broken(path: &Path) -> io::Result<PathBuf> {
+        let mut backup = path.as_os_str().to_owned();
+        backup.push(BACKUP_SUFFIX);
+        let backup = PathBuf::from(backup);
+        fs::rename(path, &backup)?;
+        Ok(backup)
+    }
+
+    pub fn path() -> PathBuf {
+        data_file(SETTINGS_FILENAME)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SettingsError> {
+        let mut settings = Settings {
+            config: GameConfig::default(),
+            preset_label: String::new(),
+            user_presets: Vec::new(),
+            keymap: Keymap::default(),
+        };
+        let mut section = None;
+        // Header lines, for the checks that can only run once a whole section is in.
+        let mut current_line = None;
+        let mut preset_lines = Vec::new();
+
+        for (index, raw) in text.lines().enumerate() {
+            let line_no = index + 1;
+            let error = |message: String| SettingsError::Parse {
+                line: line_no,
+                message,
+            };
+            let line = raw.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = Some(if header.trim() == "current" {
+                    current_line = Some(line_no);
+                    Section::Current
+                } else if header.trim() == "keys" {
+                    Section::Keys
+                } else if let Some(name) = header.strip_prefix("preset ") {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Err(error("preset sections need a name".to_string()));
+                    }
+                    if settings.user_presets.iter().any(|preset| preset.name == name) {
+                        return Err(error(format!("duplicate preset `{name}`")));
+                    }
+                    settings.user_presets.push(Preset::user(
+                        name.to_string(),
+                        String::new(),
+                        GameConfig::default(),
+                    ));
+                    preset_lines.push(line_no);
+                    Section::Preset(settings.user_presets.len() - 1)
+                } else {
+                    return Err(error(format!("unknown section `[{header}]`")));
+                });
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error("expected `key = value`".to_string()));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match section {
+                None => return Err(error("settings must start with a `[section]`".to_string())),
+                Some(Section::Current) if key == "preset" => {
+                    settings.preset_label = value.to_string();
+                }
+                Some(Section::Current) => {
+                    apply_setting(&mut settings.config, key, value).map_err(error)?
+                }
+                Some(Section::Keys) => {
+                    let action =
+                        Action::from_name(key).ok_or_else(|| error(format!("unknown action `{key}`")))?;
+                    settings.keymap.set_from_text(action, value).map_err(error)?
+                }
+                Some(Section::Preset(i)) if key == "description" => {
+                    settings.user_presets[i].description = value.to_string();
+                }
+                Some(Section::Preset(i)) => {
+                    apply_setting(&mut settings.user_presets[i].config, key, value)
+                        .map_err(error)?
+                }
+            }
+        }
+
+        // Limits spanning more than one tuning key, as the tuning file and the panel check them.
+        let sections = current_line
+            .map(|line| (line, &settings.config))
+            .into_iter()
+            .chain(
+                preset_lines
+                    .into_iter()
+                    .zip(settings.user_presets.iter().map(|preset| &preset.config)),
+            );
+        for (line, config) in sections {
+            config.tuning.check_consistency().map_err(|kind| SettingsError::Parse {
+                line,
+                message: TuningError { line: 0, kind }.to_string(),
+            })?;
+        }
+
+        Ok(settings)
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = String::from("# Asteroids settings. Edit while the game is closed.\n\n");
+        out.push_str("[current]\n");
+        out.push_str(&format!("preset = {}\n", self.preset_label));
+        write_config(&mut out, &self.config);
+        out.push_str("\n[keys]\n");
+        for (action, keys) in self.keymap.entries() {
+            out.push_str(&format!("{action} = {keys}\n"));
+        }
+        for preset in &self.user_presets {
+            out.push_str(&format!("\n[preset {}]\n", preset.name));
+            if !preset.description.is_empty() {
+                out.push_str(&format!("description = {}\n", preset.description));
+            }
+            write_config(&mut out, &preset.config);
+        }
+        out
+    }
+}
+
+/// A file in the per-user data directory, or in the working directory when there is none.
+pub fn data_file(name: &str) -> PathBuf {
+    let dir = data_dir(|key| std::env::var_os(key).map(PathBuf::from))
+        .or_else(|| std::env::current_dir().ok())
+        .unwrap_or_default();
+    dir.join(name)
+}
+
+/// Per-user data directory for the game, following each platform's convention.
+fn data_dir(env: impl Fn(&str) -> Option<PathBuf>) -> Option<PathBuf> {
+    let base = if cfg!(target_os = "windows") {
+        env("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        env("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        env("XDG_DATA_HOME")
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| env("HOME").map(|home| home.join(".local").join("share")))
+    };
+    base.filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.join(APP_DIR))
+}
+
+/// A controller's settings-file name, e.g. `ai-balanced`.
+pub fn controller_name(mode: &PlayerControllerMode) -> &'static str {
+    name_of(CONTROLLERS, mode)
+}
+
+fn name_of<T: PartialEq>(table: &[(&'static str, T)], value: &T) -> &'static str {
+    table
+        .iter()
+        .find(|(_, candidate)| candidate == value)
+        .map(|(name, _)| *name)
+        .unwrap_or_default()
+}
+
+fn parse_named<T: Clone>(table: &[(&'static str, T)], value: &str) -> Result<T, String> {
+    table
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, variant)| variant.clone())
+        .ok_or_else(|| {
+            let names: Vec<&str> = table.iter().map(|(name, _)| *name).collect();
+            format!("`{value}` is not one of {}", names.join(", "))
+        })
+}
+
+/// A comma-separated list of one to three controllers, at most one of them human.
+fn parse_rivals(value: &str) -> Result<Vec<PlayerControllerMode>, String> {
+    let rivals = value
+        .split(',')
+        .map(|name| parse_named(CONTROLLERS, name.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if !(1..=3).contains(&rivals.len()) {
+        return Err(format!("versus.rivals needs one to three ships, found {}", rivals.len()));
+    }
+    if rivals.iter().filter(|mode| **mode == PlayerControllerMode::Human).count() > 1 {
+        return Err("versus.rivals can have at most one human".to_string());
+    }
+    Ok(rivals)
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("`{value}` is not a valid number"))
+}
+
+/// Spawn and drop weights are relative odds; capping each keeps their sum well inside a `u32`.
+fn parse_weight(key: &str, value: &str) -> Result<u32, String> {
+    let weight: u32 = parse_number(value)?;
+    if weight > MAX_WEIGHT {
+        return Err(format!("{key} = {weight} is outside 0..={MAX_WEIGHT}"));
+    }
+    Ok(weight)
+}
+
+fn parse_flag(value: &str) -> Result<bool, String> {
+    match value {
+        "on" | "true" => Ok(true),
+        "off" | "false" => Ok(false),
+        _ => Err(format!("`{value}` should be on or off")),
+    }
+}
+
+fn flag(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+/// Applies one `key = value` pair using the settings file's names and ranges.
+pub fn apply_setting(config: &mut GameConfig, key: &str, value: &str) -> Result<(), String> {
+    match key {
+        "controller" => config.player_controller = parse_named(CONTROLLERS, value)?,
+        "aim" => config.aim_mode = parse_named(AIM_MODES, value)?,
+        "bullet_time" => config.bullet_time = parse_flag(value)?,
+        "coop" => config.coop.enabled = parse_flag(value)?,
+        "coop.partner" => config.coop.partner = parse_named(CONTROLLERS, value)?,
+        "coop.friendly_fire" => config.coop.friendly_fire = parse_flag(value)?,
+        "hot_seat" => config.hot_seat = parse_flag(value)?,
+        "versus" => config.versus.enabled = parse_flag(value)?,
+        "versus.rivals" => config.versus.rivals = parse_rivals(value)?,
+        "versus.match_seconds" => {
+            let seconds: u32 = parse_number(value)?;
+            if !(30..=1800).contains(&seconds) {
+                return Err(format!("versus.match_seconds = {seconds} is outside 30..=1800"));
+            }
+            config.versus.match_seconds = seconds;
+        }
+        "versus.respawn_seconds" => {
+            let seconds: f32 = parse_number(value)?;
+            if !(0.0..=10.0).contains(&seconds) {
+                return Err(format!("versus.respawn_seconds = {seconds} is outside 0..=10"));
+            }
+            config.versus.respawn_seconds = seconds;
+        }
+        "leaderboard" => config.leaderboard_mode = parse_named(LEADERBOARD_MODES, value)?,
+        "physics" => config.physics_mode = parse_named(PHYSICS_MODES, value)?,
+        "fragmentation" => config.fragmentation_mode = parse_named(FRAGMENTATION_MODES, value)?,
+        "collision" => config.collision_policy = parse_named(COLLISION_POLICIES, value)?,
+        "alien_behaviour" => config.alien_behaviour = parse_named(ALIEN_BEHAVIOURS, value)?,
+        "upgrades" => config.upgrades_enabled = parse_flag(value)?,
+        "enemies" => config.enemies_enabled = parse_flag(value)?,
+        "sentinels" => config.sentinels_enabled = parse_flag(value)?,
+        _ if key.starts_with("budgets.") || key.starts_with("guard.") => {
+            // Shares the Advanced panel's ranges so the file cannot hold values the panel rejects.
+            let field = advanced::find(key).ok_or_else(|| format!("unknown key `{key}`"))?;
+            field.set(config, parse_number(value)?)?;
+        }
+        "weights.rock" => config.asteroid_weights.rock = parse_weight(key, value)?,
+        "weights.armoured" => config.asteroid_weights.armoured = parse_weight(key, value)?,
+        "weights.volatile" => config.asteroid_weights.volatile = parse_weight(key, value)?,
+        "weights.splitter" => config.asteroid_weights.splitter = parse_weight(key, value)?,
+        "weights.magnetic" => config.asteroid_weights.magnetic = parse_weight(key, value)?,
+        "drops.chance" => {
+            let chance: f32 = parse_number(value)?;
+            if !(0.0..=1.0).contains(&chance) {
+                return Err(format!("drops.chance = {chance} is outside 0..=1"));
+            }
+            config.drop_table.chance = chance;
+        }
+        "drops.rapid_fire" => config.drop_table.rapid_fire = parse_weight(key, value)?,
+        "drops.spread_reset" => config.drop_table.spread_reset = parse_weight(key, value)?,
+        "drops.extra_life" => config.drop_table.extra_life = parse_weight(key, value)?,
+        "drops.shield" => config.drop_table.shield = parse_weight(key, value)?,
+        "drops.score_multiplier" => config.drop_table.score_multiplier = parse_weight(key, value)?,
+        "hazards.gravity_wells" => config.hazards.gravity_wells = parse_flag(value)?,
+        "hazards.nebulae" => config.hazards.nebulae = parse_flag(value)?,
+        "hazards.solar_flares" => config.hazards.solar_flares = parse_flag(value)?,
+        _ => {
+            let Some(tuning_key) = key.strip_prefix("tuning.") else {
+                return Err(format!("unknown key `{key}`"));
+            };
+            // Reuse the tuning file's range checks; the caller adds the line number.
+            config
+                .tuning
+                .set(tuning_key, value)
+                .map_err(|kind| TuningError { line: 0, kind }.to_string())?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `config` as the `key = value` lines `apply_setting` reads back.
+pub fn write_config(out: &mut String, config: &GameConfig) {
+    let mut line = |key: &str, value: &dyn fmt::Display| {
+        out.push_str(&format!("{key} = {value}\n"));
+    };
+    line("controller", &name_of(CONTROLLERS, &config.player_controller));
+    line("aim", &name_of(AIM_MODES, &config.aim_mode));
+    line("bullet_time", &flag(config.bullet_time));
+    line("coop", &flag(config.coop.enabled));
+    line("coop.partner", &name_of(CONTROLLERS, &config.coop.partner));
+    line("coop.friendly_fire", &flag(config.coop.friendly_fire));
+    line("hot_seat", &flag(config.hot_seat));
+    line("versus", &flag(config.versus.enabled));
+    let rivals: Vec<&str> = config
+        .versus
+        .rivals
+        .iter()
+        .map(|mode| name_of(CONTROLLERS, mode))
+        .collect();
+    line("versus.rivals", &rivals.join(", "));
+    line("versus.match_seconds", &config.versus.match_seconds);
+    line("versus.respawn_seconds", &config.versus.respawn_seconds);
+    line("leaderboard", &name_of(LEADERBOARD_MODES, &config.leaderboard_mode));
+    line("physics", &name_of(PHYSICS_MODES, &config.physics_mode));
+    line("fragmentation", &name_of(FRAGMENTATION_MODES, &config.fragmentation_mode));
+    line("collision", &name_of(COLLISION_POLICIES, &config.collision_policy));
+    line("alien_behaviour", &name_of(ALIEN_BEHAVIOURS, &config.alien_behaviour));
+    line("upgrades", &flag(config.upgrades_enabled));
+    line("enemies", &flag(config.enemies_enabled));
+    line("sentinels", &flag(config.sentinels_enabled));
+
+    let budgets = &config.budgets;
+    line("budgets.max_bodies", &budgets.max_bodies);
+    line("budgets.frag_event_cap", &budgets.frag_event_cap);
+    line("budgets.debris_ttl_ms", &budgets.debris_ttl_ms);
+    line("budgets.big_collision_radius", &budgets.big_collision_radius);
+    line("budgets.v_max", &budgets.v_max);
+    line("budgets.enemy_cap", &budgets.enemy_cap);
+    line("guard.degrade_fps", &config.guard.degrade_fps);
+    line("guard.body_ratio", &config.guard.body_ratio);
+
+    let weights = &config.asteroid_weights;
+    line("weights.rock", &weights.rock);
+    line("weights.armoured", &weights.armoured);
+    line("weights.volatile", &weights.volatile);
+    line("weights.splitter", &weights.splitter);
+    line("weights.magnetic", &weights.magnetic);
+
+    let drops = &config.drop_table;
+    line("drops.chance", &drops.chance);
+    line("drops.rapid_fire", &drops.rapid_fire);
+    line("drops.spread_reset", &drops.spread_reset);
+    line("drops.extra_life", &drops.extra_life);
+    line("drops.shield", &drops.shield);
+    line("drops.score_multiplier", &drops.score_multiplier);
+
+    line("hazards.gravity_wells", &flag(config.hazards.gravity_wells));
+    line("hazards.nebulae", &flag(config.hazards.nebulae));
+    line("hazards.solar_flares", &flag(config.hazards.solar_flares));
+
+    // Only tuning values that differ from the classic numbers are written, to keep the file short.
+    let classic = SimTuning::classic().entries();
+    for ((key, value), (_, default)) in config.tuning.entries().into_iter().zip(classic) {
+        if value != default {
+            line(&format!("tuning.{key}"), &value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{CoopSettings, presets};
+    use macroquad::prelude::KeyCode;
+
+    fn sample() -> Settings {
+        let mut custom = GameConfig {
+            player_controller: PlayerControllerMode::Ai {
+                profile: AiProfile::Veteran,
+            },
+            aim_mode: AimMode::Mouse,
+            bullet_time: true,
+            coop: CoopSettings {
+                enabled: true,
+                partner: PlayerControllerMode::Ai {
+                    profile: AiProfile::Casual,
+                },
+                friendly_fire: true,
+            },
+            collision_policy: CollisionPolicy::Full,
+            ..GameConfig::default()
+        };
+        custom.hazards.nebulae = true;
+        custom.tuning.ship_thrust = 612.5;
+        custom.versus.rivals = vec![
+            PlayerControllerMode::Human,
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Veteran,
+            },
+        ];
+        custom.versus.match_seconds = 120;
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::FirePrimary, 1, KeyCode::J).unwrap();
+        Settings {
+            config: presets::default_presets()[1].config.clone(),
+            preset_label: "Arcade Upgrades".to_string(),
+            user_presets: vec![Preset::user(
+                "User 1".to_string(),
+                "Veteran autopilot in a nebula".to_string(),
+                custom,
+            )],
+            keymap,
+        }
+    }
+
+    #[test]
+    fn settings_round_trip_through_text() {
+        let text = sample().serialize();
+        let parsed = Settings::parse(&text).unwrap();
+        assert_eq!(parsed.serialize(), text);
+        assert_eq!(parsed.preset_label, "Arcade Upgrades");
+        assert_eq!(parsed.user_presets[0].description, "Veteran autopilot in a nebula");
+        let custom = &parsed.user_presets[0].config;
+        assert!(custom.collision_policy == CollisionPolicy::Full);
+        assert_eq!(custom.aim_mode, AimMode::Mouse);
+        assert!(custom.bullet_time);
+        assert!(custom.coop.enabled && custom.coop.friendly_fire);
+        assert_eq!(custom.versus.rivals.len(), 2);
+        assert_eq!(custom.versus.match_seconds, 120);
+        assert_eq!(custom.tuning.ship_thrust, 612.5);
+        assert_eq!(custom.tuning.bullet_speed, SimTuning::classic().bullet_speed);
+        assert_eq!(parsed.keymap.describe(Action::FirePrimary), "Space / J");
+    }
+
+    #[test]
+    fn malformed_settings_report_the_line() {
+        let text = "[current]\nphysics = arcade\ncollision = sideways\n";
+        let Err(err) = Settings::parse(text) else {
+            panic!("invalid collision policy was accepted");
+        };
+        assert!(matches!(err, SettingsError::Parse { line: 3, .. }));
+        assert!(err.to_string().contains("sideways"));
+
+        assert!(Settings::parse("physics = arcade\n").is_err());
+        assert!(Settings::parse("[current]\ntuning.ship_drag = 99\n").is_err());
+        assert!(Settings::parse("[current]\nbudgets.max_bodies = 5\n").is_err());
+        assert!(Settings::parse("[current]\nguard.body_ratio = 2\n").is_err());
+        assert!(Settings::parse("[keys]\nwarp = W\n").is_err());
+        assert!(Settings::parse("[current]\nversus.rivals = human, human\n").is_err());
+        assert!(Settings::parse("[current]\nversus.match_seconds = 5\n").is_err());
+        assert!(Settings::parse("[current]\nweights.rock = 4294967295\n").is_err());
+        assert!(Settings::parse("[current]\ndrops.shield = 1001\n").is_err());
+        assert!(Settings::parse("[current]\ndrops.shield = 1000\n").is_ok());
+        assert!(Settings::parse("[preset A]\n[preset A]\n").is_err());
+        let Err(err) = Settings::parse("[current]\ntuning.asteroid_min_speed = 500\n") else {
+            panic!("asteroid_min_speed above asteroid_max_speed was accepted");
+        };
+        assert!(matches!(err, SettingsError::Parse { line: 1, .. }));
+        let preset = "[current]\n[preset Slow]\ntuning.asteroid_max_speed = 10\n";
+        assert!(matches!(Settings::parse(preset), Err(SettingsError::Parse { line: 2, .. })));
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    #[test]
+    fn data_dir_prefers_xdg_then_home() {
+        let xdg = |key: &str| match key {
+            "XDG_DATA_HOME" => Some(PathBuf::from("/data")),
+            "HOME" => Some(PathBuf::from("/home/pilot")),
+            _ => None,
+        };
+        assert_eq!(data_dir(xdg), Some(PathBuf::from("/data/asteroids")));
+
+        let home_only = |key: &str| (key == "HOME").then(|| PathBuf::from("/home/pilot"));
+        assert_eq!(
+            data_dir(home_only),
+            Some(PathBuf::from("/home/pilot/.local/share/asteroids"))
+        );
+        assert_eq!(data_dir(|_| None), None);
+    }
+}
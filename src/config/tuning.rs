@@ -0,0 +1,288 @@
+use std::fmt;
+use std::path::PathBuf;
+
+const TUNING_FILENAME: &str = "tuning.txt";
+
+/// Lets the tuning table tell whole-number fields apart from fractional ones.
+trait Tunable {
+    const INTEGER: bool;
+}
+
+impl Tunable for f32 {
+    const INTEGER: bool = false;
+}
+
+impl Tunable for u32 {
+    const INTEGER: bool = true;
+}
+
+impl Tunable for usize {
+    const INTEGER: bool = true;
+}
+
+/// Declares every tunable with its default and accepted range so the struct, its defaults and
+/// the file parser cannot drift apart.
+macro_rules! sim_tuning {
+    ($($(#[$doc:meta])* $field:ident: $ty:ty = $default:expr, $min:expr, $max:expr;)*) => {
+        /// Gameplay numbers the simulation reads at runtime instead of compile-time constants.
+        ///
+        /// Presets carry their own copy; `tuning.txt` can override any value without a rebuild.
+        #[derive(Clone, Debug, PartialEq)]
+        pub struct SimTuning {
+            $($(#[$doc])* pub $field: $ty,)*
+        }
+
+        impl SimTuning {
+            /// `(key, min, max, integer)` for every tunable, in declaration order.
+            pub(super) const RANGES: &'static [(&'static str, f64, f64, bool)] = &[
+                $((stringify!($field), $min as f64, $max as f64, <$ty as Tunable>::INTEGER),)*
+            ];
+
+            pub fn classic() -> Self {
+                Self {
+                    $($field: $default,)*
+                }
+            }
+
+            /// `(key, value)` pairs in declaration order, formatted so `set` reads them back.
+            pub(super) fn entries(&self) -> Vec<(&'static str, String)> {
+                vec![$((stringify!($field), self.$field.to_string()),)*]
+            }
+
+            pub(super) fn get(&self, key: &str) -> Option<f64> {
+                match key {
+                    $(stringify!($field) => Some(self.$field as f64),)*
+                    _ => None,
+                }
+            }
+
+            pub(super) fn set(&mut self, key: &str, value: &str) -> Result<(), TuningErrorKind> {
+                match key {
+                    $(stringify!($field) => {
+                        let parsed: $ty = value
+                            .parse()
+                            .map_err(|_| TuningErrorKind::InvalidNumber(value.to_string()))?;
+                        let (min, max) = ($min as f64, $max as f64);
+                        // Written as a negated range check so NaN is rejected too.
+                        if !(parsed as f64 >= min && parsed as f64 <= max) {
+                            return Err(TuningErrorKind::OutOfRange {
+                                key: stringify!($field),
+                                min,
+                                max,
+                                value: parsed as f64,
+                            });
+                        }
+                        self.$field = parsed;
+                        Ok(())
+                    })*
+                    _ => Err(TuningErrorKind::UnknownKey(key.to_string())),
+                }
+            }
+        }
+    };
+}
+
+sim_tuning! {
+    ship_thrust: f32 = 400.0, 0.0, 5000.0;
+    ship_max_speed: f32 = 320.0, 10.0, 5000.0;
+    ship_drag: f32 = 0.25, 0.0, 10.0;
+    ship_rotation_speed: f32 = 3.0, 0.1, 20.0;
+    invulnerability_duration: f32 = 3.0, 0.0, 30.0;
+    max_lives: u32 = 3, 1, 99;
+    // TODO: Increased 10x for the time being due to much more powerful weapons.
+    extra_life_score_step: u32 = 10_000, 100, 10_000_000;
+
+    bullet_speed: f32 = 520.0, 10.0, 5000.0;
+    bullet_ttl: f32 = 2.0, 0.1, 10.0;
+    /// Primary shots per second.
+    primary_fire_rate: f32 = 10.0, 0.1, 60.0;
+    /// Bullets per secondary burst; the burst shares the primary's bullet budget.
+    secondary_count: usize = 21, 1, 64;
+    /// Angle between neighbouring secondary bullets.
+    secondary_spread_degrees: f32 = 5.0, 0.0, 45.0;
+
+    asteroid_min_speed: f32 = 20.0, 0.0, 1000.0;
+    asteroid_max_speed: f32 = 90.0, 1.0, 1000.0;
+    asteroid_spawn_interval: f32 = 2.5, 0.1, 60.0;
+    /// Points for a large rock; medium and small are worth 2x and 4x.
+    asteroid_score_base: u32 = 100, 0, 100_000;
+
+    alien_spawn_score_threshold: u32 = 40_000, 0, 10_000_000;
+    alien_spawn_interval: f32 = 5.0, 0.1, 600.0;
+    max_small_aliens: usize = 2, 0, 16;
+    max_large_aliens: usize = 1, 0, 16;
+
+    carrier_spawn_interval: f32 = 14.0, 1.0, 600.0;
+    carrier_hp: u32 = 12, 1, 500;
+    carrier_launch_interval: f32 = 2.2, 0.1, 60.0;
+    carrier_score: u32 = 2500, 0, 1_000_000;
+    drone_speed: f32 = 150.0, 10.0, 2000.0;
+    drone_score: u32 = 150, 0, 1_000_000;
+
+    sentinel_ttl: f32 = 12.0, 1.0, 120.0;
+    sentinel_energy: u32 = 30, 1, 1000;
+    sentinel_fire_interval: f32 = 0.45, 0.05, 10.0;
+    sentinel_range: f32 = 320.0, 10.0, 2000.0;
+    max_sentinels: usize = 2, 0, 16;
+
+    pickup_ttl: f32 = 8.0, 1.0, 120.0;
+    rapid_fire_duration: f32 = 6.0, 0.0, 120.0;
+    rapid_fire_multiplier: f32 = 2.0, 1.0, 10.0;
+    score_multiplier_duration: f32 = 10.0, 0.0, 120.0;
+    score_multiplier: u32 = 2, 1, 10;
+    max_shield_charges: u32 = 3, 0, 10;
+
+    gravity_well_strength: f32 = 90_000.0, 0.0, 1_000_000.0;
+    nebula_drag: f32 = 1.5, 0.0, 10.0;
+    solar_flare_interval: f32 = 20.0, 1.0, 600.0;
+    solar_flare_duration: f32 = 3.0, 0.1, 60.0;
+}
+
+impl SimTuning {
+    /// Secondary bursts fire at a rate that keeps their bullet output equal to the primary's.
+    pub fn secondary_fire_rate(&self) -> f32 {
+        self.primary_fire_rate / self.secondary_count as f32
+    }
+
+    /// Applies `key = value` lines on top of these values.
+    ///
+    /// Blank lines and `#` comments are skipped. Nothing is applied unless every line is valid,
+    /// so a typo never leaves the game half-tuned.
+    pub fn apply_overrides(&mut self, text: &str) -> Result<(), Vec<TuningError>> {
+        let mut tuned = self.clone();
+        let mut errors = Vec::new();
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let result = match line.split_once('=') {
+                Some((key, value)) => tuned.set(key.trim(), value.trim()),
+                None => Err(TuningErrorKind::MissingValue),
+            };
+            if let Err(kind) = result {
+                errors.push(TuningError {
+                    line: index + 1,
+                    kind,
+                });
+            }
+        }
+
+        if let Err(kind) = tuned.check_consistency() {
+            errors.push(TuningError { line: 0, kind });
+        }
+
+        if errors.is_empty() {
+            *self = tuned;
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Checks the limits that span more than one key, which `set` cannot see on its own.
+    pub(super) fn check_consistency(&self) -> Result<(), TuningErrorKind> {
+        if self.asteroid_min_speed > self.asteroid_max_speed {
+            return Err(TuningErrorKind::Inconsistent(
+                "asteroid_min_speed must not exceed asteroid_max_speed",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn path() -> PathBuf {
+        if let Ok(current) = std::env::current_dir() {
+            current.join(TUNING_FILENAME)
+        } else {
+            PathBuf::from(TUNING_FILENAME)
+        }
+    }
+}
+
+impl Default for SimTuning {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TuningErrorKind {
+    MissingValue,
+    UnknownKey(String),
+    InvalidNumber(String),
+    OutOfRange {
+        key: &'static str,
+        min: f64,
+        max: f64,
+        value: f64,
+    },
+    Inconsistent(&'static str),
+}
+
+/// A rejected line in a tuning file. `line` is 1-based; 0 means the file as a whole.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TuningError {
+    pub line: usize,
+    pub kind: TuningErrorKind,
+}
+
+impl fmt::Display for TuningError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line > 0 {
+            write!(f, "line {}: ", self.line)?;
+        }
+        match &self.kind {
+            TuningErrorKind::MissingValue => write!(f, "expected `key = value`"),
+            TuningErrorKind::UnknownKey(key) => write!(f, "unknown key `{key}`"),
+            TuningErrorKind::InvalidNumber(value) => write!(f, "`{value}` is not a valid number"),
+            TuningErrorKind::OutOfRange {
+                key,
+                min,
+                max,
+                value,
+            } => write!(f, "{key} = {value} is outside {min}..={max}"),
+            TuningErrorKind::Inconsistent(reason) => write!(f, "{reason}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_apply_on_top_of_preset_values() {
+        let mut tuning = SimTuning::classic();
+        let text = "# faster ship\nship_thrust = 600\n\nsecondary_count=7  # narrower burst\n";
+        tuning.apply_overrides(text).unwrap();
+        assert_eq!(tuning.ship_thrust, 600.0);
+        assert_eq!(tuning.secondary_count, 7);
+        assert_eq!(tuning.bullet_speed, SimTuning::classic().bullet_speed);
+    }
+
+    #[test]
+    fn invalid_overrides_report_every_line_and_apply_nothing() {
+        let mut tuning = SimTuning::classic();
+        let text = "ship_thrust = 600\nship_drag = 50\nwarp_drive = 1\nmax_lives = lots\nbare_key\n";
+        let errors = tuning.apply_overrides(text).unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![2, 3, 4, 5]);
+        assert!(errors[0].to_string().contains("ship_drag"));
+        assert_eq!(tuning, SimTuning::classic());
+    }
+
+    #[test]
+    fn overrides_reject_inconsistent_speed_range() {
+        let mut tuning = SimTuning::classic();
+        let errors = tuning
+            .apply_overrides("asteroid_min_speed = 200\n")
+            .unwrap_err();
+        assert_eq!(errors[0].line, 0);
+    }
+
+    #[test]
+    fn overrides_reject_nan() {
+        let mut tuning = SimTuning::classic();
+        assert!(tuning.apply_overrides("ship_drag = NaN").is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use crate::config::AlienBehaviour;
+use macroquad::prelude::Vec2;
+
+/// Restricted view handed to a single saucer each tick.
+///
+/// Aliens only see themselves and the player ship; they never see asteroids or each other.
+#[derive(Clone, Copy, Debug)]
+pub struct AlienSnapshot {
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub cruise_speed: f32,
+    pub min_x: f32,
+    pub max_x: f32,
+    pub ship_position: Vec2,
+    pub ship_velocity: Vec2,
+    pub bullet_speed: f32,
+    pub ready_to_fire: bool,
+    /// Aim cone half-angle (radians) for the current score.
+    pub cone_half_angle: f32,
+    /// Uniform sample in [-1, 1] drawn by the simulation so aim noise stays on the sim RNG.
+    pub aim_noise: f32,
+}
+
+/// What a saucer wants to do this tick. The simulation clamps speed and applies fire cadence.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AlienIntent {
+    pub velocity: Vec2,
+    /// Unit direction to fire in; ignored unless the snapshot reported `ready_to_fire`.
+    pub fire: Option<Vec2>,
+}
+
+pub trait AlienController {
+    fn tick(&mut self, alien: &AlienSnapshot, dt: f32) -> AlienIntent;
+    fn behaviour(&self) -> AlienBehaviour;
+    fn clone_box(&self) -> Box<dyn AlienController>;
+}
+
+impl Clone for Box<dyn AlienController> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+pub fn controller_for(behaviour: AlienBehaviour) -> Box<dyn AlienController> {
+    match behaviour {
+        AlienBehaviour::Classic => Box::new(ClassicSaucer),
+        AlienBehaviour::Hunter => Box::new(HunterSaucer),
+        AlienBehaviour::Sniper => Box::new(SniperSaucer),
+        AlienBehaviour::Kamikaze => Box::new(KamikazeSaucer),
+    }
+}
+
+fn aim_at(alien: &AlienSnapshot, target: Vec2) -> Vec2 {
+    let base = (target - alien.position).to_angle();
+    let offset = alien.aim_noise * alien.cone_half_angle;
+    Vec2::from_angle(base + offset).normalize_or_zero()
+}
+
+/// Horizontal sweep at a fixed altitude, bouncing off the side margins, firing into a cone.
+#[derive(Clone, Copy, Default)]
+pub struct ClassicSaucer;
+
+impl AlienController for ClassicSaucer {
+    fn tick(&mut self, alien: &AlienSnapshot, _dt: f32) -> AlienIntent {
+        let mut dir = if alien.velocity.x >= 0.0 { 1.0 } else { -1.0 };
+        if alien.position.x <= alien.min_x {
+            dir = 1.0;
+        } else if alien.position.x >= alien.max_x {
+            dir = -1.0;
+        }
+
+        AlienIntent {
+            velocity: Vec2::new(dir * alien.cruise_speed, 0.0),
+            fire: alien
+                .ready_to_fire
+                .then(|| aim_at(alien, alien.ship_position)),
+        }
+    }
+
+    fn behaviour(&self) -> AlienBehaviour {
+        AlienBehaviour::Classic
+    }
+
+    fn clone_box(&self) -> Box<dyn AlienController> {
+        Box::new(*self)
+    }
+}
+
+/// Closes to a standoff distance around the ship and fires with the usual cone.
+#[derive(Clone, Copy, Default)]
+pub struct HunterSaucer;
+
+const HUNTER_STANDOFF: f32 = 180.0;
+
+impl AlienController for HunterSaucer {
+    fn tick(&mut self, alien: &AlienSnapshot, _dt: f32) -> AlienIntent {
+        let to_ship = alien.ship_position - alien.position;
+        let distance = to_ship.length();
+        let dir = to_ship.normalize_or_zero();
+        let velocity = if distance > HUNTER_STANDOFF {
+            dir * alien.cruise_speed
+        } else {
+            // Strafe around the ship instead of ramming it.
+            dir.perp() * alien.cruise_speed * 0.6
+        };
+
+        AlienIntent {
+            velocity,
+            fire: alien
+                .ready_to_fire
+                .then(|| aim_at(alien, alien.ship_position)),
+        }
+    }
+
+    fn behaviour(&self) -> AlienBehaviour {
+        AlienBehaviour::Hunter
+    }
+
+    fn clone_box(&self) -> Box<dyn AlienController> {
+        Box::new(*self)
+    }
+}
+
+/// Drifts slowly along its lane and fires leading, noise-free shots.
+#[derive(Clone, Copy, Default)]
+pub struct SniperSaucer;
+
+impl AlienController for SniperSaucer {
+    fn tick(&mut self, alien: &AlienSnapshot, _dt: f32) -> AlienIntent {
+        let mut dir = if alien.velocity.x >= 0.0 { 1.0 } else { -1.0 };
+        if alien.position.x <= alien.min_x {
+            dir = 1.0;
+        } else if alien.position.x >= alien.max_x {
+            dir = -1.0;
+        }
+
+        let fire = alien.ready_to_fire.then(|| {
+            let distance = alien.position.distance(alien.ship_position);
+            let flight_time = distance / alien.bullet_speed.max(1.0);
+            let lead = alien.ship_position + alien.ship_velocity * flight_time;
+            (lead - alien.position).normalize_or_zero()
+        });
+
+        AlienIntent {
+            velocity: Vec2::new(dir * alien.cruise_speed * 0.25, 0.0),
+            fire,
+        }
+    }
+
+    fn behaviour(&self) -> AlienBehaviour {
+        AlienBehaviour::Sniper
+    }
+
+    fn clone_box(&self) -> Box<dyn AlienController> {
+        Box::new(*self)
+    }
+}
+
+/// Never fires; dives straight at the ship and relies on body contact.
+#[derive(Clone, Copy, Default)]
+pub struct KamikazeSaucer;
+
+impl AlienController for KamikazeSaucer {
+    fn tick(&mut self, alien: &AlienSnapshot, _dt: f32) -> AlienIntent {
+        let dir = (alien.ship_position - alien.position).normalize_or_zero();
+        AlienIntent {
+            velocity: dir * alien.cruise_speed,
+            fire: None,
+        }
+    }
+
+    fn behaviour(&self) -> AlienBehaviour {
+        AlienBehaviour::Kamikaze
+    }
+
+    fn clone_box(&self) -> Box<dyn AlienController> {
+        Box::new(*self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> AlienSnapshot {
+        AlienSnapshot {
+            position: Vec2::new(400.0, 100.0),
+            velocity: Vec2::new(100.0, 0.0),
+            cruise_speed: 100.0,
+            min_x: 30.0,
+            max_x: 770.0,
+            ship_position: Vec2::new(400.0, 400.0),
+            ship_velocity: Vec2::ZERO,
+            bullet_speed: 500.0,
+            ready_to_fire: true,
+            cone_half_angle: 0.5,
+            aim_noise: 0.0,
+        }
+    }
+
+    #[test]
+    fn classic_bounces_off_margins() {
+        let mut alien = snapshot();
+        alien.position.x = alien.max_x;
+        let intent = ClassicSaucer.tick(&alien, 1.0 / 60.0);
+        assert!(intent.velocity.x < 0.0);
+        assert_eq!(intent.velocity.y, 0.0);
+    }
+
+    #[test]
+    fn classic_only_fires_when_ready() {
+        let mut alien = snapshot();
+        alien.ready_to_fire = false;
+        assert!(ClassicSaucer.tick(&alien, 1.0 / 60.0).fire.is_none());
+        alien.ready_to_fire = true;
+        let dir = ClassicSaucer.tick(&alien, 1.0 / 60.0).fire.unwrap();
+        assert!(dir.y > 0.99);
+    }
+
+    #[test]
+    fn sniper_leads_moving_target() {
+        let mut alien = snapshot();
+        alien.ship_velocity = Vec2::new(200.0, 0.0);
+        let dir = SniperSaucer.tick(&alien, 1.0 / 60.0).fire.unwrap();
+        assert!(dir.x > 0.0);
+    }
+
+    #[test]
+    fn kamikaze_dives_and_never_fires() {
+        let intent = KamikazeSaucer.tick(&snapshot(), 1.0 / 60.0);
+        assert!(intent.fire.is_none());
+        assert!(intent.velocity.y > 0.0);
+    }
+
+    #[test]
+    fn hunter_strafes_inside_standoff() {
+        let mut alien = snapshot();
+        alien.position = Vec2::new(400.0, 300.0);
+        let intent = HunterSaucer.tick(&alien, 1.0 / 60.0);
+        assert!(intent.velocity.y.abs() < 1e-3);
+        assert!(intent.velocity.x.abs() > 0.0);
+    }
+
+    #[test]
+    fn factory_round_trips_behaviour() {
+        for behaviour in [
+            AlienBehaviour::Classic,
+            AlienBehaviour::Hunter,
+            AlienBehaviour::Sniper,
+            AlienBehaviour::Kamikaze,
+        ] {
+            assert_eq!(controller_for(behaviour).behaviour(), behaviour);
+        }
+    }
+}
@@ -1,36 +1,116 @@
 use crate::ai::WorldSnapshot;
+use crate::config::AimMode;
 use crate::controllers::{ControlIntent, Controller};
-use macroquad::prelude::{KeyCode, is_key_down};
+use crate::keymap::{Keymap, Seat};
+use crate::render::viewport::Viewport;
+use macroquad::prelude::{MouseButton, Vec2, is_mouse_button_down, mouse_position};
+use std::f32::consts::{PI, TAU};
 
-pub struct HumanController;
-
-impl Default for HumanController {
-    fn default() -> Self {
-        HumanController
-    }
+pub struct HumanController {
+    keymap: Keymap,
+    seat: Seat,
+    aim: AimMode,
+    /// The ship's turn rate, so mouse aim can ease into the cursor angle instead of overshooting.
+    rotation_speed: f32,
 }
 
-impl Controller for HumanController {
-    fn tick(&mut self, _world: &WorldSnapshot, _dt: f32) -> ControlIntent {
-        let mut turn = 0.0;
-        if is_key_down(KeyCode::A) || is_key_down(KeyCode::Left) {
-            turn -= 1.0;
+impl HumanController {
+    pub fn new(keymap: Keymap, aim: AimMode, rotation_speed: f32) -> Self {
+        Self {
+            keymap,
+            seat: Seat::PLAYER_ONE,
+            aim,
+            rotation_speed,
         }
-        if is_key_down(KeyCode::D) || is_key_down(KeyCode::Right) {
-            turn += 1.0;
+    }
+
+    /// The second co-op ship, on player two's keys. The mouse stays with player one.
+    pub fn partner(keymap: Keymap) -> Self {
+        Self {
+            keymap,
+            seat: Seat::PLAYER_TWO,
+            aim: AimMode::Keyboard,
+            rotation_speed: 0.0,
         }
+    }
+}
 
-        let thrust = if is_key_down(KeyCode::W) || is_key_down(KeyCode::Up) {
-            1.0
-        } else {
-            0.0
+impl Controller for HumanController {
+    fn tick(&mut self, world: &WorldSnapshot, dt: f32) -> ControlIntent {
+        let (keys, seat) = (&self.keymap, self.seat);
+        let thrust = if keys.down(seat.thrust) { 1.0 } else { 0.0 };
+        let mut intent = ControlIntent {
+            thrust,
+            turn: 0.0,
+            fire_primary: keys.down(seat.fire_primary),
+            fire_secondary: keys.down(seat.fire_secondary),
+            deploy_sentinel: keys.pressed(seat.deploy_sentinel),
         };
 
-        ControlIntent {
-            thrust,
-            turn,
-            fire_primary: is_key_down(KeyCode::Space),
-            fire_secondary: is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift),
+        match self.aim {
+            AimMode::Keyboard => {
+                if keys.down(seat.turn_left) {
+                    intent.turn -= 1.0;
+                }
+                if keys.down(seat.turn_right) {
+                    intent.turn += 1.0;
+                }
+            }
+            AimMode::Mouse => {
+                // Recorded and netplay runs keep their own world size, scaled to the window.
+                let viewport = Viewport::current(world.world_size);
+                let cursor = viewport.world_point(Vec2::from(mouse_position()));
+                intent.turn = turn_toward(
+                    world.ship_position,
+                    world.ship_angle,
+                    cursor,
+                    self.rotation_speed * dt,
+                );
+                intent.fire_primary |= is_mouse_button_down(MouseButton::Left);
+                intent.fire_secondary |= is_mouse_button_down(MouseButton::Right);
+            }
         }
+        intent
+    }
+}
+
+/// Turn input in `-1..=1` that swings the ship toward `target`, at full rate until the remaining
+/// angle is less than one step (`max_step` radians) so it settles instead of oscillating.
+fn turn_toward(position: Vec2, angle: f32, target: Vec2, max_step: f32) -> f32 {
+    let offset = target - position;
+    if offset.length_squared() < 1.0 || max_step <= 0.0 {
+        return 0.0;
+    }
+    let delta = (offset.to_angle() - angle + PI).rem_euclid(TAU) - PI;
+    (delta / max_step).clamp(-1.0, 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mouse_aim_turns_the_short_way_and_settles() {
+        let origin = Vec2::ZERO;
+        // Cursor straight below (screen y grows downward) while facing right: turn clockwise.
+        assert_eq!(turn_toward(origin, 0.0, Vec2::new(0.0, 100.0), 0.05), 1.0);
+        assert_eq!(turn_toward(origin, 0.0, Vec2::new(0.0, -100.0), 0.05), -1.0);
+        // Across the -PI/PI seam the short way is still taken.
+        assert_eq!(turn_toward(origin, 3.0, Vec2::new(-100.0, -10.0), 0.05), 1.0);
+        // Within one step of the target the turn is proportional, so the ship lands on it.
+        let turn = turn_toward(origin, 0.0, Vec2::new(100.0, 2.0), 0.05);
+        assert!(turn > 0.0 && turn < 1.0);
+        assert_eq!(turn_toward(origin, 1.0, origin, 0.05), 0.0);
+    }
+
+    #[test]
+    fn mouse_aim_follows_the_cursor_into_a_scaled_world() {
+        // An 800x600 world shown 1.5x in a 1600x900 window, so it starts 200 px in.
+        let viewport = Viewport::fit(Vec2::new(800.0, 600.0), Vec2::new(1600.0, 900.0));
+        let ship = Vec2::new(700.0, 300.0);
+        // On screen the cursor is straight above the ship, though its raw pixels lie to the right.
+        let cursor = Vec2::new(1250.0, 300.0);
+        assert_eq!(viewport.world_point(cursor), Vec2::new(700.0, 200.0));
+        assert_eq!(turn_toward(ship, 0.0, viewport.world_point(cursor), 0.05), -1.0);
     }
 }
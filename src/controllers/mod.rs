@@ -1,15 +1,22 @@
+pub mod alien;
 pub mod human;
 
 use crate::ai::WorldSnapshot;
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
 pub struct ControlIntent {
     pub thrust: f32,
     pub turn: f32,
     pub fire_primary: bool,
     pub fire_secondary: bool,
+    pub deploy_sentinel: bool,
 }
 
 pub trait Controller {
     fn tick(&mut self, world: &WorldSnapshot, dt: f32) -> ControlIntent;
+
+    /// What the controller is busy with, for telemetry; `None` for controllers without phases.
+    fn phase(&self) -> Option<&'static str> {
+        None
+    }
 }
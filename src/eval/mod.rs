@@ -1,21 +1,34 @@
+mod profile;
+mod soak;
+
+pub use profile::{HUD_WINDOW, Profiler, Summary};
+pub use soak::Soak;
+
+use crate::config::GuardThresholds;
+
 pub struct PerformanceGuard {
     last_fps: f32,
-    degrade_threshold: f32,
+    active_bodies: usize,
+    degrade_fps: f32,
+    body_limit: f32,
 }
 
 impl PerformanceGuard {
-    pub fn new() -> Self {
+    pub fn new(thresholds: &GuardThresholds, max_bodies: usize) -> Self {
         Self {
             last_fps: 60.0,
-            degrade_threshold: 55.0,
+            active_bodies: 0,
+            degrade_fps: thresholds.degrade_fps,
+            body_limit: thresholds.body_ratio * max_bodies as f32,
         }
     }
 
-    pub fn observe(&mut self, fps: f32) {
+    pub fn observe(&mut self, fps: f32, active_bodies: usize) {
         self.last_fps = fps;
+        self.active_bodies = active_bodies;
     }
 
     pub fn should_degrade(&self) -> bool {
-        self.last_fps < self.degrade_threshold
+        self.last_fps < self.degrade_fps || self.active_bodies as f32 > self.body_limit
     }
 }
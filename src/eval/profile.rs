@@ -0,0 +1,286 @@
+use crate::simulation::{Stage, StepTimings};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Frames the HUD panel averages over: four seconds at 60 Hz.
+pub const HUD_WINDOW: usize = 240;
+
+/// Histogram bins per doubling of the value; a bin is about 9% wide.
+const BINS_PER_DOUBLING: i32 = 8;
+/// Values up to 2^MIN_EXPONENT (about a nanosecond, in milliseconds) share the bottom bin, and
+/// values past DOUBLINGS doublings above that the top one.
+const MIN_EXPONENT: i32 = -20;
+const DOUBLINGS: i32 = 48;
+
+/// The mean and 99th percentile of a run of samples, in the samples' unit.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Summary {
+    pub average: f32,
+    pub p99: f32,
+}
+
+/// Timings of each simulation stage, the whole step, drawing and the frame as a whole, in
+/// milliseconds, plus collision pair counts. Either the last `window` steps and frames, or every
+/// one since the start binned into a fixed-size histogram.
+pub struct Profiler {
+    window: usize,
+    stages: Vec<Series>,
+    step: Series,
+    collision_pairs: Series,
+    draw: Series,
+    frame: Series,
+}
+
+impl Profiler {
+    /// Keeps the last `window` samples of each: exact, and enough for the frame graph.
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self::with(window, || Series::Window(VecDeque::with_capacity(window)))
+    }
+
+    /// Keeps every sample in histograms, so memory stays flat however long it runs. Averages
+    /// stay exact; p99 is rounded up to its bin, within about 9%. There is no frame history.
+    pub fn whole_run() -> Self {
+        Self::with(0, || Series::Binned(Histogram::new()))
+    }
+
+    fn with(window: usize, series: impl Fn() -> Series) -> Self {
+        Self {
+            window,
+            stages: Stage::ALL.iter().map(|_| series()).collect(),
+            step: series(),
+            collision_pairs: series(),
+            draw: series(),
+            frame: series(),
+        }
+    }
+
+    pub fn record_step(&mut self, timings: &StepTimings) {
+        for (samples, stage) in self.stages.iter_mut().zip(Stage::ALL) {
+            samples.push(self.window, millis(timings.stage(stage)));
+        }
+        self.step.push(self.window, millis(timings.total()));
+        self.collision_pairs
+            .push(self.window, timings.collision_pairs as f32);
+    }
+
+    pub fn record_draw(&mut self, elapsed: Duration) {
+        self.draw.push(self.window, millis(elapsed));
+    }
+
+    /// A rendered frame's length, as macroquad reports it in seconds.
+    pub fn record_frame(&mut self, seconds: f32) {
+        self.frame.push(self.window, seconds * 1000.0);
+    }
+
+    pub fn stage(&self, stage: Stage) -> Summary {
+        self.stages[stage as usize].summary()
+    }
+
+    pub fn step(&self) -> Summary {
+        self.step.summary()
+    }
+
+    pub fn collision_pairs(&self) -> Summary {
+        self.collision_pairs.summary()
+    }
+
+    pub fn draw(&self) -> Summary {
+        self.draw.summary()
+    }
+
+    pub fn frame(&self) -> Summary {
+        self.frame.summary()
+    }
+
+    /// Recent rendered frame lengths in milliseconds, oldest first; none for a whole-run
+    /// profiler.
+    pub fn frame_history(&self) -> impl Iterator<Item = f32> + '_ {
+        let recent = match &self.frame {
+            Series::Window(samples) => Some(samples.iter().copied()),
+            Series::Binned(_) => None,
+        };
+        recent.into_iter().flatten()
+    }
+
+    pub fn window(&self) -> usize {
+        self.window
+    }
+}
+
+enum Series {
+    Window(VecDeque<f32>),
+    Binned(Histogram),
+}
+
+impl Series {
+    fn push(&mut self, window: usize, value: f32) {
+        match self {
+            Series::Window(samples) => {
+                if samples.len() == window {
+                    samples.pop_front();
+                }
+                samples.push_back(value);
+            }
+            Series::Binned(histogram) => histogram.push(value),
+        }
+    }
+
+    fn summary(&self) -> Summary {
+        match self {
+            Series::Window(samples) => summarize(samples),
+            Series::Binned(histogram) => histogram.summary(),
+        }
+    }
+}
+
+/// Non-negative samples counted in log-scale bins, with their exact sum and maximum.
+struct Histogram {
+    bins: Vec<u64>,
+    count: u64,
+    sum: f64,
+    max: f32,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            bins: vec![0; (DOUBLINGS * BINS_PER_DOUBLING) as usize + 1],
+            count: 0,
+            sum: 0.0,
+            max: 0.0,
+        }
+    }
+
+    fn push(&mut self, value: f32) {
+        let value = value.max(0.0);
+        let bin = bin_of(value).min(self.bins.len() - 1);
+        self.bins[bin] += 1;
+        self.count += 1;
+        self.sum += value as f64;
+        self.max = self.max.max(value);
+    }
+
+    fn summary(&self) -> Summary {
+        if self.count == 0 {
+            return Summary::default();
+        }
+        let rank = ((self.count as f64 * 0.99).ceil() as u64).max(1);
+        let mut seen = 0;
+        let mut p99 = self.max;
+        for (bin, count) in self.bins.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                // The top bin has no upper edge; its values are only bounded by the largest.
+                if bin + 1 < self.bins.len() {
+                    p99 = bin_top(bin).min(self.max);
+                }
+                break;
+            }
+        }
+        Summary {
+            average: (self.sum / self.count as f64) as f32,
+            p99,
+        }
+    }
+}
+
+/// Bin 0 holds values up to 2^MIN_EXPONENT, zero included; each bin after it the values up to
+/// `bin_top` of it, an eighth of a doubling above the bin before.
+fn bin_of(value: f32) -> usize {
+    let scaled = (value.log2() - MIN_EXPONENT as f32) * BINS_PER_DOUBLING as f32;
+    if scaled.is_nan() || scaled <= 0.0 {
+        0
+    } else {
+        scaled.ceil() as usize
+    }
+}
+
+/// The largest value that falls in `bin`.
+fn bin_top(bin: usize) -> f32 {
+    (MIN_EXPONENT as f32 + bin as f32 / BINS_PER_DOUBLING as f32).exp2()
+}
+
+fn millis(elapsed: Duration) -> f32 {
+    elapsed.as_secs_f32() * 1000.0
+}
+
+fn summarize(samples: &VecDeque<f32>) -> Summary {
+    if samples.is_empty() {
+        return Summary::default();
+    }
+    let mut sorted: Vec<f32> = samples.iter().copied().collect();
+    sorted.sort_by(f32::total_cmp);
+    let rank = (sorted.len() as f32 * 0.99).ceil() as usize;
+    Summary {
+        average: sorted.iter().sum::<f32>() / sorted.len() as f32,
+        p99: sorted[rank.clamp(1, sorted.len()) - 1],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(samples: impl IntoIterator<Item = f32>) -> Summary {
+        summarize(&samples.into_iter().collect())
+    }
+
+    fn binned(samples: impl IntoIterator<Item = f32>) -> Summary {
+        let mut histogram = Histogram::new();
+        for sample in samples {
+            histogram.push(sample);
+        }
+        histogram.summary()
+    }
+
+    #[test]
+    fn window_summaries_are_exact() {
+        assert_eq!(window([]), Summary::default());
+        assert_eq!(window([4.0]), Summary { average: 4.0, p99: 4.0 });
+        // The 99th of 100 sorted samples, whatever order they came in.
+        let hundred = (1..=100).rev().map(|n| n as f32);
+        assert_eq!(window(hundred), Summary { average: 50.5, p99: 99.0 });
+        // Below 100 samples the p99 is the largest.
+        assert_eq!(window([1.0, 9.0, 2.0]).p99, 9.0);
+        // One slow frame in a thousand does not move the p99.
+        let mostly_fast = (0..1000).map(|n| if n == 500 { 50.0 } else { 1.0 });
+        assert_eq!(window(mostly_fast).p99, 1.0);
+    }
+
+    #[test]
+    fn profiler_keeps_only_its_window() {
+        let mut profiler = Profiler::new(3);
+        for ms in [10.0, 1.0, 2.0, 3.0] {
+            profiler.record_frame(ms / 1000.0);
+        }
+        let history: Vec<f32> = profiler.frame_history().collect();
+        assert_eq!(history.len(), 3);
+        assert!((profiler.frame().average - 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn binned_summaries_are_close() {
+        assert_eq!(binned([]), Summary::default());
+        assert_eq!(binned([0.0]), Summary::default());
+        assert_eq!(binned([4.0]), Summary { average: 4.0, p99: 4.0 });
+
+        let summary = binned((1..=100).map(|n| n as f32));
+        assert_eq!(summary.average, 50.5);
+        assert!((99.0..=99.0 * 1.1).contains(&summary.p99), "{summary:?}");
+
+        let mostly_fast = binned((0..1000).map(|n| if n == 500 { 50.0 } else { 0.02 }));
+        assert!((0.02..=0.022).contains(&mostly_fast.p99), "{mostly_fast:?}");
+        assert_eq!(binned([1e9, 0.5]).p99, 1e9);
+    }
+
+    #[test]
+    fn whole_run_profiler_has_no_history() {
+        let mut profiler = Profiler::whole_run();
+        for _ in 0..10_000 {
+            profiler.record_frame(0.016);
+        }
+        assert_eq!(profiler.frame_history().count(), 0);
+        assert!((profiler.frame().average - 16.0).abs() < 1e-3);
+    }
+}
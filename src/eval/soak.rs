@@ -0,0 +1,180 @@
+use crate::ai::AiController;
+use crate::config::{self, AiProfile, GameConfig, PlayerControllerMode};
+use crate::simulation::{Simulation, Stage};
+use crate::telemetry::{self, Detail, Record, RunTrace, Sample, TelemetryLog};
+use super::Profiler;
+use macroquad::prelude::Vec2;
+use std::fmt;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// What an unattended soak saw: how long it ran, how many runs the AI got through, how
+/// crowded the playfield got against the body budget, and where each step's time went.
+pub struct Soak {
+    pub frames: u64,
+    pub runs: u32,
+    pub elapsed: Duration,
+    pub peak_bodies: usize,
+    pub max_bodies: usize,
+    pub best_score: u32,
+    /// Every step of the soak, not just the last few seconds, in fixed-size histograms.
+    pub profile: Profiler,
+    /// The first failed write to `--telemetry`'s log.
+    pub telemetry_error: Option<String>,
+    frame_time: f32,
+}
+
+impl Soak {
+    /// Plays `seconds` of game time with the AI in every seat, starting a new run (on the next
+    /// seed) whenever one ends. Paced to `time_scale` times real time; an infinite scale runs
+    /// as fast as the machine allows. With a log, each run is written to it as played on
+    /// `preset`, the one cut off by the end of the soak included; a sample's FPS is steps per
+    /// wall-clock second.
+    pub fn run(
+        config: &GameConfig,
+        preset: &str,
+        seed: u64,
+        world: Vec2,
+        seconds: u32,
+        time_scale: f32,
+        log: Option<&TelemetryLog>,
+    ) -> Self {
+        let mut simulation = autopilot_run(config, seed, world);
+        let controller = config::controller_name(&PlayerControllerMode::Ai {
+            profile: autopilot_profile(&config.player_controller),
+        });
+        let mut trace = RunTrace::new(telemetry::run_id(), 0, Some(seed));
+        trace.flown_by(controller, false);
+        let frame_time = simulation.status().frame_time;
+        let total = (seconds as f32 / frame_time).round() as u64;
+        let mut soak = Self {
+            frames: 0,
+            runs: 1,
+            elapsed: Duration::ZERO,
+            peak_bodies: 0,
+            max_bodies: config.budgets.max_bodies,
+            best_score: 0,
+            profile: Profiler::whole_run(),
+            telemetry_error: None,
+            frame_time,
+        };
+
+        let start = Instant::now();
+        while soak.frames < total {
+            simulation.poll_controllers();
+            simulation.step();
+            soak.profile.record_step(simulation.timings());
+            soak.frames += 1;
+            let status = simulation.status();
+            soak.peak_bodies = soak.peak_bodies.max(status.active_bodies);
+            soak.best_score = soak.best_score.max(status.score);
+            if let Some(log) = log {
+                let time = status.frame as f32 * frame_time;
+                for event in simulation.events() {
+                    trace.observe(event, time);
+                }
+                let mut records = Vec::new();
+                if log.detail() == Detail::Samples && trace.sample_due(time) {
+                    records.push(Record::Sample(Sample {
+                        run: trace.run,
+                        player: 0,
+                        time,
+                        bodies: status.active_bodies,
+                        asteroids: status.asteroid_count,
+                        bullets: status.bullet_count,
+                        enemies: status.enemy_count,
+                        fps: soak.frames as f32 / start.elapsed().as_secs_f32().max(f32::EPSILON),
+                        ai_phase: simulation.controller_phase(0).map(str::to_string),
+                    }));
+                }
+                if status.game_over || soak.frames == total {
+                    records.push(Record::Run(trace.finish(preset, &status)));
+                }
+                for record in records {
+                    if let Err(err) = log.append(&record) {
+                        soak.telemetry_error.get_or_insert_with(|| err.to_string());
+                    }
+                }
+            }
+            if status.game_over {
+                let next_seed = seed.wrapping_add(soak.runs as u64);
+                simulation = autopilot_run(config, next_seed, world);
+                trace = RunTrace::new(telemetry::run_id(), 0, Some(next_seed));
+                trace.flown_by(controller, false);
+                soak.runs += 1;
+            }
+            if time_scale.is_finite() {
+                let due = Duration::from_secs_f64(
+                    soak.frames as f64 * frame_time as f64 / time_scale as f64,
+                );
+                if let Some(wait) = due.checked_sub(start.elapsed()) {
+                    thread::sleep(wait);
+                }
+            }
+        }
+        soak.elapsed = start.elapsed();
+        soak
+    }
+}
+
+impl fmt::Display for Soak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let game_seconds = self.frames as f64 * self.frame_time as f64;
+        let wall = self.elapsed.as_secs_f64();
+        writeln!(
+            f,
+            "{game_seconds:.0} s of game time ({} frames, {} {}) in {wall:.1} s ({:.1}x)",
+            self.frames,
+            self.runs,
+            if self.runs == 1 { "run" } else { "runs" },
+            game_seconds / wall.max(f64::EPSILON)
+        )?;
+        writeln!(
+            f,
+            "peak bodies {} of a {} budget, best score {}",
+            self.peak_bodies, self.max_bodies, self.best_score
+        )?;
+        if let Some(err) = &self.telemetry_error {
+            writeln!(f, "telemetry not written: {err}")?;
+        }
+        // A headless step is quick, so the breakdown is in microseconds.
+        let (step, pairs) = (self.profile.step(), self.profile.collision_pairs());
+        writeln!(
+            f,
+            "step {:.1} us average, {:.1} us p99; {:.0} collision pairs average, {:.0} p99",
+            step.average * 1000.0,
+            step.p99 * 1000.0,
+            pairs.average,
+            pairs.p99
+        )?;
+        for stage in Stage::ALL {
+            let timing = self.profile.stage(stage);
+            writeln!(
+                f,
+                "  {:<12} {:>8.1} us  p99 {:>8.1} us",
+                stage.label(),
+                timing.average * 1000.0,
+                timing.p99 * 1000.0
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A run with every seat on autopilot; human seats get the balanced profile, since there is no
+/// keyboard without a window.
+fn autopilot_run(config: &GameConfig, seed: u64, world: Vec2) -> Simulation {
+    let mut simulation = Simulation::with_world(config.clone(), seed, world);
+    for (seat, mode) in config.seat_controllers().iter().enumerate() {
+        let profile = autopilot_profile(mode);
+        simulation.set_controller(seat, Box::new(AiController::new(profile)));
+    }
+    simulation
+}
+
+fn autopilot_profile(mode: &PlayerControllerMode) -> AiProfile {
+    match mode {
+        PlayerControllerMode::Ai { profile } => *profile,
+        PlayerControllerMode::Human => AiProfile::Balanced,
+    }
+}
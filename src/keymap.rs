@@ -0,0 +1,617 @@
+use macroquad::prelude::{KeyCode, is_key_down, is_key_pressed};
+
+/// Keys kept for navigation on every screen (back, confirm, text editing); never rebindable.
+const RESERVED_KEYS: [KeyCode; 3] = [KeyCode::Escape, KeyCode::Enter, KeyCode::Backspace];
+
+/// Alternate keys per action (e.g. `W` and `Up` for thrust).
+pub const SLOTS: usize = 2;
+
+/// Where an action is live. Two actions only conflict when they share a context.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    /// Playing, paused and game over.
+    Flight,
+    /// The second human's ship in co-op or versus.
+    Partner,
+    MainMenu,
+    Options,
+}
+
+impl Context {
+    pub fn label(self) -> &'static str {
+        match self {
+            Context::Flight => "Flight",
+            Context::Partner => "Player 2",
+            Context::MainMenu => "Main menu",
+            Context::Options => "Options",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Thrust,
+    TurnLeft,
+    TurnRight,
+    FirePrimary,
+    FireSecondary,
+    DeploySentinel,
+    Pause,
+    CycleAiProfile,
+    ToggleAutopilot,
+    ToggleStats,
+    ToggleProfiler,
+    ToggleInvulnerability,
+    SaveAndQuit,
+    StepBack,
+    StepForward,
+    ScrubBack,
+    ScrubForward,
+    SlowDown,
+    SpeedUp,
+    PartnerThrust,
+    PartnerTurnLeft,
+    PartnerTurnRight,
+    PartnerFirePrimary,
+    PartnerFireSecondary,
+    PartnerDeploySentinel,
+    Play,
+    ContinueRun,
+    OpenOptions,
+    OpenLeaderboard,
+    CycleCollision,
+    CyclePhysics,
+    CycleFragmentation,
+    CycleLeaderboard,
+    ToggleUpgrades,
+    ToggleEnemies,
+    ToggleSentinels,
+    CycleAlienBehaviour,
+    ToggleHazards,
+    ToggleAimMode,
+    ToggleBulletTime,
+    ToggleCoop,
+    CycleCoopPartner,
+    ToggleFriendlyFire,
+    ToggleHotSeat,
+    ToggleVersus,
+    CycleVersusLineup,
+    CycleMatchLength,
+    NextPreset,
+    AdvancedSettings,
+    Controls,
+    SavePreset,
+    DeletePreset,
+    ResetOptions,
+}
+
+struct ActionInfo {
+    action: Action,
+    /// Key in the settings file's `[keys]` section.
+    name: &'static str,
+    label: &'static str,
+    context: Context,
+    defaults: [Option<KeyCode>; SLOTS],
+}
+
+const fn info(
+    action: Action,
+    name: &'static str,
+    label: &'static str,
+    context: Context,
+    defaults: [Option<KeyCode>; SLOTS],
+) -> ActionInfo {
+    ActionInfo {
+        action,
+        name,
+        label,
+        context,
+        defaults,
+    }
+}
+
+const fn one(key: KeyCode) -> [Option<KeyCode>; SLOTS] {
+    [Some(key), None]
+}
+
+const fn two(key: KeyCode, alternate: KeyCode) -> [Option<KeyCode>; SLOTS] {
+    [Some(key), Some(alternate)]
+}
+
+/// Indexed by `Action as usize`; the order is also the order of the Controls screen.
+const ACTIONS: [ActionInfo; 53] = {
+    use Action::*;
+    use Context::{Flight, MainMenu, Options, Partner};
+    [
+        info(Thrust, "thrust", "Thrust", Flight, two(KeyCode::W, KeyCode::Up)),
+        info(TurnLeft, "turn_left", "Rotate left", Flight, two(KeyCode::A, KeyCode::Left)),
+        info(TurnRight, "turn_right", "Rotate right", Flight, two(KeyCode::D, KeyCode::Right)),
+        info(FirePrimary, "fire_primary", "Primary fire", Flight, one(KeyCode::Space)),
+        info(
+            FireSecondary,
+            "fire_secondary",
+            "Secondary fire",
+            Flight,
+            two(KeyCode::LeftShift, KeyCode::RightShift),
+        ),
+        info(DeploySentinel, "deploy_sentinel", "Deploy sentinel", Flight, one(KeyCode::E)),
+        info(Pause, "pause", "Pause / resume", Flight, one(KeyCode::P)),
+        info(CycleAiProfile, "cycle_ai_profile", "Cycle AI profile", Flight, one(KeyCode::O)),
+        info(ToggleAutopilot, "toggle_autopilot", "Toggle autopilot", Flight, one(KeyCode::U)),
+        info(ToggleStats, "toggle_stats", "Toggle stats", Flight, one(KeyCode::T)),
+        info(ToggleProfiler, "toggle_profiler", "Toggle profiler", Flight, one(KeyCode::F3)),
+        info(
+            ToggleInvulnerability,
+            "toggle_invulnerability",
+            "Toggle invulnerability",
+            Flight,
+            one(KeyCode::I),
+        ),
+        info(SaveAndQuit, "save_and_quit", "Save and quit (paused)", Flight, one(KeyCode::Q)),
+        info(StepBack, "step_back", "Pause / step back", Flight, one(KeyCode::Comma)),
+        info(StepForward, "step_forward", "Step forward (paused)", Flight, one(KeyCode::Period)),
+        info(ScrubBack, "scrub_back", "Scrub back (paused, hold)", Flight, one(KeyCode::LeftBracket)),
+        info(
+            ScrubForward,
+            "scrub_forward",
+            "Scrub forward (paused, hold)",
+            Flight,
+            one(KeyCode::RightBracket),
+        ),
+        info(SlowDown, "slow_down", "Slower game speed", Flight, one(KeyCode::Minus)),
+        info(SpeedUp, "speed_up", "Faster game speed", Flight, one(KeyCode::Equal)),
+        info(PartnerThrust, "p2_thrust", "Thrust", Partner, one(KeyCode::Up)),
+        info(PartnerTurnLeft, "p2_turn_left", "Rotate left", Partner, one(KeyCode::Left)),
+        info(PartnerTurnRight, "p2_turn_right", "Rotate right", Partner, one(KeyCode::Right)),
+        info(
+            PartnerFirePrimary,
+            "p2_fire_primary",
+            "Primary fire",
+            Partner,
+            one(KeyCode::RightControl),
+        ),
+        info(
+            PartnerFireSecondary,
+            "p2_fire_secondary",
+            "Secondary fire",
+            Partner,
+            one(KeyCode::RightShift),
+        ),
+        info(
+            PartnerDeploySentinel,
+            "p2_deploy_sentinel",
+            "Deploy sentinel",
+            Partner,
+            one(KeyCode::Slash),
+        ),
+        info(Play, "play", "Play", MainMenu, one(KeyCode::P)),
+        info(ContinueRun, "continue", "Continue saved run", MainMenu, one(KeyCode::C)),
+        info(OpenOptions, "options", "Options", MainMenu, one(KeyCode::O)),
+        info(OpenLeaderboard, "leaderboard", "Leaderboard", MainMenu, one(KeyCode::L)),
+        info(CycleCollision, "cycle_collision", "Collision Policy", Options, one(KeyCode::C)),
+        info(CyclePhysics, "cycle_physics", "Physics Mode", Options, one(KeyCode::K)),
+        info(
+            CycleFragmentation,
+            "cycle_fragmentation",
+            "Fragmentation Mode",
+            Options,
+            one(KeyCode::F),
+        ),
+        info(
+            CycleLeaderboard,
+            "cycle_leaderboard",
+            "Leaderboard Mode",
+            Options,
+            one(KeyCode::L),
+        ),
+        info(ToggleUpgrades, "toggle_upgrades", "Upgrades", Options, one(KeyCode::G)),
+        info(ToggleEnemies, "toggle_enemies", "Enemies", Options, one(KeyCode::E)),
+        info(ToggleSentinels, "toggle_sentinels", "Sentinels", Options, one(KeyCode::S)),
+        info(
+            CycleAlienBehaviour,
+            "cycle_alien_behaviour",
+            "Alien Behaviour",
+            Options,
+            one(KeyCode::A),
+        ),
+        info(ToggleHazards, "toggle_hazards", "Hazards", Options, one(KeyCode::H)),
+        info(ToggleAimMode, "toggle_aim", "Aim", Options, one(KeyCode::M)),
+        info(ToggleBulletTime, "toggle_bullet_time", "Bullet time", Options, one(KeyCode::I)),
+        info(ToggleCoop, "toggle_coop", "Co-op", Options, one(KeyCode::P)),
+        info(CycleCoopPartner, "cycle_coop_partner", "Co-op partner", Options, one(KeyCode::W)),
+        info(
+            ToggleFriendlyFire,
+            "toggle_friendly_fire",
+            "Friendly fire",
+            Options,
+            one(KeyCode::X),
+        ),
+        info(ToggleHotSeat, "toggle_hot_seat", "Hot-seat", Options, one(KeyCode::T)),
+        info(ToggleVersus, "toggle_versus", "Versus", Options, one(KeyCode::J)),
+        info(CycleVersusLineup, "cycle_versus_lineup", "Rivals", Options, one(KeyCode::Q)),
+        info(CycleMatchLength, "cycle_match_length", "Match length", Options, one(KeyCode::Z)),
+        info(NextPreset, "next_preset", "Preset", Options, one(KeyCode::Y)),
+        info(
+            AdvancedSettings,
+            "advanced_settings",
+            "Advanced settings",
+            Options,
+            one(KeyCode::V),
+        ),
+        info(Controls, "controls", "Controls", Options, one(KeyCode::B)),
+        info(SavePreset, "save_preset", "Save as preset", Options, one(KeyCode::N)),
+        info(DeletePreset, "delete_preset", "Delete preset", Options, one(KeyCode::D)),
+        info(ResetOptions, "reset_options", "Reset to defaults", Options, one(KeyCode::R)),
+    ]
+};
+
+impl Action {
+    pub fn all() -> impl Iterator<Item = Action> {
+        ACTIONS.iter().map(|info| info.action)
+    }
+
+    fn info(self) -> &'static ActionInfo {
+        &ACTIONS[self as usize]
+    }
+
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn label(self) -> &'static str {
+        self.info().label
+    }
+
+    pub fn context(self) -> Context {
+        self.info().context
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .find(|info| info.name == name)
+            .map(|info| info.action)
+    }
+}
+
+/// The flight actions one ship is steered with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Seat {
+    pub thrust: Action,
+    pub turn_left: Action,
+    pub turn_right: Action,
+    pub fire_primary: Action,
+    pub fire_secondary: Action,
+    pub deploy_sentinel: Action,
+}
+
+impl Seat {
+    pub const PLAYER_ONE: Seat = Seat {
+        thrust: Action::Thrust,
+        turn_left: Action::TurnLeft,
+        turn_right: Action::TurnRight,
+        fire_primary: Action::FirePrimary,
+        fire_secondary: Action::FireSecondary,
+        deploy_sentinel: Action::DeploySentinel,
+    };
+
+    pub const PLAYER_TWO: Seat = Seat {
+        thrust: Action::PartnerThrust,
+        turn_left: Action::PartnerTurnLeft,
+        turn_right: Action::PartnerTurnRight,
+        fire_primary: Action::PartnerFirePrimary,
+        fire_secondary: Action::PartnerFireSecondary,
+        deploy_sentinel: Action::PartnerDeploySentinel,
+    };
+}
+
+/// Which keys trigger each action. Every action keeps at least one key.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    keys: [[Option<KeyCode>; SLOTS]; ACTIONS.len()],
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            keys: ACTIONS.map(|info| info.defaults),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn keys(&self, action: Action) -> [Option<KeyCode>; SLOTS] {
+        self.keys[action as usize]
+    }
+
+    pub fn down(&self, action: Action) -> bool {
+        self.keys(action).into_iter().flatten().any(is_key_down)
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keys(action).into_iter().flatten().any(is_key_pressed)
+    }
+
+    /// The bound keys for menus and help text, e.g. `W / Up`.
+    pub fn describe(&self, action: Action) -> String {
+        let names: Vec<&str> = self.keys(action).into_iter().flatten().map(key_name).collect();
+        names.join(" / ")
+    }
+
+    /// Binds `key` to one slot of `action`, refusing reserved keys and keys another action in
+    /// the same context already uses.
+    pub fn bind(&mut self, action: Action, slot: usize, key: KeyCode) -> Result<(), String> {
+        if RESERVED_KEYS.contains(&key) {
+            return Err(format!("{} is reserved for menus", key_name(key)));
+        }
+        if !KEY_NAMES.iter().any(|&(_, known)| known == key) {
+            return Err("That key cannot be bound".to_string());
+        }
+        if let Some(other) = self.user_of(key, action.context()) {
+            if other != action {
+                return Err(format!("{} is already bound to {}", key_name(key), other.label()));
+            }
+            // Re-binding within the same action moves the key between slots.
+            self.keys[action as usize] = self.keys[action as usize].map(|k| k.filter(|k| *k != key));
+        }
+        self.keys[action as usize][slot] = Some(key);
+        Ok(())
+    }
+
+    /// Removes one key, unless it is the action's last.
+    pub fn clear(&mut self, action: Action, slot: usize) -> Result<(), String> {
+        let bound = self.keys(action).into_iter().flatten().count();
+        if bound <= 1 && self.keys(action)[slot].is_some() {
+            return Err(format!("{} needs at least one key", action.label()));
+        }
+        self.keys[action as usize][slot] = None;
+        Ok(())
+    }
+
+    /// Pairs of actions in the same context sharing a key, worded for notices.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        let actions: Vec<Action> = Action::all().collect();
+        for (index, &first) in actions.iter().enumerate() {
+            for &second in &actions[index + 1..] {
+                if first.context() != second.context() {
+                    continue;
+                }
+                for key in self.keys(first).into_iter().flatten() {
+                    if self.keys(second).contains(&Some(key)) {
+                        conflicts.push(format!(
+                            "{} is bound to both {} and {}",
+                            key_name(key),
+                            first.label(),
+                            second.label()
+                        ));
+                    }
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// The keymap player one uses in co-op: keys bound to player two are taken away, so the
+    /// default arrows-and-WASD layout splits cleanly across one keyboard.
+    pub fn without_partner_keys(&self) -> Keymap {
+        let partner: Vec<KeyCode> = Action::all()
+            .filter(|action| action.context() == Context::Partner)
+            .flat_map(|action| self.keys(action).into_iter().flatten())
+            .collect();
+        let mut split = self.clone();
+        for action in Action::all().filter(|action| action.context() == Context::Flight) {
+            split.keys[action as usize] =
+                self.keys(action).map(|key| key.filter(|key| !partner.contains(key)));
+        }
+        split
+    }
+
+    /// Player-one actions left without any key once co-op splits the keyboard, worded for the
+    /// Controls screen.
+    pub fn coop_gaps(&self) -> Vec<String> {
+        let split = self.without_partner_keys();
+        Action::all()
+            .filter(|action| action.context() == Context::Flight)
+            .filter(|&action| split.keys(action).iter().all(Option::is_none))
+            .map(|action| format!("With two humans, {} has no key left for player 1", action.label()))
+            .collect()
+    }
+
+    fn user_of(&self, key: KeyCode, context: Context) -> Option<Action> {
+        Action::all()
+            .filter(|action| action.context() == context)
+            .find(|&action| self.keys(action).contains(&Some(key)))
+    }
+
+    /// Reads a settings-file value such as `W, Up`.
+    pub fn set_from_text(&mut self, action: Action, value: &str) -> Result<(), String> {
+        let mut keys = [None; SLOTS];
+        let names: Vec<&str> = value.split(',').map(str::trim).collect();
+        if names.len() > SLOTS || names.iter().any(|name| name.is_empty()) {
+            return Err(format!("expected one or two keys for `{}`", action.name()));
+        }
+        for (slot, name) in names.into_iter().enumerate() {
+            let key = key_from_name(name).ok_or_else(|| format!("unknown key `{name}`"))?;
+            if RESERVED_KEYS.contains(&key) {
+                return Err(format!("{name} is reserved for menus"));
+            }
+            keys[slot] = Some(key);
+        }
+        self.keys[action as usize] = keys;
+        Ok(())
+    }
+
+    /// `(action name, keys)` pairs in settings-file format.
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        Action::all()
+            .map(|action| {
+                let names: Vec<&str> =
+                    self.keys(action).into_iter().flatten().map(key_name).collect();
+                (action.name(), names.join(", "))
+            })
+            .collect()
+    }
+}
+
+const KEY_NAMES: &[(&str, KeyCode)] = &[
+    ("A", KeyCode::A),
+    ("B", KeyCode::B),
+    ("C", KeyCode::C),
+    ("D", KeyCode::D),
+    ("E", KeyCode::E),
+    ("F", KeyCode::F),
+    ("G", KeyCode::G),
+    ("H", KeyCode::H),
+    ("I", KeyCode::I),
+    ("J", KeyCode::J),
+    ("K", KeyCode::K),
+    ("L", KeyCode::L),
+    ("M", KeyCode::M),
+    ("N", KeyCode::N),
+    ("O", KeyCode::O),
+    ("P", KeyCode::P),
+    ("Q", KeyCode::Q),
+    ("R", KeyCode::R),
+    ("S", KeyCode::S),
+    ("T", KeyCode::T),
+    ("U", KeyCode::U),
+    ("V", KeyCode::V),
+    ("W", KeyCode::W),
+    ("X", KeyCode::X),
+    ("Y", KeyCode::Y),
+    ("Z", KeyCode::Z),
+    ("0", KeyCode::Key0),
+    ("1", KeyCode::Key1),
+    ("2", KeyCode::Key2),
+    ("3", KeyCode::Key3),
+    ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5),
+    ("6", KeyCode::Key6),
+    ("7", KeyCode::Key7),
+    ("8", KeyCode::Key8),
+    ("9", KeyCode::Key9),
+    ("Space", KeyCode::Space),
+    ("Tab", KeyCode::Tab),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("LeftShift", KeyCode::LeftShift),
+    ("RightShift", KeyCode::RightShift),
+    ("LeftControl", KeyCode::LeftControl),
+    ("RightControl", KeyCode::RightControl),
+    ("LeftAlt", KeyCode::LeftAlt),
+    ("RightAlt", KeyCode::RightAlt),
+    ("Minus", KeyCode::Minus),
+    ("Equal", KeyCode::Equal),
+    ("Comma", KeyCode::Comma),
+    ("Period", KeyCode::Period),
+    ("Slash", KeyCode::Slash),
+    ("Semicolon", KeyCode::Semicolon),
+    ("Apostrophe", KeyCode::Apostrophe),
+    ("LeftBracket", KeyCode::LeftBracket),
+    ("RightBracket", KeyCode::RightBracket),
+    ("Backslash", KeyCode::Backslash),
+    ("GraveAccent", KeyCode::GraveAccent),
+    ("Insert", KeyCode::Insert),
+    ("Delete", KeyCode::Delete),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+    ("F1", KeyCode::F1),
+    ("F2", KeyCode::F2),
+    ("F3", KeyCode::F3),
+    ("F4", KeyCode::F4),
+    ("F5", KeyCode::F5),
+    ("F6", KeyCode::F6),
+    ("F7", KeyCode::F7),
+    ("F8", KeyCode::F8),
+    ("F9", KeyCode::F9),
+    ("F10", KeyCode::F10),
+    ("F11", KeyCode::F11),
+    ("F12", KeyCode::F12),
+    ("Escape", KeyCode::Escape),
+    ("Enter", KeyCode::Enter),
+    ("Backspace", KeyCode::Backspace),
+];
+
+pub fn key_name(key: KeyCode) -> &'static str {
+    KEY_NAMES
+        .iter()
+        .find(|&&(_, known)| known == key)
+        .map_or("?", |&(name, _)| name)
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEY_NAMES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(name))
+        .map(|&(_, key)| key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn action_table_matches_enum_order() {
+        for (index, info) in ACTIONS.iter().enumerate() {
+            assert_eq!(info.action as usize, index, "{} is out of order", info.name);
+            assert_eq!(Action::from_name(info.name), Some(info.action));
+        }
+    }
+
+    #[test]
+    fn defaults_have_no_conflicts() {
+        assert!(Keymap::default().conflicts().is_empty());
+    }
+
+    #[test]
+    fn binding_checks_context_conflicts() {
+        let mut keymap = Keymap::default();
+        // `P` already pauses in flight, but Play lives on the main menu.
+        let err = keymap.bind(Action::CycleAiProfile, 0, KeyCode::P).unwrap_err();
+        assert!(err.contains("Pause"));
+        keymap.bind(Action::OpenOptions, 1, KeyCode::F1).unwrap();
+        keymap.bind(Action::ToggleStats, 0, KeyCode::F1).unwrap();
+        assert!(keymap.bind(Action::Thrust, 0, KeyCode::Escape).is_err());
+
+        keymap.bind(Action::Thrust, 0, KeyCode::Up).unwrap();
+        assert_eq!(keymap.keys(Action::Thrust), [Some(KeyCode::Up), None]);
+        assert!(keymap.clear(Action::Thrust, 0).is_err());
+        assert!(keymap.conflicts().is_empty());
+    }
+
+    #[test]
+    fn coop_splits_the_keyboard() {
+        let keymap = Keymap::default();
+        let split = keymap.without_partner_keys();
+        assert_eq!(split.keys(Action::Thrust), [Some(KeyCode::W), None]);
+        assert_eq!(split.keys(Action::FireSecondary), [Some(KeyCode::LeftShift), None]);
+        assert_eq!(split.keys(Action::Pause), keymap.keys(Action::Pause));
+        assert!(keymap.coop_gaps().is_empty());
+
+        let mut keymap = keymap;
+        keymap.bind(Action::PartnerFirePrimary, 1, KeyCode::Space).unwrap();
+        assert_eq!(keymap.coop_gaps().len(), 1);
+    }
+
+    #[test]
+    fn keys_round_trip_through_text() {
+        let mut keymap = Keymap::default();
+        keymap.bind(Action::FirePrimary, 1, KeyCode::J).unwrap();
+        let mut parsed = Keymap::default();
+        for (name, value) in keymap.entries() {
+            let action = Action::from_name(name).unwrap();
+            parsed.set_from_text(action, &value).unwrap();
+        }
+        assert_eq!(parsed, keymap);
+        assert_eq!(parsed.describe(Action::FirePrimary), "Space / J");
+
+        assert!(parsed.set_from_text(Action::Pause, "Hyper").is_err());
+        assert!(parsed.set_from_text(Action::Pause, "P, Q, R").is_err());
+        assert!(parsed.set_from_text(Action::Pause, "Enter").is_err());
+    }
+}
@@ -1,18 +1,122 @@
 mod ai;
 mod app;
+mod cli;
 mod config;
 mod controllers;
 mod eval;
+mod keymap;
+mod netplay;
 mod render;
+mod replay;
+mod savegame;
 mod scoreboard;
 mod simulation;
 mod stats;
+mod telemetry;
 mod ui;
 
-use app::App;
+use app::{App, Launch};
+use cli::CliOptions;
+use eval::Soak;
+use macroquad::window::Conf;
+use replay::Replay;
+use std::path::Path;
 
-#[macroquad::main("Asteroids Systems")]
-async fn main() {
-    let mut app = App::new();
-    while app.tick().await {}
+const WINDOW_TITLE: &str = "Asteroids Systems";
+
+fn main() {
+    let cli = match CliOptions::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(err) => exit_with(&format!("{err}\n\n{}", cli::USAGE)),
+    };
+    if cli.help {
+        print!("{}", cli::USAGE);
+        return;
+    }
+    if let Some(path) = &cli.verify {
+        verify(path);
+        return;
+    }
+    if cli.telemetry_report {
+        let dir = telemetry::data_dir();
+        let records = telemetry::read_dir(&dir).unwrap_or_else(|err| exit_with(&err));
+        print!("{}: {}", dir.display(), telemetry::Report::new(records));
+        return;
+    }
+    let launch = Launch::resolve(&cli).unwrap_or_else(|err| exit_with(&err));
+
+    if cli.list_presets {
+        for preset in launch.presets() {
+            println!("{:<18} {:<9} {}", preset.name, preset.warning.label(), preset.description);
+        }
+        return;
+    }
+    if cli.print_config {
+        print!("{}", launch.config_text());
+        return;
+    }
+    if let Some(seconds) = cli.soak {
+        soak(&launch, seconds, cli.time_scale.unwrap_or(1.0));
+        return;
+    }
+
+    let mut conf = Conf {
+        window_title: WINDOW_TITLE.to_string(),
+        ..Conf::default()
+    };
+    if let Some((width, height)) = launch.window() {
+        conf.window_width = width as i32;
+        conf.window_height = height as i32;
+    }
+    macroquad::Window::from_config(conf, async move {
+        let mut app = App::new(launch);
+        while app.tick().await {}
+    });
+}
+
+/// `--verify`: exits with status 1 if the replay no longer plays back the way it was recorded.
+fn verify(path: &Path) {
+    let replay = Replay::load(path).unwrap_or_else(|err| exit_with(&err));
+    if replay.checksums.is_empty() {
+        exit_with(&format!(
+            "{}: no checksums to verify",
+            path.display()
+        ));
+    }
+    match replay.verify() {
+        Ok(frames) => println!("{}: all {frames} frames match", path.display()),
+        Err(divergence) => {
+            print!("{}: {divergence}", path.display());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `--soak`: the AI plays the resolved configuration headless, e.g. to watch for entities or
+/// memory piling up over a long session.
+fn soak(launch: &Launch, seconds: u32, time_scale: f32) {
+    let (width, height) = launch.window().unwrap_or_else(|| {
+        let conf = Conf::default();
+        (conf.window_width as u32, conf.window_height as u32)
+    });
+    let seed = launch.seed().unwrap_or_default();
+    let world = macroquad::prelude::vec2(width as f32, height as f32);
+    let telemetry = launch.telemetry();
+    print!(
+        "{}",
+        Soak::run(
+            launch.run_config(),
+            launch.preset_label(),
+            seed,
+            world,
+            seconds,
+            time_scale,
+            telemetry.as_ref(),
+        )
+    );
+}
+
+fn exit_with(message: &str) -> ! {
+    eprintln!("asteroids: {message}");
+    std::process::exit(2);
 }
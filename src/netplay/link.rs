@@ -0,0 +1,139 @@
+use std::collections::VecDeque;
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Largest datagram either side sends; a welcome with the full configuration fits easily.
+const MAX_PACKET: usize = 16 * 1024;
+
+/// An unreliable, unordered packet pipe to the other peer.
+pub trait Link {
+    fn send(&mut self, packet: &[u8]);
+    /// The next packet that has arrived, if any. Never blocks.
+    fn recv(&mut self) -> Option<Vec<u8>>;
+}
+
+/// A non-blocking UDP socket talking to one peer. The host learns its peer from the first
+/// packet it receives and ignores everyone else from then on.
+pub struct UdpLink {
+    socket: UdpSocket,
+    peer: Option<SocketAddr>,
+    buffer: Vec<u8>,
+}
+
+impl UdpLink {
+    /// Listens on `port` on every interface and waits for a peer to speak first.
+    pub fn host(port: u16) -> io::Result<Self> {
+        Self::bind(("0.0.0.0", port), None)
+    }
+
+    /// Talks to a host at `address`, e.g. `192.168.1.20:7000`, from an ephemeral port.
+    pub fn join(address: &str) -> io::Result<Self> {
+        let peer = address.to_socket_addrs()?.next().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::NotFound, format!("`{address}` did not resolve"))
+        })?;
+        let local = if peer.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
+        Self::bind(local, Some(peer))
+    }
+
+    fn bind(address: impl ToSocketAddrs, peer: Option<SocketAddr>) -> io::Result<Self> {
+        let socket = UdpSocket::bind(address)?;
+        socket.set_nonblocking(true)?;
+        Ok(Self {
+            socket,
+            peer,
+            buffer: vec![0; MAX_PACKET],
+        })
+    }
+}
+
+impl Link for UdpLink {
+    fn send(&mut self, packet: &[u8]) {
+        // Lost packets are normal; the protocol resends whatever was not acknowledged.
+        if let Some(peer) = self.peer {
+            let _ = self.socket.send_to(packet, peer);
+        }
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        loop {
+            let (len, from) = self.socket.recv_from(&mut self.buffer).ok()?;
+            match self.peer {
+                Some(peer) if peer != from => continue,
+                Some(_) => {}
+                None => self.peer = Some(from),
+            }
+            return Some(self.buffer[..len].to_vec());
+        }
+    }
+}
+
+/// Simulates a bad connection over another link: outgoing packets are dropped with some
+/// probability and the rest are held back for the latency plus up to a quarter of it again in
+/// jitter, which also reorders them.
+pub struct LossyLink<L> {
+    inner: L,
+    loss: f32,
+    latency: Duration,
+    queue: VecDeque<(Instant, Vec<u8>)>,
+    rng: u64,
+}
+
+impl<L: Link> LossyLink<L> {
+    /// `loss` is a fraction of packets, 0.0 to 1.0.
+    pub fn new(inner: L, loss: f32, latency: Duration) -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::with_seed(inner, loss, latency, seed)
+    }
+
+    pub fn with_seed(inner: L, loss: f32, latency: Duration, seed: u64) -> Self {
+        Self {
+            inner,
+            loss,
+            latency,
+            queue: VecDeque::new(),
+            rng: seed | 1,
+        }
+    }
+
+    /// Uniform in [0, 1); xorshift is plenty for deciding which packets to lose.
+    fn roll(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        (self.rng >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn flush(&mut self) {
+        let now = Instant::now();
+        let mut index = 0;
+        while index < self.queue.len() {
+            if self.queue[index].0 <= now {
+                if let Some((_, packet)) = self.queue.remove(index) {
+                    self.inner.send(&packet);
+                }
+            } else {
+                index += 1;
+            }
+        }
+    }
+}
+
+impl<L: Link> Link for LossyLink<L> {
+    fn send(&mut self, packet: &[u8]) {
+        if self.roll() >= self.loss {
+            let jitter = self.latency.mul_f32(self.roll() / 4.0);
+            self.queue
+                .push_back((Instant::now() + self.latency + jitter, packet.to_vec()));
+        }
+        self.flush();
+    }
+
+    fn recv(&mut self) -> Option<Vec<u8>> {
+        self.flush();
+        self.inner.recv()
+    }
+}
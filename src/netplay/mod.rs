@@ -0,0 +1,522 @@
+//! Two-player peer-to-peer play over UDP. Each peer runs the whole simulation and the peers
+//! trade only their `ControlIntent`s, one per frame. Remote input that has not arrived yet is
+//! predicted, and a wrong guess is repaired by rolling back to a saved state and re-simulating;
+//! see `docs/sim/SIM_0002_netplay_rollback.md`.
+
+pub mod link;
+pub mod protocol;
+pub mod rollback;
+
+use crate::config::GameConfig;
+use crate::controllers::ControlIntent;
+use crate::simulation::{SimEvent, Simulation, SimulationState};
+use link::{Link, LossyLink, UdpLink};
+use protocol::{Message, PROTOCOL_VERSION};
+use rollback::{Desync, Rollback, RollbackSession, RollbackStats};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_INPUT_DELAY: usize = 2;
+pub const MAX_INPUT_DELAY: usize = 10;
+/// Frames the simulation may run past the peer's last confirmed input before it waits.
+const MAX_PREDICTION: usize = 8;
+/// Unacknowledged input frames sent per packet.
+const MAX_RESEND: usize = 32;
+/// Silence after which the peer is considered gone.
+const PEER_TIMEOUT: Duration = Duration::from_secs(5);
+const HELLO_INTERVAL: Duration = Duration::from_millis(250);
+const HANDSHAKE_POLL: Duration = Duration::from_millis(5);
+/// How long the host waits for someone to join, and a joiner for the host to answer.
+pub const HOST_WAIT: Duration = Duration::from_secs(120);
+pub const JOIN_WAIT: Duration = Duration::from_secs(10);
+
+/// Which side of the connection this process is.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Role {
+    Host { port: u16 },
+    Join { address: String },
+}
+
+/// Netplay settings from the command line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetOptions {
+    pub role: Role,
+    /// Frames between pressing a key and the ship reacting, on both peers. The host's wins.
+    pub input_delay: usize,
+    /// Packet-loss and latency shim for testing, applied to what this peer sends.
+    pub loss: f32,
+    pub latency: Duration,
+}
+
+impl NetOptions {
+    /// Opens the socket, wrapped in the loss/latency shim when either is set.
+    pub fn open_link(&self) -> Result<Box<dyn Link>, String> {
+        let udp = match &self.role {
+            Role::Host { port } => UdpLink::host(*port),
+            Role::Join { address } => UdpLink::join(address),
+        }
+        .map_err(|err| format!("netplay: {err}"))?;
+        if self.loss > 0.0 || !self.latency.is_zero() {
+            Ok(Box::new(LossyLink::new(udp, self.loss, self.latency)))
+        } else {
+            Ok(Box::new(udp))
+        }
+    }
+}
+
+/// The run both peers simulate, as decided by the host.
+#[derive(Clone)]
+pub struct Agreement {
+    pub seed: u64,
+    pub window: (u32, u32),
+    pub input_delay: usize,
+    pub config: GameConfig,
+}
+
+impl Agreement {
+    fn welcome(&self) -> Message {
+        Message::Welcome {
+            version: PROTOCOL_VERSION,
+            seed: self.seed,
+            window: self.window,
+            input_delay: self.input_delay,
+            config: Box::new(self.config.clone()),
+        }
+    }
+}
+
+/// Why a netplay game stopped exchanging input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Closed {
+    PeerLeft,
+    TimedOut,
+}
+
+/// What the HUD shows about the connection.
+pub struct NetStatus {
+    pub local_seat: usize,
+    pub input_delay: usize,
+    pub stats: RollbackStats,
+    pub closed: Option<Closed>,
+    /// The frame the peers' states first differed at.
+    pub desync_frame: Option<usize>,
+}
+
+/// One peer's end of a netplay game: the link, the rollback bookkeeping and the handshake's
+/// outcome. Generic over the saved state so the protocol can be tested without a window.
+pub struct Netplay<S = SimulationState> {
+    link: Box<dyn Link>,
+    session: RollbackSession<S>,
+    local_seat: usize,
+    input_delay: usize,
+    /// How many of our input frames the peer has; we resend from there.
+    peer_ack: usize,
+    last_heard: Instant,
+    /// The host's welcome, resent if the joiner says hello again because it was lost.
+    welcome: Option<Vec<u8>>,
+    closed: Option<Closed>,
+}
+
+impl<S: Clone> Netplay<S> {
+    fn new(link: Box<dyn Link>, local_seat: usize, input_delay: usize) -> Self {
+        Self {
+            link,
+            session: RollbackSession::new(local_seat, input_delay, MAX_PREDICTION),
+            local_seat,
+            input_delay,
+            peer_ack: 0,
+            last_heard: Instant::now(),
+            welcome: None,
+            closed: None,
+        }
+    }
+
+    /// Waits for a peer to say hello, then tells it what to play. The host is seat 0.
+    pub fn host(mut link: Box<dyn Link>, agreement: &Agreement, wait: Duration) -> Result<Self, String> {
+        let deadline = Instant::now() + wait;
+        loop {
+            while let Some(packet) = link.recv() {
+                match Message::decode(&packet) {
+                    Ok(Message::Hello { version }) if version == PROTOCOL_VERSION => {
+                        let welcome = agreement.welcome().encode();
+                        link.send(&welcome);
+                        let mut netplay = Self::new(link, 0, agreement.input_delay);
+                        netplay.welcome = Some(welcome);
+                        return Ok(netplay);
+                    }
+                    Ok(Message::Hello { version }) => {
+                        // Tell the joiner why, so it fails with a clear message too.
+                        link.send(&agreement.welcome().encode());
+                        return Err(format!(
+                            "netplay: peer speaks protocol {version}, this build {PROTOCOL_VERSION}"
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err("netplay: nobody joined".to_string());
+            }
+            std::thread::sleep(HANDSHAKE_POLL);
+        }
+    }
+
+    /// Says hello until the host answers with the run to play. The joiner is seat 1.
+    pub fn join(mut link: Box<dyn Link>, wait: Duration) -> Result<(Self, Agreement), String> {
+        let deadline = Instant::now() + wait;
+        let hello = Message::Hello {
+            version: PROTOCOL_VERSION,
+        }
+        .encode();
+        let mut next_hello = Instant::now();
+        loop {
+            if Instant::now() >= next_hello {
+                link.send(&hello);
+                next_hello += HELLO_INTERVAL;
+            }
+            while let Some(packet) = link.recv() {
+                if let Ok(Message::Welcome {
+                    version,
+                    seed,
+                    window,
+                    input_delay,
+                    config,
+                }) = Message::decode(&packet)
+                {
+                    if version != PROTOCOL_VERSION {
+                        return Err(format!(
+                            "netplay: host speaks protocol {version}, this build {PROTOCOL_VERSION}"
+                        ));
+                    }
+                    let agreement = Agreement {
+                        seed,
+                        window,
+                        input_delay,
+                        config: *config,
+                    };
+                    return Ok((Self::new(link, 1, input_delay), agreement));
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err("netplay: no answer from the host".to_string());
+            }
+            std::thread::sleep(HANDSHAKE_POLL);
+        }
+    }
+
+    pub fn local_seat(&self) -> usize {
+        self.local_seat
+    }
+
+    /// Trades packets with the peer and runs the next frame if the prediction window allows.
+    /// `poll_local` is asked for our input only when a frame is run. Returns whether one was.
+    pub fn tick<G: Rollback<State = S>>(
+        &mut self,
+        game: &mut G,
+        poll_local: impl FnOnce(&mut G) -> ControlIntent,
+    ) -> bool {
+        self.receive();
+        if self.closed.is_some() {
+            return false;
+        }
+        let advanced = self.session.advance(game, poll_local);
+        self.send_input();
+        advanced
+    }
+
+    /// Trades packets without running a frame.
+    pub fn keep_alive(&mut self) {
+        self.receive();
+        self.send_input();
+    }
+
+    /// Tells the peer we are leaving. Sent a few times since nothing is resent after this.
+    pub fn leave(&mut self) {
+        let bye = Message::Bye.encode();
+        for _ in 0..3 {
+            self.link.send(&bye);
+        }
+        self.closed.get_or_insert(Closed::PeerLeft);
+    }
+
+    pub fn status(&self) -> NetStatus {
+        NetStatus {
+            local_seat: self.local_seat,
+            input_delay: self.input_delay,
+            stats: self.session.stats,
+            closed: self.closed,
+            desync_frame: self.session.desync().map(|desync| desync.frame),
+        }
+    }
+
+    pub fn desync(&self) -> Option<&Desync<S>> {
+        self.session.desync()
+    }
+
+    /// Whether every simulated frame used confirmed input, so what is on screen is final.
+    pub fn settled(&self) -> bool {
+        self.session.confirmed() >= self.session.frame()
+    }
+
+    /// The latest simulated frame, numbered as `SimulationStatus::frame` counts them, that ran
+    /// on confirmed input only; no rollback can change it or anything before it.
+    pub fn confirmed_frame(&self) -> u64 {
+        self.session.confirmed().min(self.session.frame()) as u64
+    }
+
+    fn receive(&mut self) {
+        while let Some(packet) = self.link.recv() {
+            let Ok(message) = Message::decode(&packet) else {
+                continue;
+            };
+            self.last_heard = Instant::now();
+            match message {
+                Message::Input {
+                    ack,
+                    start,
+                    check,
+                    intents,
+                } => {
+                    self.peer_ack = self.peer_ack.max(ack);
+                    self.session.receive_remote(start, &intents);
+                    if let Some((frame, checksum)) = check {
+                        self.session.receive_check(frame, checksum);
+                    }
+                }
+                Message::Hello { .. } => {
+                    if let Some(welcome) = &self.welcome {
+                        self.link.send(welcome);
+                    }
+                }
+                Message::Bye => self.closed = Some(Closed::PeerLeft),
+                Message::Welcome { .. } => {}
+            }
+        }
+        if self.last_heard.elapsed() > PEER_TIMEOUT {
+            self.closed.get_or_insert(Closed::TimedOut);
+        }
+    }
+
+    fn send_input(&mut self) {
+        let unacked = self.session.local_inputs(self.peer_ack);
+        let message = Message::Input {
+            ack: self.session.confirmed(),
+            start: self.peer_ack,
+            check: self.session.latest_check(),
+            intents: unacked[..unacked.len().min(MAX_RESEND)].to_vec(),
+        };
+        self.link.send(&message.encode());
+    }
+}
+
+/// The events of frames not yet confirmed, kept by frame until they are. Re-simulating a frame
+/// after a misprediction replaces the events it produced the first time.
+#[derive(Default)]
+pub struct FrameEvents(BTreeMap<u64, Vec<SimEvent>>);
+
+impl FrameEvents {
+    pub fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    fn record(&mut self, frame: u64, events: &[SimEvent]) {
+        self.0.insert(frame, events.to_vec());
+    }
+
+    /// Takes the events of every frame up to and including `frame`, oldest first.
+    pub fn take_through(&mut self, frame: u64) -> Vec<(u64, Vec<SimEvent>)> {
+        let later = self.0.split_off(&(frame + 1));
+        std::mem::replace(&mut self.0, later).into_iter().collect()
+    }
+}
+
+/// A simulation stepped by netplay, noting each frame's events as it runs.
+pub struct Tracked<'a> {
+    pub simulation: &'a mut Simulation,
+    pub events: &'a mut FrameEvents,
+}
+
+impl Rollback for Tracked<'_> {
+    type State = SimulationState;
+
+    fn save(&self) -> SimulationState {
+        self.simulation.save_state()
+    }
+
+    fn restore(&mut self, state: &SimulationState) {
+        self.simulation.restore_state(state);
+    }
+
+    fn advance(&mut self, intents: &[ControlIntent]) {
+        Rollback::advance(&mut *self.simulation, intents);
+        let frame = self.simulation.status().frame;
+        self.events.record(frame, self.simulation.events());
+    }
+
+    fn checksum(state: &SimulationState) -> u64 {
+        state.checksum()
+    }
+}
+
+impl Rollback for Simulation {
+    type State = SimulationState;
+
+    fn save(&self) -> SimulationState {
+        self.save_state()
+    }
+
+    fn restore(&mut self, state: &SimulationState) {
+        self.restore_state(state);
+    }
+
+    fn advance(&mut self, intents: &[ControlIntent]) {
+        self.apply_intents(intents);
+        self.step();
+    }
+
+    fn checksum(state: &SimulationState) -> u64 {
+        state.checksum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::mpsc;
+
+    /// Two ships on a line, each pushed by its seat's thrust; small enough to check by hand and
+    /// sensitive to every input bit.
+    #[derive(Clone, Default)]
+    struct Track {
+        positions: [f32; 2],
+        frame: u64,
+    }
+
+    impl Rollback for Track {
+        type State = Track;
+        fn save(&self) -> Track {
+            self.clone()
+        }
+        fn restore(&mut self, state: &Track) {
+            *self = state.clone();
+        }
+        fn advance(&mut self, intents: &[ControlIntent]) {
+            for (position, intent) in self.positions.iter_mut().zip(intents) {
+                *position = *position * 0.99 + intent.thrust - intent.turn;
+            }
+            self.frame += 1;
+        }
+        fn checksum(state: &Track) -> u64 {
+            state.positions.iter().fold(state.frame, |hash, position| {
+                hash.wrapping_mul(0x100_0000_01b3) ^ u64::from(position.to_bits())
+            })
+        }
+    }
+
+    /// Input that changes every few frames, differently per seat, so predictions go wrong.
+    fn pattern(seat: usize, frame: usize) -> ControlIntent {
+        ControlIntent {
+            thrust: ((frame / 3 + seat) % 4) as f32 * 0.25,
+            turn: if (frame / 7) % 2 == seat { 0.5 } else { 0.0 },
+            ..ControlIntent::default()
+        }
+    }
+
+    fn shim(udp: UdpLink, seed: u64) -> Box<dyn Link> {
+        Box::new(LossyLink::with_seed(udp, 0.2, Duration::from_millis(15), seed))
+    }
+
+    /// Plays until both peers have verified `frames` frames against each other.
+    fn play(netplay: &mut Netplay<Track>, frames: usize, done: &AtomicUsize) {
+        let mut game = Track::default();
+        let seat = netplay.local_seat();
+        let mut polled = 0;
+        let mut finished = false;
+        let deadline = Instant::now() + Duration::from_secs(20);
+        while done.load(Ordering::SeqCst) < 2 {
+            assert!(Instant::now() < deadline, "peers never verified {frames} frames");
+            netplay.tick(&mut game, |_| {
+                polled += 1;
+                pattern(seat, polled)
+            });
+            assert!(netplay.desync().is_none());
+            if !finished && netplay.status().stats.verified >= Some(frames) {
+                finished = true;
+                done.fetch_add(1, Ordering::SeqCst);
+            }
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn resimulated_frames_replace_their_predicted_events() {
+        let mut simulation =
+            Simulation::with_world(GameConfig::default(), 3, macroquad::prelude::vec2(800.0, 600.0));
+        let mut events = FrameEvents::default();
+        let fire = ControlIntent {
+            fire_primary: true,
+            ..ControlIntent::default()
+        };
+        let fired = |frame: &[SimEvent]| {
+            frame.iter().any(|event| matches!(event, SimEvent::BulletFired { .. }))
+        };
+
+        let mut tracked = Tracked {
+            simulation: &mut simulation,
+            events: &mut events,
+        };
+        let before = tracked.save();
+        tracked.advance(&[fire]);
+        tracked.advance(&[ControlIntent::default()]);
+        // The guess for frame 1 was wrong: it is run again without the shot.
+        tracked.restore(&before);
+        tracked.advance(&[ControlIntent::default()]);
+
+        let confirmed = events.take_through(1);
+        assert_eq!(confirmed.len(), 1);
+        assert_eq!(confirmed[0].0, 1);
+        assert!(!fired(&confirmed[0].1));
+        let rest = events.take_through(10);
+        assert_eq!(rest.iter().map(|(frame, _)| *frame).collect::<Vec<_>>(), [2]);
+        assert!(events.take_through(10).is_empty());
+    }
+
+    #[test]
+    fn peers_over_a_lossy_localhost_link_stay_in_sync() {
+        let frames = 4 * rollback::CHECK_INTERVAL;
+        let done = Arc::new(AtomicUsize::new(0));
+        // Borrow a free port from the OS for the host to listen on.
+        let port = std::net::UdpSocket::bind("127.0.0.1:0")
+            .and_then(|socket| socket.local_addr())
+            .unwrap()
+            .port();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        let host_done = Arc::clone(&done);
+        let host = std::thread::spawn(move || {
+            let udp = UdpLink::host(port).unwrap();
+            ready_tx.send(()).unwrap();
+            let agreement = Agreement {
+                seed: 7,
+                window: (800, 600),
+                input_delay: 2,
+                config: GameConfig::default(),
+            };
+            let mut netplay = Netplay::host(shim(udp, 1), &agreement, JOIN_WAIT).unwrap();
+            play(&mut netplay, frames, &host_done);
+            netplay.status().stats
+        });
+
+        ready_rx.recv().unwrap();
+        let udp = UdpLink::join(&format!("127.0.0.1:{port}")).unwrap();
+        let (mut netplay, agreement) = Netplay::<Track>::join(shim(udp, 2), JOIN_WAIT).unwrap();
+        assert_eq!((agreement.seed, agreement.window, agreement.input_delay), (7, (800, 600), 2));
+        play(&mut netplay, frames, &done);
+
+        // Every 30 frames each peer compared checksums and found them equal; a mismatch would
+        // have failed `play`. With a lossy link, some predictions were wrong along the way.
+        let host_stats = host.join().unwrap();
+        assert!(host_stats.rolled_back_frames + netplay.status().stats.rolled_back_frames > 0);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::config::{self, GameConfig};
+use crate::controllers::ControlIntent;
+use crate::replay::{format_intent, parse_intent, parse_window};
+
+/// Bumped whenever packets or the simulation change in a way that would desync older builds.
+pub const PROTOCOL_VERSION: u32 = 3;
+
+/// Everything the peers say to each other. Packets are plain text, one message per datagram,
+/// so a capture can be read by eye.
+pub enum Message {
+    /// The joining peer, repeated until it hears a `Welcome`.
+    Hello { version: u32 },
+    /// The host's answer: the run both peers will simulate.
+    Welcome {
+        version: u32,
+        seed: u64,
+        window: (u32, u32),
+        input_delay: usize,
+        config: Box<GameConfig>,
+    },
+    /// The sender's input from frame `start`, everything the receiver has not acknowledged yet.
+    Input {
+        /// How many of the receiver's input frames the sender has.
+        ack: usize,
+        start: usize,
+        /// The sender's latest settled checksum: frame and value.
+        check: Option<(usize, u64)>,
+        intents: Vec<ControlIntent>,
+    },
+    /// The sender left the game.
+    Bye,
+}
+
+impl Message {
+    pub fn encode(&self) -> Vec<u8> {
+        let text = match self {
+            Message::Hello { version } => format!("HELLO {version}"),
+            Message::Welcome {
+                version,
+                seed,
+                window,
+                input_delay,
+                config,
+            } => {
+                let mut text = format!(
+                    "WELCOME {version} {seed} {}x{} {input_delay}\n",
+                    window.0, window.1
+                );
+                config::write_config(&mut text, config);
+                text
+            }
+            Message::Input {
+                ack,
+                start,
+                check,
+                intents,
+            } => {
+                let check = match check {
+                    Some((frame, checksum)) => format!("{frame} {checksum:016x}"),
+                    None => "- -".to_string(),
+                };
+                let mut text = format!("INPUT {ack} {start} {check}");
+                for intent in intents {
+                    text.push_str("  ");
+                    text.push_str(&format_intent(intent));
+                }
+                text
+            }
+            Message::Bye => "BYE".to_string(),
+        };
+        text.into_bytes()
+    }
+
+    pub fn decode(packet: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(packet).map_err(|_| "packet is not text".to_string())?;
+        let (first, rest) = text.split_once('\n').unwrap_or((text, ""));
+        let fields: Vec<&str> = first.split_whitespace().collect();
+        let number = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("`{value}` is not a whole number"))
+        };
+        match fields.as_slice() {
+            ["HELLO", version] => Ok(Message::Hello {
+                version: number(version)? as u32,
+            }),
+            ["WELCOME", version, seed, window, input_delay] => {
+                let mut config = GameConfig::default();
+                for line in rest.lines().filter(|line| !line.trim().is_empty()) {
+                    let Some((key, value)) = line.split_once('=') else {
+                        return Err(format!("expected `key = value`, found `{line}`"));
+                    };
+                    config::apply_setting(&mut config, key.trim(), value.trim())?;
+                }
+                Ok(Message::Welcome {
+                    version: number(version)? as u32,
+                    seed: number(seed)?,
+                    window: parse_window(window)?,
+                    input_delay: number(input_delay)? as usize,
+                    config: Box::new(config),
+                })
+            }
+            ["INPUT", ack, start, check_frame, check_sum, intents @ ..] => {
+                let check = match (*check_frame, *check_sum) {
+                    ("-", "-") => None,
+                    (frame, sum) => Some((
+                        number(frame)? as usize,
+                        u64::from_str_radix(sum, 16)
+                            .map_err(|_| format!("`{sum}` is not a checksum"))?,
+                    )),
+                };
+                if intents.len() % 5 != 0 {
+                    return Err(format!("{} intent fields is not whole intents", intents.len()));
+                }
+                Ok(Message::Input {
+                    ack: number(ack)? as usize,
+                    start: number(start)? as usize,
+                    check,
+                    intents: intents.chunks(5).map(parse_intent).collect::<Result<_, _>>()?,
+                })
+            }
+            ["BYE"] => Ok(Message::Bye),
+            _ => Err(format!("unknown packet `{first}`")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PlayerControllerMode;
+
+    #[test]
+    fn messages_round_trip() {
+        let mut config = GameConfig::default();
+        config.coop.enabled = true;
+        config.coop.partner = PlayerControllerMode::Human;
+        config.tuning.ship_thrust *= 1.5;
+        let messages = [
+            Message::Hello {
+                version: PROTOCOL_VERSION,
+            },
+            Message::Welcome {
+                version: PROTOCOL_VERSION,
+                seed: u64::MAX,
+                window: (1280, 720),
+                input_delay: 3,
+                config: Box::new(config),
+            },
+            Message::Input {
+                ack: 40,
+                start: 37,
+                check: Some((30, 0xdead_beef_0000_0001)),
+                intents: vec![
+                    ControlIntent {
+                        thrust: 1.0,
+                        turn: -0.123_456_79,
+                        fire_primary: true,
+                        ..ControlIntent::default()
+                    },
+                    ControlIntent::default(),
+                ],
+            },
+            Message::Input {
+                ack: 0,
+                start: 0,
+                check: None,
+                intents: Vec::new(),
+            },
+            Message::Bye,
+        ];
+        for message in messages {
+            let packet = message.encode();
+            assert_eq!(Message::decode(&packet).unwrap().encode(), packet);
+        }
+
+        let Ok(Message::Input { check, intents, .. }) = Message::decode(b"INPUT 4 2 30 ff  0.5 -1 0 1 0")
+        else {
+            panic!("input packet did not decode");
+        };
+        assert_eq!(check, Some((30, 0xff)));
+        assert_eq!(intents[0].turn, -1.0);
+        assert!(intents[0].fire_secondary);
+    }
+
+    #[test]
+    fn garbage_is_rejected() {
+        assert!(Message::decode(b"HELLO").is_err());
+        assert!(Message::decode(b"INPUT 1 2 - - 1 0 0").is_err());
+        assert!(Message::decode(b"INPUT 1 2 30 zz").is_err());
+        assert!(Message::decode(&[0xff, 0xfe]).is_err());
+        assert!(Message::decode(b"WELCOME 1 2 800x600 2\nlives = lots").is_err());
+    }
+}
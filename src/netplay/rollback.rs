@@ -0,0 +1,370 @@
+use crate::controllers::ControlIntent;
+use std::collections::{BTreeMap, VecDeque};
+
+/// States are compared between peers every this many frames.
+pub const CHECK_INTERVAL: usize = 30;
+/// Compared-or-not, only the most recent checks are kept.
+const CHECKS_KEPT: usize = 8;
+
+/// A game that can be stepped with explicit intents and rewound to a saved state.
+pub trait Rollback {
+    type State: Clone;
+    fn save(&self) -> Self::State;
+    fn restore(&mut self, state: &Self::State);
+    /// Runs one frame with one intent per seat, in seat order.
+    fn advance(&mut self, intents: &[ControlIntent]);
+    fn checksum(state: &Self::State) -> u64;
+}
+
+/// The two peers' states stopped matching.
+pub struct Desync<S> {
+    pub frame: usize,
+    pub local: u64,
+    pub remote: u64,
+    /// Our state at `frame`, for writing out and diffing against the peer's.
+    pub state: S,
+}
+
+/// Two-seat rollback bookkeeping: local input is known ahead of time (by the input delay),
+/// remote input is predicted as "same as last frame" until it arrives, and a wrong guess
+/// rewinds to the saved state before that frame and re-simulates up to the present.
+pub struct RollbackSession<S> {
+    local_seat: usize,
+    max_prediction: usize,
+    /// Frames simulated so far; the next frame to run.
+    frame: usize,
+    /// Our input by frame. The first `input_delay` frames are idle on both peers.
+    local: Vec<ControlIntent>,
+    /// The peer's confirmed input by frame, contiguous from frame 0.
+    remote: Vec<ControlIntent>,
+    /// The remote input assumed for each frame from `remote.len()` up to `frame`.
+    predicted: VecDeque<ControlIntent>,
+    /// Earliest frame whose prediction turned out wrong.
+    rollback_from: Option<usize>,
+    /// The state before each frame from `states_base` up to `frame`.
+    states: VecDeque<S>,
+    states_base: usize,
+    /// Our checksums at check frames, with the state they came from.
+    local_checks: BTreeMap<usize, (u64, S)>,
+    remote_checks: BTreeMap<usize, u64>,
+    /// Check frames up to this one have been computed.
+    checked_through: usize,
+    desync: Option<Desync<S>>,
+    pub stats: RollbackStats,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct RollbackStats {
+    pub frame: usize,
+    /// How far the simulation runs ahead of the peer's confirmed input.
+    pub prediction: usize,
+    /// Frames re-simulated by the latest rollback, and in total.
+    pub last_rollback: usize,
+    pub rolled_back_frames: u64,
+    /// Ticks spent waiting because the prediction window was full.
+    pub stalls: u64,
+    /// The latest frame whose checksum matched the peer's.
+    pub verified: Option<usize>,
+}
+
+impl<S: Clone> RollbackSession<S> {
+    pub fn new(local_seat: usize, input_delay: usize, max_prediction: usize) -> Self {
+        Self {
+            local_seat,
+            max_prediction,
+            frame: 0,
+            local: vec![ControlIntent::default(); input_delay],
+            remote: vec![ControlIntent::default(); input_delay],
+            predicted: VecDeque::new(),
+            rollback_from: None,
+            states: VecDeque::new(),
+            states_base: 0,
+            local_checks: BTreeMap::new(),
+            remote_checks: BTreeMap::new(),
+            checked_through: 0,
+            desync: None,
+            stats: RollbackStats::default(),
+        }
+    }
+
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+
+    /// Peer input frames confirmed so far; sent back as the ack.
+    pub fn confirmed(&self) -> usize {
+        self.remote.len()
+    }
+
+    /// Our input from `from` onwards, for resending until the peer acks it.
+    pub fn local_inputs(&self, from: usize) -> &[ControlIntent] {
+        &self.local[from.min(self.local.len())..]
+    }
+
+    /// Takes the peer's input for frames `start..`. Frames already confirmed are ignored, as is
+    /// anything after a gap; the peer resends until acked.
+    pub fn receive_remote(&mut self, start: usize, intents: &[ControlIntent]) {
+        for (frame, intent) in (start..).zip(intents) {
+            if frame < self.remote.len() {
+                continue;
+            }
+            if frame > self.remote.len() {
+                break;
+            }
+            if let Some(guess) = self.predicted.pop_front()
+                && guess != *intent
+                && self.rollback_from.is_none()
+            {
+                self.rollback_from = Some(frame);
+            }
+            self.remote.push(*intent);
+        }
+    }
+
+    /// The peer's checksum for a check frame.
+    pub fn receive_check(&mut self, frame: usize, checksum: u64) {
+        match self.local_checks.get(&frame) {
+            Some((local, state)) => {
+                let (local, state) = (*local, state.clone());
+                self.compare(frame, local, checksum, state);
+            }
+            None if frame > self.checked_through => {
+                self.remote_checks.insert(frame, checksum);
+                while self.remote_checks.len() > CHECKS_KEPT {
+                    self.remote_checks.pop_first();
+                }
+            }
+            None => {}
+        }
+    }
+
+    /// Our latest check, repeated in every packet so a lost one does not matter.
+    pub fn latest_check(&self) -> Option<(usize, u64)> {
+        self.local_checks
+            .last_key_value()
+            .map(|(frame, (checksum, _))| (*frame, *checksum))
+    }
+
+    pub fn desync(&self) -> Option<&Desync<S>> {
+        self.desync.as_ref()
+    }
+
+    /// Repairs any misprediction, then runs the next frame unless that would take the
+    /// simulation too far past the peer's confirmed input. `poll_local` is asked for our input
+    /// only when a frame is run. Returns whether one was.
+    pub fn advance<G: Rollback<State = S>>(
+        &mut self,
+        game: &mut G,
+        poll_local: impl FnOnce(&mut G) -> ControlIntent,
+    ) -> bool {
+        self.stats.last_rollback = 0;
+        if let Some(from) = self.rollback_from.take() {
+            self.resimulate(game, from);
+        }
+        self.record_checks(game);
+
+        let advanced = self.frame < self.remote.len() + self.max_prediction;
+        if advanced {
+            let input = poll_local(game);
+            self.local.push(input);
+            self.run_frame(game);
+        } else {
+            self.stats.stalls += 1;
+        }
+
+        // Nothing before the first unconfirmed frame can be rolled back to again, but the next
+        // check frame's state is kept until it has been checksummed.
+        let keep_from = self.remote.len().min(self.frame).min(self.next_check());
+        while self.states_base < keep_from && !self.states.is_empty() {
+            self.states.pop_front();
+            self.states_base += 1;
+        }
+        self.stats.frame = self.frame;
+        self.stats.prediction = self.frame.saturating_sub(self.remote.len());
+        advanced
+    }
+
+    fn resimulate<G: Rollback<State = S>>(&mut self, game: &mut G, from: usize) {
+        let Some(state) = self.states.get(from - self.states_base) else {
+            return;
+        };
+        game.restore(state);
+        let to = self.frame;
+        self.states.truncate(from - self.states_base);
+        self.predicted.clear();
+        self.frame = from;
+        while self.frame < to {
+            self.run_frame(game);
+        }
+        self.stats.last_rollback = to - from;
+        self.stats.rolled_back_frames += (to - from) as u64;
+    }
+
+    /// Saves the state, then runs `self.frame` with confirmed or predicted remote input.
+    fn run_frame<G: Rollback<State = S>>(&mut self, game: &mut G) {
+        let remote = match self.remote.get(self.frame) {
+            Some(intent) => *intent,
+            None => {
+                let guess = self.remote.last().copied().unwrap_or_default();
+                self.predicted.push_back(guess);
+                guess
+            }
+        };
+        let local = self.local[self.frame];
+        let intents = if self.local_seat == 0 {
+            [local, remote]
+        } else {
+            [remote, local]
+        };
+        self.states.push_back(game.save());
+        game.advance(&intents);
+        self.frame += 1;
+    }
+
+    /// Checksums every check frame whose state no longer depends on a prediction.
+    fn record_checks<G: Rollback<State = S>>(&mut self, game: &G) {
+        let settled = self.remote.len().min(self.frame);
+        let mut frame = self.next_check();
+        while frame <= settled {
+            let state = if frame == self.frame {
+                game.save()
+            } else {
+                match self.states.get(frame - self.states_base) {
+                    Some(state) => state.clone(),
+                    None => break,
+                }
+            };
+            let checksum = G::checksum(&state);
+            if let Some(remote) = self.remote_checks.remove(&frame) {
+                self.compare(frame, checksum, remote, state.clone());
+            }
+            self.local_checks.insert(frame, (checksum, state));
+            while self.local_checks.len() > CHECKS_KEPT {
+                self.local_checks.pop_first();
+            }
+            self.checked_through = frame;
+            frame += CHECK_INTERVAL;
+        }
+    }
+
+    fn next_check(&self) -> usize {
+        (self.checked_through / CHECK_INTERVAL + 1) * CHECK_INTERVAL
+    }
+
+    fn compare(&mut self, frame: usize, local: u64, remote: u64, state: S) {
+        if local == remote {
+            self.stats.verified = self.stats.verified.max(Some(frame));
+        } else if self.desync.is_none() {
+            self.desync = Some(Desync {
+                frame,
+                local,
+                remote,
+                state,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Mixes every intent into a running hash, so any input difference shows up in the state.
+    #[derive(Clone, Default)]
+    struct Mixer {
+        value: u64,
+    }
+
+    impl Rollback for Mixer {
+        type State = Mixer;
+        fn save(&self) -> Mixer {
+            self.clone()
+        }
+        fn restore(&mut self, state: &Mixer) {
+            *self = state.clone();
+        }
+        fn advance(&mut self, intents: &[ControlIntent]) {
+            for intent in intents {
+                self.value = self.value.wrapping_mul(31).wrapping_add(intent.thrust.to_bits().into());
+            }
+        }
+        fn checksum(state: &Mixer) -> u64 {
+            state.value
+        }
+    }
+
+    fn input(frame: usize) -> ControlIntent {
+        ControlIntent {
+            thrust: (frame % 7) as f32 / 7.0,
+            ..ControlIntent::default()
+        }
+    }
+
+    #[test]
+    fn late_remote_input_rolls_back_to_the_same_result() {
+        let delay = 2;
+        let mut late = RollbackSession::new(0, delay, 8);
+        let mut game = Mixer::default();
+        // Both peers start with `delay` idle frames.
+        let remote: Vec<ControlIntent> = (0..60)
+            .map(|frame| if frame < delay { ControlIntent::default() } else { input(frame) })
+            .collect();
+
+        let mut polled = 0;
+        for frame in 0..40 {
+            // The peer's input arrives in bursts of five, four frames late.
+            if frame >= 4 && frame % 5 == 0 {
+                let start = late.confirmed();
+                late.receive_remote(start, &remote[start..frame - 4 + delay]);
+            }
+            late.advance(&mut game, |_| {
+                polled += 1;
+                input(polled + 3)
+            });
+        }
+        let start = late.confirmed();
+        late.receive_remote(start, &remote[start..]);
+        late.advance(&mut game, |_| input(0));
+
+        let mut reference = Mixer::default();
+        for (local, remote) in late.local.iter().zip(&remote).take(late.frame()) {
+            reference.advance(&[*local, *remote]);
+        }
+        assert_eq!(game.value, reference.value);
+        assert!(late.stats.rolled_back_frames > 0);
+    }
+
+    #[test]
+    fn prediction_window_stalls_without_remote_input() {
+        let mut session = RollbackSession::new(1, 0, 3);
+        let mut game = Mixer::default();
+        let ran: Vec<bool> = (0..5)
+            .map(|_| session.advance(&mut game, |_| ControlIntent::default()))
+            .collect();
+        assert_eq!(ran, [true, true, true, false, false]);
+        assert_eq!(session.stats.stalls, 2);
+
+        session.receive_remote(0, &[ControlIntent::default(); 2]);
+        assert!(session.advance(&mut game, |_| ControlIntent::default()));
+    }
+
+    #[test]
+    fn mismatched_checks_report_a_desync() {
+        let mut session = RollbackSession::new(0, 0, 64);
+        let mut game = Mixer::default();
+        let idle = vec![ControlIntent::default(); 2 * CHECK_INTERVAL];
+        session.receive_remote(0, &idle);
+        for _ in 0..=2 * CHECK_INTERVAL {
+            session.advance(&mut game, |_| ControlIntent::default());
+        }
+        let (frame, checksum) = session.latest_check().unwrap();
+        assert_eq!(frame, 2 * CHECK_INTERVAL);
+
+        session.receive_check(CHECK_INTERVAL, session.local_checks[&CHECK_INTERVAL].0);
+        assert!(session.desync().is_none());
+        assert_eq!(session.stats.verified, Some(CHECK_INTERVAL));
+        session.receive_check(frame, checksum ^ 1);
+        let desync = session.desync().unwrap();
+        assert_eq!((desync.frame, desync.local), (frame, checksum));
+    }
+}
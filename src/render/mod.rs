@@ -1 +1,2 @@
 pub mod shapes;
+pub mod viewport;
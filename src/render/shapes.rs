@@ -157,3 +157,55 @@ pub fn saucer_small_lines(scale: f32) -> Vec<(Vec2, Vec2)> {
 
     lines
 }
+
+/// Hexagonal hull with a launch bay slot across the middle.
+pub fn carrier_lines(scale: f32) -> Vec<(Vec2, Vec2)> {
+    let hull = [
+        (-1.0, 0.0),
+        (-0.6, 0.55),
+        (0.6, 0.55),
+        (1.0, 0.0),
+        (0.6, -0.55),
+        (-0.6, -0.55),
+        (-1.0, 0.0),
+    ];
+
+    let mut lines = hull
+        .windows(2)
+        .map(|window| {
+            (
+                scaled_point(window[0].0, window[0].1, scale),
+                scaled_point(window[1].0, window[1].1, scale),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    lines.push((scaled_point(-0.5, 0.15, scale), scaled_point(0.5, 0.15, scale)));
+    lines.push((scaled_point(-0.5, -0.15, scale), scaled_point(0.5, -0.15, scale)));
+    lines
+}
+
+/// Small arrowhead pointing along +x.
+pub fn drone_lines(scale: f32) -> Vec<(Vec2, Vec2)> {
+    let nose = scaled_point(1.0, 0.0, scale);
+    let left = scaled_point(-0.8, 0.7, scale);
+    let right = scaled_point(-0.8, -0.7, scale);
+    let tail = scaled_point(-0.4, 0.0, scale);
+    vec![(nose, left), (left, tail), (tail, right), (right, nose)]
+}
+
+/// Diamond turret body with a crosshair.
+pub fn sentinel_lines(scale: f32) -> Vec<(Vec2, Vec2)> {
+    let top = scaled_point(0.0, 1.0, scale);
+    let right = scaled_point(1.0, 0.0, scale);
+    let bottom = scaled_point(0.0, -1.0, scale);
+    let left = scaled_point(-1.0, 0.0, scale);
+    vec![
+        (top, right),
+        (right, bottom),
+        (bottom, left),
+        (left, top),
+        (scaled_point(-0.4, 0.0, scale), scaled_point(0.4, 0.0, scale)),
+        (scaled_point(0.0, -0.4, scale), scaled_point(0.0, 0.4, scale)),
+    ]
+}
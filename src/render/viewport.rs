@@ -0,0 +1,57 @@
+use macroquad::prelude::{Camera2D, Rect, Vec2, screen_height, screen_width, vec2};
+
+/// Where the playfield sits in the window: the world scaled to fit and centred, with bars on
+/// the sides when the window's shape differs. Live runs keep the world the window's size, so
+/// this only scales recorded, replayed and netplay runs, whose world is fixed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub scale: f32,
+    /// Screen position of the world's top-left corner.
+    pub offset: Vec2,
+    screen: Vec2,
+}
+
+impl Viewport {
+    pub fn fit(world: Vec2, screen: Vec2) -> Self {
+        let scale = (screen.x / world.x).min(screen.y / world.y);
+        Self {
+            scale,
+            offset: (screen - world * scale) / 2.0,
+            screen,
+        }
+    }
+
+    /// The viewport for the window as it is this frame.
+    pub fn current(world: Vec2) -> Self {
+        Self::fit(world, vec2(screen_width(), screen_height()))
+    }
+
+    /// The world point under a screen position, e.g. the mouse cursor.
+    pub fn world_point(&self, point: Vec2) -> Vec2 {
+        (point - self.offset) / self.scale
+    }
+
+    /// A camera that draws world coordinates into this viewport.
+    pub fn camera(&self) -> Camera2D {
+        let corner = self.world_point(Vec2::ZERO);
+        let size = self.screen / self.scale;
+        Camera2D::from_display_rect(Rect::new(corner.x, corner.y, size.x, size.y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wider_windows_letterbox_the_world_at_the_sides() {
+        let viewport = Viewport::fit(vec2(800.0, 600.0), vec2(1600.0, 900.0));
+        assert_eq!(viewport.scale, 1.5);
+        assert_eq!(viewport.offset, vec2(200.0, 0.0));
+        assert_eq!(viewport.world_point(vec2(200.0, 0.0)), Vec2::ZERO);
+        assert_eq!(viewport.world_point(vec2(800.0, 450.0)), vec2(400.0, 300.0));
+
+        let same = Viewport::fit(vec2(800.0, 600.0), vec2(800.0, 600.0));
+        assert_eq!(same.world_point(vec2(123.0, 45.0)), vec2(123.0, 45.0));
+    }
+}
@@ -0,0 +1,459 @@
+use crate::ai::WorldSnapshot;
+use crate::config::{self, GameConfig};
+use crate::controllers::{ControlIntent, Controller};
+use crate::simulation::{Simulation, StateDump};
+use macroquad::prelude::vec2;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Version 2 added state checksums. Version 3 came with bodies removed by swapping, which
+/// changes the order collisions resolve and random numbers are drawn in; older files no longer
+/// play back the way they were recorded, so they are refused.
+const REPLAY_VERSION: u32 = 3;
+const OLDEST_REPLAY_VERSION: u32 = 3;
+/// A full state is written every this many frames, for field-level diffs when verifying.
+const STATE_INTERVAL: usize = 600;
+/// Differing fields listed in a divergence report.
+const DIFF_LINES: usize = 20;
+
+/// Everything needed to re-run a game exactly: the seed, the resolved configuration, the window
+/// size the simulation saw, and one frame of `ControlIntent`s (one per seat) per simulation step.
+/// Each frame also carries the checksum of the state after it, and every `STATE_INTERVAL` frames
+/// the whole state is kept, so a re-run can tell where and how it went differently.
+#[derive(Clone)]
+pub struct Replay {
+    pub seed: u64,
+    pub window: (u32, u32),
+    pub config: GameConfig,
+    pub frames: Vec<Vec<ControlIntent>>,
+    /// The checksum after each frame, parallel to `frames`.
+    pub checksums: Vec<u64>,
+    /// Full states by frame number (steps run so far).
+    pub states: Vec<(usize, StateDump)>,
+}
+
+/// Where a re-run first stopped matching its replay.
+pub struct Divergence {
+    /// Frame number (steps run) whose checksum differed.
+    pub frame: usize,
+    pub expected: u64,
+    pub actual: u64,
+    /// Differing fields at the first recorded state at or after `frame`, if there is one.
+    pub diff: Option<(usize, Vec<String>)>,
+}
+
+enum Section {
+    Header,
+    Config,
+    Intents,
+    State,
+}
+
+impl Replay {
+    pub fn new(seed: u64, window: (u32, u32), config: GameConfig) -> Self {
+        Self {
+            seed,
+            window,
+            config,
+            frames: Vec::new(),
+            checksums: Vec::new(),
+            states: Vec::new(),
+        }
+    }
+
+    /// Appends a step: the intents it ran with and the simulation as it was afterwards.
+    pub fn record_frame(&mut self, intents: &[ControlIntent], simulation: &Simulation) {
+        self.frames.push(intents.to_vec());
+        self.checksums.push(simulation.checksum());
+        if self.frames.len().is_multiple_of(STATE_INTERVAL) {
+            self.states.push((self.frames.len(), simulation.state_dump()));
+        }
+    }
+
+    /// Drops everything after the first `frames` frames, when play resumes from a rewound point.
+    pub fn truncate(&mut self, frames: usize) {
+        self.frames.truncate(frames);
+        self.checksums.truncate(frames);
+        self.states.retain(|(at, _)| *at <= frames);
+    }
+
+    /// Re-simulates the replay without a window and compares every frame's checksum with the
+    /// recorded one. Returns the number of frames that matched.
+    pub fn verify(&self) -> Result<usize, Divergence> {
+        let world = vec2(self.window.0 as f32, self.window.1 as f32);
+        let mut simulation = Simulation::with_world(self.config.clone(), self.seed, world);
+        let mut diverged: Option<Divergence> = None;
+        for (frame, (intents, expected)) in (1..).zip(self.frames.iter().zip(&self.checksums)) {
+            simulation.apply_intents(intents);
+            simulation.step();
+            let actual = simulation.checksum();
+            if diverged.is_none() && actual != *expected {
+                diverged = Some(Divergence {
+                    frame,
+                    expected: *expected,
+                    actual,
+                    diff: None,
+                });
+            }
+            // After a mismatch, keep going to the next recorded state to see which fields differ.
+            if let Some(divergence) = &mut diverged
+                && let Some((_, state)) = self.states.iter().find(|(at, _)| *at == frame)
+            {
+                divergence.diff = Some((frame, state.diff(&simulation.state_dump())));
+                break;
+            }
+        }
+        match diverged {
+            Some(divergence) => Err(divergence),
+            None => Ok(self.checksums.len()),
+        }
+    }
+
+    /// One seat's intents across the whole run, for its `ReplayController`.
+    pub fn seat_intents(&self, seat: usize) -> Vec<ControlIntent> {
+        self.frames
+            .iter()
+            .map(|frame| frame.get(seat).copied().unwrap_or_default())
+            .collect()
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        Self::parse(&text).map_err(|err| format!("{}: {err}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.serialize())
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut replay = Replay::new(0, (0, 0), GameConfig::default());
+        let mut version = None;
+        let mut section = Section::Header;
+
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let at_line = |message: String| format!("line {}: {message}", index + 1);
+            match line {
+                "[config]" => {
+                    section = Section::Config;
+                    continue;
+                }
+                "[intents]" => {
+                    section = Section::Intents;
+                    continue;
+                }
+                _ => {}
+            }
+            if let Some(frame) = line.strip_prefix("[state ").and_then(|rest| rest.strip_suffix(']')) {
+                let frame = frame
+                    .trim()
+                    .parse()
+                    .map_err(|_| at_line(format!("`{frame}` is not a frame number")))?;
+                replay.states.push((frame, StateDump::default()));
+                section = Section::State;
+                continue;
+            }
+
+            match section {
+                Section::Intents => {
+                    let (intents, checksum) = line.split_once('@').unwrap_or((line, ""));
+                    let frame = parse_frame(intents, replay.config.seats()).map_err(at_line)?;
+                    replay.frames.push(frame);
+                    if !checksum.trim().is_empty() {
+                        let checksum = u64::from_str_radix(checksum.trim(), 16).map_err(|_| {
+                            at_line(format!("`{}` is not a checksum", checksum.trim()))
+                        })?;
+                        replay.checksums.push(checksum);
+                    }
+                }
+                Section::State => {
+                    if let Some((_, state)) = replay.states.last_mut() {
+                        state.push_line(line).map_err(at_line)?;
+                    }
+                }
+                Section::Header | Section::Config => {
+                    let Some((key, value)) = line.split_once('=') else {
+                        return Err(at_line("expected `key = value`".to_string()));
+                    };
+                    let (key, value) = (key.trim(), value.trim());
+                    if let Section::Config = section {
+                        config::apply_setting(&mut replay.config, key, value).map_err(at_line)?;
+                        continue;
+                    }
+                    match key {
+                        "version" => version = value.parse().ok(),
+                        "seed" => {
+                            replay.seed = value
+                                .parse()
+                                .map_err(|_| at_line(format!("`{value}` is not a valid seed")))?
+                        }
+                        "window" => {
+                            replay.window = parse_window(value).map_err(at_line)?;
+                        }
+                        _ => return Err(at_line(format!("unknown key `{key}`"))),
+                    }
+                }
+            }
+        }
+
+        match version {
+            Some(OLDEST_REPLAY_VERSION..=REPLAY_VERSION) => {}
+            Some(other) => {
+                return Err(format!(
+                    "replay version {other} is not supported (expected {OLDEST_REPLAY_VERSION} to {REPLAY_VERSION})"
+                ));
+            }
+            None => return Err("not a replay file (missing `version`)".to_string()),
+        }
+        if !replay.checksums.is_empty() && replay.checksums.len() != replay.frames.len() {
+            return Err(format!(
+                "{} of {} frames have a checksum; expected all or none",
+                replay.checksums.len(),
+                replay.frames.len()
+            ));
+        }
+        Ok(replay)
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut out = String::from("# Asteroids replay\n");
+        out.push_str(&format!("version = {REPLAY_VERSION}\n"));
+        out.push_str(&format!("seed = {}\n", self.seed));
+        out.push_str(&format!("window = {}x{}\n", self.window.0, self.window.1));
+        out.push_str("\n[config]\n");
+        config::write_config(&mut out, &self.config);
+        out.push_str("\n[intents]\n");
+        out.push_str("# thrust turn primary secondary sentinel (repeated per seat) @ checksum after the step\n");
+        for (index, frame) in self.frames.iter().enumerate() {
+            let fields: Vec<String> = frame.iter().map(format_intent).collect();
+            out.push_str(&fields.join("  "));
+            if let Some(checksum) = self.checksums.get(index) {
+                out.push_str(&format!("  @ {checksum:016x}"));
+            }
+            out.push('\n');
+        }
+        for (frame, state) in &self.states {
+            out.push_str(&format!("\n[state {frame}]\n{state}"));
+        }
+        out
+    }
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "diverged at frame {}: checksum {:016x}, recorded {:016x}",
+            self.frame, self.actual, self.expected
+        )?;
+        let Some((frame, lines)) = &self.diff else {
+            return writeln!(f, "no recorded state from frame {} on to compare fields", self.frame);
+        };
+        writeln!(f, "{} fields differ at frame {frame}:", lines.len())?;
+        for line in lines.iter().take(DIFF_LINES) {
+            writeln!(f, "  {line}")?;
+        }
+        if lines.len() > DIFF_LINES {
+            writeln!(f, "  ... and {} more", lines.len() - DIFF_LINES)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `WIDTHxHEIGHT`, as used by replay headers and `--window`.
+pub fn parse_window(value: &str) -> Result<(u32, u32), String> {
+    let parsed = value
+        .split_once('x')
+        .and_then(|(w, h)| Some((w.trim().parse().ok()?, h.trim().parse().ok()?)));
+    match parsed {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("`{value}` is not a window size like 1280x720")),
+    }
+}
+
+/// Parses one step: five fields for each seat, in seat order.
+fn parse_frame(line: &str, seats: usize) -> Result<Vec<ControlIntent>, String> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != seats * 5 {
+        return Err(format!(
+            "expected {} intent fields, found {}",
+            seats * 5,
+            fields.len()
+        ));
+    }
+    fields.chunks(5).map(parse_intent).collect()
+}
+
+/// One intent as five space-separated fields; netplay packets use the same layout.
+pub fn format_intent(intent: &ControlIntent) -> String {
+    format!(
+        "{} {} {} {} {}",
+        intent.thrust,
+        intent.turn,
+        u8::from(intent.fire_primary),
+        u8::from(intent.fire_secondary),
+        u8::from(intent.deploy_sentinel),
+    )
+}
+
+pub fn parse_intent(fields: &[&str]) -> Result<ControlIntent, String> {
+    let [thrust, turn, primary, secondary, sentinel] = fields[..] else {
+        return Err(format!("expected 5 intent fields, found {}", fields.len()));
+    };
+    let number = |value: &str| {
+        value
+            .parse::<f32>()
+            .map_err(|_| format!("`{value}` is not a valid number"))
+    };
+    let flag = |value: &str| match value {
+        "0" => Ok(false),
+        "1" => Ok(true),
+        _ => Err(format!("`{value}` is not 0 or 1")),
+    };
+    Ok(ControlIntent {
+        thrust: number(thrust)?,
+        turn: number(turn)?,
+        fire_primary: flag(primary)?,
+        fire_secondary: flag(secondary)?,
+        deploy_sentinel: flag(sentinel)?,
+    })
+}
+
+/// Plays recorded intents back in order, then idles.
+pub struct ReplayController {
+    intents: std::vec::IntoIter<ControlIntent>,
+}
+
+impl ReplayController {
+    pub fn new(intents: Vec<ControlIntent>) -> Self {
+        Self {
+            intents: intents.into_iter(),
+        }
+    }
+}
+
+impl Controller for ReplayController {
+    fn tick(&mut self, _world: &WorldSnapshot, _dt: f32) -> ControlIntent {
+        self.intents.next().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CollisionPolicy;
+
+    #[test]
+    fn replay_round_trips_through_text() {
+        let config = GameConfig {
+            collision_policy: CollisionPolicy::Full,
+            ..GameConfig::default()
+        };
+        let mut replay = Replay::new(42, (1280, 720), config);
+        replay.frames.push(vec![ControlIntent {
+            thrust: 1.0,
+            turn: -0.35,
+            fire_primary: true,
+            ..ControlIntent::default()
+        }]);
+        replay.frames.push(vec![ControlIntent::default()]);
+
+        let text = replay.serialize();
+        let parsed = Replay::parse(&text).unwrap();
+        assert_eq!(parsed.seed, 42);
+        assert_eq!(parsed.window, (1280, 720));
+        assert!(parsed.config.collision_policy == CollisionPolicy::Full);
+        assert_eq!(parsed.frames.len(), 2);
+        assert_eq!(parsed.frames[0][0].turn, -0.35);
+        assert!(parsed.frames[0][0].fire_primary);
+        assert_eq!(parsed.serialize(), text);
+    }
+
+    #[test]
+    fn coop_replays_carry_one_intent_per_seat() {
+        let mut config = GameConfig::default();
+        config.toggle_coop();
+        let mut replay = Replay::new(7, (800, 600), config);
+        let partner = ControlIntent {
+            fire_secondary: true,
+            ..ControlIntent::default()
+        };
+        replay.frames.push(vec![ControlIntent::default(), partner]);
+
+        let parsed = Replay::parse(&replay.serialize()).unwrap();
+        assert_eq!(parsed.seat_intents(1).len(), 1);
+        assert!(parsed.seat_intents(1)[0].fire_secondary);
+        assert!(!parsed.seat_intents(0)[0].fire_secondary);
+
+        // A solo line in a co-op replay is malformed.
+        let solo = replay.serialize().replace("0 0 0 0 0  0 0 0 1 0", "0 0 0 0 0");
+        assert!(Replay::parse(&solo).is_err());
+    }
+
+    /// Records `frames` steps of a fixed input pattern, as a session with `--record` would.
+    fn recorded(frames: usize) -> Replay {
+        let mut replay = Replay::new(11, (800, 600), GameConfig::default());
+        let mut simulation = Simulation::with_world(replay.config.clone(), replay.seed, vec2(800.0, 600.0));
+        for frame in 0..frames {
+            let intents = [ControlIntent {
+                thrust: if frame % 90 < 40 { 1.0 } else { 0.0 },
+                turn: if frame % 150 < 60 { 0.5 } else { -0.25 },
+                fire_primary: frame % 12 == 0,
+                ..ControlIntent::default()
+            }];
+            simulation.apply_intents(&intents);
+            simulation.step();
+            replay.record_frame(&intents, &simulation);
+        }
+        replay
+    }
+
+    #[test]
+    fn recorded_checksums_verify() {
+        let replay = Replay::parse(&recorded(700).serialize()).unwrap();
+        assert_eq!(replay.checksums.len(), 700);
+        assert_eq!(replay.states.len(), 1);
+        assert!(replay.states[0].1 == recorded(600).states[0].1);
+        assert_eq!(replay.verify().ok(), Some(700));
+    }
+
+    #[test]
+    fn verify_reports_the_first_diverging_frame() {
+        let mut replay = recorded(700);
+        replay.frames[299][0].turn = 1.0;
+        let divergence = replay.verify().err().unwrap();
+        assert_eq!(divergence.frame, 300);
+        let (frame, diff) = divergence.diff.as_ref().unwrap();
+        assert_eq!(*frame, 600);
+        assert!(diff.iter().any(|line| line.starts_with("pilot[0].angle: expected")));
+        assert!(divergence.to_string().starts_with("diverged at frame 300"));
+
+        // Past the last recorded state there is nothing to compare fields with.
+        replay.frames[299] = recorded(300).frames[299].clone();
+        replay.frames[650][0].thrust = 0.5;
+        let late = replay.verify().err().unwrap();
+        assert_eq!(late.frame, 651);
+        assert!(late.diff.is_none());
+    }
+
+    #[test]
+    fn bad_replays_are_rejected() {
+        assert!(Replay::parse("seed = 1\n").is_err());
+        assert!(Replay::parse("version = 99\n").is_err());
+        let partial = "version = 3\n[intents]\n0 0 0 0 0  @ 00000000000000ff\n0 0 0 0 0\n";
+        assert!(Replay::parse(partial).err().unwrap().contains("checksum"));
+        let err = Replay::parse("version = 3\n[intents]\n1 0 1\n").err().unwrap();
+        assert!(err.starts_with("line 3"));
+        let old = Replay::parse("version = 2\n[intents]\n").err().unwrap();
+        assert!(old.contains("version 2 is not supported"), "{old}");
+        assert_eq!(parse_window("800x600"), Ok((800, 600)));
+        assert!(parse_window("800").is_err());
+        assert!(parse_window("0x600").is_err());
+    }
+}
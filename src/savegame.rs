@@ -0,0 +1,220 @@
+use crate::config::{self, GameConfig};
+use crate::simulation::Simulation;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Bumped whenever the file layout or the simulation state it holds changes. A file with another
+/// version is refused rather than half-restored.
+const SAVE_VERSION: u32 = 2;
+const SAVE_FILENAME: &str = "saved-run.txt";
+
+/// A run put aside with Save and quit: the configuration it was started with and its state at
+/// that moment, controllers aside.
+pub struct SavedRun {
+    pub config: GameConfig,
+    pub simulation: Simulation,
+}
+
+enum Section {
+    Header,
+    Config,
+    State,
+}
+
+impl SavedRun {
+    /// The single save slot, next to the settings file.
+    pub fn path() -> PathBuf {
+        config::data_file(SAVE_FILENAME)
+    }
+
+    pub fn serialize(config: &GameConfig, simulation: &Simulation) -> String {
+        let mut out = String::new();
+        out.push_str("# Asteroids saved run\n");
+        out.push_str(&format!("version = {SAVE_VERSION}\n"));
+        out.push_str("\n[config]\n");
+        config::write_config(&mut out, config);
+        out.push_str("\n[state]\n");
+        simulation.write_state(&mut out);
+        out
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut version = None;
+        let mut config = GameConfig::default();
+        let mut state = String::new();
+        let mut section = Section::Header;
+
+        for (number, raw) in text.lines().enumerate() {
+            let at_line = |err: String| format!("line {}: {err}", number + 1);
+            let line = raw.split('#').next().unwrap_or("").trim();
+            if line.is_empty() {
+                continue;
+            }
+            match line {
+                "[config]" => {
+                    section = Section::Config;
+                    continue;
+                }
+                "[state]" => {
+                    section = Section::State;
+                    continue;
+                }
+                _ => {}
+            }
+            if let Section::State = section {
+                state.push_str(line);
+                state.push('\n');
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(at_line(format!("expected `key = value`, found `{line}`")));
+            };
+            let (key, value) = (key.trim(), value.trim());
+            match section {
+                Section::Header if key == "version" => {
+                    version = Some(
+                        value
+                            .parse::<u32>()
+                            .map_err(|_| at_line(format!("`{value}` is not a version")))?,
+                    );
+                }
+                Section::Header => return Err(at_line(format!("unknown key `{key}`"))),
+                Section::Config => config::apply_setting(&mut config, key, value).map_err(at_line)?,
+                Section::State => {}
+            }
+        }
+
+        match version {
+            Some(SAVE_VERSION) => {}
+            Some(other) => {
+                return Err(format!(
+                    "saved by an incompatible build (save version {other}, this build reads {SAVE_VERSION})"
+                ));
+            }
+            None => return Err("not a saved run (missing `version`)".to_string()),
+        }
+        let simulation = Simulation::read_state(config.clone(), &state)?;
+        Ok(Self { config, simulation })
+    }
+
+    /// `Ok(None)` when there is no saved run.
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text).map(Some),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+
+    /// Writes through a temporary file, like the settings file, so a crash never leaves half a save.
+    pub fn save(path: &Path, config: &GameConfig, simulation: &Simulation) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, Self::serialize(config, simulation))?;
+        fs::rename(temp, path)
+    }
+
+    /// A continued run starts from the file once; it is deleted so the same moment cannot be
+    /// replayed over and over.
+    pub fn remove(path: &Path) -> io::Result<()> {
+        match fs::remove_file(path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controllers::ControlIntent;
+    use macroquad::prelude::vec2;
+    use tempfile::tempdir;
+
+    /// A busy co-op run: mixed rocks, enemies, sentinels, pickups and hazards.
+    fn busy_run(frames: u32) -> (GameConfig, Simulation) {
+        let mut config = config::presets::default_presets()
+            .into_iter()
+            .find(|preset| preset.name == "Horde")
+            .map(|preset| preset.config)
+            .unwrap_or_default();
+        config.toggle_hazards();
+        config.toggle_coop();
+        let mut simulation = Simulation::with_world(config.clone(), 5, vec2(1024.0, 768.0));
+        for frame in 0..frames {
+            let intent = ControlIntent {
+                thrust: if frame % 100 < 30 { 1.0 } else { 0.0 },
+                turn: 0.4,
+                fire_primary: frame % 8 == 0,
+                fire_secondary: frame % 97 == 0,
+                deploy_sentinel: frame % 400 == 0,
+            };
+            simulation.apply_intents(&[intent, ControlIntent { turn: -0.7, ..intent }]);
+            simulation.step();
+        }
+        (config, simulation)
+    }
+
+    #[test]
+    fn saved_runs_continue_exactly() {
+        let (config, mut original) = busy_run(900);
+        let text = SavedRun::serialize(&config, &original);
+        assert!(text.contains("\nasteroid "));
+        assert!(text.contains("\ndebris "));
+        let mut restored = SavedRun::parse(&text).unwrap().simulation;
+        assert_eq!(SavedRun::serialize(&config, &restored), text);
+        assert_eq!(restored.status().score, original.status().score);
+
+        // Both copies must stay identical from here on, asteroid shapes and debris included.
+        for _ in 0..300 {
+            let intents = [ControlIntent { thrust: 1.0, fire_primary: true, ..ControlIntent::default() }; 2];
+            original.apply_intents(&intents);
+            original.step();
+            restored.apply_intents(&intents);
+            restored.step();
+        }
+        assert_eq!(restored.checksum(), original.checksum());
+        assert_eq!(
+            SavedRun::serialize(&config, &restored),
+            SavedRun::serialize(&config, &original)
+        );
+    }
+
+    #[test]
+    fn incompatible_or_damaged_saves_are_refused() {
+        let (config, simulation) = busy_run(60);
+        let text = SavedRun::serialize(&config, &simulation);
+
+        let newer = text.replace("version = 2", "version = 3");
+        assert!(SavedRun::parse(&newer).err().unwrap().contains("incompatible build"));
+        assert!(SavedRun::parse("[state]\nworld 1 1\n").is_err());
+        assert!(SavedRun::parse(&text.replace("\nrng ", "\nrng x")).is_err());
+        assert!(SavedRun::parse(&text.replace("\nframe 60", "\nframe 60 61")).is_err());
+        assert!(SavedRun::parse(&format!("{text}warp 9\n")).is_err());
+        // A co-op save needs both ships.
+        let last_pilot = text.rfind("\npilot ").unwrap() + 1;
+        let end = last_pilot + text[last_pilot..].find('\n').unwrap() + 1;
+        let one_pilot = format!("{}{}", &text[..last_pilot], &text[end..]);
+        assert!(SavedRun::parse(&one_pilot).err().unwrap().contains("ships saved"));
+    }
+
+    #[test]
+    fn saves_land_in_place_and_are_removed_once_continued() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("nested").join(SAVE_FILENAME);
+        assert!(SavedRun::load(&path).unwrap().is_none());
+
+        let (config, simulation) = busy_run(30);
+        SavedRun::save(&path, &config, &simulation).unwrap();
+        let loaded = SavedRun::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.simulation.checksum(), simulation.checksum());
+        assert!(loaded.config.coop.enabled);
+
+        SavedRun::remove(&path).unwrap();
+        SavedRun::remove(&path).unwrap();
+        assert!(!path.exists());
+    }
+}
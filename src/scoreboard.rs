@@ -12,18 +12,32 @@ pub struct ScoreEntry {
     pub name: String,
     pub score: u32,
     pub stats: RunStats,
+    /// Set for co-op runs, which are entered once with the combined score.
+    pub team: bool,
+    /// Set for runs played partly slowed down, by the time scale or the bullet-time assist.
+    pub assisted: bool,
 }
 
 impl ScoreEntry {
     fn serialize(&self) -> String {
-        format!("{}|{}|{}", self.score, self.name, self.stats.serialize())
+        let mut line = format!("{}|{}|{}", self.score, self.name, self.stats.serialize());
+        if self.team {
+            line.push_str("|team");
+        }
+        if self.assisted {
+            line.push_str("|assisted");
+        }
+        line
     }
 
     fn parse(line: &str) -> Option<Self> {
-        let mut parts = line.splitn(3, '|');
+        let mut parts = line.splitn(4, '|');
         let score_part = parts.next()?;
         let name_part = parts.next()?;
         let stats_part = parts.next();
+        let tags: Vec<&str> = parts.next().map(|rest| rest.split('|').collect()).unwrap_or_default();
+        let team = tags.contains(&"team");
+        let assisted = tags.contains(&"assisted");
         let score = score_part.parse().ok()?;
         let stats = stats_part
             .and_then(|text| RunStats::parse(text))
@@ -32,6 +46,8 @@ impl ScoreEntry {
             name: name_part.to_string(),
             score,
             stats,
+            team,
+            assisted,
         })
     }
 }
@@ -69,11 +85,13 @@ impl Leaderboard {
         }
     }
 
-    pub fn submit(&mut self, name: &str, score: u32, stats: RunStats) {
+    pub fn submit(&mut self, name: &str, score: u32, stats: RunStats, team: bool, assisted: bool) {
         self.entries.push(ScoreEntry {
             name: name.to_string(),
             score,
             stats,
+            team,
+            assisted,
         });
         self.normalize();
     }
@@ -117,21 +135,48 @@ mod tests {
                 hits_small_asteroid: 3,
                 hits_large_alien: 1,
                 hits_small_alien: 0,
+                hits_carrier: 2,
+                hits_drone: 5,
+                sentinel_kills: 7,
+                pickups_collected: 3,
             },
+            team: false,
+            assisted: false,
         };
         let serialized = entry.serialize();
         let parsed = ScoreEntry::parse(&serialized).expect("should parse serialized");
         assert_eq!(parsed.name, "tester");
         assert_eq!(parsed.score, 1234);
         assert_eq!(parsed.stats, entry.stats);
+        assert!(!parsed.team);
         assert!(ScoreEntry::parse("garbage").is_none());
+
+        let team = ScoreEntry { team: true, ..entry.clone() };
+        assert!(team.serialize().ends_with("|team"));
+        assert!(ScoreEntry::parse(&team.serialize()).unwrap().team);
+
+        let assisted = ScoreEntry { assisted: true, ..entry.clone() };
+        let parsed = ScoreEntry::parse(&assisted.serialize()).unwrap();
+        assert!(parsed.assisted && !parsed.team);
+        let both = ScoreEntry { team: true, assisted: true, ..entry };
+        assert!(both.serialize().ends_with("|team|assisted"));
+        let parsed = ScoreEntry::parse(&both.serialize()).unwrap();
+        assert!(parsed.team && parsed.assisted);
+    }
+
+    #[test]
+    fn score_entry_parses_legacy_stats() {
+        let parsed = ScoreEntry::parse("900|old|10,4,1,1,1,1,0").expect("legacy line parses");
+        assert_eq!(parsed.stats.shots_hit, 4);
+        assert_eq!(parsed.stats.hits_carrier, 0);
+        assert_eq!(parsed.stats.hits_drone, 0);
     }
 
     #[test]
     fn leaderboard_submit_normalizes() {
         let mut leaderboard = Leaderboard::default();
         for score in 0u32..(MAX_ENTRIES as u32 + 5) {
-            leaderboard.submit("player", score, RunStats::default());
+            leaderboard.submit("player", score, RunStats::default(), false, false);
         }
         assert_eq!(leaderboard.entries().len(), MAX_ENTRIES);
         assert_eq!(leaderboard.entries()[0].score, MAX_ENTRIES as u32 + 4);
@@ -161,13 +206,14 @@ mod tests {
         let dir = tempdir().unwrap();
         run_in_temp_dir(&dir, || {
             let mut board = Leaderboard::default();
-            board.submit("alpha", 50, RunStats::default());
-            board.submit("bravo", 150, RunStats::default());
+            board.submit("alpha", 50, RunStats::default(), false, false);
+            board.submit("bravo", 150, RunStats::default(), true, false);
             board.save();
             let reloaded = Leaderboard::load();
             assert_eq!(reloaded.entries().len(), 2);
             assert_eq!(reloaded.entries()[0].name, "bravo");
             assert_eq!(reloaded.entries()[0].score, 150);
+            assert!(reloaded.entries()[0].team);
             assert_eq!(reloaded.entries()[1].name, "alpha");
         });
     }
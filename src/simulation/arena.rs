@@ -0,0 +1,218 @@
+use std::ops::{Deref, DerefMut};
+
+/// A stable handle to a body: the slot it lives in and that slot's generation when it moved in.
+/// Once the body is removed the slot's generation moves on, so an old handle never finds whatever
+/// takes the slot next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct EntityId {
+    pub index: u32,
+    pub generation: u32,
+}
+
+/// Bodies of one kind, packed in a `Vec` for iteration, with generational IDs that survive
+/// other bodies being removed. Removal swaps the last body into the gap, so order is not kept;
+/// storage is reused, so a steady population allocates nothing frame to frame.
+///
+/// Derefs to a slice of the bodies: iteration, indexing and in-place edits go through that.
+/// Bodies are found by ID outside the simulation, in the snapshots handed to controllers.
+#[derive(Clone)]
+pub(super) struct Arena<T> {
+    items: Vec<T>,
+    /// The ID of each body in `items`, at the same index.
+    ids: Vec<EntityId>,
+    /// Each slot's current generation, bumped whenever its body is removed.
+    generations: Vec<u32>,
+    /// Slots with no body in them, reused last freed first.
+    free: Vec<u32>,
+}
+
+impl<T> Arena<T> {
+    pub(super) fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            ids: Vec::new(),
+            generations: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+    pub(super) fn insert(&mut self, item: T) -> EntityId {
+        let id = match self.free.pop() {
+            Some(index) => EntityId {
+                index,
+                generation: self.generations[index as usize],
+            },
+            None => {
+                self.generations.push(0);
+                EntityId {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+        self.items.push(item);
+        self.ids.push(id);
+        id
+    }
+
+    /// Removes the bodies whose flag is set, flags being in iteration order as the pass that set
+    /// them saw it. Goes from the back, so each swap only moves a body that stays.
+    pub(super) fn remove_marked(&mut self, marked: &[bool]) {
+        for dense in (0..self.items.len().min(marked.len())).rev() {
+            if marked[dense] {
+                self.remove_at(dense);
+            }
+        }
+    }
+
+    /// Removes the body at `dense` in iteration order, moving the last body into its place.
+    fn remove_at(&mut self, dense: usize) {
+        let id = self.ids.swap_remove(dense);
+        let generation = &mut self.generations[id.index as usize];
+        *generation = generation.wrapping_add(1);
+        self.free.push(id.index);
+        self.items.swap_remove(dense);
+    }
+
+    /// Keeps the bodies `keep` says yes to. Removal swaps, so the body moved into a gap is
+    /// checked next.
+    pub(super) fn retain_mut(&mut self, mut keep: impl FnMut(&mut T) -> bool) {
+        let mut dense = 0;
+        while dense < self.items.len() {
+            if keep(&mut self.items[dense]) {
+                dense += 1;
+            } else {
+                self.remove_at(dense);
+            }
+        }
+    }
+
+    /// Every body with its ID, in iteration order.
+    pub(super) fn iter_with_ids(&self) -> impl Iterator<Item = (EntityId, &T)> {
+        self.ids.iter().copied().zip(&self.items)
+    }
+}
+
+impl<T> Deref for Arena<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items
+    }
+}
+
+impl<T> DerefMut for Arena<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items
+    }
+}
+
+impl<T> Extend<T> for Arena<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, bodies: I) {
+        for body in bodies {
+            self.insert(body);
+        }
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Arena<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Arena<T> {
+    type Item = &'a mut T;
+    type IntoIter = std::slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(items: &[char]) -> (Arena<char>, Vec<EntityId>) {
+        let mut arena = Arena::new();
+        let ids = items.iter().map(|&item| arena.insert(item)).collect();
+        (arena, ids)
+    }
+
+    fn holder(arena: &Arena<char>, id: EntityId) -> Option<char> {
+        arena
+            .iter_with_ids()
+            .find(|&(held, _)| held == id)
+            .map(|(_, &item)| item)
+    }
+
+    #[test]
+    fn insert_reuses_freed_slots_with_a_new_generation() {
+        let (mut arena, ids) = filled(&['a', 'b', 'c']);
+        let slots: Vec<_> = ids.iter().map(|id| (id.index, id.generation)).collect();
+        assert_eq!(slots, [(0, 0), (1, 0), (2, 0)]);
+
+        arena.remove_marked(&[false, true, false]);
+        let reused = arena.insert('d');
+        assert_eq!((reused.index, reused.generation), (1, 1));
+
+        // Now 'a', 'c', 'd' in slots 0, 2, 1. Removal goes from the back, so slot 0 is freed
+        // last and reused first; a fresh slot only comes once none are free.
+        arena.remove_marked(&[true, false, true]);
+        let first = arena.insert('e');
+        let second = arena.insert('f');
+        let fresh = arena.insert('g');
+        assert_eq!((first.index, first.generation), (0, 1));
+        assert_eq!((second.index, second.generation), (1, 2));
+        assert_eq!((fresh.index, fresh.generation), (3, 0));
+    }
+
+    #[test]
+    fn remove_marked_handles_runs_and_the_last_body() {
+        let (mut arena, ids) = filled(&['a', 'b', 'c', 'd', 'e', 'f']);
+        arena.remove_marked(&[false, true, true, false, true, true]);
+        assert_eq!(&arena[..], ['a', 'd']);
+        assert_eq!(holder(&arena, ids[0]), Some('a'));
+        assert_eq!(holder(&arena, ids[3]), Some('d'));
+        for &id in &[ids[1], ids[2], ids[4], ids[5]] {
+            assert_eq!(holder(&arena, id), None);
+        }
+
+        // Flags past the end are ignored; missing flags keep their bodies.
+        arena.remove_marked(&[false, true, true]);
+        arena.remove_marked(&[]);
+        assert_eq!(&arena[..], ['a']);
+    }
+
+    #[test]
+    fn retain_mut_checks_the_body_swapped_into_a_gap() {
+        let (mut arena, ids) = filled(&['a', 'x', 'b', 'x', 'x']);
+        let mut seen = Vec::new();
+        arena.retain_mut(|item| {
+            seen.push(*item);
+            *item = item.to_ascii_uppercase();
+            *item != 'X'
+        });
+        // Each removed 'x' has the last body swapped into its place, which is checked next:
+        // the two trailing 'x's, then 'b'.
+        assert_eq!(seen, ['a', 'x', 'x', 'x', 'b']);
+        assert_eq!(&arena[..], ['A', 'B']);
+        assert_eq!(holder(&arena, ids[2]), Some('B'));
+    }
+
+    #[test]
+    fn stale_ids_never_match_the_new_holder_of_their_slot() {
+        let (mut arena, ids) = filled(&['a', 'b']);
+        arena.remove_marked(&[true, false]);
+        let replacement = arena.insert('c');
+        assert_eq!(replacement.index, ids[0].index);
+        assert_ne!(replacement, ids[0]);
+        assert_eq!(holder(&arena, ids[0]), None);
+        assert_eq!(holder(&arena, replacement), Some('c'));
+        assert_eq!(holder(&arena, ids[1]), Some('b'));
+    }
+}
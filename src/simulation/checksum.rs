@@ -0,0 +1,304 @@
+use macroquad::prelude::Vec2;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use super::Simulation;
+use super::model::{BulletSource, SentinelAnchor};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// One named value in a simulation's state, e.g. `pilot[1].x`.
+pub(super) struct StateField<'a> {
+    pub(super) group: &'a str,
+    pub(super) index: usize,
+    pub(super) name: &'a str,
+    pub(super) value: StateValue,
+}
+
+#[derive(Clone, Copy)]
+pub enum StateValue {
+    Int(u64),
+    /// Compared and hashed by its bits, so `-0.0` and `0.0` differ and `NaN` equals itself.
+    Float(f32),
+}
+
+impl StateValue {
+    fn bits(self) -> u64 {
+        match self {
+            StateValue::Int(value) => value,
+            StateValue::Float(value) => value.to_bits().into(),
+        }
+    }
+}
+
+impl PartialEq for StateValue {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits() == other.bits()
+    }
+}
+
+impl fmt::Display for StateValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StateValue::Int(value) => write!(f, "{value}"),
+            StateValue::Float(value) => write!(f, "{value} ({:#010x})", value.to_bits()),
+        }
+    }
+}
+
+impl std::str::FromStr for StateValue {
+    type Err = String;
+
+    /// Reads what `Display` writes. A float is restored from its bits, not its decimal form.
+    fn from_str(text: &str) -> Result<Self, String> {
+        let invalid = || format!("`{text}` is not a state value");
+        match text.split_once("(0x") {
+            Some((_, bits)) => {
+                let bits = bits.strip_suffix(')').ok_or_else(invalid)?;
+                let bits = u32::from_str_radix(bits, 16).map_err(|_| invalid())?;
+                Ok(StateValue::Float(f32::from_bits(bits)))
+            }
+            None => text.parse().map(StateValue::Int).map_err(|_| invalid()),
+        }
+    }
+}
+
+/// Every field of a simulation's state by name, e.g. `pilot[1].x`, in `visit_state` order.
+/// Written into replays and desync reports, and compared field by field.
+#[derive(Clone, Default, PartialEq)]
+pub struct StateDump {
+    fields: Vec<(String, StateValue)>,
+}
+
+impl StateDump {
+    /// Adds one `name = value` line as written by `Display`.
+    pub fn push_line(&mut self, line: &str) -> Result<(), String> {
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("expected `name = value`, found `{line}`"))?;
+        self.fields.push((name.trim().to_string(), value.trim().parse()?));
+        Ok(())
+    }
+
+    /// The fields whose values differ, or that only one side has, as readable lines: expected
+    /// (self) first, then actual.
+    pub fn diff(&self, actual: &StateDump) -> Vec<String> {
+        let expected: BTreeMap<&str, StateValue> = self
+            .fields
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+            .collect();
+        let mut seen = BTreeSet::new();
+        let mut lines = Vec::new();
+        for (name, value) in &actual.fields {
+            seen.insert(name.as_str());
+            match expected.get(name.as_str()) {
+                Some(want) if want == value => {}
+                Some(want) => lines.push(format!("{name}: expected {want}, got {value}")),
+                None => lines.push(format!("{name}: unexpected, got {value}")),
+            }
+        }
+        for (name, value) in &self.fields {
+            if !seen.contains(name.as_str()) {
+                lines.push(format!("{name}: expected {value}, missing"));
+            }
+        }
+        lines
+    }
+}
+
+impl fmt::Display for StateDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.fields {
+            writeln!(f, "{name} = {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Hands named values to a `visit_state` callback.
+struct Fields<'v> {
+    visit: &'v mut dyn FnMut(StateField),
+}
+
+impl Fields<'_> {
+    fn value(&mut self, group: &str, index: usize, name: &str, value: u64) {
+        (self.visit)(StateField {
+            group,
+            index,
+            name,
+            value: StateValue::Int(value),
+        });
+    }
+
+    fn float(&mut self, group: &str, index: usize, name: &str, value: f32) {
+        (self.visit)(StateField {
+            group,
+            index,
+            name,
+            value: StateValue::Float(value),
+        });
+    }
+
+    fn point(&mut self, group: &str, index: usize, names: [&str; 2], value: Vec2) {
+        self.float(group, index, names[0], value.x);
+        self.float(group, index, names[1], value.y);
+    }
+}
+
+impl Simulation {
+    /// Feeds every value `step` carries from one frame to the next to `visit`, in a fixed order.
+    /// Left out: debris, which is cosmetic; asteroid outlines, drawn from the random stream at
+    /// spawn and never changed; and run stats, which only count what happened.
+    /// Variants are hashed as their position in their enum.
+    pub(super) fn visit_state(&self, visit: &mut dyn FnMut(StateField)) {
+        let mut out = Fields { visit };
+        out.value("world", 0, "frame", self.status.frame);
+        out.value("world", 0, "rng", self.rng.state());
+        out.float("world", 0, "spawn_acc", self.spawn_acc);
+        out.float("world", 0, "alien_spawn_acc", self.alien_spawn_acc);
+        out.float("world", 0, "carrier_spawn_acc", self.carrier_spawn_acc);
+        out.value("world", 0, "next_carrier_id", self.next_carrier_id.into());
+        if let Some(versus) = &self.versus {
+            out.float("world", 0, "match_remaining", versus.remaining);
+        }
+        if let Some(flare) = &self.solar_flare {
+            out.float("flare", 0, "cooldown", flare.cooldown);
+            out.float("flare", 0, "remaining", flare.remaining);
+        }
+        out.value("wells", 0, "count", self.gravity_wells.len() as u64);
+        for (index, well) in self.gravity_wells.iter().enumerate() {
+            out.point("well", index, ["x", "y"], well.position);
+            out.float("well", index, "strength", well.strength);
+        }
+        out.value("nebulae", 0, "count", self.nebulae.len() as u64);
+        for (index, nebula) in self.nebulae.iter().enumerate() {
+            out.point("nebula", index, ["x", "y"], nebula.position);
+            out.float("nebula", index, "radius", nebula.radius);
+        }
+
+        for (seat, pilot) in self.pilots.iter().enumerate() {
+            let ship = &pilot.ship;
+            out.point("pilot", seat, ["x", "y"], ship.position);
+            out.point("pilot", seat, ["vx", "vy"], ship.velocity);
+            out.float("pilot", seat, "angle", ship.angle);
+            out.value("pilot", seat, "lives", pilot.lives.into());
+            out.value("pilot", seat, "score", pilot.score.into());
+            out.value("pilot", seat, "next_extra_life", pilot.next_extra_life_score.into());
+            out.value("pilot", seat, "frags", pilot.frags.into());
+            out.value("pilot", seat, "deaths", pilot.deaths.into());
+            out.value("pilot", seat, "shield_charges", pilot.shield_charges.into());
+            out.float("pilot", seat, "invulnerable", pilot.invulnerability_timer);
+            out.float("pilot", seat, "primary_cooldown", pilot.primary_cooldown);
+            out.float("pilot", seat, "secondary_cooldown", pilot.secondary_cooldown);
+            out.float("pilot", seat, "sentinel_cooldown", pilot.sentinel_cooldown);
+            out.float("pilot", seat, "rapid_fire", pilot.rapid_fire_timer);
+            out.float("pilot", seat, "score_multiplier", pilot.score_multiplier_timer);
+            out.value("pilot", seat, "hazard_hit", pilot.hazard_hit.into());
+            out.point("pilot", seat, ["last_seen_x", "last_seen_y"], pilot.last_seen);
+            // A ship that is flying has no timer; NaN marks that without a separate flag.
+            out.float("pilot", seat, "respawn_timer", pilot.respawn_timer.unwrap_or(f32::NAN));
+        }
+
+        out.value("asteroids", 0, "count", self.asteroids.len() as u64);
+        for (index, asteroid) in self.asteroids.iter().enumerate() {
+            out.point("asteroid", index, ["x", "y"], asteroid.position);
+            out.point("asteroid", index, ["vx", "vy"], asteroid.velocity);
+            out.value("asteroid", index, "hp", asteroid.hp.into());
+            out.value("asteroid", index, "size", asteroid.size as u64);
+            out.value("asteroid", index, "archetype", asteroid.archetype as u64);
+            out.float("asteroid", index, "angle", asteroid.angle);
+            out.float("asteroid", index, "spin", asteroid.rotation_speed);
+        }
+        out.value("bullets", 0, "count", self.bullets.len() as u64);
+        for (index, bullet) in self.bullets.iter().enumerate() {
+            out.point("bullet", index, ["x", "y"], bullet.position);
+            out.point("bullet", index, ["vx", "vy"], bullet.velocity);
+            out.float("bullet", index, "ttl", bullet.ttl);
+            out.value("bullet", index, "source", source_code(bullet.source));
+        }
+        out.value("aliens", 0, "count", self.aliens.len() as u64);
+        for (index, alien) in self.aliens.iter().enumerate() {
+            out.point("alien", index, ["x", "y"], alien.position);
+            out.point("alien", index, ["vx", "vy"], alien.velocity);
+            out.value("alien", index, "size", alien.size as u64);
+            out.float("alien", index, "angle", alien.angle);
+            out.float("alien", index, "fire_timer", alien.fire_timer);
+            // Saucer controllers keep no state beyond which behaviour they are.
+            out.value("alien", index, "behaviour", alien.controller.behaviour() as u64);
+        }
+        out.value("carriers", 0, "count", self.carriers.len() as u64);
+        for (index, carrier) in self.carriers.iter().enumerate() {
+            out.value("carrier", index, "id", carrier.id.into());
+            out.point("carrier", index, ["x", "y"], carrier.position);
+            out.point("carrier", index, ["vx", "vy"], carrier.velocity);
+            out.value("carrier", index, "hp", carrier.hp.into());
+            out.float("carrier", index, "launch_timer", carrier.launch_timer);
+        }
+        out.value("drones", 0, "count", self.drones.len() as u64);
+        for (index, drone) in self.drones.iter().enumerate() {
+            out.value("drone", index, "carrier", drone.carrier.into());
+            out.point("drone", index, ["x", "y"], drone.position);
+            out.point("drone", index, ["vx", "vy"], drone.velocity);
+            out.value("drone", index, "mode", drone.mode as u64);
+            out.float("drone", index, "orbit_phase", drone.orbit_phase);
+            out.float("drone", index, "ttl", drone.ttl);
+        }
+        out.value("sentinels", 0, "count", self.sentinels.len() as u64);
+        for (index, sentinel) in self.sentinels.iter().enumerate() {
+            out.value("sentinel", index, "owner", sentinel.owner as u64);
+            out.point("sentinel", index, ["x", "y"], sentinel.position);
+            // An orbiting sentinel has no hold point; NaN marks that, as for respawn timers.
+            let hold = match sentinel.anchor {
+                SentinelAnchor::Hold(point) => point,
+                SentinelAnchor::Orbit => Vec2::NAN,
+            };
+            out.point("sentinel", index, ["hold_x", "hold_y"], hold);
+            out.float("sentinel", index, "orbit_phase", sentinel.orbit_phase);
+            out.float("sentinel", index, "fire_timer", sentinel.fire_timer);
+            out.float("sentinel", index, "ttl", sentinel.ttl);
+            out.value("sentinel", index, "energy", sentinel.energy.into());
+        }
+        out.value("pickups", 0, "count", self.pickups.len() as u64);
+        for (index, pickup) in self.pickups.iter().enumerate() {
+            out.value("pickup", index, "kind", pickup.kind as u64);
+            out.point("pickup", index, ["x", "y"], pickup.position);
+            out.point("pickup", index, ["vx", "vy"], pickup.velocity);
+            out.float("pickup", index, "ttl", pickup.ttl);
+        }
+    }
+
+    /// FNV-1a over `visit_state`. Stable across builds and platforms, so it can be written to
+    /// files and compared between machines.
+    pub fn checksum(&self) -> u64 {
+        let mut hash = FNV_OFFSET;
+        self.visit_state(&mut |field| {
+            for byte in field.value.bits().to_le_bytes() {
+                hash ^= u64::from(byte);
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        });
+        hash
+    }
+
+    /// Every field of `visit_state` by name, for writing out and diffing.
+    pub fn state_dump(&self) -> StateDump {
+        let mut dump = StateDump::default();
+        self.visit_state(&mut |field| {
+            let name = format!("{}[{}].{}", field.group, field.index, field.name);
+            dump.fields.push((name, field.value));
+        });
+        dump
+    }
+}
+
+/// Who fired a bullet, as one number: the seat times three, plus 0 for its ship and 1 for its
+/// sentinels; saucer shots are 2.
+fn source_code(source: BulletSource) -> u64 {
+    match source {
+        BulletSource::Player(seat) => seat as u64 * 3,
+        BulletSource::Sentinel(seat) => seat as u64 * 3 + 1,
+        BulletSource::Alien => 2,
+    }
+}
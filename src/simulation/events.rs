@@ -0,0 +1,153 @@
+use crate::config::PickupKind;
+use macroquad::prelude::Vec2;
+
+use super::model::HitTarget;
+
+/// Something that happened during a step, for listeners outside the simulation: sound, HUD
+/// effects, telemetry. Events only describe what the step already did; nothing reads them back,
+/// so they are neither checksummed nor saved.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimEvent {
+    /// A player's gun fired one bullet; a secondary spread fires several at once.
+    BulletFired { seat: usize, position: Vec2 },
+    AlienSpawned { position: Vec2, large: bool },
+    CarrierSpawned { position: Vec2 },
+    /// A rock or enemy was destroyed. `by` is the seat whose shot, sentinel or blast did it;
+    /// `None` for saucer fire and for ships ramming it.
+    Destroyed {
+        target: HitTarget,
+        position: Vec2,
+        by: Option<usize>,
+    },
+    /// A ship was hit. A shielded hit spent a shield charge instead of a life.
+    ShipHit {
+        seat: usize,
+        position: Vec2,
+        shielded: bool,
+        cause: HitCause,
+    },
+    /// Awarded for reaching the next score step; extra lives from pickups are `PickupCollected`.
+    ExtraLife { seat: usize },
+    PickupCollected { seat: usize, kind: PickupKind },
+}
+
+impl SimEvent {
+    /// A ship went down rather than being saved by its shield. Counting these, not lives, is
+    /// what tells a death apart: an extra life in the same step leaves the count unchanged.
+    pub fn ship_lost(&self) -> bool {
+        matches!(self, SimEvent::ShipHit { shielded: false, .. })
+    }
+}
+
+/// What hit a ship. When several things hit it in the same step, the first one checked counts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitCause {
+    Asteroid,
+    /// A volatile rock going off nearby.
+    Blast,
+    Saucer,
+    SaucerShot,
+    /// Another ship's bullet: a versus rival, or a partner with friendly fire on.
+    PlayerShot,
+    Carrier,
+    Drone,
+    GravityWell,
+}
+
+impl HitCause {
+    pub fn label(&self) -> &'static str {
+        match self {
+            HitCause::Asteroid => "asteroid",
+            HitCause::Blast => "blast",
+            HitCause::Saucer => "saucer",
+            HitCause::SaucerShot => "saucer-shot",
+            HitCause::PlayerShot => "player-shot",
+            HitCause::Carrier => "carrier",
+            HitCause::Drone => "drone",
+            HitCause::GravityWell => "gravity-well",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::model::{Asteroid, AsteroidSize, Bullet, BulletSource};
+    use super::super::{Simulation, test_simulation};
+    use super::*;
+    use crate::config::{AsteroidArchetype, GameConfig};
+    use crate::controllers::ControlIntent;
+    use macroquad::prelude::vec2;
+
+    fn simulation() -> Simulation {
+        let mut sim = test_simulation(GameConfig::default(), 13);
+        sim.asteroids.retain_mut(|_| false);
+        sim
+    }
+
+    fn rock(sim: &mut Simulation, at: Vec2) {
+        let (size, archetype) = (AsteroidSize::Large, AsteroidArchetype::Rock);
+        let asteroid = Asteroid::new(size, archetype, at, Vec2::ZERO, &mut sim.rng);
+        sim.asteroids.insert(asteroid);
+    }
+
+    fn shoot_rock(sim: &mut Simulation, at: Vec2) {
+        rock(sim, at);
+        sim.bullets.insert(Bullet::new(at, Vec2::ZERO, BulletSource::Player(0), 1.0));
+        sim.step();
+    }
+
+    #[test]
+    fn firing_reports_each_bullet() {
+        let mut sim = simulation();
+        let intent = ControlIntent { fire_primary: true, ..ControlIntent::default() };
+        sim.apply_intents(&[intent]);
+        sim.step();
+        let fired = sim.events().iter().filter(|event| {
+            matches!(event, SimEvent::BulletFired { seat: 0, .. })
+        });
+        assert_eq!(fired.count(), sim.bullets.len());
+        assert!(!sim.bullets.is_empty());
+    }
+
+    #[test]
+    fn shot_rocks_report_the_shooter() {
+        let mut sim = simulation();
+        let at = vec2(100.0, 100.0);
+        shoot_rock(&mut sim, at);
+        assert!(sim.events().iter().any(|event| matches!(
+            event,
+            SimEvent::Destroyed { target: HitTarget::LargeAsteroid, by: Some(0), .. }
+        )));
+    }
+
+    #[test]
+    fn ship_hits_report_the_cause_and_the_shield() {
+        let mut sim = simulation();
+        sim.pilots[0].invulnerability_timer = 0.0;
+        sim.pilots[0].shield_charges = 1;
+        let at = sim.pilots[0].ship.position;
+        rock(&mut sim, at);
+        sim.step();
+        let hit = sim.events().iter().find(|event| matches!(event, SimEvent::ShipHit { .. }));
+        let cause = HitCause::Asteroid;
+        assert_eq!(hit, Some(&SimEvent::ShipHit { seat: 0, position: at, shielded: true, cause }));
+        assert!(!hit.is_some_and(SimEvent::ship_lost));
+    }
+
+    #[test]
+    fn crossing_the_score_step_reports_an_extra_life() {
+        let mut sim = simulation();
+        sim.pilots[0].score = sim.pilots[0].next_extra_life_score - 1;
+        shoot_rock(&mut sim, vec2(100.0, 100.0));
+        assert!(sim.events().contains(&SimEvent::ExtraLife { seat: 0 }));
+    }
+
+    #[test]
+    fn each_step_starts_with_no_events() {
+        let mut sim = simulation();
+        shoot_rock(&mut sim, vec2(100.0, 100.0));
+        assert!(!sim.events().is_empty());
+        sim.step();
+        assert!(sim.events().is_empty(), "events carried into the next step");
+    }
+}
@@ -1,50 +1,68 @@
-use crate::ai::{AsteroidSnapshot, WorldSnapshot};
-use crate::config::{GameConfig, PlayerControllerMode};
+use crate::ai::{self, AsteroidSnapshot, HazardKind, HazardSnapshot, WorldSnapshot};
+use crate::config::{
+    AlienBehaviour, ArchetypeWeights, DropTable, GameConfig, PlayerControllerMode, SimTuning,
+};
+use crate::controllers::human::HumanController;
 use crate::controllers::{ControlIntent, Controller};
+use crate::keymap::Keymap;
 use crate::stats::RunStats;
-use macroquad::prelude::{Color, Vec2};
-use std::f32::consts::PI;
+use macroquad::prelude::{Color, Vec2, screen_height, screen_width, vec2};
+use std::time::{Duration, Instant};
 
-const SHIP_THRUST: f32 = 400.0;
-const SHIP_MAX_SPEED: f32 = 320.0;
-const SHIP_DRAG: f32 = 0.25;
-const SHIP_ROTATION_SPEED: f32 = 3.0;
 const SHIP_SIZE: f32 = 14.0;
+/// Distance between co-op ships at spawn.
+const SPAWN_SPACING: f32 = 60.0;
+/// Versus spawn ring radius, as a fraction of the shorter screen side.
+const VERSUS_RING_RADIUS: f32 = 0.3;
+/// Candidate points on the ring a fragged versus ship can respawn at.
+const VERSUS_RESPAWN_POINTS: usize = 8;
 const SHIP_STROKE: f32 = 2.0;
 const SAUCER_STROKE: f32 = 2.0;
 const SMALL_ALIEN_DRAW_SCALE: f32 = 12.0;
 const LARGE_ALIEN_DRAW_SCALE: f32 = 24.0;
-const INVULNERABILITY_DURATION: f32 = 3.0;
 const SHIP_DRAW_OFFSET: f32 = -std::f32::consts::PI / 2.0;
 
-// TODO: Increased 10x for the time being due to much more powerful weapons.
-const EXTRA_LIFE_SCORE_STEP: u32 = 10_000;
-
-const ALIEN_SPAWN_SCORE_THRESHOLD: u32 = 40_000;
-const ALIEN_SPAWN_INTERVAL: f32 = 5.0;
 const SMALL_ALIEN_Y: f32 = 110.0;
 const LARGE_ALIEN_Y: f32 = 70.0;
 const SMALL_ALIEN_SPEED: f32 = 160.0;
 const LARGE_ALIEN_SPEED: f32 = 96.0;
 const SMALL_ALIEN_FIRE_INTERVAL: f32 = 1.1;
 const LARGE_ALIEN_FIRE_INTERVAL: f32 = 1.9;
-const MAX_SMALL_ALIENS: usize = 2;
-const MAX_LARGE_ALIENS: usize = 1;
 const SMALL_ALIEN_SCORE: u32 = 1000;
 const LARGE_ALIEN_SCORE: u32 = 200;
 
-const ASTEROID_MIN_SPEED: f32 = 20.0;
-const ASTEROID_MAX_SPEED: f32 = 90.0;
-const ASTEROID_SPAWN_INTERVAL: f32 = 2.5;
-const BULLET_SPEED: f32 = 520.0;
+const CARRIER_SPEED: f32 = 36.0;
+const CARRIER_RADIUS: f32 = 30.0;
+const CARRIER_DRAW_SCALE: f32 = 30.0;
+const MAX_CARRIERS: usize = 1;
+const DRONE_STEER: f32 = 4.0;
+const DRONE_RADIUS: f32 = 6.0;
+const DRONE_ORBIT_RADIUS: f32 = 70.0;
+const DRONE_ORBIT_RATE: f32 = 1.4;
+const DRONE_ENGAGE_RANGE: f32 = 220.0;
+const DRONE_SEPARATION: f32 = 22.0;
+const DRONE_SCATTER_TTL: f32 = 4.0;
+const ENEMY_DEBRIS_COLOR: Color = Color::new(0.7, 0.4, 1.0, 1.0);
+
+const SENTINEL_ORBIT_RADIUS: f32 = 48.0;
+const SENTINEL_ORBIT_RATE: f32 = 2.0;
+const SENTINEL_DEPLOY_COOLDOWN: f32 = 1.0;
+const SENTINEL_SIZE: f32 = 8.0;
+
+const PICKUP_RADIUS: f32 = 8.0;
+const PICKUP_DRIFT_SPEED: f32 = 30.0;
+const SHIELD_GRACE: f32 = 1.0;
+
+const GRAVITY_WELL_COUNT: usize = 1;
+const GRAVITY_WELL_MIN_DISTANCE: f32 = 40.0;
+const GRAVITY_WELL_RANGE: f32 = 260.0;
+const GRAVITY_WELL_CORE: f32 = 14.0;
+const NEBULA_COUNT: usize = 2;
+const NEBULA_RADIUS: f32 = 90.0;
+const HAZARD_CLEARANCE: f32 = 160.0;
+const SOLAR_FLARE_WARNING: f32 = 2.0;
+
 const BULLET_RADIUS: f32 = 2.0;
-const BULLET_TTL: f32 = 2.0;
-const PRIMARY_FIRE_RATE: f32 = 10.0;
-const SECONDARY_COUNT: usize = 21;
-const SECONDARY_FIRE_RATE: f32 = PRIMARY_FIRE_RATE / SECONDARY_COUNT as f32;
-const SECONDARY_SPREAD: f32 = PI / 36.0;
-const MAX_LIVES: u32 = 3;
-const ASTEROID_SCORE_BASE: u32 = 100;
 const DEBRIS_TTL: f32 = 1.0;
 const DEBRIS_SPEED: f32 = 120.0;
 const DEBRIS_COUNT: usize = 6;
@@ -54,96 +72,288 @@ const PLAYER_DEBRIS_COLOR: Color = Color::new(0.35, 0.8, 1.0, 1.0);
 
 const TARGET_FPS: f32 = 60.0;
 
+mod arena;
+pub use self::arena::EntityId;
+use self::arena::Arena;
+mod checksum;
+pub use self::checksum::StateDump;
+mod events;
+pub use self::events::{HitCause, SimEvent};
 mod model;
+pub use self::model::HitTarget;
 use self::model::*;
+mod profile;
+pub use self::profile::{Stage, StepTimings};
 mod render;
+pub use self::render::seat_color;
+mod rng;
+mod save;
 mod systems;
 
+use self::rng::SimRng;
+use self::systems::collision::CollisionScratch;
+
+#[derive(Clone)]
 pub struct Simulation {
-    controller: Box<dyn Controller>,
+    /// One per seat; index 0 is player one.
+    pilots: Vec<Pilot>,
+    controllers: Controllers,
+    friendly_fire: bool,
+    /// Set for versus matches; `None` in solo, co-op and hot-seat runs.
+    versus: Option<VersusMatch>,
     policy: SimulationPolicy,
-    ship: Ship,
-    asteroids: Vec<Asteroid>,
+    /// Size of the playfield; everything wraps at its edges.
+    world: Vec2,
+    rng: SimRng,
+    tuning: SimTuning,
+    asteroids: Arena<Asteroid>,
+    asteroid_weights: ArchetypeWeights,
     spawn_acc: f32,
-    primary_cooldown: f32,
-    secondary_cooldown: f32,
-    bullets: Vec<Bullet>,
-    debris: Vec<Debris>,
-    lives: u32,
+    bullets: Arena<Bullet>,
+    debris: Arena<Debris>,
     dt: f32,
     status: SimulationStatus,
-    invulnerability_timer: f32,
     invulnerability_enabled: bool,
-    run_stats: RunStats,
-    aliens: Vec<Alien>,
+    /// Set once any of the run is played slowed down; never cleared, not even by a rewind.
+    assisted: bool,
+    aliens: Arena<Alien>,
     alien_spawn_acc: f32,
-    next_extra_life_score: u32,
+    alien_behaviour: AlienBehaviour,
+    enemies_enabled: bool,
+    enemy_cap: usize,
+    carriers: Vec<Carrier>,
+    drones: Vec<Drone>,
+    carrier_spawn_acc: f32,
+    next_carrier_id: u32,
+    sentinels_enabled: bool,
+    sentinels: Vec<Sentinel>,
+    drop_table: DropTable,
+    pickups: Vec<Pickup>,
+    gravity_wells: Vec<GravityWell>,
+    nebulae: Vec<Nebula>,
+    solar_flare: Option<SolarFlare>,
+    /// What happened during the last step; cleared when the next one starts.
+    events: Vec<SimEvent>,
+    /// Where the last step's time went, and controller time waiting for the next step.
+    timings: StepTimings,
+    controller_time: Duration,
+    collision_scratch: CollisionScratch,
 }
 
 impl Simulation {
-    pub fn new(config: GameConfig) -> Self {
-        let controller: Box<dyn Controller> = match &config.player_controller {
-            PlayerControllerMode::Human => {
-                Box::new(crate::controllers::human::HumanController::default())
-            }
-            PlayerControllerMode::Ai { profile } => {
-                Box::new(crate::ai::AiController::new(profile.clone()))
-            }
-        };
+    /// A simulation whose playfield is the current window.
+    pub fn new(config: GameConfig, seed: u64) -> Self {
+        Self::with_world(config, seed, vec2(screen_width(), screen_height()))
+    }
 
-        Self {
-            controller,
+    /// A simulation with an explicit playfield size; needs no window, so replays can be checked
+    /// headless.
+    pub fn with_world(config: GameConfig, seed: u64, world: Vec2) -> Self {
+        let tuning = config.tuning.clone();
+        let seats = config.seats();
+        let controllers = config
+            .seat_controllers()
+            .iter()
+            .enumerate()
+            .map(|(seat, mode)| Some(default_controller(mode, seat, &config)))
+            .collect();
+        let pilots = (0..seats)
+            .map(|seat| {
+                let spawn = if config.versus.enabled {
+                    ring_point(seat, seats, world)
+                } else {
+                    spawn_point(seat, seats, world)
+                };
+                Pilot::new(spawn, &tuning)
+            })
+            .collect();
+        let versus = config.versus.enabled.then_some(VersusMatch {
+            remaining: config.versus.match_seconds as f32,
+            respawn_delay: config.versus.respawn_seconds,
+        });
+
+        let mut simulation = Self {
+            pilots,
+            controllers: Controllers(controllers),
+            // Versus is every ship for itself, so shots always hit rivals.
+            friendly_fire: versus.is_some() || (config.coop.enabled && config.coop.friendly_fire),
             policy: SimulationPolicy::from_config(&config),
-            ship: Ship::centered(),
-            asteroids: Vec::new(),
+            world,
+            rng: SimRng::new(seed),
+            asteroids: Arena::new(),
+            asteroid_weights: config.asteroid_weights.clone(),
             spawn_acc: 0.0,
-            primary_cooldown: 0.0,
-            secondary_cooldown: 0.0,
-            bullets: Vec::new(),
-            debris: Vec::new(),
-            lives: MAX_LIVES,
+            bullets: Arena::new(),
+            debris: Arena::new(),
             dt: 1.0 / TARGET_FPS,
-            status: SimulationStatus::default(),
-            invulnerability_timer: INVULNERABILITY_DURATION,
+            status: SimulationStatus {
+                lives: tuning.max_lives * seats as u32,
+                pilots: vec![
+                    PilotStatus {
+                        score: 0,
+                        lives: tuning.max_lives,
+                        frags: 0,
+                        deaths: 0,
+                    };
+                    seats
+                ],
+                match_remaining: versus.as_ref().map(|versus| versus.remaining),
+                score_multiplier: tuning.score_multiplier,
+                ..SimulationStatus::default()
+            },
+            versus,
             invulnerability_enabled: false,
-            run_stats: RunStats::default(),
-            aliens: Vec::new(),
+            assisted: false,
+            aliens: Arena::new(),
             alien_spawn_acc: 0.0,
-            next_extra_life_score: EXTRA_LIFE_SCORE_STEP,
+            alien_behaviour: config.alien_behaviour,
+            enemies_enabled: config.enemies_enabled,
+            enemy_cap: config.budgets.enemy_cap,
+            carriers: Vec::new(),
+            drones: Vec::new(),
+            carrier_spawn_acc: 0.0,
+            next_carrier_id: 0,
+            sentinels_enabled: config.sentinels_enabled,
+            sentinels: Vec::new(),
+            drop_table: config.drop_table.clone(),
+            pickups: Vec::new(),
+            gravity_wells: Vec::new(),
+            nebulae: Vec::new(),
+            solar_flare: None,
+            events: Vec::new(),
+            timings: StepTimings::default(),
+            controller_time: Duration::ZERO,
+            collision_scratch: CollisionScratch::default(),
+            tuning,
+        };
+        simulation.place_hazards(&config.hazards);
+        simulation
+    }
+
+    /// Follows the window when it is resized; the next step wraps at the new edges.
+    /// The playfield's size in world units.
+    pub fn world(&self) -> Vec2 {
+        self.world
+    }
+
+    pub fn set_world(&mut self, world: Vec2) {
+        self.world = world;
+    }
+
+    /// Ships in this run: one, two in co-op, or two to four in versus.
+    pub fn seats(&self) -> usize {
+        self.pilots.len()
+    }
+
+    /// Asks every seat's controller for this step's input and returns the intents in seat order.
+    pub fn poll_controllers(&mut self) -> Vec<ControlIntent> {
+        let intents: Vec<ControlIntent> =
+            (0..self.pilots.len()).map(|seat| self.poll_seat(seat)).collect();
+        self.apply_intents(&intents);
+        intents
+    }
+
+    /// Asks one seat's controller for input without applying it; netplay polls only its own seat.
+    pub fn poll_seat(&mut self, seat: usize) -> ControlIntent {
+        let start = Instant::now();
+        let snapshot = self.snapshot(seat);
+        let intent = match &mut self.controllers.0[seat] {
+            Some(controller) => controller.tick(&snapshot, self.dt),
+            None => ControlIntent::default(),
+        };
+        self.controller_time += start.elapsed();
+        intent
+    }
+
+    /// Sets this step's input for every seat, in seat order, bypassing the controllers.
+    pub fn apply_intents(&mut self, intents: &[ControlIntent]) {
+        for (pilot, intent) in self.pilots.iter_mut().zip(intents) {
+            pilot.intent = *intent;
         }
     }
 
-    pub fn controller(&mut self) -> &mut dyn Controller {
-        self.controller.as_mut()
+    /// Copies everything `step` advances, for netplay rollback.
+    pub fn save_state(&self) -> SimulationState {
+        SimulationState(Box::new(self.clone()))
+    }
+
+    /// Rewinds to a saved state. The installed controllers stay as they are, and so does the
+    /// assisted flag: going back does not undo having had help.
+    pub fn restore_state(&mut self, state: &SimulationState) {
+        let controllers = std::mem::replace(&mut self.controllers, Controllers(Vec::new()));
+        let assisted = self.assisted;
+        *self = (*state.0).clone();
+        self.controllers = controllers;
+        if assisted {
+            self.mark_assisted();
+        }
     }
 
-    pub fn snapshot(&self) -> WorldSnapshot {
+    /// The world from one seat's point of view.
+    fn snapshot(&self, seat: usize) -> WorldSnapshot {
+        let ship = &self.pilots[seat].ship;
+        let others = self
+            .pilots
+            .iter()
+            .enumerate()
+            .filter(|&(other, pilot)| other != seat && pilot.in_play());
         WorldSnapshot {
-            ship_position: self.ship.position,
-            ship_velocity: self.ship.velocity,
-            ship_angle: self.ship.angle,
+            ship_position: ship.position,
+            ship_velocity: ship.velocity,
+            ship_angle: ship.angle,
+            world_size: self.world,
             asteroids: self
                 .asteroids
-                .iter()
-                .map(|ast| AsteroidSnapshot {
+                .iter_with_ids()
+                .map(|(id, ast)| AsteroidSnapshot {
+                    id,
                     position: ast.position,
                     velocity: ast.velocity,
                     radius: ast.radius(),
+                    archetype: ast.archetype,
+                })
+                .collect(),
+            hazards: self
+                .gravity_wells
+                .iter()
+                .map(|well| HazardSnapshot {
+                    kind: HazardKind::GravityWell,
+                    position: well.position,
+                    radius: GRAVITY_WELL_RANGE,
                 })
+                .chain(self.nebulae.iter().map(|nebula| HazardSnapshot {
+                    kind: HazardKind::Nebula,
+                    position: nebula.position,
+                    radius: nebula.radius,
+                }))
                 .collect(),
+            solar_flare_active: self.solar_flare_active(),
+            allies: if self.versus.is_some() {
+                Vec::new()
+            } else {
+                others.clone().map(|(_, pilot)| pilot.ship.snapshot()).collect()
+            },
+            // Rivals in a nebula are as hidden from each other as they are from saucers.
+            opponents: if self.versus.is_some() {
+                others
+                    .filter(|&(other, _)| !self.ship_hidden(other))
+                    .map(|(_, pilot)| pilot.ship.snapshot())
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            friendly_fire: self.friendly_fire,
+            bullet_speed: self.tuning.bullet_speed,
         }
     }
 
-    pub fn dt(&self) -> f32 {
-        self.dt
-    }
-
-    pub fn apply_intent(&mut self, intent: ControlIntent) {
-        self.status.last_intent = Some(intent);
+    /// What one seat's controller is busy with, if it says.
+    pub fn controller_phase(&self, seat: usize) -> Option<&'static str> {
+        self.controllers.0[seat].as_ref().and_then(|controller| controller.phase())
     }
 
-    pub fn set_controller(&mut self, controller: Box<dyn Controller>) {
-        self.controller = controller;
+    pub fn set_controller(&mut self, seat: usize, controller: Box<dyn Controller>) {
+        self.controllers.0[seat] = Some(controller);
     }
 
     pub fn toggle_invulnerability(&mut self) {
@@ -151,62 +361,168 @@ impl Simulation {
         self.status.invulnerability_enabled = self.invulnerability_enabled;
     }
 
-    fn record_player_shot(&mut self) {
-        self.run_stats.shots_fired = self.run_stats.shots_fired.saturating_add(1);
+    /// Flags the run as played with help (slow motion), for the leaderboard.
+    pub fn mark_assisted(&mut self) {
+        self.assisted = true;
+        self.status.assisted = true;
+    }
+
+    /// Seconds until the first ship still flying is hit by a rock on its current course.
+    pub fn time_to_impact(&self) -> Option<f32> {
+        (0..self.pilots.len())
+            .filter(|&seat| self.pilots[seat].in_play())
+            .filter_map(|seat| ai::time_to_impact(&self.snapshot(seat), SHIP_SIZE * 0.9))
+            .min_by(f32::total_cmp)
+    }
+
+    /// Positions of the ships still flying, in seat order.
+    fn ships_in_play(&self) -> Vec<Vec2> {
+        self.pilots
+            .iter()
+            .filter(|pilot| pilot.in_play())
+            .map(|pilot| pilot.ship.position)
+            .collect()
+    }
+
+    fn record_player_shot(&mut self, seat: usize) {
+        let stats = &mut self.pilots[seat].run_stats;
+        stats.shots_fired = stats.shots_fired.saturating_add(1);
     }
 
-    fn record_player_hit(&mut self, target: HitTarget) {
-        self.run_stats.shots_hit = self.run_stats.shots_hit.saturating_add(1);
+    fn record_player_hit(&mut self, seat: usize, target: HitTarget) {
+        let stats = &mut self.pilots[seat].run_stats;
+        stats.shots_hit = stats.shots_hit.saturating_add(1);
         match target {
             HitTarget::LargeAsteroid => {
-                self.run_stats.hits_large_asteroid =
-                    self.run_stats.hits_large_asteroid.saturating_add(1)
+                stats.hits_large_asteroid =
+                    stats.hits_large_asteroid.saturating_add(1)
             }
             HitTarget::MediumAsteroid => {
-                self.run_stats.hits_medium_asteroid =
-                    self.run_stats.hits_medium_asteroid.saturating_add(1)
+                stats.hits_medium_asteroid =
+                    stats.hits_medium_asteroid.saturating_add(1)
             }
             HitTarget::SmallAsteroid => {
-                self.run_stats.hits_small_asteroid =
-                    self.run_stats.hits_small_asteroid.saturating_add(1)
+                stats.hits_small_asteroid =
+                    stats.hits_small_asteroid.saturating_add(1)
             }
             HitTarget::LargeAlien => {
-                self.run_stats.hits_large_alien = self.run_stats.hits_large_alien.saturating_add(1)
+                stats.hits_large_alien = stats.hits_large_alien.saturating_add(1)
             }
             HitTarget::SmallAlien => {
-                self.run_stats.hits_small_alien = self.run_stats.hits_small_alien.saturating_add(1)
+                stats.hits_small_alien = stats.hits_small_alien.saturating_add(1)
+            }
+            HitTarget::Carrier => {
+                stats.hits_carrier = stats.hits_carrier.saturating_add(1)
+            }
+            HitTarget::Drone => {
+                stats.hits_drone = stats.hits_drone.saturating_add(1)
             }
         }
     }
 
     pub fn step(&mut self) {
+        self.events.clear();
+        self.timings = StepTimings::default();
+        self.timings
+            .add(Stage::Controllers, std::mem::take(&mut self.controller_time));
         self.status.frame += 1;
-        let intent = self.status.last_intent.unwrap_or_default();
-        self.update_ship(intent);
-        self.handle_firing(intent);
-        self.update_asteroids();
-        self.tick_asteroid_spawns();
+        for seat in 0..self.pilots.len() {
+            if !self.pilots[seat].in_play() {
+                continue;
+            }
+            self.timed(Stage::Movement, |sim| sim.update_ship(seat));
+            self.timed(Stage::Firing, |sim| {
+                sim.handle_firing(seat);
+                sim.handle_sentinel_deploy(seat);
+            });
+        }
+        self.timed(Stage::Movement, Self::update_asteroids);
+        self.timed(Stage::Spawning, Self::tick_asteroid_spawns);
 
-        self.update_bullets();
-        self.update_debris();
-        self.update_aliens();
-        self.resolve_collisions();
+        self.timed(Stage::Bullets, Self::update_bullets);
+        self.timed(Stage::Hazards, Self::update_hazards);
+        self.timed(Stage::Debris, Self::update_debris);
+        self.timed(Stage::Aliens, Self::update_aliens);
+        self.timed(Stage::Enemies, Self::update_enemies);
+        self.timed(Stage::Sentinels, Self::update_sentinels);
+        self.timed(Stage::Pickups, Self::update_pickups);
+        self.timed(Stage::Collisions, Self::resolve_collisions);
+        self.timed(Stage::Bookkeeping, |sim| {
+            sim.update_versus();
+            for pilot in &mut sim.pilots {
+                pilot.tick_timers(sim.dt);
+            }
+            sim.update_status();
+        });
+    }
+
+    fn timed(&mut self, stage: Stage, system: impl FnOnce(&mut Self)) {
+        let start = Instant::now();
+        system(self);
+        self.timings.add(stage, start.elapsed());
+    }
+
+    /// Where the last step's time went, with the controller polls that led up to it.
+    pub fn timings(&self) -> &StepTimings {
+        &self.timings
+    }
 
-        self.primary_cooldown = (self.primary_cooldown - self.dt).max(0.0);
-        self.secondary_cooldown = (self.secondary_cooldown - self.dt).max(0.0);
-        self.invulnerability_timer = (self.invulnerability_timer - self.dt).max(0.0);
+    /// What happened during the last step, in the order it happened.
+    pub fn events(&self) -> &[SimEvent] {
+        &self.events
+    }
 
+    /// Recomputes the status from the state; `step` does this at the end of every frame.
+    fn update_status(&mut self) {
         self.status.asteroid_count = self.asteroids.len();
         self.status.bullet_count = self.bullets.len();
-        self.status.active_bodies = 1 + self.asteroids.len() + self.bullets.len();
-        self.status.primary_cooldown = self.primary_cooldown;
-        self.status.secondary_cooldown = self.secondary_cooldown;
+        self.status.enemy_count = self.carriers.len() + self.drones.len();
+        self.status.sentinel_count = self.sentinels.len();
+        self.status.pickup_count = self.pickups.len();
+        self.status.solar_flare_active = self.solar_flare_active();
+        self.status.solar_flare_incoming = self
+            .solar_flare
+            .as_ref()
+            .is_some_and(|flare| flare.incoming());
+        self.status.active_bodies = self.pilots.len()
+            + self.asteroids.len()
+            + self.bullets.len()
+            + self.status.enemy_count;
         self.status.frame_time = self.dt;
         self.status.fps = 1.0 / self.dt;
-        self.status.lives = self.lives;
-        self.status.game_over = self.lives == 0;
         self.status.invulnerability_enabled = self.invulnerability_enabled;
-        self.status.run_stats = self.run_stats.clone();
+        self.status.assisted = self.assisted;
+
+        // Timers and cooldowns on the HUD are player one's; score, lives and stats cover everyone.
+        let lead = &self.pilots[0];
+        self.status.rapid_fire_remaining = lead.rapid_fire_timer;
+        self.status.score_multiplier_remaining = lead.score_multiplier_timer;
+        self.status.score_multiplier = self.tuning.score_multiplier;
+        self.status.shield_charges = lead.shield_charges;
+        self.status.primary_cooldown = lead.primary_cooldown;
+        self.status.secondary_cooldown = lead.secondary_cooldown;
+        self.status.ship_hidden = self.ship_hidden(0);
+        self.status.pilots = self
+            .pilots
+            .iter()
+            .map(|pilot| PilotStatus {
+                score: pilot.score,
+                lives: pilot.lives,
+                frags: pilot.frags,
+                deaths: pilot.deaths,
+            })
+            .collect();
+        self.status.score = self.pilots.iter().map(|pilot| pilot.score).sum();
+        self.status.lives = self.pilots.iter().map(|pilot| pilot.lives).sum();
+        self.status.match_remaining = self.versus.as_ref().map(|versus| versus.remaining);
+        self.status.game_over = match &self.versus {
+            Some(versus) => versus.remaining <= 0.0,
+            None => self.pilots.iter().all(|pilot| !pilot.in_play()),
+        };
+        self.status.run_stats = RunStats::default();
+        for pilot in &self.pilots {
+            self.status.run_stats.merge(&pilot.run_stats);
+        }
     }
 
     pub fn policy(&mut self) -> &mut SimulationPolicy {
@@ -218,15 +534,64 @@ impl Simulation {
     }
 
     fn spawn_bullet(&mut self, position: Vec2, velocity: Vec2, source: BulletSource) {
-        self.bullets.push(Bullet::new(position, velocity, source));
-        if source == BulletSource::Player {
-            self.record_player_shot();
+        self.bullets
+            .insert(Bullet::new(position, velocity, source, self.tuning.bullet_ttl));
+        if let BulletSource::Player(seat) = source {
+            self.record_player_shot(seat);
+            self.events.push(SimEvent::BulletFired { seat, position });
         }
     }
 
     // Rendering helpers live in `render.rs`.
 }
 
+/// Each seat's controller. They sit beside the state `step` advances rather than inside it, so
+/// cloning a simulation copies only that state: a clone's seats have no controller and poll as
+/// idle until `set_controller` fills them.
+struct Controllers(Vec<Option<Box<dyn Controller>>>);
+
+impl Clone for Controllers {
+    fn clone(&self) -> Self {
+        Self(self.0.iter().map(|_| None).collect())
+    }
+}
+
+/// A saved copy of a simulation's state; see `Simulation::save_state`.
+#[derive(Clone)]
+pub struct SimulationState(Box<Simulation>);
+
+impl SimulationState {
+    /// The checksum the simulation had when this state was saved.
+    pub fn checksum(&self) -> u64 {
+        self.0.checksum()
+    }
+
+    /// The checksummed fields by name.
+    pub fn dump(&self) -> StateDump {
+        self.0.state_dump()
+    }
+}
+
+/// The controller a seat starts with. The app swaps in its own (keymap, autopilot, replay)
+/// before the first step.
+fn default_controller(mode: &PlayerControllerMode, seat: usize, config: &GameConfig) -> Box<dyn Controller> {
+    match mode {
+        PlayerControllerMode::Human if seat == 0 => Box::new(HumanController::new(
+            Keymap::default(),
+            config.aim_mode,
+            config.tuning.ship_rotation_speed,
+        )),
+        PlayerControllerMode::Human => Box::new(HumanController::partner(Keymap::default())),
+        PlayerControllerMode::Ai { profile } => Box::new(crate::ai::AiController::new(*profile)),
+    }
+}
+
+/// A simulation on a fixed 800x600 playfield, for the systems' tests.
+#[cfg(test)]
+pub(in crate::simulation) fn test_simulation(config: GameConfig, seed: u64) -> Simulation {
+    Simulation::with_world(config, seed, vec2(800.0, 600.0))
+}
+
 #[derive(Clone)]
 pub struct SimulationPolicy {
     pub collision_policy: super::config::CollisionPolicy,
@@ -244,12 +609,24 @@ impl SimulationPolicy {
     }
 }
 
+/// One seat's share of the run, for the HUD and the leaderboard.
+#[derive(Clone)]
+pub struct PilotStatus {
+    pub score: u32,
+    pub lives: u32,
+    /// Versus only: rivals this seat destroyed, and times it was destroyed.
+    pub frags: u32,
+    pub deaths: u32,
+}
+
 #[derive(Clone)]
 pub struct SimulationStatus {
     pub frame: u64,
-    pub last_intent: Option<ControlIntent>,
     pub asteroid_count: usize,
     pub bullet_count: usize,
+    pub enemy_count: usize,
+    pub sentinel_count: usize,
+    pub pickup_count: usize,
     pub active_bodies: usize,
     pub primary_cooldown: f32,
     pub secondary_cooldown: f32,
@@ -259,26 +636,84 @@ pub struct SimulationStatus {
     pub lives: u32,
     pub game_over: bool,
     pub invulnerability_enabled: bool,
+    /// Some of the run was played slowed down, by the time scale or the bullet-time assist, or
+    /// stepped back through with rewind.
+    pub assisted: bool,
+    pub rapid_fire_remaining: f32,
+    pub score_multiplier_remaining: f32,
+    /// What the score multiplier pickup multiplies by while it lasts.
+    pub score_multiplier: u32,
+    pub shield_charges: u32,
+    pub solar_flare_active: bool,
+    pub solar_flare_incoming: bool,
+    pub ship_hidden: bool,
+    /// Per-seat score and lives; `score`, `lives` and `run_stats` are the totals across seats.
+    pub pilots: Vec<PilotStatus>,
+    /// Seconds left in a versus match; `None` outside versus.
+    pub match_remaining: Option<f32>,
     pub run_stats: RunStats,
 }
 
+impl SimulationStatus {
+    /// Seats in versus finishing order: most frags first, fewest deaths breaking ties.
+    pub fn standings(&self) -> Vec<usize> {
+        let mut seats: Vec<usize> = (0..self.pilots.len()).collect();
+        seats.sort_by_key(|&seat| {
+            let pilot = &self.pilots[seat];
+            (std::cmp::Reverse(pilot.frags), pilot.deaths)
+        });
+        seats
+    }
+}
+
 impl Default for SimulationStatus {
     fn default() -> Self {
         Self {
             frame: 0,
-            last_intent: None,
             asteroid_count: 0,
             bullet_count: 0,
+            enemy_count: 0,
+            sentinel_count: 0,
+            pickup_count: 0,
             active_bodies: 1,
             primary_cooldown: 0.0,
             secondary_cooldown: 0.0,
             frame_time: 1.0 / TARGET_FPS,
             fps: TARGET_FPS,
             score: 0,
-            lives: MAX_LIVES,
+            lives: SimTuning::classic().max_lives,
             game_over: false,
             invulnerability_enabled: false,
+            assisted: false,
+            rapid_fire_remaining: 0.0,
+            score_multiplier_remaining: 0.0,
+            score_multiplier: SimTuning::classic().score_multiplier,
+            shield_charges: 0,
+            solar_flare_active: false,
+            solar_flare_incoming: false,
+            ship_hidden: false,
+            pilots: Vec::new(),
+            match_remaining: None,
             run_stats: RunStats::default(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::AiController;
+    use crate::config::AiProfile;
+
+    #[test]
+    fn clones_poll_as_idle_until_given_controllers() {
+        let original = test_simulation(GameConfig::default(), 3);
+        let mut clone = original.clone();
+        assert_eq!(clone.poll_controllers(), [ControlIntent::default()]);
+        assert_eq!(clone.controller_phase(0), None);
+
+        clone.set_controller(0, Box::new(AiController::new(AiProfile::Balanced)));
+        clone.poll_seat(0);
+        assert!(clone.controller_phase(0).is_some());
+    }
+}
@@ -1,7 +1,15 @@
-use macroquad::prelude::{Color, Vec2, vec2, screen_height, screen_width};
-use macroquad::rand::gen_range;
+use crate::ai::ShipSnapshot;
+use crate::config::{AsteroidArchetype, PickupKind, SimTuning};
+use crate::controllers::alien::{AlienController, AlienSnapshot};
+use crate::controllers::ControlIntent;
+use crate::stats::RunStats;
+use macroquad::prelude::{Color, Vec2, vec2};
 use std::f32::consts::PI;
 
+use super::rng::SimRng;
+
+const ALIEN_MARGIN: f32 = 30.0;
+
 #[derive(Clone, Copy)]
 pub(super) enum AsteroidSize {
     Large,
@@ -26,28 +34,52 @@ impl AsteroidSize {
         }
     }
 
-    pub(super) fn score(&self) -> u32 {
+    pub(super) fn score(&self, base: u32) -> u32 {
         match self {
-            AsteroidSize::Large => super::ASTEROID_SCORE_BASE,
-            AsteroidSize::Medium => super::ASTEROID_SCORE_BASE * 2,
-            AsteroidSize::Small => super::ASTEROID_SCORE_BASE * 4,
+            AsteroidSize::Large => base,
+            AsteroidSize::Medium => base * 2,
+            AsteroidSize::Small => base * 4,
+        }
+    }
+
+    pub(super) fn hit_target(&self) -> HitTarget {
+        match self {
+            AsteroidSize::Large => HitTarget::LargeAsteroid,
+            AsteroidSize::Medium => HitTarget::MediumAsteroid,
+            AsteroidSize::Small => HitTarget::SmallAsteroid,
         }
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-pub(super) enum HitTarget {
+/// What a shot can hit, as counted in the run stats and reported in events.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HitTarget {
     LargeAsteroid,
     MediumAsteroid,
     SmallAsteroid,
     LargeAlien,
     SmallAlien,
+    Carrier,
+    Drone,
 }
 
+/// Who fired a bullet. Player and sentinel shots carry the seat of the pilot they belong to.
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub(super) enum BulletSource {
-    Player,
+    Player(usize),
     Alien,
+    Sentinel(usize),
+}
+
+impl BulletSource {
+    /// The pilot credited with the bullet's kills. Player and sentinel kills both feed the score;
+    /// only player shots feed accuracy stats.
+    pub(super) fn owner(&self) -> Option<usize> {
+        match *self {
+            BulletSource::Player(seat) | BulletSource::Sentinel(seat) => Some(seat),
+            BulletSource::Alien => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -120,35 +152,66 @@ pub(super) struct Alien {
     pub(super) size: AlienSize,
     pub(super) fire_timer: f32,
     pub(super) angle: f32,
+    pub(super) controller: Box<dyn AlienController>,
 }
 
 impl Alien {
-    pub(super) fn new(size: AlienSize, direction: f32, starting_x: f32, width: f32) -> Self {
+    pub(super) fn new(
+        size: AlienSize,
+        direction: f32,
+        starting_x: f32,
+        width: f32,
+        controller: Box<dyn AlienController>,
+    ) -> Self {
         let y = size.y();
         let angle = if direction >= 0.0 { 0.0 } else { PI };
         Self {
-            position: Vec2::new(starting_x.clamp(30.0, width - 30.0), y),
+            position: Vec2::new(starting_x.clamp(ALIEN_MARGIN, width - ALIEN_MARGIN), y),
             velocity: Vec2::new(direction * size.speed(), 0.0),
             size,
             fire_timer: size.fire_interval(),
             angle,
+            controller,
         }
     }
 
-    pub(super) fn update(&mut self, dt: f32, width: f32) {
-        self.position.x += self.velocity.x * dt;
-        if self.position.x < 30.0 {
-            self.position.x = 30.0;
-            self.velocity.x = -self.velocity.x;
-        } else if self.position.x > width - 30.0 {
-            self.position.x = width - 30.0;
-            self.velocity.x = -self.velocity.x;
+    pub(super) fn snapshot(
+        &self,
+        ship_position: Vec2,
+        ship_velocity: Vec2,
+        score: u32,
+        width: f32,
+        bullet_speed: f32,
+        aim_noise: f32,
+    ) -> AlienSnapshot {
+        AlienSnapshot {
+            position: self.position,
+            velocity: self.velocity,
+            cruise_speed: self.size.speed(),
+            min_x: ALIEN_MARGIN,
+            max_x: width - ALIEN_MARGIN,
+            ship_position,
+            ship_velocity,
+            bullet_speed,
+            ready_to_fire: self.ready_to_fire(),
+            cone_half_angle: self.size.cone_half_angle(score),
+            aim_noise,
         }
+    }
 
-        self.fire_timer -= dt;
+    /// Applies the controller's requested velocity (clamped to cruise speed) and moves.
+    pub(super) fn steer(&mut self, velocity: Vec2, dt: f32, width: f32, height: f32) {
+        self.velocity = clamp_length(velocity, self.size.speed());
+        self.position += self.velocity * dt;
+        self.position.x = self.position.x.clamp(ALIEN_MARGIN, width - ALIEN_MARGIN);
+        self.position.y = self.position.y.clamp(ALIEN_MARGIN, height - ALIEN_MARGIN);
         self.angle = if self.velocity.x >= 0.0 { 0.0 } else { PI };
     }
 
+    pub(super) fn tick_fire_timer(&mut self, dt: f32) {
+        self.fire_timer -= dt;
+    }
+
     pub(super) fn ready_to_fire(&self) -> bool {
         self.fire_timer <= 0.0
     }
@@ -156,15 +219,186 @@ impl Alien {
     pub(super) fn reset_fire_timer(&mut self) {
         self.fire_timer = self.size.fire_interval();
     }
+}
+
+/// What a drone does once its parent carrier is gone.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum DroneMode {
+    Escort,
+    Scatter,
+    Kamikaze,
+}
+
+#[derive(Clone)]
+pub(super) struct Carrier {
+    pub(super) id: u32,
+    pub(super) position: Vec2,
+    pub(super) velocity: Vec2,
+    pub(super) hp: u32,
+    pub(super) launch_timer: f32,
+}
+
+impl Carrier {
+    pub(super) fn new(id: u32, position: Vec2, velocity: Vec2, hp: u32, launch_timer: f32) -> Self {
+        Self {
+            id,
+            position,
+            velocity,
+            hp,
+            launch_timer,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Drone {
+    pub(super) carrier: u32,
+    pub(super) position: Vec2,
+    pub(super) velocity: Vec2,
+    pub(super) mode: DroneMode,
+    pub(super) orbit_phase: f32,
+    /// Remaining lifetime once scattering; escorts and kamikazes ignore it.
+    pub(super) ttl: f32,
+}
+
+impl Drone {
+    pub(super) fn new(carrier: u32, position: Vec2, orbit_phase: f32) -> Self {
+        Self {
+            carrier,
+            position,
+            velocity: Vec2::ZERO,
+            mode: DroneMode::Escort,
+            orbit_phase,
+            ttl: super::DRONE_SCATTER_TTL,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum SentinelAnchor {
+    /// Stays where it was dropped.
+    Hold(Vec2),
+    /// Circles the ship at a fixed radius.
+    Orbit,
+}
+
+#[derive(Clone)]
+pub(super) struct Sentinel {
+    /// Seat of the pilot that deployed it; it escorts that ship and scores for it.
+    pub(super) owner: usize,
+    pub(super) position: Vec2,
+    pub(super) anchor: SentinelAnchor,
+    pub(super) orbit_phase: f32,
+    pub(super) fire_timer: f32,
+    pub(super) ttl: f32,
+    pub(super) energy: u32,
+}
+
+impl Sentinel {
+    pub(super) fn new(
+        owner: usize,
+        position: Vec2,
+        anchor: SentinelAnchor,
+        orbit_phase: f32,
+        ttl: f32,
+        energy: u32,
+    ) -> Self {
+        Self {
+            owner,
+            position,
+            anchor,
+            orbit_phase,
+            fire_timer: 0.0,
+            ttl,
+            energy,
+        }
+    }
+
+    pub(super) fn expired(&self) -> bool {
+        self.ttl <= 0.0 || self.energy == 0
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Pickup {
+    pub(super) kind: PickupKind,
+    pub(super) position: Vec2,
+    pub(super) velocity: Vec2,
+    pub(super) ttl: f32,
+}
+
+impl Pickup {
+    pub(super) fn new(kind: PickupKind, position: Vec2, velocity: Vec2, ttl: f32) -> Self {
+        Self {
+            kind,
+            position,
+            velocity,
+            ttl,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct GravityWell {
+    pub(super) position: Vec2,
+    pub(super) strength: f32,
+}
+
+impl GravityWell {
+    /// Inverse-square pull towards the core, zero outside the well's range.
+    pub(super) fn accel_at(&self, point: Vec2) -> Vec2 {
+        let offset = self.position - point;
+        let distance = offset.length();
+        if distance >= super::GRAVITY_WELL_RANGE || distance <= 0.0 {
+            return Vec2::ZERO;
+        }
+        let falloff = distance.max(super::GRAVITY_WELL_MIN_DISTANCE);
+        offset / distance * (self.strength / (falloff * falloff))
+    }
+
+    pub(super) fn swallows(&self, point: Vec2, radius: f32) -> bool {
+        let reach = super::GRAVITY_WELL_CORE + radius;
+        self.position.distance_squared(point) <= reach * reach
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct Nebula {
+    pub(super) position: Vec2,
+    pub(super) radius: f32,
+}
+
+impl Nebula {
+    pub(super) fn contains(&self, point: Vec2) -> bool {
+        self.position.distance_squared(point) <= self.radius * self.radius
+    }
+}
+
+/// Flare cycle: `cooldown` counts down to the next flare, `remaining` to the end of the current one.
+#[derive(Clone)]
+pub(super) struct SolarFlare {
+    pub(super) cooldown: f32,
+    pub(super) remaining: f32,
+}
+
+impl SolarFlare {
+    pub(super) fn new(interval: f32) -> Self {
+        Self {
+            cooldown: interval,
+            remaining: 0.0,
+        }
+    }
+
+    pub(super) fn active(&self) -> bool {
+        self.remaining > 0.0
+    }
 
-    pub(super) fn fire_direction(&self, ship_pos: Vec2, score: u32) -> Vec2 {
-        let base = (ship_pos - self.position).to_angle();
-        let cone = self.size.cone_half_angle(score);
-        let offset = gen_range(-cone, cone);
-        Vec2::from_angle(base + offset).normalize_or_zero()
+    pub(super) fn incoming(&self) -> bool {
+        !self.active() && self.cooldown <= super::SOLAR_FLARE_WARNING
     }
 }
 
+#[derive(Clone)]
 pub(super) struct Ship {
     pub(super) position: Vec2,
     pub(super) velocity: Vec2,
@@ -172,13 +406,180 @@ pub(super) struct Ship {
 }
 
 impl Ship {
-    pub(super) fn centered() -> Self {
+    pub(super) fn at(position: Vec2) -> Self {
         Self {
-            position: vec2(screen_width() / 2.0, screen_height() / 2.0),
+            position,
             velocity: Vec2::ZERO,
             angle: -PI / 2.0,
         }
     }
+
+    /// How other seats' controllers see this ship.
+    pub(super) fn snapshot(&self) -> ShipSnapshot {
+        ShipSnapshot {
+            position: self.position,
+            velocity: self.velocity,
+            angle: self.angle,
+        }
+    }
+}
+
+/// Where a seat's ship starts and respawns: the centre for one ship, side by side for more.
+pub(super) fn spawn_point(seat: usize, seats: usize, world: Vec2) -> Vec2 {
+    let center = world / 2.0;
+    let offset = seat as f32 - (seats as f32 - 1.0) * 0.5;
+    center + vec2(offset * super::SPAWN_SPACING, 0.0)
+}
+
+/// Where versus ships start and respawn: `count` points evenly spaced on a ring around the centre.
+pub(super) fn ring_point(index: usize, count: usize, world: Vec2) -> Vec2 {
+    let center = world / 2.0;
+    let radius = world.min_element() * super::VERSUS_RING_RADIUS;
+    let angle = index as f32 / count.max(1) as f32 * 2.0 * PI - PI / 2.0;
+    center + Vec2::from_angle(angle) * radius
+}
+
+/// Versus match state: the clock and how long a fragged ship sits out.
+#[derive(Clone)]
+pub(super) struct VersusMatch {
+    pub(super) remaining: f32,
+    pub(super) respawn_delay: f32,
+}
+
+/// One player's ship and everything that belongs to it: controls, lives, score, cooldowns and
+/// pickups. Solo runs have one pilot; co-op has two sharing the asteroid field.
+#[derive(Clone)]
+pub(super) struct Pilot {
+    pub(super) ship: Ship,
+    /// This step's input, set by `Simulation::poll_controllers` or `Simulation::apply_intents`.
+    pub(super) intent: ControlIntent,
+    pub(super) lives: u32,
+    pub(super) score: u32,
+    pub(super) next_extra_life_score: u32,
+    pub(super) primary_cooldown: f32,
+    pub(super) secondary_cooldown: f32,
+    pub(super) sentinel_cooldown: f32,
+    pub(super) invulnerability_timer: f32,
+    pub(super) rapid_fire_timer: f32,
+    pub(super) score_multiplier_timer: f32,
+    pub(super) shield_charges: u32,
+    pub(super) run_stats: RunStats,
+    /// Set when a hazard (a gravity well core) catches the ship; consumed by collision resolution.
+    pub(super) hazard_hit: bool,
+    /// Where saucers last saw the ship before it slipped into a nebula.
+    pub(super) last_seen: Vec2,
+    /// Versus only: rivals destroyed and times destroyed.
+    pub(super) frags: u32,
+    pub(super) deaths: u32,
+    /// Versus only: seconds until a fragged ship comes back; `None` while flying.
+    pub(super) respawn_timer: Option<f32>,
+}
+
+impl Pilot {
+    pub(super) fn new(spawn: Vec2, tuning: &SimTuning) -> Self {
+        Self {
+            ship: Ship::at(spawn),
+            intent: ControlIntent::default(),
+            lives: tuning.max_lives,
+            score: 0,
+            next_extra_life_score: tuning.extra_life_score_step,
+            primary_cooldown: 0.0,
+            secondary_cooldown: 0.0,
+            sentinel_cooldown: 0.0,
+            invulnerability_timer: tuning.invulnerability_duration,
+            rapid_fire_timer: 0.0,
+            score_multiplier_timer: 0.0,
+            shield_charges: 0,
+            run_stats: RunStats::default(),
+            hazard_hit: false,
+            last_seen: spawn,
+            frags: 0,
+            deaths: 0,
+            respawn_timer: None,
+        }
+    }
+
+    /// Whether the ship is still flying; a pilot out of lives sits out the rest of the run, and a
+    /// fragged versus ship sits out its respawn delay.
+    pub(super) fn in_play(&self) -> bool {
+        self.lives > 0 && self.respawn_timer.is_none()
+    }
+
+    pub(super) fn tick_timers(&mut self, dt: f32) {
+        self.primary_cooldown = (self.primary_cooldown - dt).max(0.0);
+        self.secondary_cooldown = (self.secondary_cooldown - dt).max(0.0);
+        self.sentinel_cooldown = (self.sentinel_cooldown - dt).max(0.0);
+        self.rapid_fire_timer = (self.rapid_fire_timer - dt).max(0.0);
+        self.score_multiplier_timer = (self.score_multiplier_timer - dt).max(0.0);
+        self.invulnerability_timer = (self.invulnerability_timer - dt).max(0.0);
+    }
+}
+
+/// The point in `points` closest to `from`, if there is one.
+pub(super) fn nearest(points: &[Vec2], from: Vec2) -> Option<Vec2> {
+    points
+        .iter()
+        .copied()
+        .min_by(|a, b| a.distance_squared(from).total_cmp(&b.distance_squared(from)))
+}
+
+/// Per-archetype numbers. Kept as data so archetypes differ by table row, not by code path.
+pub(super) struct ArchetypeDef {
+    /// Bullet hits needed to break the rock.
+    pub(super) hits: u32,
+    pub(super) score_multiplier: u32,
+    /// Fragments produced when a non-small rock breaks.
+    pub(super) fragments: usize,
+    /// Radius of the explosion on destruction; zero for inert rocks.
+    pub(super) blast_radius: f32,
+    /// Acceleration toward the ship; zero for rocks that drift ballistically.
+    pub(super) magnet_accel: f32,
+}
+
+pub(super) fn archetype_def(archetype: AsteroidArchetype) -> &'static ArchetypeDef {
+    const ROCK: ArchetypeDef = ArchetypeDef {
+        hits: 1,
+        score_multiplier: 1,
+        fragments: 2,
+        blast_radius: 0.0,
+        magnet_accel: 0.0,
+    };
+    const ARMOURED: ArchetypeDef = ArchetypeDef {
+        hits: 3,
+        score_multiplier: 3,
+        fragments: 2,
+        blast_radius: 0.0,
+        magnet_accel: 0.0,
+    };
+    const VOLATILE: ArchetypeDef = ArchetypeDef {
+        hits: 1,
+        score_multiplier: 2,
+        fragments: 2,
+        blast_radius: 70.0,
+        magnet_accel: 0.0,
+    };
+    const SPLITTER: ArchetypeDef = ArchetypeDef {
+        hits: 1,
+        score_multiplier: 2,
+        fragments: 3,
+        blast_radius: 0.0,
+        magnet_accel: 0.0,
+    };
+    const MAGNETIC: ArchetypeDef = ArchetypeDef {
+        hits: 1,
+        score_multiplier: 2,
+        fragments: 2,
+        blast_radius: 0.0,
+        magnet_accel: 28.0,
+    };
+
+    match archetype {
+        AsteroidArchetype::Rock => &ROCK,
+        AsteroidArchetype::Armoured => &ARMOURED,
+        AsteroidArchetype::Volatile => &VOLATILE,
+        AsteroidArchetype::Splitter => &SPLITTER,
+        AsteroidArchetype::Magnetic => &MAGNETIC,
+    }
 }
 
 #[derive(Clone)]
@@ -186,20 +587,30 @@ pub(super) struct Asteroid {
     pub(super) position: Vec2,
     pub(super) velocity: Vec2,
     pub(super) size: AsteroidSize,
+    pub(super) archetype: AsteroidArchetype,
+    pub(super) hp: u32,
     pub(super) angle: f32,
     pub(super) rotation_speed: f32,
     pub(super) shape: Vec<Vec2>,
 }
 
 impl Asteroid {
-    pub(super) fn new(size: AsteroidSize, position: Vec2, velocity: Vec2) -> Self {
+    pub(super) fn new(
+        size: AsteroidSize,
+        archetype: AsteroidArchetype,
+        position: Vec2,
+        velocity: Vec2,
+        rng: &mut SimRng,
+    ) -> Self {
         Self {
             position,
             velocity,
             size,
-            angle: gen_range(0.0, 2.0 * PI),
-            rotation_speed: gen_range(-0.8, 0.8),
-            shape: generate_shape(size),
+            archetype,
+            hp: archetype_def(archetype).hits,
+            angle: rng.gen_range(0.0, 2.0 * PI),
+            rotation_speed: rng.gen_range(-0.8, 0.8),
+            shape: generate_shape(size, rng),
         }
     }
 
@@ -207,6 +618,14 @@ impl Asteroid {
         self.size.radius()
     }
 
+    pub(super) fn def(&self) -> &'static ArchetypeDef {
+        archetype_def(self.archetype)
+    }
+
+    pub(super) fn score(&self, base: u32) -> u32 {
+        self.size.score(base) * self.def().score_multiplier
+    }
+
     pub(super) fn points(&self) -> Vec<Vec2> {
         self.shape
             .iter()
@@ -214,15 +633,24 @@ impl Asteroid {
             .collect()
     }
 
-    pub(super) fn split(&self) -> Vec<Asteroid> {
+    /// Fragments fly apart at least as fast as `min_speed`, even off a near-stationary parent.
+    pub(super) fn split(&self, min_speed: f32, rng: &mut SimRng) -> Vec<Asteroid> {
         if let Some(next_size) = self.size.next() {
-            let mut fragments = Vec::with_capacity(2);
-            let base_len = self.velocity.length().max(super::ASTEROID_MIN_SPEED);
+            let count = self.def().fragments.max(1);
+            let mut fragments = Vec::with_capacity(count);
+            let base_len = self.velocity.length().max(min_speed);
             let base_angle = self.velocity.to_angle();
-            for i in 0..2 {
-                let offset = Vec2::from_angle(base_angle + (i as f32 - 0.5) * 0.6);
+            let center = (count as f32 - 1.0) * 0.5;
+            for i in 0..count {
+                let offset = Vec2::from_angle(base_angle + (i as f32 - center) * 0.6);
                 let velocity = offset * base_len;
-                fragments.push(Asteroid::new(next_size, self.position, velocity));
+                fragments.push(Asteroid::new(
+                    next_size,
+                    self.archetype,
+                    self.position,
+                    velocity,
+                    rng,
+                ));
             }
             fragments
         } else {
@@ -240,11 +668,11 @@ pub(super) struct Bullet {
 }
 
 impl Bullet {
-    pub(super) fn new(position: Vec2, velocity: Vec2, source: BulletSource) -> Self {
+    pub(super) fn new(position: Vec2, velocity: Vec2, source: BulletSource, ttl: f32) -> Self {
         Self {
             position,
             velocity,
-            ttl: super::BULLET_TTL,
+            ttl,
             source,
         }
     }
@@ -269,9 +697,8 @@ impl Debris {
     }
 }
 
-pub(super) fn wrap_position(position: Vec2) -> Vec2 {
-    let width = screen_width();
-    let height = screen_height();
+pub(super) fn wrap_position(position: Vec2, world: Vec2) -> Vec2 {
+    let (width, height) = (world.x, world.y);
     let mut result = position;
     if result.x < 0.0 {
         result.x += width;
@@ -297,7 +724,7 @@ pub(super) fn clamp_length(value: Vec2, max: f32) -> Vec2 {
     }
 }
 
-fn generate_shape(size: AsteroidSize) -> Vec<Vec2> {
+fn generate_shape(size: AsteroidSize, rng: &mut SimRng) -> Vec<Vec2> {
     let base_radius = size.radius();
     let vertex_count = match size {
         AsteroidSize::Large => 12,
@@ -307,7 +734,7 @@ fn generate_shape(size: AsteroidSize) -> Vec<Vec2> {
     (0..vertex_count)
         .map(|i| {
             let theta = (i as f32 / vertex_count as f32) * 2.0 * PI;
-            let jitter = gen_range(0.8, 1.2);
+            let jitter = rng.gen_range(0.8, 1.2);
             Vec2::from_angle(theta) * base_radius * jitter
         })
         .collect()
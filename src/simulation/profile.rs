@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+/// The parts of a step that are timed separately, in the order `step` runs them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    /// Every seat's `Controller::tick`, including building its snapshot.
+    Controllers,
+    /// Ships and asteroids moving.
+    Movement,
+    /// Ships firing and deploying sentinels.
+    Firing,
+    Spawning,
+    Bullets,
+    Hazards,
+    Debris,
+    /// Saucers, including their spawns and shots.
+    Aliens,
+    /// Carriers and drones.
+    Enemies,
+    Sentinels,
+    Pickups,
+    Collisions,
+    /// The versus clock, pilot timers and the status.
+    Bookkeeping,
+}
+
+impl Stage {
+    pub const ALL: [Stage; 13] = [
+        Stage::Controllers,
+        Stage::Movement,
+        Stage::Firing,
+        Stage::Spawning,
+        Stage::Bullets,
+        Stage::Hazards,
+        Stage::Debris,
+        Stage::Aliens,
+        Stage::Enemies,
+        Stage::Sentinels,
+        Stage::Pickups,
+        Stage::Collisions,
+        Stage::Bookkeeping,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Stage::Controllers => "controllers",
+            Stage::Movement => "movement",
+            Stage::Firing => "firing",
+            Stage::Spawning => "spawning",
+            Stage::Bullets => "bullets",
+            Stage::Hazards => "hazards",
+            Stage::Debris => "debris",
+            Stage::Aliens => "aliens",
+            Stage::Enemies => "enemies",
+            Stage::Sentinels => "sentinels",
+            Stage::Pickups => "pickups",
+            Stage::Collisions => "collisions",
+            Stage::Bookkeeping => "bookkeeping",
+        }
+    }
+}
+
+/// Where the last step's time went. Wall-clock measurements, so never part of the state that
+/// is checksummed or saved.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StepTimings {
+    stages: [Duration; Stage::ALL.len()],
+    /// Bullet and ship tests collision resolution had to consider: each against every body it
+    /// could hit, before anything is skipped.
+    pub collision_pairs: usize,
+}
+
+impl StepTimings {
+    pub fn stage(&self, stage: Stage) -> Duration {
+        self.stages[stage as usize]
+    }
+
+    pub(super) fn add(&mut self, stage: Stage, elapsed: Duration) {
+        self.stages[stage as usize] += elapsed;
+    }
+
+    /// The whole step, controllers included.
+    pub fn total(&self) -> Duration {
+        self.stages.iter().sum()
+    }
+}
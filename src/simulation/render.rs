@@ -1,52 +1,59 @@
 use crate::render::shapes::{
-    draw_shape, saucer_large_lines, saucer_small_lines, ship_lines,
+    carrier_lines, draw_shape, drone_lines, saucer_large_lines, saucer_small_lines,
+    sentinel_lines, ship_lines,
+};
+use macroquad::prelude::{
+    BLACK, Color, Vec2, WHITE, draw_circle, draw_circle_lines, draw_line, draw_rectangle,
+    draw_rectangle_lines, draw_text,
 };
-use macroquad::prelude::{Color, Vec2, WHITE, draw_circle, draw_line};
 use macroquad::rand::gen_range;
 
 use super::{
-    BULLET_RADIUS, INVULNERABILITY_DURATION, LARGE_ALIEN_DRAW_SCALE, SAUCER_STROKE, SHIP_DRAW_OFFSET,
-    SHIP_SIZE, SHIP_STROKE, SMALL_ALIEN_DRAW_SCALE,
+    BULLET_RADIUS, CARRIER_DRAW_SCALE, DRONE_RADIUS, ENEMY_DEBRIS_COLOR,
+    GRAVITY_WELL_CORE, GRAVITY_WELL_RANGE,
+    LARGE_ALIEN_DRAW_SCALE, PICKUP_RADIUS, SAUCER_STROKE, SENTINEL_SIZE,
+    SHIP_DRAW_OFFSET, SHIP_SIZE, SHIP_STROKE, SMALL_ALIEN_DRAW_SCALE,
 };
-use super::model::{AlienSize};
+use super::model::{AlienSize, Asteroid, DroneMode, Pilot, Ship};
+use crate::config::{AsteroidArchetype, PickupKind};
 use super::Simulation;
 
 impl Simulation {
     pub fn draw_debug(&self) {
+        self.draw_hazards();
+
         for asteroid in &self.asteroids {
-            let points = asteroid.points();
-            if points.len() > 1 {
-                for i in 0..points.len() {
-                    let a = points[i];
-                    let b = points[(i + 1) % points.len()];
-                    draw_line(a.x, a.y, b.x, b.y, 2.0, macroquad::prelude::LIGHTGRAY);
-                }
-            }
+            draw_asteroid(asteroid);
         }
 
         let ship_segments = ship_lines(SHIP_SIZE);
-        draw_shape(
-            &ship_segments,
-            self.ship.position,
-            self.ship.angle + SHIP_DRAW_OFFSET,
-            SHIP_STROKE,
-            WHITE,
-            true,
-        );
-        self.draw_thruster();
-
-        if self.invulnerability_timer > 0.0 || self.invulnerability_enabled {
-            let alpha = if self.invulnerability_timer > 0.0 {
-                ((self.invulnerability_timer / INVULNERABILITY_DURATION) * 0.8).clamp(0.2, 0.8)
-            } else {
-                0.6
-            };
-            draw_circle(
-                self.ship.position.x,
-                self.ship.position.y,
-                SHIP_SIZE * 1.4,
-                Color::new(0.2, 0.8, 1.0, alpha),
+        for (seat, pilot) in self.pilots.iter().enumerate() {
+            if !pilot.in_play() {
+                continue;
+            }
+            draw_shape(
+                &ship_segments,
+                pilot.ship.position,
+                pilot.ship.angle + SHIP_DRAW_OFFSET,
+                SHIP_STROKE,
+                seat_color(seat),
+                true,
             );
+            draw_thruster(pilot);
+
+            if pilot.invulnerability_timer > 0.0 || self.invulnerability_enabled {
+                let alpha = if pilot.invulnerability_timer > 0.0 {
+                    ((pilot.invulnerability_timer / self.tuning.invulnerability_duration) * 0.8).clamp(0.2, 0.8)
+                } else {
+                    0.6
+                };
+                draw_circle(
+                    pilot.ship.position.x,
+                    pilot.ship.position.y,
+                    SHIP_SIZE * 1.4,
+                    Color::new(0.2, 0.8, 1.0, alpha),
+                );
+            }
         }
 
         for bullet in &self.bullets {
@@ -69,50 +76,239 @@ impl Simulation {
             };
             draw_shape(&lines, alien.position, alien.angle, SAUCER_STROKE, WHITE, true);
         }
+
+        let carrier_shape = carrier_lines(CARRIER_DRAW_SCALE);
+        for carrier in &self.carriers {
+            draw_shape(&carrier_shape, carrier.position, 0.0, SAUCER_STROKE, ENEMY_DEBRIS_COLOR, true);
+            let bar_width = CARRIER_DRAW_SCALE * 2.0;
+            let fill = carrier.hp as f32 / self.tuning.carrier_hp as f32;
+            let bar_x = carrier.position.x - CARRIER_DRAW_SCALE;
+            let bar_y = carrier.position.y - CARRIER_DRAW_SCALE * 0.9;
+            draw_rectangle(bar_x, bar_y, bar_width, 3.0, Color::new(0.3, 0.3, 0.3, 0.8));
+            draw_rectangle(bar_x, bar_y, bar_width * fill, 3.0, ENEMY_DEBRIS_COLOR);
+        }
+
+        let drone_shape = drone_lines(DRONE_RADIUS * 1.4);
+        for drone in &self.drones {
+            let color = match drone.mode {
+                DroneMode::Escort => ENEMY_DEBRIS_COLOR,
+                DroneMode::Scatter => Color::new(0.7, 0.7, 0.7, 1.0),
+                DroneMode::Kamikaze => Color::new(1.0, 0.3, 0.2, 1.0),
+            };
+            let heading = if drone.velocity.length_squared() > 0.0 {
+                drone.velocity.to_angle()
+            } else {
+                0.0
+            };
+            draw_shape(&drone_shape, drone.position, heading, 1.5, color, false);
+        }
+
+        for pickup in &self.pickups {
+            let (label, color) = pickup_style(pickup.kind);
+            // Blink during the last two seconds so the player knows it is about to vanish.
+            if pickup.ttl < 2.0 && (pickup.ttl * 8.0) as i32 % 2 == 0 {
+                continue;
+            }
+            draw_circle_lines(pickup.position.x, pickup.position.y, PICKUP_RADIUS, 1.5, color);
+            draw_text(
+                label,
+                pickup.position.x - PICKUP_RADIUS * 0.6,
+                pickup.position.y + PICKUP_RADIUS * 0.5,
+                PICKUP_RADIUS * 1.8,
+                color,
+            );
+        }
+
+        let sentinel_shape = sentinel_lines(SENTINEL_SIZE);
+        let sentinel_color = Color::new(0.4, 1.0, 0.6, 1.0);
+        for sentinel in &self.sentinels {
+            draw_shape(&sentinel_shape, sentinel.position, 0.0, 1.5, sentinel_color, true);
+            // The ring shrinks as the sentinel's lifetime runs out.
+            let remaining = (sentinel.ttl / self.tuning.sentinel_ttl).clamp(0.0, 1.0);
+            draw_circle_lines(
+                sentinel.position.x,
+                sentinel.position.y,
+                SENTINEL_SIZE * (1.2 + remaining),
+                1.0,
+                Color::new(0.4, 1.0, 0.6, 0.4),
+            );
+        }
     }
 
-    fn draw_thruster(&self) {
-        let intent = self.status.last_intent.unwrap_or_default();
-        if intent.thrust <= 0.0 {
-            return;
-        }
-        let left = self.world_ship_point(-0.5, 0.15);
-        let right = self.world_ship_point(-0.5, -0.15);
-        let jitter = gen_range(-0.05, 0.05);
-        let tip = self.world_ship_point(-0.95 - intent.thrust * 0.2 + jitter, 0.0);
-        let flame_alpha = 0.7 * intent.thrust;
-        draw_line(
-            left.x,
-            left.y,
-            tip.x,
-            tip.y,
-            2.0,
-            Color::new(1.0, 1.0, 1.0, flame_alpha),
-        );
-        draw_line(
-            right.x,
-            right.y,
-            tip.x,
-            tip.y,
-            2.0,
-            Color::new(1.0, 1.0, 1.0, flame_alpha),
-        );
-        draw_line(
-            left.x,
-            left.y,
-            right.x,
-            right.y,
-            1.5,
-            Color::new(1.0, 1.0, 1.0, flame_alpha * 0.8),
-        );
+    fn draw_hazards(&self) {
+        for nebula in &self.nebulae {
+            draw_circle(
+                nebula.position.x,
+                nebula.position.y,
+                nebula.radius,
+                Color::new(0.45, 0.3, 0.7, 0.18),
+            );
+            draw_circle_lines(
+                nebula.position.x,
+                nebula.position.y,
+                nebula.radius,
+                1.0,
+                Color::new(0.6, 0.45, 0.9, 0.35),
+            );
+        }
+
+        for well in &self.gravity_wells {
+            // Faint rings mark the pull range; the core is solid.
+            for ring in 1..=3 {
+                let radius = GRAVITY_WELL_RANGE * ring as f32 / 3.0;
+                draw_circle_lines(
+                    well.position.x,
+                    well.position.y,
+                    radius,
+                    1.0,
+                    Color::new(0.5, 0.6, 1.0, 0.12 * (4 - ring) as f32),
+                );
+            }
+            draw_circle(well.position.x, well.position.y, GRAVITY_WELL_CORE, BLACK);
+            draw_circle_lines(
+                well.position.x,
+                well.position.y,
+                GRAVITY_WELL_CORE,
+                2.0,
+                Color::new(0.6, 0.7, 1.0, 0.9),
+            );
+        }
+
+        if self.solar_flare_active() {
+            let edge = Color::new(1.0, 0.6, 0.1, 0.6);
+            draw_rectangle_lines(0.0, 0.0, self.world.x, self.world.y, 8.0, edge);
+        }
+    }
+}
+
+/// Player one flies white; the other seats are tinted so the ships can be told apart.
+pub fn seat_color(seat: usize) -> Color {
+    match seat {
+        0 => WHITE,
+        1 => Color::new(1.0, 0.8, 0.3, 1.0),
+        2 => Color::new(0.4, 1.0, 0.5, 1.0),
+        _ => Color::new(1.0, 0.45, 0.8, 1.0),
+    }
+}
+
+fn draw_thruster(pilot: &Pilot) {
+    let intent = pilot.intent;
+    if intent.thrust <= 0.0 {
+        return;
+    }
+    let ship = &pilot.ship;
+    let left = world_ship_point(ship, -0.5, 0.15);
+    let right = world_ship_point(ship, -0.5, -0.15);
+    let jitter = gen_range(-0.05, 0.05);
+    let tip = world_ship_point(ship, -0.95 - intent.thrust * 0.2 + jitter, 0.0);
+    let flame_alpha = 0.7 * intent.thrust;
+    draw_line(
+        left.x,
+        left.y,
+        tip.x,
+        tip.y,
+        2.0,
+        Color::new(1.0, 1.0, 1.0, flame_alpha),
+    );
+    draw_line(
+        right.x,
+        right.y,
+        tip.x,
+        tip.y,
+        2.0,
+        Color::new(1.0, 1.0, 1.0, flame_alpha),
+    );
+    draw_line(
+        left.x,
+        left.y,
+        right.x,
+        right.y,
+        1.5,
+        Color::new(1.0, 1.0, 1.0, flame_alpha * 0.8),
+    );
+}
+
+fn world_ship_point(ship: &Ship, x: f32, y: f32) -> Vec2 {
+    let scale = SHIP_SIZE;
+    let cos = ship.angle.cos();
+    let sin = ship.angle.sin();
+    let local = Vec2::new(x * scale, y * scale);
+    Vec2::new(local.x * cos - local.y * sin, local.x * sin + local.y * cos) + ship.position
+}
+
+fn draw_outline(points: &[Vec2], thickness: f32, color: Color) {
+    if points.len() > 1 {
+        for i in 0..points.len() {
+            let a = points[i];
+            let b = points[(i + 1) % points.len()];
+            draw_line(a.x, a.y, b.x, b.y, thickness, color);
+        }
     }
+}
 
-    fn world_ship_point(&self, x: f32, y: f32) -> Vec2 {
-        let scale = SHIP_SIZE;
-        let cos = self.ship.angle.cos();
-        let sin = self.ship.angle.sin();
-        let local = Vec2::new(x * scale, y * scale);
-        Vec2::new(local.x * cos - local.y * sin, local.x * sin + local.y * cos) + self.ship.position
+/// Each archetype keeps the rock silhouette but adds a distinct overlay so it reads at a glance.
+fn draw_asteroid(asteroid: &Asteroid) {
+    let points = asteroid.points();
+    match asteroid.archetype {
+        AsteroidArchetype::Rock => draw_outline(&points, 2.0, macroquad::prelude::LIGHTGRAY),
+        AsteroidArchetype::Armoured => {
+            let color = Color::new(0.55, 0.7, 0.9, 1.0);
+            draw_outline(&points, 3.0, color);
+            // One inner plate per remaining hit beyond the last.
+            for plate in 1..asteroid.hp {
+                let scale = 1.0 - 0.2 * plate as f32;
+                let inner: Vec<Vec2> = points
+                    .iter()
+                    .map(|p| asteroid.position + (*p - asteroid.position) * scale)
+                    .collect();
+                draw_outline(&inner, 1.5, color);
+            }
+        }
+        AsteroidArchetype::Volatile => {
+            let color = Color::new(1.0, 0.55, 0.15, 1.0);
+            draw_outline(&points, 2.0, color);
+            draw_circle(
+                asteroid.position.x,
+                asteroid.position.y,
+                asteroid.radius() * 0.25,
+                Color::new(1.0, 0.35, 0.1, 0.7),
+            );
+        }
+        AsteroidArchetype::Splitter => {
+            let color = Color::new(0.75, 0.95, 0.6, 1.0);
+            draw_outline(&points, 2.0, color);
+            let step = (points.len() / 3).max(1);
+            for point in points.iter().step_by(step).take(3) {
+                draw_line(
+                    asteroid.position.x,
+                    asteroid.position.y,
+                    point.x,
+                    point.y,
+                    1.0,
+                    color,
+                );
+            }
+        }
+        AsteroidArchetype::Magnetic => {
+            let color = Color::new(0.8, 0.45, 1.0, 1.0);
+            draw_outline(&points, 2.0, color);
+            draw_circle_lines(
+                asteroid.position.x,
+                asteroid.position.y,
+                asteroid.radius() * 0.4,
+                1.0,
+                color,
+            );
+        }
     }
 }
 
+fn pickup_style(kind: PickupKind) -> (&'static str, Color) {
+    match kind {
+        PickupKind::RapidFire => ("R", Color::new(1.0, 0.9, 0.3, 1.0)),
+        PickupKind::SpreadReset => ("S", Color::new(0.4, 0.9, 1.0, 1.0)),
+        PickupKind::ExtraLife => ("+", Color::new(0.5, 1.0, 0.4, 1.0)),
+        PickupKind::Shield => ("H", Color::new(0.2, 0.8, 1.0, 1.0)),
+        PickupKind::ScoreMultiplier => ("x", Color::new(1.0, 0.5, 0.9, 1.0)),
+    }
+}
@@ -0,0 +1,92 @@
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+/// PCG random source owned by the simulation.
+///
+/// Keeping it out of macroquad's global generator means rendering and menus never perturb the
+/// game, so a seed plus the recorded intents reproduces a run exactly.
+#[derive(Clone, Debug)]
+pub(super) struct SimRng {
+    state: u64,
+}
+
+impl SimRng {
+    pub(super) fn new(seed: u64) -> Self {
+        let mut rng = Self { state: 0 };
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+        rng
+    }
+
+    /// Resumes a stream at a position `state` returned, for saved runs.
+    pub(super) fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    /// The generator's position in its stream, for checksums and saved runs.
+    pub(super) fn state(&self) -> u64 {
+        self.state
+    }
+
+    pub(super) fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+
+    /// Uniform in `low..high`, like `macroquad::rand::gen_range`.
+    pub(super) fn gen_range<T: RandomRange>(&mut self, low: T, high: T) -> T {
+        let unit = self.next_u32() as f64 / (u32::MAX as f64 + 1.0);
+        T::lerp(low, high, unit)
+    }
+}
+
+pub(super) trait RandomRange: Copy {
+    fn lerp(low: Self, high: Self, unit: f64) -> Self;
+}
+
+macro_rules! random_range {
+    ($($ty:ty),*) => {
+        $(impl RandomRange for $ty {
+            fn lerp(low: Self, high: Self, unit: f64) -> Self {
+                (low as f64 + (high as f64 - low as f64) * unit) as Self
+            }
+        })*
+    };
+}
+
+random_range!(i32, u32, usize);
+
+impl RandomRange for f32 {
+    fn lerp(low: Self, high: Self, unit: f64) -> Self {
+        // Rounding to f32 can land on `high` itself; pull it back so the range stays half-open.
+        let value = (low as f64 + (high as f64 - low as f64) * unit) as f32;
+        if high > low {
+            value.min(high.next_down())
+        } else {
+            value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn float_ranges_never_reach_their_end() {
+        let last = u32::MAX as f64 / (u32::MAX as f64 + 1.0);
+        assert!(f32::lerp(0.0, 1.0, last) < 1.0);
+        assert!(f32::lerp(-0.8, 0.8, last) < 0.8);
+        assert_eq!(f32::lerp(2.0, 2.0, last), 2.0);
+        assert_eq!(f32::lerp(0.0, 1.0, 0.0), 0.0);
+
+        let mut rng = SimRng::new(3);
+        for _ in 0..10_000 {
+            let value = rng.gen_range(10.0, 20.0);
+            assert!((10.0..20.0).contains(&value), "{value}");
+        }
+    }
+}
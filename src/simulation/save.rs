@@ -0,0 +1,580 @@
+use crate::config::{AlienBehaviour, AsteroidArchetype, CollisionPolicy, GameConfig, PickupKind};
+use crate::controllers::alien::controller_for;
+use crate::stats::RunStats;
+use macroquad::prelude::{Color, Vec2, vec2};
+use std::fmt::Write;
+use std::str::SplitWhitespace;
+
+use super::model::*;
+use super::rng::SimRng;
+use super::Simulation;
+
+/// Record writing and reading for saved runs. Each line is one record: a kind followed by
+/// space-separated fields. Floats are written with `Display`, which reads back to the same bits.
+impl Simulation {
+    /// Writes the whole run: everything `step` carries forward, debris included, so a restored
+    /// run looks and plays exactly like the one that was saved. Controllers are not included.
+    pub fn write_state(&self, out: &mut String) {
+        let mut line = |text: String| {
+            out.push_str(&text);
+            out.push('\n');
+        };
+        line(format!("world {} {}", self.world.x, self.world.y));
+        line(format!("rng {}", self.rng.state()));
+        line(format!("frame {}", self.status.frame));
+        line(format!(
+            "spawns {} {} {} {}",
+            self.spawn_acc, self.alien_spawn_acc, self.carrier_spawn_acc, self.next_carrier_id
+        ));
+        line(format!(
+            "rules {} {} {}",
+            policy_name(&self.policy.collision_policy),
+            flag(self.invulnerability_enabled),
+            flag(self.assisted)
+        ));
+        if let Some(versus) = &self.versus {
+            line(format!("versus {} {}", versus.remaining, versus.respawn_delay));
+        }
+
+        for pilot in &self.pilots {
+            let ship = &pilot.ship;
+            let respawn = pilot
+                .respawn_timer
+                .map_or_else(|| "-".to_string(), |timer| timer.to_string());
+            line(format!(
+                "pilot {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+                ship.position.x,
+                ship.position.y,
+                ship.velocity.x,
+                ship.velocity.y,
+                ship.angle,
+                pilot.lives,
+                pilot.score,
+                pilot.next_extra_life_score,
+                pilot.primary_cooldown,
+                pilot.secondary_cooldown,
+                pilot.sentinel_cooldown,
+                pilot.invulnerability_timer,
+                pilot.rapid_fire_timer,
+                pilot.score_multiplier_timer,
+                pilot.shield_charges,
+                flag(pilot.hazard_hit),
+                pilot.last_seen.x,
+                pilot.last_seen.y,
+                pilot.frags,
+                pilot.deaths,
+                respawn,
+                pilot.run_stats.serialize()
+            ));
+        }
+
+        for asteroid in &self.asteroids {
+            let mut text = format!(
+                "asteroid {} {} {} {} {} {} {} {} {} {}",
+                asteroid_size_name(asteroid.size),
+                archetype_name(asteroid.archetype),
+                asteroid.position.x,
+                asteroid.position.y,
+                asteroid.velocity.x,
+                asteroid.velocity.y,
+                asteroid.hp,
+                asteroid.angle,
+                asteroid.rotation_speed,
+                asteroid.shape.len()
+            );
+            for vertex in &asteroid.shape {
+                let _ = write!(text, " {} {}", vertex.x, vertex.y);
+            }
+            line(text);
+        }
+        for bullet in &self.bullets {
+            line(format!(
+                "bullet {} {} {} {} {} {}",
+                bullet.position.x,
+                bullet.position.y,
+                bullet.velocity.x,
+                bullet.velocity.y,
+                bullet.ttl,
+                source_name(bullet.source)
+            ));
+        }
+        for debris in &self.debris {
+            let color = debris.color;
+            line(format!(
+                "debris {} {} {} {} {} {} {} {} {}",
+                debris.position.x,
+                debris.position.y,
+                debris.velocity.x,
+                debris.velocity.y,
+                debris.ttl,
+                color.r,
+                color.g,
+                color.b,
+                color.a
+            ));
+        }
+        for alien in &self.aliens {
+            line(format!(
+                "alien {} {} {} {} {} {} {} {}",
+                alien_size_name(alien.size),
+                behaviour_name(alien.controller.behaviour()),
+                alien.position.x,
+                alien.position.y,
+                alien.velocity.x,
+                alien.velocity.y,
+                alien.fire_timer,
+                alien.angle
+            ));
+        }
+        for carrier in &self.carriers {
+            line(format!(
+                "carrier {} {} {} {} {} {} {}",
+                carrier.id,
+                carrier.position.x,
+                carrier.position.y,
+                carrier.velocity.x,
+                carrier.velocity.y,
+                carrier.hp,
+                carrier.launch_timer
+            ));
+        }
+        for drone in &self.drones {
+            line(format!(
+                "drone {} {} {} {} {} {} {} {}",
+                drone.carrier,
+                drone.position.x,
+                drone.position.y,
+                drone.velocity.x,
+                drone.velocity.y,
+                drone_mode_name(drone.mode),
+                drone.orbit_phase,
+                drone.ttl
+            ));
+        }
+        for sentinel in &self.sentinels {
+            let anchor = match sentinel.anchor {
+                SentinelAnchor::Hold(point) => format!("hold {} {}", point.x, point.y),
+                SentinelAnchor::Orbit => "orbit".to_string(),
+            };
+            line(format!(
+                "sentinel {} {} {} {} {} {} {} {}",
+                sentinel.owner,
+                sentinel.position.x,
+                sentinel.position.y,
+                sentinel.orbit_phase,
+                sentinel.fire_timer,
+                sentinel.ttl,
+                sentinel.energy,
+                anchor
+            ));
+        }
+        for pickup in &self.pickups {
+            line(format!(
+                "pickup {} {} {} {} {} {}",
+                pickup_name(pickup.kind),
+                pickup.position.x,
+                pickup.position.y,
+                pickup.velocity.x,
+                pickup.velocity.y,
+                pickup.ttl
+            ));
+        }
+        for well in &self.gravity_wells {
+            line(format!("well {} {} {}", well.position.x, well.position.y, well.strength));
+        }
+        for nebula in &self.nebulae {
+            line(format!("nebula {} {} {}", nebula.position.x, nebula.position.y, nebula.radius));
+        }
+        if let Some(flare) = &self.solar_flare {
+            line(format!("flare {} {}", flare.cooldown, flare.remaining));
+        }
+    }
+
+    /// Rebuilds a run from `write_state` records. `config` must be the one the run was started
+    /// with; the records replace everything that changed since. The controllers are the
+    /// defaults for `config` until the caller installs its own.
+    pub fn read_state(config: GameConfig, text: &str) -> Result<Self, String> {
+        let seats = config.seats();
+        let mut simulation = Self::with_world(config, 0, Vec2::ZERO);
+        simulation.pilots.clear();
+        simulation.gravity_wells.clear();
+        simulation.nebulae.clear();
+        simulation.solar_flare = None;
+        let mut world = None;
+        let mut rng = None;
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let mut record = Record(line.split_whitespace());
+            let kind = record.word()?;
+            simulation
+                .read_record(kind, &mut record, &mut world, &mut rng)
+                .and_then(|()| record.finish())
+                .map_err(|err| format!("state line {}: {err}", number + 1))?;
+        }
+
+        simulation.world = world.ok_or("no `world` record")?;
+        simulation.rng = rng.ok_or("no `rng` record")?;
+        if simulation.pilots.len() != seats {
+            return Err(format!(
+                "{} ships saved for a {seats}-seat configuration",
+                simulation.pilots.len()
+            ));
+        }
+        let owners = simulation.bullets.iter().filter_map(|bullet| bullet.source.owner());
+        if owners
+            .chain(simulation.sentinels.iter().map(|sentinel| sentinel.owner))
+            .any(|seat| seat >= seats)
+        {
+            return Err("a shot or sentinel belongs to a ship that is not in the run".to_string());
+        }
+        simulation.update_status();
+        Ok(simulation)
+    }
+
+    fn read_record(
+        &mut self,
+        kind: &str,
+        record: &mut Record,
+        world: &mut Option<Vec2>,
+        rng: &mut Option<SimRng>,
+    ) -> Result<(), String> {
+        match kind {
+            "world" => *world = Some(record.vec2()?),
+            "rng" => *rng = Some(SimRng::from_state(record.number()?)),
+            "frame" => self.status.frame = record.number()?,
+            "spawns" => {
+                self.spawn_acc = record.number()?;
+                self.alien_spawn_acc = record.number()?;
+                self.carrier_spawn_acc = record.number()?;
+                self.next_carrier_id = record.number()?;
+            }
+            "rules" => {
+                self.policy.collision_policy = parse_policy(record.word()?)?;
+                self.invulnerability_enabled = record.flag()?;
+                self.assisted = record.flag()?;
+            }
+            "versus" => {
+                let versus = self.versus.as_mut().ok_or("versus record in a non-versus run")?;
+                versus.remaining = record.number()?;
+                versus.respawn_delay = record.number()?;
+            }
+            "pilot" => {
+                let position = record.vec2()?;
+                let mut pilot = Pilot::new(position, &self.tuning);
+                pilot.ship.velocity = record.vec2()?;
+                pilot.ship.angle = record.number()?;
+                pilot.lives = record.number()?;
+                pilot.score = record.number()?;
+                pilot.next_extra_life_score = record.number()?;
+                pilot.primary_cooldown = record.number()?;
+                pilot.secondary_cooldown = record.number()?;
+                pilot.sentinel_cooldown = record.number()?;
+                pilot.invulnerability_timer = record.number()?;
+                pilot.rapid_fire_timer = record.number()?;
+                pilot.score_multiplier_timer = record.number()?;
+                pilot.shield_charges = record.number()?;
+                pilot.hazard_hit = record.flag()?;
+                pilot.last_seen = record.vec2()?;
+                pilot.frags = record.number()?;
+                pilot.deaths = record.number()?;
+                pilot.respawn_timer = match record.word()? {
+                    "-" => None,
+                    timer => Some(parse_number(timer)?),
+                };
+                let stats = record.word()?;
+                pilot.run_stats =
+                    RunStats::parse(stats).ok_or_else(|| format!("`{stats}` is not run stats"))?;
+                self.pilots.push(pilot);
+            }
+            "asteroid" => {
+                let size = parse_asteroid_size(record.word()?)?;
+                let archetype = parse_archetype(record.word()?)?;
+                let position = record.vec2()?;
+                let velocity = record.vec2()?;
+                let hp = record.number()?;
+                let angle = record.number()?;
+                let rotation_speed = record.number()?;
+                let vertices: usize = record.number()?;
+                let shape = (0..vertices).map(|_| record.vec2()).collect::<Result<_, _>>()?;
+                self.asteroids.insert(Asteroid {
+                    position,
+                    velocity,
+                    size,
+                    archetype,
+                    hp,
+                    angle,
+                    rotation_speed,
+                    shape,
+                });
+            }
+            "bullet" => {
+                let position = record.vec2()?;
+                let velocity = record.vec2()?;
+                let ttl = record.number()?;
+                let source = parse_source(record.word()?)?;
+                self.bullets.insert(Bullet::new(position, velocity, source, ttl));
+            }
+            "debris" => {
+                let position = record.vec2()?;
+                let velocity = record.vec2()?;
+                let ttl = record.number()?;
+                let color = Color::new(
+                    record.number()?,
+                    record.number()?,
+                    record.number()?,
+                    record.number()?,
+                );
+                let mut debris = Debris::new(position, velocity, color);
+                debris.ttl = ttl;
+                self.debris.insert(debris);
+            }
+            "alien" => {
+                let size = parse_alien_size(record.word()?)?;
+                let behaviour = parse_behaviour(record.word()?)?;
+                self.aliens.insert(Alien {
+                    size,
+                    controller: controller_for(behaviour),
+                    position: record.vec2()?,
+                    velocity: record.vec2()?,
+                    fire_timer: record.number()?,
+                    angle: record.number()?,
+                });
+            }
+            "carrier" => self.carriers.push(Carrier::new(
+                record.number()?,
+                record.vec2()?,
+                record.vec2()?,
+                record.number()?,
+                record.number()?,
+            )),
+            "drone" => {
+                let mut drone = Drone::new(record.number()?, record.vec2()?, 0.0);
+                drone.velocity = record.vec2()?;
+                drone.mode = parse_drone_mode(record.word()?)?;
+                drone.orbit_phase = record.number()?;
+                drone.ttl = record.number()?;
+                self.drones.push(drone);
+            }
+            "sentinel" => {
+                let owner = record.number()?;
+                let position = record.vec2()?;
+                let orbit_phase = record.number()?;
+                let fire_timer = record.number()?;
+                let ttl = record.number()?;
+                let energy = record.number()?;
+                let anchor = match record.word()? {
+                    "hold" => SentinelAnchor::Hold(record.vec2()?),
+                    "orbit" => SentinelAnchor::Orbit,
+                    other => return Err(format!("unknown sentinel anchor `{other}`")),
+                };
+                let mut sentinel = Sentinel::new(owner, position, anchor, orbit_phase, ttl, energy);
+                sentinel.fire_timer = fire_timer;
+                self.sentinels.push(sentinel);
+            }
+            "pickup" => self.pickups.push(Pickup::new(
+                parse_pickup(record.word()?)?,
+                record.vec2()?,
+                record.vec2()?,
+                record.number()?,
+            )),
+            "well" => self.gravity_wells.push(GravityWell {
+                position: record.vec2()?,
+                strength: record.number()?,
+            }),
+            "nebula" => self.nebulae.push(Nebula {
+                position: record.vec2()?,
+                radius: record.number()?,
+            }),
+            "flare" => {
+                let mut flare = SolarFlare::new(record.number()?);
+                flare.remaining = record.number()?;
+                self.solar_flare = Some(flare);
+            }
+            other => return Err(format!("unknown record `{other}`")),
+        }
+        Ok(())
+    }
+}
+
+/// The fields of one record, read left to right.
+struct Record<'a>(SplitWhitespace<'a>);
+
+impl<'a> Record<'a> {
+    fn word(&mut self) -> Result<&'a str, String> {
+        self.0.next().ok_or_else(|| "too few fields".to_string())
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> Result<T, String> {
+        parse_number(self.word()?)
+    }
+
+    fn vec2(&mut self) -> Result<Vec2, String> {
+        Ok(vec2(self.number()?, self.number()?))
+    }
+
+    fn flag(&mut self) -> Result<bool, String> {
+        match self.word()? {
+            "0" => Ok(false),
+            "1" => Ok(true),
+            other => Err(format!("`{other}` is not 0 or 1")),
+        }
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        match self.0.next() {
+            Some(extra) => Err(format!("unexpected field `{extra}`")),
+            None => Ok(()),
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(text: &str) -> Result<T, String> {
+    text.parse().map_err(|_| format!("`{text}` is not a number"))
+}
+
+fn flag(value: bool) -> u8 {
+    value.into()
+}
+
+/// Names for the enums a record holds. Each table is read both ways, so a name can only ever
+/// mean one variant.
+const POLICIES: &[(&str, CollisionPolicy)] = &[
+    ("player-only", CollisionPolicy::PlayerOnly),
+    ("big-only", CollisionPolicy::BigOnly),
+    ("full", CollisionPolicy::Full),
+];
+const ARCHETYPES: &[(&str, AsteroidArchetype)] = &[
+    ("rock", AsteroidArchetype::Rock),
+    ("armoured", AsteroidArchetype::Armoured),
+    ("volatile", AsteroidArchetype::Volatile),
+    ("splitter", AsteroidArchetype::Splitter),
+    ("magnetic", AsteroidArchetype::Magnetic),
+];
+const BEHAVIOURS: &[(&str, AlienBehaviour)] = &[
+    ("classic", AlienBehaviour::Classic),
+    ("hunter", AlienBehaviour::Hunter),
+    ("sniper", AlienBehaviour::Sniper),
+    ("kamikaze", AlienBehaviour::Kamikaze),
+];
+const PICKUPS: &[(&str, PickupKind)] = &[
+    ("rapid-fire", PickupKind::RapidFire),
+    ("spread-reset", PickupKind::SpreadReset),
+    ("extra-life", PickupKind::ExtraLife),
+    ("shield", PickupKind::Shield),
+    ("score-multiplier", PickupKind::ScoreMultiplier),
+];
+const DRONE_MODES: &[(&str, DroneMode)] = &[
+    ("escort", DroneMode::Escort),
+    ("scatter", DroneMode::Scatter),
+    ("kamikaze", DroneMode::Kamikaze),
+];
+
+fn name_in<T: PartialEq>(table: &[(&'static str, T)], value: &T) -> &'static str {
+    table
+        .iter()
+        .find(|(_, known)| known == value)
+        .map_or("?", |(name, _)| name)
+}
+
+fn parse_in<T: Clone>(table: &[(&'static str, T)], what: &str, name: &str) -> Result<T, String> {
+    table
+        .iter()
+        .find(|(known, _)| *known == name)
+        .map(|(_, value)| value.clone())
+        .ok_or_else(|| format!("unknown {what} `{name}`"))
+}
+
+fn policy_name(policy: &CollisionPolicy) -> &'static str {
+    name_in(POLICIES, policy)
+}
+
+fn parse_policy(name: &str) -> Result<CollisionPolicy, String> {
+    parse_in(POLICIES, "collision policy", name)
+}
+
+fn archetype_name(archetype: AsteroidArchetype) -> &'static str {
+    name_in(ARCHETYPES, &archetype)
+}
+
+fn parse_archetype(name: &str) -> Result<AsteroidArchetype, String> {
+    parse_in(ARCHETYPES, "asteroid archetype", name)
+}
+
+fn behaviour_name(behaviour: AlienBehaviour) -> &'static str {
+    name_in(BEHAVIOURS, &behaviour)
+}
+
+fn parse_behaviour(name: &str) -> Result<AlienBehaviour, String> {
+    parse_in(BEHAVIOURS, "alien behaviour", name)
+}
+
+fn pickup_name(kind: PickupKind) -> &'static str {
+    name_in(PICKUPS, &kind)
+}
+
+fn parse_pickup(name: &str) -> Result<PickupKind, String> {
+    parse_in(PICKUPS, "pickup", name)
+}
+
+fn drone_mode_name(mode: DroneMode) -> &'static str {
+    name_in(DRONE_MODES, &mode)
+}
+
+fn parse_drone_mode(name: &str) -> Result<DroneMode, String> {
+    parse_in(DRONE_MODES, "drone mode", name)
+}
+
+fn asteroid_size_name(size: AsteroidSize) -> &'static str {
+    match size {
+        AsteroidSize::Large => "large",
+        AsteroidSize::Medium => "medium",
+        AsteroidSize::Small => "small",
+    }
+}
+
+fn parse_asteroid_size(name: &str) -> Result<AsteroidSize, String> {
+    match name {
+        "large" => Ok(AsteroidSize::Large),
+        "medium" => Ok(AsteroidSize::Medium),
+        "small" => Ok(AsteroidSize::Small),
+        _ => Err(format!("unknown asteroid size `{name}`")),
+    }
+}
+
+fn alien_size_name(size: AlienSize) -> &'static str {
+    match size {
+        AlienSize::Small => "small",
+        AlienSize::Large => "large",
+    }
+}
+
+fn parse_alien_size(name: &str) -> Result<AlienSize, String> {
+    match name {
+        "small" => Ok(AlienSize::Small),
+        "large" => Ok(AlienSize::Large),
+        _ => Err(format!("unknown alien size `{name}`")),
+    }
+}
+
+/// `p<seat>` for a player's shot, `s<seat>` for a sentinel's, `alien` for a saucer's.
+fn source_name(source: BulletSource) -> String {
+    match source {
+        BulletSource::Player(seat) => format!("p{seat}"),
+        BulletSource::Sentinel(seat) => format!("s{seat}"),
+        BulletSource::Alien => "alien".to_string(),
+    }
+}
+
+fn parse_source(name: &str) -> Result<BulletSource, String> {
+    let seat = |digits: &str| parse_number::<usize>(digits);
+    match name {
+        "alien" => Ok(BulletSource::Alien),
+        _ if name.starts_with('p') => Ok(BulletSource::Player(seat(&name[1..])?)),
+        _ if name.starts_with('s') => Ok(BulletSource::Sentinel(seat(&name[1..])?)),
+        _ => Err(format!("unknown bullet source `{name}`")),
+    }
+}
@@ -1,34 +1,141 @@
-use macroquad::prelude::{Color, Vec2, screen_height, screen_width, vec2};
-use macroquad::rand::gen_range;
+use macroquad::prelude::{Color, Vec2};
 use std::f32::consts::PI;
 
 use super::super::{
-    ALIEN_DEBRIS_COLOR, BULLET_RADIUS, DEBRIS_COLOR, DEBRIS_COUNT, DEBRIS_SPEED,
-    EXTRA_LIFE_SCORE_STEP, INVULNERABILITY_DURATION, PLAYER_DEBRIS_COLOR, SHIP_SIZE,
+    ALIEN_DEBRIS_COLOR, BULLET_RADIUS, CARRIER_RADIUS, DEBRIS_COLOR, DEBRIS_COUNT, DEBRIS_SPEED,
+    DRONE_RADIUS, ENEMY_DEBRIS_COLOR, PLAYER_DEBRIS_COLOR, SHIELD_GRACE, SHIP_SIZE,
 };
-use super::super::model::{AsteroidSize, BulletSource, Debris, HitTarget};
-use super::super::Simulation;
+use super::super::model::{Asteroid, BulletSource, Debris, HitTarget, Ship, spawn_point};
+use super::super::{HitCause, SimEvent, Simulation};
+
+/// Where a destroyed body was, what it was and which seat gets the credit.
+type Destroyed = (Vec2, HitTarget, Option<usize>);
+
+/// Working buffers for `resolve_collisions`. They live on the simulation between steps and are
+/// cleared and resized at the start of each one, so a steady population reuses their storage.
+#[derive(Clone, Default)]
+pub(in crate::simulation) struct CollisionScratch {
+    bullet_hits: Vec<bool>,
+    asteroid_hits: Vec<bool>,
+    asteroid_damage: Vec<u32>,
+    /// Volatile rocks that went off: where, how far the blast reaches and who set it off.
+    blasts: Vec<(Vec2, f32, Option<usize>)>,
+    alien_hits: Vec<bool>,
+    carrier_damage: Vec<u32>,
+    carrier_killers: Vec<Option<usize>>,
+    drone_hits: Vec<bool>,
+    pending_hits: Vec<(usize, HitTarget)>,
+    sentinel_kills: Vec<u32>,
+    fragments: Vec<Asteroid>,
+    earned_score: Vec<u32>,
+    destroyed_asteroids: Vec<Destroyed>,
+    rammed_asteroids: Vec<(HitTarget, Vec2)>,
+    destroyed_aliens: Vec<Destroyed>,
+    destroyed_enemies: Vec<Destroyed>,
+    ship_hits: Vec<Option<HitCause>>,
+    killers: Vec<Option<usize>>,
+    frags: Vec<usize>,
+    orphaned: Vec<u32>,
+}
+
+impl CollisionScratch {
+    /// Empties every buffer and sizes the per-body ones for this step's populations.
+    fn reset(
+        &mut self,
+        bullets: usize,
+        asteroids: usize,
+        aliens: usize,
+        carriers: usize,
+        drones: usize,
+        seats: usize,
+    ) {
+        fn refill<T: Clone>(buffer: &mut Vec<T>, len: usize, value: T) {
+            buffer.clear();
+            buffer.resize(len, value);
+        }
+        refill(&mut self.bullet_hits, bullets, false);
+        refill(&mut self.asteroid_hits, asteroids, false);
+        refill(&mut self.asteroid_damage, asteroids, 0);
+        refill(&mut self.alien_hits, aliens, false);
+        refill(&mut self.carrier_damage, carriers, 0);
+        refill(&mut self.carrier_killers, carriers, None);
+        refill(&mut self.drone_hits, drones, false);
+        refill(&mut self.sentinel_kills, seats, 0);
+        refill(&mut self.earned_score, seats, 0);
+        refill(&mut self.killers, seats, None);
+        self.blasts.clear();
+        self.pending_hits.clear();
+        self.fragments.clear();
+        self.destroyed_asteroids.clear();
+        self.rammed_asteroids.clear();
+        self.destroyed_aliens.clear();
+        self.destroyed_enemies.clear();
+        self.ship_hits.clear();
+        self.frags.clear();
+        self.orphaned.clear();
+    }
+}
 
 impl Simulation {
     pub(in crate::simulation) fn award_extra_lives(&mut self) {
-        while self.status.score >= self.next_extra_life_score {
-            self.lives = self.lives.saturating_add(1);
-            self.next_extra_life_score = self
-                .next_extra_life_score
-                .saturating_add(EXTRA_LIFE_SCORE_STEP);
+        let step = self.tuning.extra_life_score_step;
+        for (seat, pilot) in self.pilots.iter_mut().enumerate() {
+            while pilot.score >= pilot.next_extra_life_score {
+                pilot.lives = pilot.lives.saturating_add(1);
+                pilot.next_extra_life_score = pilot.next_extra_life_score.saturating_add(step);
+                self.events.push(SimEvent::ExtraLife { seat });
+            }
         }
     }
 
     pub(in crate::simulation) fn resolve_collisions(&mut self) {
-        let mut bullet_hits = vec![false; self.bullets.len()];
-        let mut asteroid_hits = vec![false; self.asteroids.len()];
-        let mut alien_hits = vec![false; self.aliens.len()];
-        let mut pending_hits = Vec::new();
+        let seats = self.pilots.len();
+        // Taken for the step so the buffers can be filled while `self` is borrowed.
+        let mut scratch = std::mem::take(&mut self.collision_scratch);
+        scratch.reset(
+            self.bullets.len(),
+            self.asteroids.len(),
+            self.aliens.len(),
+            self.carriers.len(),
+            self.drones.len(),
+            seats,
+        );
+        let CollisionScratch {
+            bullet_hits,
+            asteroid_hits,
+            asteroid_damage,
+            blasts,
+            alien_hits,
+            carrier_damage,
+            carrier_killers,
+            drone_hits,
+            pending_hits,
+            sentinel_kills,
+            fragments,
+            earned_score,
+            destroyed_asteroids,
+            rammed_asteroids,
+            destroyed_aliens,
+            destroyed_enemies,
+            ship_hits,
+            killers,
+            frags,
+            orphaned,
+        } = &mut scratch;
+        let targets =
+            self.asteroids.len() + self.aliens.len() + self.carriers.len() + self.drones.len();
+        let ships = self.pilots.iter().filter(|pilot| pilot.in_play()).count();
+        self.timings.collision_pairs =
+            self.bullets.len() * targets + ships * (targets + self.bullets.len());
         let ship_radius = SHIP_SIZE * 0.9;
-        let mut fragments = Vec::new();
-        let mut earned_score: u32 = 0;
-        let mut destroyed_asteroids = Vec::new();
-        let mut destroyed_aliens = Vec::new();
+        // Score is credited to whoever owns the bullet, before that pilot's multiplier.
+        let mut credit = |owner: Option<usize>, points: u32| {
+            if let Some(seat) = owner {
+                earned_score[seat] = earned_score[seat].saturating_add(points);
+            }
+        };
+        let score_base = self.tuning.asteroid_score_base;
+        let min_split_speed = self.tuning.asteroid_min_speed;
 
         for (bi, bullet) in self.bullets.iter().enumerate() {
             if bullet_hits[bi] {
@@ -43,17 +150,28 @@ impl Simulation {
                 let radius_sum = asteroid.radius() + BULLET_RADIUS;
                 if bullet.position.distance_squared(asteroid.position) <= radius_sum * radius_sum {
                     bullet_hits[bi] = true;
-                    asteroid_hits[ai] = true;
-                    fragments.extend(asteroid.split());
-                    destroyed_asteroids.push(asteroid.clone());
-                    if bullet.source == BulletSource::Player {
-                        earned_score = earned_score.saturating_add(asteroid.size.score());
-                        let target = match asteroid.size {
-                            AsteroidSize::Large => HitTarget::LargeAsteroid,
-                            AsteroidSize::Medium => HitTarget::MediumAsteroid,
-                            AsteroidSize::Small => HitTarget::SmallAsteroid,
-                        };
-                        pending_hits.push(target);
+                    asteroid_damage[ai] += 1;
+                    let destroyed = asteroid_damage[ai] >= asteroid.hp;
+                    if destroyed {
+                        asteroid_hits[ai] = true;
+                        fragments.extend(asteroid.split(min_split_speed, &mut self.rng));
+                        destroyed_asteroids.push((
+                            asteroid.position,
+                            asteroid.size.hit_target(),
+                            bullet.source.owner(),
+                        ));
+                        credit(bullet.source.owner(), asteroid.score(score_base));
+                        let blast_radius = asteroid.def().blast_radius;
+                        if blast_radius > 0.0 {
+                            blasts.push((asteroid.position, blast_radius, bullet.source.owner()));
+                        }
+                    }
+                    match bullet.source {
+                        BulletSource::Player(seat) => {
+                            pending_hits.push((seat, asteroid.size.hit_target()))
+                        }
+                        BulletSource::Sentinel(seat) if destroyed => sentinel_kills[seat] += 1,
+                        _ => {}
                     }
                     handled = true;
                     break;
@@ -72,10 +190,14 @@ impl Simulation {
                 if bullet.position.distance_squared(alien.position) <= radius_sum * radius_sum {
                     bullet_hits[bi] = true;
                     alien_hits[ai] = true;
-                    earned_score = earned_score.saturating_add(alien.size.score_value());
-                    destroyed_aliens.push(alien.position);
-                    if bullet.source == BulletSource::Player {
-                        pending_hits.push(alien.size.hit_target());
+                    // Saucers downed by another saucer's shot have always paid out; player one
+                    // keeps that credit.
+                    credit(Some(bullet.source.owner().unwrap_or(0)), alien.size.score_value());
+                    destroyed_aliens.push((alien.position, alien.size.hit_target(), bullet.source.owner()));
+                    match bullet.source {
+                        BulletSource::Player(seat) => pending_hits.push((seat, alien.size.hit_target())),
+                        BulletSource::Sentinel(seat) => sentinel_kills[seat] += 1,
+                        BulletSource::Alien => {}
                     }
                     handled = true;
                     break;
@@ -85,102 +207,500 @@ impl Simulation {
             if handled {
                 continue;
             }
+
+            for (ci, carrier) in self.carriers.iter().enumerate() {
+                if carrier_damage[ci] >= carrier.hp {
+                    continue;
+                }
+                let radius_sum = CARRIER_RADIUS + BULLET_RADIUS;
+                if bullet.position.distance_squared(carrier.position) <= radius_sum * radius_sum {
+                    bullet_hits[bi] = true;
+                    carrier_damage[ci] += 1;
+                    let destroyed = carrier_damage[ci] >= carrier.hp;
+                    if destroyed {
+                        credit(bullet.source.owner(), self.tuning.carrier_score);
+                        carrier_killers[ci] = bullet.source.owner();
+                    }
+                    match bullet.source {
+                        BulletSource::Player(seat) => pending_hits.push((seat, HitTarget::Carrier)),
+                        BulletSource::Sentinel(seat) if destroyed => sentinel_kills[seat] += 1,
+                        _ => {}
+                    }
+                    handled = true;
+                    break;
+                }
+            }
+
+            if handled {
+                continue;
+            }
+
+            for (di, drone) in self.drones.iter().enumerate() {
+                if drone_hits[di] {
+                    continue;
+                }
+                let radius_sum = DRONE_RADIUS + BULLET_RADIUS;
+                if bullet.position.distance_squared(drone.position) <= radius_sum * radius_sum {
+                    bullet_hits[bi] = true;
+                    drone_hits[di] = true;
+                    destroyed_enemies.push((drone.position, HitTarget::Drone, bullet.source.owner()));
+                    credit(bullet.source.owner(), self.tuning.drone_score);
+                    match bullet.source {
+                        BulletSource::Player(seat) => pending_hits.push((seat, HitTarget::Drone)),
+                        BulletSource::Sentinel(seat) => sentinel_kills[seat] += 1,
+                        BulletSource::Alien => {}
+                    }
+                    break;
+                }
+            }
         }
 
-        let mut ship_hit = false;
-        for (ai, asteroid) in self.asteroids.iter().enumerate() {
-            let radius_sum = asteroid.radius() + ship_radius;
-            if self.invulnerability_timer <= 0.0
-                && self.ship.position.distance_squared(asteroid.position) <= radius_sum * radius_sum
-            {
-                asteroid_hits[ai] = true;
-                if !self.invulnerability_enabled {
-                    ship_hit = true;
+        // What hit each ship this step, if anything did, and whose shot or blast destroyed it
+        // when that was another seat's; the latter are versus frags.
+        ship_hits.extend(
+            self.pilots
+                .iter_mut()
+                .map(|pilot| std::mem::take(&mut pilot.hazard_hit).then_some(HitCause::GravityWell)),
+        );
+
+        // Volatile rocks take their neighbours (and possibly a ship) with them. Rocks caught in a
+        // blast do not detonate in turn, which keeps the cost to one pass per explosion.
+        for &(origin, radius, owner) in blasts.iter() {
+            for (ai, asteroid) in self.asteroids.iter().enumerate() {
+                if asteroid_hits[ai] {
+                    continue;
+                }
+                let reach = radius + asteroid.radius();
+                if origin.distance_squared(asteroid.position) <= reach * reach {
+                    asteroid_hits[ai] = true;
+                    fragments.extend(asteroid.split(min_split_speed, &mut self.rng));
+                    destroyed_asteroids.push((asteroid.position, asteroid.size.hit_target(), owner));
+                    credit(owner, asteroid.score(score_base));
                 }
             }
         }
 
-        for (bi, bullet) in self.bullets.iter().enumerate() {
-            if bullet_hits[bi] {
+        for (seat, pilot) in self.pilots.iter().enumerate() {
+            if !pilot.in_play() {
                 continue;
             }
-            if bullet.source == BulletSource::Alien
-                && self.invulnerability_timer <= 0.0
-                && !self.invulnerability_enabled
-                && self.ship.position.distance_squared(bullet.position) <= ship_radius * ship_radius
-            {
-                bullet_hits[bi] = true;
-                ship_hit = true;
+            let ship = &pilot.ship;
+            let vulnerable = pilot.invulnerability_timer <= 0.0;
+            let ship_hit = &mut ship_hits[seat];
+            let killer = &mut killers[seat];
+
+            for &(origin, radius, owner) in blasts.iter() {
+                let reach = radius + ship_radius;
+                if vulnerable
+                    && !self.invulnerability_enabled
+                    && ship.position.distance_squared(origin) <= reach * reach
+                {
+                    ship_hit.get_or_insert(HitCause::Blast);
+                    if owner != Some(seat) {
+                        *killer = killer.or(owner);
+                    }
+                }
+            }
+            for (ai, asteroid) in self.asteroids.iter().enumerate() {
+                let radius_sum = asteroid.radius() + ship_radius;
+                if vulnerable
+                    && ship.position.distance_squared(asteroid.position) <= radius_sum * radius_sum
+                {
+                    if !asteroid_hits[ai] {
+                        rammed_asteroids.push((asteroid.size.hit_target(), asteroid.position));
+                    }
+                    asteroid_hits[ai] = true;
+                    if !self.invulnerability_enabled {
+                        ship_hit.get_or_insert(HitCause::Asteroid);
+                    }
+                }
+            }
+
+            for (ai, alien) in self.aliens.iter().enumerate() {
+                if alien_hits[ai] {
+                    continue;
+                }
+                // Saucers are not rocks to be plowed through: with invulnerability on, a ram
+                // harms neither side.
+                let radius_sum = alien.size.hit_radius() + ship_radius;
+                if vulnerable
+                    && !self.invulnerability_enabled
+                    && ship.position.distance_squared(alien.position) <= radius_sum * radius_sum
+                {
+                    alien_hits[ai] = true;
+                    destroyed_aliens.push((alien.position, alien.size.hit_target(), None));
+                    ship_hit.get_or_insert(HitCause::Saucer);
+                }
+            }
+
+            if vulnerable {
+                for carrier in &self.carriers {
+                    let radius_sum = CARRIER_RADIUS + ship_radius;
+                    if ship.position.distance_squared(carrier.position) <= radius_sum * radius_sum
+                        && !self.invulnerability_enabled
+                    {
+                        ship_hit.get_or_insert(HitCause::Carrier);
+                    }
+                }
+
+                for (di, drone) in self.drones.iter().enumerate() {
+                    if drone_hits[di] {
+                        continue;
+                    }
+                    let radius_sum = DRONE_RADIUS + ship_radius;
+                    if ship.position.distance_squared(drone.position) <= radius_sum * radius_sum {
+                        drone_hits[di] = true;
+                        destroyed_enemies.push((drone.position, HitTarget::Drone, None));
+                        if !self.invulnerability_enabled {
+                            ship_hit.get_or_insert(HitCause::Drone);
+                        }
+                    }
+                }
             }
-        }
 
-        if ship_hit {
-            if self.lives > 0 {
-                self.lives -= 1;
+            for (bi, bullet) in self.bullets.iter().enumerate() {
+                if bullet_hits[bi] {
+                    continue;
+                }
+                // With friendly fire on, a partner's shots are as dangerous as a saucer's.
+                // Sentinels never aim at ships, so their shots pass through every one.
+                let hostile = match bullet.source {
+                    BulletSource::Alien => true,
+                    BulletSource::Player(owner) => self.friendly_fire && owner != seat,
+                    BulletSource::Sentinel(_) => false,
+                };
+                if hostile
+                    && vulnerable
+                    && !self.invulnerability_enabled
+                    && ship.position.distance_squared(bullet.position) <= ship_radius * ship_radius
+                {
+                    bullet_hits[bi] = true;
+                    ship_hit.get_or_insert(match bullet.source.owner() {
+                        None => HitCause::SaucerShot,
+                        Some(_) => HitCause::PlayerShot,
+                    });
+                    *killer = killer.or(bullet.source.owner());
+                }
             }
-            self.spawn_debris(self.ship.position, PLAYER_DEBRIS_COLOR);
-            if self.lives > 0 {
-                self.reset_ship();
+        }
+
+        let flare = self.solar_flare_active();
+        for (seat, ship_hit) in ship_hits.drain(..).enumerate() {
+            let pilot = &mut self.pilots[seat];
+            let Some(cause) = ship_hit.filter(|_| pilot.in_play()) else {
+                continue;
+            };
+            if pilot.shield_charges > 0 && !flare {
+                // A shield charge soaks the hit and buys a short grace window instead of a life.
+                // Solar flares knock shields offline, so hits during a flare cost a life.
+                pilot.shield_charges -= 1;
+                pilot.invulnerability_timer = SHIELD_GRACE;
+                let position = pilot.ship.position;
+                self.events.push(SimEvent::ShipHit { seat, position, shielded: true, cause });
+            } else if let Some(versus) = &self.versus {
+                // Versus ships never run out of lives; they sit out the respawn delay instead.
+                pilot.deaths = pilot.deaths.saturating_add(1);
+                pilot.respawn_timer = Some(versus.respawn_delay);
+                frags.extend(killers[seat]);
+                let position = pilot.ship.position;
+                self.events.push(SimEvent::ShipHit { seat, position, shielded: false, cause });
+                self.spawn_debris(position, PLAYER_DEBRIS_COLOR);
+            } else {
+                pilot.lives -= 1;
+                let position = pilot.ship.position;
+                let respawn = pilot.in_play();
+                self.events.push(SimEvent::ShipHit { seat, position, shielded: false, cause });
+                self.spawn_debris(position, PLAYER_DEBRIS_COLOR);
+                if respawn {
+                    self.reset_ship(seat);
+                }
             }
         }
+        for seat in frags.drain(..) {
+            let pilot = &mut self.pilots[seat];
+            pilot.frags = pilot.frags.saturating_add(1);
+        }
 
-        self.status.score = self.status.score.saturating_add(earned_score);
+        for (pilot, &earned) in self.pilots.iter_mut().zip(earned_score.iter()) {
+            let earned = if pilot.score_multiplier_timer > 0.0 {
+                earned.saturating_mul(self.tuning.score_multiplier)
+            } else {
+                earned
+            };
+            pilot.score = pilot.score.saturating_add(earned);
+        }
         self.status.invulnerability_enabled = self.invulnerability_enabled;
         self.award_extra_lives();
 
-        for asteroid in destroyed_asteroids {
-            self.spawn_debris(asteroid.position, DEBRIS_COLOR);
+        for (position, target, by) in destroyed_asteroids.drain(..) {
+            self.spawn_debris(position, DEBRIS_COLOR);
+            self.roll_pickup_drop(position);
+            self.events.push(SimEvent::Destroyed { target, position, by });
+        }
+        for (target, position) in rammed_asteroids.drain(..) {
+            self.events.push(SimEvent::Destroyed { target, position, by: None });
         }
 
-        let mut survivors = Vec::new();
-        for (i, asteroid) in self.asteroids.iter().enumerate() {
-            if asteroid_hits[i] {
-                continue;
-            }
-            survivors.push(asteroid.clone());
+        // Flags and damage are by position, so they are applied before anything moves.
+        for (asteroid, &damage) in self.asteroids.iter_mut().zip(asteroid_damage.iter()) {
+            asteroid.hp = asteroid.hp.saturating_sub(damage);
         }
-        survivors.extend(fragments);
-        self.asteroids = survivors;
+        self.asteroids.remove_marked(asteroid_hits);
+        self.asteroids.extend(fragments.drain(..));
 
-        let mut remaining_aliens = Vec::new();
-        for (i, alien) in self.aliens.iter().enumerate() {
-            if alien_hits[i] {
-                continue;
+        self.aliens.remove_marked(alien_hits);
+        for (position, target, by) in destroyed_aliens.drain(..) {
+            self.spawn_debris(position, ALIEN_DEBRIS_COLOR);
+            self.roll_pickup_drop(position);
+            self.events.push(SimEvent::Destroyed { target, position, by });
+        }
+
+        let mut index = 0;
+        self.carriers.retain_mut(|carrier| {
+            let (damage, killer) = (carrier_damage[index], carrier_killers[index]);
+            index += 1;
+            if damage >= carrier.hp {
+                orphaned.push(carrier.id);
+                destroyed_enemies.push((carrier.position, HitTarget::Carrier, killer));
+                false
+            } else {
+                carrier.hp -= damage;
+                true
             }
-            remaining_aliens.push(alien.clone());
+        });
+        let mut index = 0;
+        self.drones.retain(|_| {
+            let keep = !drone_hits[index];
+            index += 1;
+            keep
+        });
+        for carrier_id in orphaned.drain(..) {
+            self.orphan_drones(carrier_id);
         }
-        self.aliens = remaining_aliens;
-        for pos in destroyed_aliens {
-            self.spawn_debris(pos, ALIEN_DEBRIS_COLOR);
+        for (position, target, by) in destroyed_enemies.drain(..) {
+            self.spawn_debris(position, ENEMY_DEBRIS_COLOR);
+            self.roll_pickup_drop(position);
+            self.events.push(SimEvent::Destroyed { target, position, by });
         }
 
-        for hit in pending_hits {
-            self.record_player_hit(hit);
+        for (seat, hit) in pending_hits.drain(..) {
+            self.record_player_hit(seat, hit);
+        }
+        for (pilot, &kills) in self.pilots.iter_mut().zip(sentinel_kills.iter()) {
+            pilot.run_stats.sentinel_kills = pilot.run_stats.sentinel_kills.saturating_add(kills);
         }
 
         // Remove bullets that collided.
-        let mut remaining_bullets = Vec::new();
-        for (i, bullet) in self.bullets.iter().enumerate() {
-            if bullet_hits[i] {
-                continue;
-            }
-            remaining_bullets.push(bullet.clone());
-        }
-        self.bullets = remaining_bullets;
+        self.bullets.remove_marked(bullet_hits);
+        self.collision_scratch = scratch;
     }
 
     pub(in crate::simulation) fn spawn_debris(&mut self, origin: Vec2, color: Color) {
         for _ in 0..DEBRIS_COUNT {
-            let disk = Vec2::from_angle(gen_range(0.0, 2.0 * PI));
+            let disk = Vec2::from_angle(self.rng.gen_range(0.0, 2.0 * PI));
             let velocity = disk * DEBRIS_SPEED;
-            self.debris.push(Debris::new(origin, velocity, color));
+            self.debris.insert(Debris::new(origin, velocity, color));
+        }
+    }
+
+    pub(in crate::simulation) fn reset_ship(&mut self, seat: usize) {
+        let spawn = spawn_point(seat, self.pilots.len(), self.world);
+        let pilot = &mut self.pilots[seat];
+        pilot.ship = Ship::at(spawn);
+        pilot.invulnerability_timer = self.tuning.invulnerability_duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::model::{
+        Alien, AlienSize, AsteroidSize, Bullet, Carrier, Drone, DroneMode,
+    };
+    use super::super::super::test_simulation;
+    use super::*;
+    use crate::config::{AlienBehaviour, AsteroidArchetype, CoopSettings, GameConfig};
+    use crate::controllers::alien::controller_for;
+    use macroquad::prelude::vec2;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            enemies_enabled: true,
+            ..GameConfig::default()
         }
     }
 
-    pub(in crate::simulation) fn reset_ship(&mut self) {
-        self.ship.position = vec2(screen_width() / 2.0, screen_height() / 2.0);
-        self.ship.velocity = Vec2::ZERO;
-        self.ship.angle = -PI / 2.0;
-        self.invulnerability_timer = INVULNERABILITY_DURATION;
+    fn shoot(sim: &mut Simulation, at: Vec2, shots: usize) {
+        for _ in 0..shots {
+            sim.bullets.insert(Bullet::new(at, Vec2::ZERO, BulletSource::Player(0), 1.0));
+        }
+        sim.resolve_collisions();
+    }
+
+    fn rock(sim: &mut Simulation, archetype: AsteroidArchetype, size: AsteroidSize, at: Vec2) {
+        let asteroid = Asteroid::new(size, archetype, at, Vec2::ZERO, &mut sim.rng);
+        sim.asteroids.insert(asteroid);
+    }
+
+    fn carrier_destroyed(sim: &Simulation) -> bool {
+        sim.events.iter().any(|event| {
+            matches!(event, SimEvent::Destroyed { target: HitTarget::Carrier, by: Some(0), .. })
+        })
+    }
+
+    #[test]
+    fn carriers_take_one_hit_point_per_shot_and_orphan_their_drones() {
+        let mut sim = test_simulation(config(), 5);
+        let at = vec2(100.0, 100.0);
+        sim.carriers.push(Carrier::new(4, at, Vec2::ZERO, 3, 60.0));
+        sim.drones.push(Drone::new(4, vec2(100.0, 500.0), 0.0));
+
+        shoot(&mut sim, at, 2);
+        assert_eq!(sim.carriers[0].hp, 1);
+        assert!(sim.bullets.is_empty(), "a shot passed through the carrier");
+        assert!(!carrier_destroyed(&sim));
+        assert_eq!(sim.pilots[0].score, 0);
+
+        sim.events.clear();
+        shoot(&mut sim, at, 1);
+        assert!(sim.carriers.is_empty());
+        assert!(carrier_destroyed(&sim));
+        assert_eq!(sim.pilots[0].score, sim.tuning.carrier_score);
+        assert!(sim.drones[0].mode != DroneMode::Escort, "drone still escorting a dead carrier");
+    }
+
+    fn ram_a_saucer(invulnerable: bool) -> Simulation {
+        let mut sim = test_simulation(config(), 5);
+        if invulnerable {
+            sim.toggle_invulnerability();
+        }
+        sim.pilots[0].invulnerability_timer = 0.0;
+        let controller = controller_for(AlienBehaviour::Classic);
+        let mut alien = Alien::new(AlienSize::Large, 1.0, 0.0, sim.world.x, controller);
+        alien.position = sim.pilots[0].ship.position;
+        sim.aliens.insert(alien);
+        sim.resolve_collisions();
+        sim
+    }
+
+    #[test]
+    fn rams_leave_saucers_alone_while_invulnerable() {
+        let lives = test_simulation(config(), 5).pilots[0].lives;
+
+        let shielded = ram_a_saucer(true);
+        assert_eq!(shielded.aliens.len(), 1, "saucer destroyed by an invulnerable ship");
+        assert_eq!(shielded.pilots[0].lives, lives);
+
+        let rammed = ram_a_saucer(false);
+        assert!(rammed.aliens.is_empty());
+        assert_eq!(rammed.pilots[0].lives, lives - 1);
+    }
+
+    #[test]
+    fn surplus_shots_on_a_dying_carrier_fly_on() {
+        let mut sim = test_simulation(config(), 5);
+        let at = vec2(100.0, 100.0);
+        sim.carriers.push(Carrier::new(0, at, Vec2::ZERO, 2, 60.0));
+        shoot(&mut sim, at, 3);
+        assert!(sim.carriers.is_empty());
+        assert_eq!(sim.bullets.len(), 1);
+    }
+
+    #[test]
+    fn armoured_rocks_take_one_shot_per_hit_point() {
+        let mut sim = test_simulation(config(), 5);
+        let at = vec2(100.0, 100.0);
+        rock(&mut sim, AsteroidArchetype::Armoured, AsteroidSize::Small, at);
+        let hp = sim.asteroids[0].hp;
+        assert_eq!(hp, 3);
+
+        for left in (1..hp).rev() {
+            shoot(&mut sim, at, 1);
+            assert_eq!(sim.asteroids.len(), 1, "broke with {left} hit points left");
+            assert_eq!(sim.asteroids[0].hp, left);
+            assert_eq!(sim.pilots[0].score, 0, "paid out for a dent");
+        }
+        shoot(&mut sim, at, 1);
+        assert!(sim.asteroids.is_empty());
+        assert!(sim.pilots[0].score > 0);
+    }
+
+    #[test]
+    fn volatile_blasts_take_nearby_rocks_and_ships_with_them() {
+        let mut sim = test_simulation(config(), 5);
+        sim.pilots[0].invulnerability_timer = 0.0;
+        sim.pilots[0].ship.position = vec2(200.0, 360.0);
+        let lives = sim.pilots[0].lives;
+        let volatile = vec2(200.0, 300.0);
+        rock(&mut sim, AsteroidArchetype::Volatile, AsteroidSize::Small, volatile);
+        rock(&mut sim, AsteroidArchetype::Rock, AsteroidSize::Small, vec2(240.0, 300.0));
+        let far = vec2(200.0, 100.0);
+        rock(&mut sim, AsteroidArchetype::Rock, AsteroidSize::Small, far);
+
+        shoot(&mut sim, volatile, 1);
+        assert_eq!(sim.asteroids.len(), 1, "the blast missed its neighbour or hit the far rock");
+        assert_eq!(sim.asteroids[0].position, far);
+        let credited = sim.events.iter().filter(|event| {
+            matches!(event, SimEvent::Destroyed { by: Some(0), .. })
+        });
+        assert_eq!(credited.count(), 2, "the blast's kill not credited to the shooter");
+        assert_eq!(sim.pilots[0].lives, lives - 1);
+        assert!(sim.events.iter().any(|event| matches!(
+            event,
+            SimEvent::ShipHit { seat: 0, cause: HitCause::Blast, shielded: false, .. }
+        )));
+    }
+
+    #[test]
+    fn splitters_break_into_an_extra_fragment() {
+        let cases = [(AsteroidArchetype::Rock, 2), (AsteroidArchetype::Splitter, 3)];
+        for (archetype, fragments) in cases {
+            let mut sim = test_simulation(config(), 5);
+            let at = vec2(100.0, 100.0);
+            rock(&mut sim, archetype, AsteroidSize::Large, at);
+            shoot(&mut sim, at, 1);
+            assert_eq!(sim.asteroids.len(), fragments);
+            for fragment in sim.asteroids.iter() {
+                assert!(matches!(fragment.size, AsteroidSize::Medium));
+                assert_eq!(fragment.archetype, archetype);
+            }
+        }
+    }
+
+    fn partner_shot(friendly_fire: bool) -> Simulation {
+        let config = GameConfig {
+            coop: CoopSettings { enabled: true, friendly_fire, ..CoopSettings::off() },
+            ..config()
+        };
+        let mut sim = test_simulation(config, 5);
+        for pilot in &mut sim.pilots {
+            pilot.invulnerability_timer = 0.0;
+            pilot.score = 500;
+        }
+        let at = sim.pilots[0].ship.position;
+        sim.bullets.insert(Bullet::new(at, Vec2::ZERO, BulletSource::Player(1), 1.0));
+        sim.resolve_collisions();
+        sim
+    }
+
+    #[test]
+    fn partner_shots_pass_through_without_friendly_fire() {
+        let lives = test_simulation(config(), 5).pilots[0].lives;
+        let sim = partner_shot(false);
+        assert_eq!(sim.pilots[0].lives, lives);
+        assert_eq!(sim.bullets.len(), 1, "the partner's shot was stopped");
+        assert!(!sim.events.iter().any(|event| matches!(event, SimEvent::ShipHit { .. })));
+    }
+
+    #[test]
+    fn friendly_fire_costs_only_the_hit_ship_a_life() {
+        let lives = test_simulation(config(), 5).pilots[0].lives;
+        let sim = partner_shot(true);
+        assert!(sim.bullets.is_empty());
+        assert!(sim.events.iter().any(|event| matches!(
+            event,
+            SimEvent::ShipHit { seat: 0, cause: HitCause::PlayerShot, shielded: false, .. }
+        )));
+        assert_eq!((sim.pilots[0].lives, sim.pilots[0].score), (lives - 1, 500));
+        assert_eq!((sim.pilots[1].lives, sim.pilots[1].score), (lives, 500));
     }
 }
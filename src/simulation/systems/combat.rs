@@ -1,10 +1,6 @@
-use crate::controllers::ControlIntent;
 use macroquad::prelude::Vec2;
 
-use super::super::{
-    BULLET_RADIUS, BULLET_SPEED, PRIMARY_FIRE_RATE, SECONDARY_COUNT, SECONDARY_FIRE_RATE,
-    SECONDARY_SPREAD, SHIP_SIZE,
-};
+use super::super::{BULLET_RADIUS, SHIP_SIZE};
 use super::super::model::{BulletSource, wrap_position};
 use super::super::Simulation;
 
@@ -12,18 +8,50 @@ impl Simulation {
     pub(in crate::simulation) fn update_aliens(&mut self) {
         self.tick_alien_spawns();
 
-        let width = macroquad::prelude::screen_width();
-        let ship_pos = self.ship.position;
-        let score = self.status.score;
+        let (width, height) = (self.world.x, self.world.y);
+        // Each saucer goes after the nearest ship still flying. A ship inside a nebula is
+        // invisible: saucers chase its last known position and hold fire.
+        let targets: Vec<(Vec2, Vec2, Vec2, bool)> = self
+            .pilots
+            .iter()
+            .enumerate()
+            .filter(|(_, pilot)| pilot.in_play())
+            .map(|(seat, pilot)| {
+                let ship = &pilot.ship;
+                (ship.position, ship.velocity, pilot.last_seen, self.ship_hidden(seat))
+            })
+            .collect();
+        let fallback = &self.pilots[0];
+        let fallback = (fallback.ship.position, fallback.ship.velocity, fallback.last_seen, false);
+        let score = self.pilots.iter().map(|pilot| pilot.score).sum();
+        let bullet_speed = self.tuning.bullet_speed;
         let mut alien_shots = Vec::new();
         for alien in &mut self.aliens {
-            alien.update(self.dt, width);
-            if alien.ready_to_fire() {
-                let direction = alien.fire_direction(ship_pos, score);
+            alien.tick_fire_timer(self.dt);
+            let (position, velocity, last_seen, hidden) = targets
+                .iter()
+                .copied()
+                .min_by(|a, b| {
+                    a.0.distance_squared(alien.position)
+                        .total_cmp(&b.0.distance_squared(alien.position))
+                })
+                .unwrap_or(fallback);
+            let (ship_pos, ship_vel) = if hidden {
+                (last_seen, Vec2::ZERO)
+            } else {
+                (position, velocity)
+            };
+            let mut snapshot =
+                alien.snapshot(ship_pos, ship_vel, score, width, bullet_speed, self.rng.gen_range(-1.0, 1.0));
+            snapshot.ready_to_fire &= !hidden;
+            let intent = alien.controller.tick(&snapshot, self.dt);
+            alien.steer(intent.velocity, self.dt, width, height);
+            if snapshot.ready_to_fire {
+                let direction = intent.fire.unwrap_or_default().normalize_or_zero();
                 if direction.length_squared() > 0.0 {
                     let spawn_pos =
                         alien.position + direction * (alien.size.hit_radius() + BULLET_RADIUS + 2.0);
-                    alien_shots.push((spawn_pos, direction * BULLET_SPEED));
+                    alien_shots.push((spawn_pos, direction * bullet_speed));
                 }
                 alien.reset_fire_timer();
             }
@@ -34,25 +62,38 @@ impl Simulation {
         }
     }
 
-    pub(in crate::simulation) fn handle_firing(&mut self, intent: ControlIntent) {
-        if intent.fire_primary && self.primary_cooldown <= 0.0 {
-            self.primary_cooldown = 1.0 / PRIMARY_FIRE_RATE;
-            let forward = Vec2::from_angle(self.ship.angle);
-            let spawn_pos = self.ship.position + forward * SHIP_SIZE;
+    pub(in crate::simulation) fn handle_firing(&mut self, seat: usize) {
+        let bullet_speed = self.tuning.bullet_speed;
+        let source = BulletSource::Player(seat);
+        let pilot = &mut self.pilots[seat];
+        let intent = pilot.intent;
+        let (angle, position, velocity) = (pilot.ship.angle, pilot.ship.position, pilot.ship.velocity);
+
+        if intent.fire_primary && pilot.primary_cooldown <= 0.0 {
+            let rate = if pilot.rapid_fire_timer > 0.0 {
+                self.tuning.primary_fire_rate * self.tuning.rapid_fire_multiplier
+            } else {
+                self.tuning.primary_fire_rate
+            };
+            pilot.primary_cooldown = 1.0 / rate;
+            let forward = Vec2::from_angle(angle);
+            let spawn_pos = position + forward * SHIP_SIZE;
             // Bullets inherit the ship's velocity for better feel and more interesting interactions.
-            let spawn_velocity = forward * BULLET_SPEED + self.ship.velocity;
-            self.spawn_bullet(spawn_pos, spawn_velocity, BulletSource::Player);
+            let spawn_velocity = forward * bullet_speed + velocity;
+            self.spawn_bullet(spawn_pos, spawn_velocity, source);
         }
 
-        if intent.fire_secondary && self.secondary_cooldown <= 0.0 {
-            self.secondary_cooldown = 1.0 / SECONDARY_FIRE_RATE;
-            let base_angle = self.ship.angle;
-            let center = (SECONDARY_COUNT as f32 - 1.0) * 0.5;
-            for i in 0..SECONDARY_COUNT {
-                let offset = (i as f32 - center) * SECONDARY_SPREAD;
-                let dir = Vec2::from_angle(base_angle + offset);
-                let spawn_pos = self.ship.position + dir * SHIP_SIZE;
-                self.spawn_bullet(spawn_pos, dir * BULLET_SPEED, BulletSource::Player);
+        let pilot = &mut self.pilots[seat];
+        if intent.fire_secondary && pilot.secondary_cooldown <= 0.0 {
+            pilot.secondary_cooldown = 1.0 / self.tuning.secondary_fire_rate();
+            let count = self.tuning.secondary_count;
+            let spread = self.tuning.secondary_spread_degrees.to_radians();
+            let center = (count as f32 - 1.0) * 0.5;
+            for i in 0..count {
+                let offset = (i as f32 - center) * spread;
+                let dir = Vec2::from_angle(angle + offset);
+                let spawn_pos = position + dir * SHIP_SIZE;
+                self.spawn_bullet(spawn_pos, dir * bullet_speed, source);
             }
         }
     }
@@ -63,7 +104,7 @@ impl Simulation {
             if bullet.ttl <= 0.0 {
                 return false;
             }
-            bullet.position = wrap_position(bullet.position + bullet.velocity * self.dt);
+            bullet.position = wrap_position(bullet.position + bullet.velocity * self.dt, self.world);
             true
         });
     }
@@ -74,7 +115,7 @@ impl Simulation {
             if debris.ttl <= 0.0 {
                 return false;
             }
-            debris.position = wrap_position(debris.position + debris.velocity * self.dt);
+            debris.position = wrap_position(debris.position + debris.velocity * self.dt, self.world);
             true
         });
     }
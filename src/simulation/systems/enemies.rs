@@ -0,0 +1,257 @@
+use macroquad::prelude::{Vec2, vec2};
+use std::f32::consts::PI;
+
+use super::super::{
+    CARRIER_SPEED, DRONE_ENGAGE_RANGE, DRONE_ORBIT_RADIUS, DRONE_ORBIT_RATE, DRONE_SCATTER_TTL,
+    DRONE_SEPARATION, DRONE_STEER, MAX_CARRIERS,
+};
+use super::super::model::{Carrier, Drone, DroneMode, nearest, wrap_position};
+use super::super::{SimEvent, Simulation};
+
+impl Simulation {
+    pub(in crate::simulation) fn update_enemies(&mut self) {
+        if !self.enemies_enabled {
+            return;
+        }
+
+        self.tick_carrier_spawns();
+        self.update_carriers();
+        self.update_drones();
+    }
+
+    fn enemy_count(&self) -> usize {
+        self.carriers.len() + self.drones.len()
+    }
+
+    fn tick_carrier_spawns(&mut self) {
+        self.carrier_spawn_acc += self.dt;
+        let interval = self.tuning.carrier_spawn_interval;
+        while self.carrier_spawn_acc >= interval {
+            self.carrier_spawn_acc -= interval;
+            if self.carriers.len() < MAX_CARRIERS && self.enemy_count() < self.enemy_cap {
+                self.spawn_carrier();
+            }
+        }
+    }
+
+    fn spawn_carrier(&mut self) {
+        let (width, height) = (self.world.x, self.world.y);
+        let from_left = self.rng.gen_range(0.0, 1.0) < 0.5;
+        let position = vec2(
+            if from_left { 0.0 } else { width },
+            self.rng.gen_range(height * 0.2, height * 0.8),
+        );
+        let velocity = vec2(if from_left { CARRIER_SPEED } else { -CARRIER_SPEED }, 0.0);
+        let id = self.next_carrier_id;
+        self.next_carrier_id = self.next_carrier_id.wrapping_add(1);
+        self.carriers.push(Carrier::new(
+            id,
+            position,
+            velocity,
+            self.tuning.carrier_hp,
+            self.tuning.carrier_launch_interval,
+        ));
+        self.events.push(SimEvent::CarrierSpawned { position });
+    }
+
+    fn update_carriers(&mut self) {
+        let mut launches = Vec::new();
+        for carrier in &mut self.carriers {
+            carrier.position = wrap_position(carrier.position + carrier.velocity * self.dt, self.world);
+            carrier.launch_timer = (carrier.launch_timer - self.dt).max(0.0);
+            if carrier.launch_timer <= 0.0 {
+                launches.push((carrier.id, carrier.position));
+            }
+        }
+
+        for (id, position) in launches {
+            // At the cap the timer stays expired so the carrier launches as soon as room frees up.
+            if self.enemy_count() >= self.enemy_cap {
+                break;
+            }
+            self.drones
+                .push(Drone::new(id, position, self.rng.gen_range(0.0, 2.0 * PI)));
+            if let Some(carrier) = self.carriers.iter_mut().find(|c| c.id == id) {
+                carrier.launch_timer = self.tuning.carrier_launch_interval;
+            }
+        }
+    }
+
+    fn update_drones(&mut self) {
+        let ships = self.ships_in_play();
+        let fallback = self.pilots[0].ship.position;
+        let dt = self.dt;
+        let speed = self.tuning.drone_speed;
+        // Separation is O(n^2) over drones, which `enemy_cap` keeps small.
+        let positions: Vec<Vec2> = self.drones.iter().map(|drone| drone.position).collect();
+        let carriers = &self.carriers;
+
+        for (i, drone) in self.drones.iter_mut().enumerate() {
+            // Each drone hunts whichever ship is closest to it.
+            let ship_pos = nearest(&ships, drone.position).unwrap_or(fallback);
+            let desired = match drone.mode {
+                DroneMode::Escort => {
+                    let carrier = carriers.iter().find(|c| c.id == drone.carrier);
+                    match carrier {
+                        Some(carrier) if drone.position.distance(ship_pos) > DRONE_ENGAGE_RANGE => {
+                            drone.orbit_phase += DRONE_ORBIT_RATE * dt;
+                            let slot = carrier.position
+                                + Vec2::from_angle(drone.orbit_phase) * DRONE_ORBIT_RADIUS;
+                            (slot - drone.position).normalize_or_zero() * speed
+                        }
+                        _ => (ship_pos - drone.position).normalize_or_zero() * speed,
+                    }
+                }
+                DroneMode::Scatter => {
+                    drone.ttl -= dt;
+                    (drone.position - ship_pos).normalize_or_zero() * speed
+                }
+                DroneMode::Kamikaze => {
+                    (ship_pos - drone.position).normalize_or_zero() * speed * 1.3
+                }
+            };
+
+            let mut separation = Vec2::ZERO;
+            for (j, other) in positions.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let away = drone.position - *other;
+                let distance = away.length();
+                if distance > 0.0 && distance < DRONE_SEPARATION {
+                    separation += away / distance * (DRONE_SEPARATION - distance);
+                }
+            }
+
+            let target_velocity = desired + separation * DRONE_STEER;
+            let blend = (DRONE_STEER * dt).min(1.0);
+            drone.velocity += (target_velocity - drone.velocity) * blend;
+            drone.position = wrap_position(drone.position + drone.velocity * dt, self.world);
+        }
+
+        self.drones
+            .retain(|drone| drone.mode != DroneMode::Scatter || drone.ttl > 0.0);
+    }
+
+    /// Called when a carrier dies: its escorts either scatter or turn kamikaze.
+    pub(in crate::simulation) fn orphan_drones(&mut self, carrier_id: u32) {
+        for drone in &mut self.drones {
+            if drone.carrier == carrier_id && drone.mode == DroneMode::Escort {
+                drone.mode = if self.rng.gen_range(0.0, 1.0) < 0.5 {
+                    DroneMode::Scatter
+                } else {
+                    DroneMode::Kamikaze
+                };
+                drone.ttl = DRONE_SCATTER_TTL;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::{DRONE_ORBIT_RADIUS, test_simulation};
+    use super::*;
+    use crate::config::GameConfig;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            enemies_enabled: true,
+            ..GameConfig::default()
+        }
+    }
+
+    fn carrier(sim: &mut Simulation, id: u32, position: Vec2) {
+        let hp = sim.tuning.carrier_hp;
+        sim.carriers.push(Carrier::new(id, position, Vec2::ZERO, hp, 60.0));
+    }
+
+    #[test]
+    fn carriers_launch_on_their_cooldown_until_the_enemy_cap() {
+        let mut sim = test_simulation(config(), 11);
+        sim.enemy_cap = 3;
+        carrier(&mut sim, 0, vec2(100.0, 100.0));
+        sim.carriers[0].launch_timer = 0.0;
+        sim.update_carriers();
+        assert_eq!(sim.drones.len(), 1);
+        assert_eq!(sim.carriers[0].launch_timer, sim.tuning.carrier_launch_interval);
+
+        sim.update_carriers();
+        assert_eq!(sim.drones.len(), 1, "launched during the cooldown");
+
+        for _ in 0..2 {
+            sim.carriers[0].launch_timer = 0.0;
+            sim.update_carriers();
+        }
+        assert_eq!(sim.drones.len(), 2, "launched past the cap");
+        // Stalled at the cap, ready to launch once a drone goes.
+        assert_eq!(sim.carriers[0].launch_timer, 0.0);
+    }
+
+    #[test]
+    fn escorts_orbit_their_carrier_until_a_ship_comes_in_range() {
+        let mut sim = test_simulation(config(), 11);
+        let ship = sim.pilots[0].ship.position;
+        let base = ship + vec2(-300.0, 0.0);
+        carrier(&mut sim, 0, base);
+        sim.drones.push(Drone::new(0, base, 0.0));
+        for _ in 0..240 {
+            sim.update_drones();
+        }
+        let orbit = sim.drones[0].position.distance(base);
+        assert!(orbit < DRONE_ORBIT_RADIUS * 1.5, "strayed {orbit} from its carrier");
+
+        sim.drones[0].position = ship + vec2(-100.0, 0.0);
+        sim.drones[0].velocity = Vec2::ZERO;
+        sim.update_drones();
+        assert!(sim.drones[0].velocity.x > 0.0, "did not turn on the ship in range");
+    }
+
+    #[test]
+    fn crowded_drones_push_apart() {
+        let mut sim = test_simulation(config(), 11);
+        let spot = vec2(100.0, 500.0);
+        sim.drones.push(Drone::new(0, spot, 0.0));
+        sim.drones.push(Drone::new(0, spot + vec2(4.0, 0.0), 0.0));
+        let before = sim.drones[0].position.distance(sim.drones[1].position);
+        sim.update_drones();
+        let after = sim.drones[0].position.distance(sim.drones[1].position);
+        assert!(after > before, "{before} -> {after}");
+    }
+
+    #[test]
+    fn orphaned_drones_scatter_or_dive_and_scatterers_expire() {
+        let mut sim = test_simulation(config(), 11);
+        let ship = sim.pilots[0].ship.position;
+        for index in 0..16 {
+            let offset = Vec2::from_angle(index as f32) * 150.0;
+            sim.drones.push(Drone::new(index % 2, ship + offset, 0.0));
+        }
+        sim.orphan_drones(0);
+
+        let orphans = sim.drones.iter().filter(|drone| drone.carrier == 0);
+        assert!(orphans.clone().all(|drone| drone.mode != DroneMode::Escort));
+        assert!(orphans.clone().any(|drone| drone.mode == DroneMode::Scatter));
+        assert!(orphans.clone().any(|drone| drone.mode == DroneMode::Kamikaze));
+        let others = sim.drones.iter().filter(|drone| drone.carrier == 1);
+        assert!(others.clone().all(|drone| drone.mode == DroneMode::Escort));
+
+        let before: Vec<f32> = sim.drones.iter().map(|drone| drone.position.distance(ship)).collect();
+        sim.update_drones();
+        for (drone, before) in sim.drones.iter().zip(before) {
+            let after = drone.position.distance(ship);
+            match drone.mode {
+                DroneMode::Scatter => assert!(after > before, "scatterer closed in"),
+                DroneMode::Kamikaze => assert!(after < before, "kamikaze backed off"),
+                DroneMode::Escort => {}
+            }
+        }
+
+        let steps = (DRONE_SCATTER_TTL / sim.dt).ceil() as usize;
+        for _ in 0..steps {
+            sim.update_drones();
+        }
+        assert!(sim.drones.iter().all(|drone| drone.mode != DroneMode::Scatter));
+        assert!(sim.drones.iter().any(|drone| drone.mode == DroneMode::Kamikaze));
+    }
+}
@@ -0,0 +1,261 @@
+use crate::config::HazardSettings;
+use macroquad::prelude::{Vec2, vec2};
+
+use super::super::{
+    BULLET_RADIUS, GRAVITY_WELL_COUNT, HAZARD_CLEARANCE, NEBULA_COUNT, NEBULA_RADIUS, SHIP_SIZE,
+};
+use super::super::model::{GravityWell, Nebula, SolarFlare, clamp_length};
+use super::super::Simulation;
+
+impl Simulation {
+    /// Scatters the enabled hazards across the field, keeping the spawn point clear.
+    pub(in crate::simulation) fn place_hazards(&mut self, settings: &HazardSettings) {
+        if settings.gravity_wells {
+            for _ in 0..GRAVITY_WELL_COUNT {
+                let position = self.hazard_position();
+                self.gravity_wells.push(GravityWell {
+                    position,
+                    strength: self.tuning.gravity_well_strength,
+                });
+            }
+        }
+        if settings.nebulae {
+            for _ in 0..NEBULA_COUNT {
+                let position = self.hazard_position();
+                self.nebulae.push(Nebula {
+                    position,
+                    radius: NEBULA_RADIUS,
+                });
+            }
+        }
+        if settings.solar_flares {
+            self.solar_flare = Some(SolarFlare::new(self.tuning.solar_flare_interval));
+        }
+    }
+
+    pub(in crate::simulation) fn update_hazards(&mut self) {
+        self.update_gravity_wells();
+        self.update_nebulae();
+        self.update_solar_flare();
+    }
+
+    pub(in crate::simulation) fn solar_flare_active(&self) -> bool {
+        self.solar_flare.as_ref().is_some_and(|flare| flare.active())
+    }
+
+    pub(in crate::simulation) fn ship_hidden(&self, seat: usize) -> bool {
+        let position = self.pilots[seat].ship.position;
+        self.nebulae.iter().any(|nebula| nebula.contains(position))
+    }
+
+    fn update_gravity_wells(&mut self) {
+        if self.gravity_wells.is_empty() {
+            return;
+        }
+
+        let dt = self.dt;
+        let max_asteroid_speed = self.tuning.asteroid_max_speed * 2.0;
+        let wells = &self.gravity_wells;
+        let pull = |point: Vec2| -> Vec2 { wells.iter().map(|well| well.accel_at(point)).sum() };
+        let swallowed =
+            |point: Vec2, radius: f32| wells.iter().any(|well| well.swallows(point, radius));
+
+        for pilot in self.pilots.iter_mut().filter(|pilot| pilot.in_play()) {
+            pilot.ship.velocity += pull(pilot.ship.position) * dt;
+            if pilot.invulnerability_timer <= 0.0
+                && !self.invulnerability_enabled
+                && swallowed(pilot.ship.position, SHIP_SIZE * 0.5)
+            {
+                pilot.hazard_hit = true;
+            }
+        }
+
+        self.bullets.retain_mut(|bullet| {
+            bullet.velocity += pull(bullet.position) * dt;
+            !swallowed(bullet.position, BULLET_RADIUS)
+        });
+
+        // Rocks are allowed to slingshot past their normal top speed, but not without bound.
+        self.asteroids.retain_mut(|asteroid| {
+            asteroid.velocity = clamp_length(
+                asteroid.velocity + pull(asteroid.position) * dt,
+                max_asteroid_speed,
+            );
+            !swallowed(asteroid.position, asteroid.radius() * 0.5)
+        });
+    }
+
+    fn update_nebulae(&mut self) {
+        for seat in 0..self.pilots.len() {
+            let hidden = self.ship_hidden(seat);
+            let pilot = &mut self.pilots[seat];
+            if hidden {
+                pilot.ship.velocity -= pilot.ship.velocity * self.tuning.nebula_drag * self.dt;
+            } else {
+                pilot.last_seen = pilot.ship.position;
+            }
+        }
+    }
+
+    fn update_solar_flare(&mut self) {
+        let interval = self.tuning.solar_flare_interval;
+        let duration = self.tuning.solar_flare_duration;
+        let Some(flare) = self.solar_flare.as_mut() else {
+            return;
+        };
+        if flare.active() {
+            flare.remaining = (flare.remaining - self.dt).max(0.0);
+            return;
+        }
+        flare.cooldown -= self.dt;
+        if flare.cooldown <= 0.0 {
+            flare.cooldown = interval;
+            flare.remaining = duration;
+        }
+    }
+
+    fn hazard_position(&mut self) -> Vec2 {
+        let (width, height) = (self.world.x, self.world.y);
+        let center = self.world / 2.0;
+        let mut position = center;
+        // A handful of rejection samples is plenty; on tiny windows fall back to the last roll.
+        for _ in 0..16 {
+            position = vec2(
+                self.rng.gen_range(width * 0.1, width * 0.9),
+                self.rng.gen_range(height * 0.1, height * 0.9),
+            );
+            if position.distance(center) >= HAZARD_CLEARANCE {
+                break;
+            }
+        }
+        position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::model::{
+        Alien, AlienSize, Asteroid, AsteroidSize, Bullet, BulletSource,
+    };
+    use super::super::super::test_simulation;
+    use super::super::super::events::{HitCause, SimEvent};
+    use super::*;
+    use crate::config::{AlienBehaviour, AsteroidArchetype, GameConfig, VersusSettings};
+    use crate::controllers::alien::controller_for;
+
+    fn well(sim: &mut Simulation, position: Vec2) {
+        let strength = sim.tuning.gravity_well_strength;
+        sim.gravity_wells.push(GravityWell { position, strength });
+    }
+
+    fn nebula(sim: &mut Simulation, position: Vec2) {
+        sim.nebulae.push(Nebula { position, radius: NEBULA_RADIUS });
+    }
+
+    fn ship_hit(sim: &Simulation) -> Option<(bool, HitCause)> {
+        sim.events.iter().find_map(|event| match event {
+            SimEvent::ShipHit { shielded, cause, .. } => Some((*shielded, *cause)),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn gravity_wells_pull_ships_and_bullets_and_swallow_them_at_the_core() {
+        let mut sim = test_simulation(GameConfig::default(), 3);
+        sim.pilots[0].invulnerability_timer = 0.0;
+        let ship = sim.pilots[0].ship.position;
+        well(&mut sim, ship + vec2(100.0, 0.0));
+        let shot = ship + vec2(0.0, 100.0);
+        sim.bullets.insert(Bullet::new(shot, Vec2::ZERO, BulletSource::Player(0), 1.0));
+        sim.update_hazards();
+        assert!(sim.pilots[0].ship.velocity.x > 0.0, "ship not pulled toward the well");
+        assert!(!sim.pilots[0].hazard_hit);
+        let bullet = sim.bullets.iter().next().expect("bullet swallowed outside the core");
+        assert!(bullet.velocity.x > 0.0 && bullet.velocity.y < 0.0);
+
+        sim.gravity_wells[0].position = ship;
+        sim.gravity_wells.push(GravityWell { position: shot, strength: 0.0 });
+        sim.update_hazards();
+        assert!(sim.bullets.is_empty(), "bullet survived the core");
+        assert!(sim.pilots[0].hazard_hit);
+
+        let lives = sim.pilots[0].lives;
+        sim.resolve_collisions();
+        assert_eq!(ship_hit(&sim), Some((false, HitCause::GravityWell)));
+        assert_eq!(sim.pilots[0].lives, lives - 1);
+    }
+
+    #[test]
+    fn nebulae_drag_ships_and_hide_them_from_saucers() {
+        let mut sim = test_simulation(GameConfig::default(), 3);
+        let ship = sim.pilots[0].ship.position;
+        let controller = controller_for(AlienBehaviour::Classic);
+        let mut alien = Alien::new(AlienSize::Large, 1.0, 0.0, sim.world.x, controller);
+        alien.position = ship + vec2(150.0, 0.0);
+        alien.fire_timer = 0.0;
+        sim.aliens.insert(alien);
+
+        nebula(&mut sim, ship);
+        sim.pilots[0].ship.velocity = vec2(100.0, 0.0);
+        sim.pilots[0].ship.position = ship + vec2(10.0, 0.0);
+        sim.update_hazards();
+        let speed = sim.pilots[0].ship.velocity.x;
+        assert!(speed < 100.0, "no drag inside the nebula");
+        assert!(sim.ship_hidden(0));
+        assert_eq!(sim.pilots[0].last_seen, ship, "last sighting moved inside the nebula");
+        sim.update_aliens();
+        assert!(sim.bullets.is_empty(), "saucer fired at a hidden ship");
+
+        sim.nebulae.clear();
+        sim.aliens.iter_mut().for_each(|alien| alien.fire_timer = 0.0);
+        sim.update_hazards();
+        assert_eq!(sim.pilots[0].ship.velocity.x, speed);
+        assert_eq!(sim.pilots[0].last_seen, sim.pilots[0].ship.position);
+        sim.update_aliens();
+        assert!(sim.bullets.iter().any(|bullet| bullet.source == BulletSource::Alien));
+    }
+
+    #[test]
+    fn rivals_in_a_nebula_drop_out_of_the_snapshot() {
+        let config = GameConfig {
+            versus: VersusSettings { enabled: true, ..VersusSettings::off() },
+            ..GameConfig::default()
+        };
+        let mut sim = test_simulation(config, 3);
+        assert_eq!(sim.snapshot(0).opponents.len(), 1);
+
+        let rival = sim.pilots[1].ship.position;
+        nebula(&mut sim, rival);
+        assert!(sim.snapshot(0).opponents.is_empty(), "hidden rival still in the snapshot");
+        assert_eq!(sim.snapshot(1).opponents.len(), 1);
+    }
+
+    fn rammed_with_a_shield(flare: bool) -> Simulation {
+        let mut sim = test_simulation(GameConfig::default(), 3);
+        sim.pilots[0].invulnerability_timer = 0.0;
+        sim.pilots[0].shield_charges = 1;
+        if flare {
+            sim.solar_flare = Some(SolarFlare { cooldown: 10.0, remaining: 1.0 });
+        }
+        let at = sim.pilots[0].ship.position;
+        let (size, archetype) = (AsteroidSize::Large, AsteroidArchetype::Rock);
+        let asteroid = Asteroid::new(size, archetype, at, Vec2::ZERO, &mut sim.rng);
+        sim.asteroids.insert(asteroid);
+        sim.resolve_collisions();
+        sim
+    }
+
+    #[test]
+    fn solar_flares_let_hits_through_shields() {
+        let lives = test_simulation(GameConfig::default(), 3).pilots[0].lives;
+
+        let shielded = rammed_with_a_shield(false);
+        assert_eq!(ship_hit(&shielded), Some((true, HitCause::Asteroid)));
+        assert_eq!(shielded.pilots[0].lives, lives);
+        assert_eq!(shielded.pilots[0].shield_charges, 0);
+
+        let flared = rammed_with_a_shield(true);
+        assert_eq!(ship_hit(&flared), Some((false, HitCause::Asteroid)));
+        assert_eq!(flared.pilots[0].lives, lives - 1);
+    }
+}
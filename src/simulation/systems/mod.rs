@@ -1,4 +1,9 @@
 pub(super) mod combat;
+pub(super) mod enemies;
 pub(super) mod collision;
+pub(super) mod hazards;
 pub(super) mod movement;
+pub(super) mod pickups;
+pub(super) mod sentinels;
 pub(super) mod spawn;
+pub(super) mod versus;
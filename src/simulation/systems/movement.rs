@@ -1,29 +1,73 @@
-use crate::controllers::ControlIntent;
 use macroquad::prelude::Vec2;
 
-use super::super::{SHIP_DRAG, SHIP_MAX_SPEED, SHIP_ROTATION_SPEED, SHIP_THRUST};
-use super::super::model::{clamp_length, wrap_position};
+use super::super::model::{clamp_length, nearest, wrap_position};
 use super::super::Simulation;
 
 impl Simulation {
-    pub(in crate::simulation) fn update_ship(&mut self, intent: ControlIntent) {
-        self.ship.angle += intent.turn * SHIP_ROTATION_SPEED * self.dt;
-        let forward = Vec2::from_angle(self.ship.angle);
+    pub(in crate::simulation) fn update_ship(&mut self, seat: usize) {
+        let dt = self.dt;
+        let pilot = &mut self.pilots[seat];
+        let intent = pilot.intent;
+        let ship = &mut pilot.ship;
+        ship.angle += intent.turn * self.tuning.ship_rotation_speed * dt;
+        let forward = Vec2::from_angle(ship.angle);
 
         if intent.thrust > 0.0 {
-            self.ship.velocity += forward * (intent.thrust * SHIP_THRUST * self.dt);
+            ship.velocity += forward * (intent.thrust * self.tuning.ship_thrust * dt);
         }
 
-        self.ship.velocity -= self.ship.velocity * SHIP_DRAG * self.dt;
-        self.ship.velocity = clamp_length(self.ship.velocity, SHIP_MAX_SPEED);
-        self.ship.position = wrap_position(self.ship.position + self.ship.velocity * self.dt);
+        ship.velocity -= ship.velocity * self.tuning.ship_drag * dt;
+        ship.velocity = clamp_length(ship.velocity, self.tuning.ship_max_speed);
+        ship.position = wrap_position(ship.position + ship.velocity * dt, self.world);
     }
 
     pub(in crate::simulation) fn update_asteroids(&mut self) {
+        let ships = self.ships_in_play();
+        let max_speed = self.tuning.asteroid_max_speed;
         for asteroid in &mut self.asteroids {
+            let magnet = asteroid.def().magnet_accel;
+            if magnet > 0.0
+                && let Some(ship_pos) = nearest(&ships, asteroid.position)
+            {
+                let pull = (ship_pos - asteroid.position).normalize_or_zero();
+                asteroid.velocity = clamp_length(
+                    asteroid.velocity + pull * magnet * self.dt,
+                    max_speed,
+                );
+            }
             asteroid.angle += asteroid.rotation_speed * self.dt;
             let target = asteroid.position + asteroid.velocity * self.dt;
-            asteroid.position = wrap_position(target);
+            asteroid.position = wrap_position(target, self.world);
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::model::{Asteroid, AsteroidSize};
+    use super::super::super::test_simulation;
+    use super::*;
+    use crate::config::{AsteroidArchetype, GameConfig};
+    use macroquad::prelude::vec2;
+
+    #[test]
+    fn magnetic_rocks_are_pulled_toward_the_nearest_ship() {
+        let mut sim = test_simulation(GameConfig::default(), 3);
+        let ship = sim.pilots[0].ship.position;
+        for archetype in [AsteroidArchetype::Magnetic, AsteroidArchetype::Rock] {
+            let at = ship - vec2(200.0, 0.0);
+            let rng = &mut sim.rng;
+            let asteroid = Asteroid::new(AsteroidSize::Large, archetype, at, Vec2::ZERO, rng);
+            sim.asteroids.insert(asteroid);
+        }
+
+        for _ in 0..30 {
+            sim.update_asteroids();
+        }
+        let (magnetic, inert) = (&sim.asteroids[0], &sim.asteroids[1]);
+        assert!(magnetic.velocity.x > 0.0, "pulled away at {}", magnetic.velocity);
+        assert!(magnetic.velocity.y.abs() < 1e-4);
+        assert!(magnetic.position.x > inert.position.x);
+        assert_eq!(inert.velocity, Vec2::ZERO);
+    }
+}
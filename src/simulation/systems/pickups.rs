@@ -0,0 +1,171 @@
+use crate::config::PickupKind;
+use macroquad::prelude::Vec2;
+use std::f32::consts::PI;
+
+use super::super::{PICKUP_DRIFT_SPEED, PICKUP_RADIUS, SHIP_SIZE};
+use super::super::model::{Pickup, wrap_position};
+use super::super::{SimEvent, Simulation};
+
+impl Simulation {
+    /// Rolls the preset's drop table for something destroyed at `origin`.
+    pub(in crate::simulation) fn roll_pickup_drop(&mut self, origin: Vec2) {
+        let total = self.drop_table.total();
+        if total == 0 || self.rng.gen_range(0.0, 1.0) >= self.drop_table.chance {
+            return;
+        }
+        if let Some(kind) = self.drop_table.pick(self.rng.gen_range(0, total)) {
+            let velocity = Vec2::from_angle(self.rng.gen_range(0.0, 2.0 * PI)) * PICKUP_DRIFT_SPEED;
+            self.pickups
+                .push(Pickup::new(kind, origin, velocity, self.tuning.pickup_ttl));
+        }
+    }
+
+    pub(in crate::simulation) fn update_pickups(&mut self) {
+        let dt = self.dt;
+        let ships: Vec<(usize, Vec2)> = self
+            .pilots
+            .iter()
+            .enumerate()
+            .filter(|(_, pilot)| pilot.in_play())
+            .map(|(seat, pilot)| (seat, pilot.ship.position))
+            .collect();
+        let reach = PICKUP_RADIUS + SHIP_SIZE * 0.9;
+        let mut collected = Vec::new();
+        self.pickups.retain_mut(|pickup| {
+            pickup.ttl -= dt;
+            if pickup.ttl <= 0.0 {
+                return false;
+            }
+            pickup.position = wrap_position(pickup.position + pickup.velocity * dt, self.world);
+            // Whoever touches it first takes it; on a tie the lower seat wins.
+            if let Some(&(seat, _)) = ships
+                .iter()
+                .find(|(_, ship_pos)| pickup.position.distance_squared(*ship_pos) <= reach * reach)
+            {
+                collected.push((seat, pickup.kind));
+                return false;
+            }
+            true
+        });
+
+        for (seat, kind) in collected {
+            self.apply_pickup(seat, kind);
+        }
+    }
+
+    fn apply_pickup(&mut self, seat: usize, kind: PickupKind) {
+        let pilot = &mut self.pilots[seat];
+        match kind {
+            PickupKind::RapidFire => pilot.rapid_fire_timer = self.tuning.rapid_fire_duration,
+            PickupKind::SpreadReset => pilot.secondary_cooldown = 0.0,
+            PickupKind::ExtraLife => pilot.lives = pilot.lives.saturating_add(1),
+            PickupKind::Shield => {
+                pilot.shield_charges = (pilot.shield_charges + 1).min(self.tuning.max_shield_charges)
+            }
+            PickupKind::ScoreMultiplier => {
+                pilot.score_multiplier_timer = self.tuning.score_multiplier_duration
+            }
+        }
+        pilot.run_stats.pickups_collected = pilot.run_stats.pickups_collected.saturating_add(1);
+        self.events.push(SimEvent::PickupCollected { seat, kind });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::model::{Asteroid, AsteroidSize, Bullet, BulletSource};
+    use super::super::super::test_simulation;
+    use super::*;
+    use crate::config::{AsteroidArchetype, DropTable, GameConfig};
+    use macroquad::prelude::vec2;
+
+    fn config(chance: f32) -> GameConfig {
+        GameConfig {
+            drop_table: DropTable {
+                chance,
+                ..DropTable::arcade()
+            },
+            ..GameConfig::default()
+        }
+    }
+
+    fn drops_from_a_shot_rock(chance: f32) -> usize {
+        let mut sim = test_simulation(config(chance), 9);
+        let at = vec2(100.0, 100.0);
+        let rng = &mut sim.rng;
+        let rock = Asteroid::new(AsteroidSize::Small, AsteroidArchetype::Rock, at, Vec2::ZERO, rng);
+        sim.asteroids.insert(rock);
+        sim.bullets.insert(Bullet::new(at, Vec2::ZERO, BulletSource::Player(0), 1.0));
+        sim.resolve_collisions();
+        assert!(sim.asteroids.is_empty());
+        sim.pickups.len()
+    }
+
+    fn place(sim: &mut Simulation, kind: PickupKind, position: Vec2, ttl: f32) {
+        sim.pickups.push(Pickup::new(kind, position, Vec2::ZERO, ttl));
+    }
+
+    #[test]
+    fn destroyed_bodies_drop_at_the_table_chance() {
+        assert_eq!(drops_from_a_shot_rock(1.0), 1);
+        assert_eq!(drops_from_a_shot_rock(0.0), 0);
+    }
+
+    #[test]
+    fn collecting_applies_each_kind() {
+        let mut sim = test_simulation(config(0.0), 9);
+        let ship = sim.pilots[0].ship.position;
+        let lives = sim.pilots[0].lives;
+        sim.pilots[0].secondary_cooldown = 1.0;
+        let kinds = [
+            PickupKind::RapidFire,
+            PickupKind::SpreadReset,
+            PickupKind::ExtraLife,
+            PickupKind::Shield,
+            PickupKind::ScoreMultiplier,
+        ];
+        for kind in kinds {
+            place(&mut sim, kind, ship, 5.0);
+        }
+        sim.update_pickups();
+
+        let (pilot, tuning) = (&sim.pilots[0], &sim.tuning);
+        assert!(sim.pickups.is_empty());
+        assert_eq!(pilot.rapid_fire_timer, tuning.rapid_fire_duration);
+        assert_eq!(pilot.secondary_cooldown, 0.0);
+        assert_eq!(pilot.lives, lives + 1);
+        assert_eq!(pilot.shield_charges, 1);
+        assert_eq!(pilot.score_multiplier_timer, tuning.score_multiplier_duration);
+        assert_eq!(pilot.run_stats.pickups_collected, kinds.len() as u32);
+        let collected: Vec<PickupKind> = sim
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                SimEvent::PickupCollected { seat: 0, kind } => Some(*kind),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(collected, kinds);
+
+        // Shield charges stop at the cap.
+        let cap = sim.tuning.max_shield_charges;
+        sim.pilots[0].shield_charges = cap;
+        place(&mut sim, PickupKind::Shield, ship, 5.0);
+        sim.update_pickups();
+        assert_eq!(sim.pilots[0].shield_charges, cap);
+    }
+
+    #[test]
+    fn uncollected_pickups_expire() {
+        let mut sim = test_simulation(config(0.0), 9);
+        let away = sim.pilots[0].ship.position + vec2(200.0, 0.0);
+        let ttl = sim.dt * 2.5;
+        place(&mut sim, PickupKind::ExtraLife, away, ttl);
+        sim.update_pickups();
+        sim.update_pickups();
+        assert_eq!(sim.pickups.len(), 1, "expired early");
+        sim.update_pickups();
+        assert!(sim.pickups.is_empty());
+        assert_eq!(sim.pilots[0].run_stats.pickups_collected, 0);
+    }
+}
@@ -0,0 +1,271 @@
+use macroquad::prelude::Vec2;
+
+use super::super::{
+    SENTINEL_DEPLOY_COOLDOWN, SENTINEL_ORBIT_RADIUS, SENTINEL_ORBIT_RATE, SENTINEL_SIZE,
+};
+use super::super::model::{BulletSource, Sentinel, SentinelAnchor};
+use super::super::Simulation;
+
+impl Simulation {
+    /// Each pilot has their own sentinel allowance and deploy cooldown.
+    pub(in crate::simulation) fn handle_sentinel_deploy(&mut self, seat: usize) {
+        let pilot = &self.pilots[seat];
+        let intent = pilot.intent;
+        let deployed = self.sentinels.iter().filter(|sentinel| sentinel.owner == seat).count();
+        if !self.sentinels_enabled
+            || !intent.deploy_sentinel
+            || pilot.sentinel_cooldown > 0.0
+            || deployed >= self.tuning.max_sentinels
+        {
+            return;
+        }
+
+        // Deploying under thrust leaves an escort; deploying while coasting plants a turret.
+        let anchor = if intent.thrust > 0.0 {
+            SentinelAnchor::Orbit
+        } else {
+            SentinelAnchor::Hold(pilot.ship.position)
+        };
+        let phase = pilot.ship.angle + std::f32::consts::PI;
+        self.sentinels.push(Sentinel::new(
+            seat,
+            pilot.ship.position,
+            anchor,
+            phase,
+            self.tuning.sentinel_ttl,
+            self.tuning.sentinel_energy,
+        ));
+        self.pilots[seat].sentinel_cooldown = SENTINEL_DEPLOY_COOLDOWN;
+    }
+
+    pub(in crate::simulation) fn update_sentinels(&mut self) {
+        if self.sentinels.is_empty() {
+            return;
+        }
+
+        let bullet_speed = self.tuning.bullet_speed;
+        let mut shots = Vec::new();
+        for i in 0..self.sentinels.len() {
+            let dt = self.dt;
+            let owner = self.sentinels[i].owner;
+            let ship_pos = self.pilots[owner].ship.position;
+            let sentinel = &mut self.sentinels[i];
+            sentinel.ttl -= dt;
+            sentinel.fire_timer = (sentinel.fire_timer - dt).max(0.0);
+            sentinel.position = match sentinel.anchor {
+                SentinelAnchor::Hold(position) => position,
+                SentinelAnchor::Orbit => {
+                    sentinel.orbit_phase += SENTINEL_ORBIT_RATE * dt;
+                    ship_pos + Vec2::from_angle(sentinel.orbit_phase) * SENTINEL_ORBIT_RADIUS
+                }
+            };
+
+            if sentinel.fire_timer > 0.0 || sentinel.expired() {
+                continue;
+            }
+
+            let origin = self.sentinels[i].position;
+            if let Some(aim) = self.sentinel_target(origin, owner) {
+                let direction = (aim - origin).normalize_or_zero();
+                if direction.length_squared() > 0.0 {
+                    let sentinel = &mut self.sentinels[i];
+                    sentinel.fire_timer = self.tuning.sentinel_fire_interval;
+                    sentinel.energy = sentinel.energy.saturating_sub(1);
+                    shots.push((
+                        origin + direction * SENTINEL_SIZE,
+                        direction * bullet_speed,
+                        BulletSource::Sentinel(owner),
+                    ));
+                }
+            }
+        }
+
+        for (position, velocity, source) in shots {
+            self.spawn_bullet(position, velocity, source);
+        }
+        self.sentinels.retain(|sentinel| !sentinel.expired());
+    }
+
+    /// Picks the most threatening body in range and returns a lead-corrected aim point.
+    ///
+    /// Threat favours things closing on the owner's ship over things that are merely near the
+    /// sentinel.
+    fn sentinel_target(&self, origin: Vec2, owner: usize) -> Option<Vec2> {
+        let ship_pos = self.pilots[owner].ship.position;
+        let ship_vel = self.pilots[owner].ship.velocity;
+        let range = self.tuning.sentinel_range;
+        let bullet_speed = self.tuning.bullet_speed;
+        let score = |position: Vec2, velocity: Vec2, weight: f32| -> Option<f32> {
+            if position.distance_squared(origin) > range * range {
+                return None;
+            }
+            let rel = position - ship_pos;
+            let distance = rel.length().max(40.0);
+            let closing = (-rel.dot(velocity - ship_vel) / distance).max(0.0);
+            Some(weight * (1.0 + closing / 100.0) * (200.0 / distance))
+        };
+
+        let candidates = self
+            .asteroids
+            .iter()
+            .map(|ast| (ast.position, ast.velocity, 1.0))
+            .chain(self.aliens.iter().map(|alien| (alien.position, alien.velocity, 1.5)))
+            .chain(self.drones.iter().map(|drone| (drone.position, drone.velocity, 1.3)))
+            .chain(self.carriers.iter().map(|carrier| (carrier.position, carrier.velocity, 1.5)));
+
+        let mut best: Option<(Vec2, f32)> = None;
+        for (position, velocity, weight) in candidates {
+            let Some(threat) = score(position, velocity, weight) else {
+                continue;
+            };
+            if best.is_none_or(|(_, current)| threat > current) {
+                let flight_time = position.distance(origin) / bullet_speed;
+                best = Some((position + velocity * flight_time, threat));
+            }
+        }
+
+        best.map(|(aim, _)| aim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::model::{Asteroid, AsteroidSize, Bullet, Carrier};
+    use super::super::super::test_simulation;
+    use super::*;
+    use crate::config::{AsteroidArchetype, GameConfig};
+    use crate::controllers::ControlIntent;
+    use macroquad::prelude::vec2;
+
+    fn config() -> GameConfig {
+        GameConfig {
+            sentinels_enabled: true,
+            ..GameConfig::default()
+        }
+    }
+
+    fn deploy(sim: &mut Simulation, thrust: f32) {
+        sim.pilots[0].intent = ControlIntent {
+            thrust,
+            deploy_sentinel: true,
+            ..ControlIntent::default()
+        };
+        sim.handle_sentinel_deploy(0);
+    }
+
+    fn rock(sim: &mut Simulation, archetype: AsteroidArchetype, position: Vec2, velocity: Vec2) {
+        let asteroid = Asteroid::new(AsteroidSize::Small, archetype, position, velocity, &mut sim.rng);
+        sim.asteroids.insert(asteroid);
+    }
+
+    #[test]
+    fn deploys_wait_for_the_cooldown_and_stop_at_the_cap() {
+        let mut sim = test_simulation(config(), 7);
+        assert_eq!(sim.tuning.max_sentinels, 2);
+        deploy(&mut sim, 0.0);
+        assert_eq!(sim.sentinels.len(), 1);
+        assert_eq!(sim.pilots[0].sentinel_cooldown, SENTINEL_DEPLOY_COOLDOWN);
+
+        deploy(&mut sim, 0.0);
+        assert_eq!(sim.sentinels.len(), 1, "deployed during the cooldown");
+
+        sim.pilots[0].sentinel_cooldown = 0.0;
+        deploy(&mut sim, 0.0);
+        assert_eq!(sim.sentinels.len(), 2);
+        sim.pilots[0].sentinel_cooldown = 0.0;
+        deploy(&mut sim, 0.0);
+        assert_eq!(sim.sentinels.len(), 2, "deployed past the cap");
+
+        let mut disabled = test_simulation(config(), 7);
+        disabled.sentinels_enabled = false;
+        deploy(&mut disabled, 0.0);
+        assert!(disabled.sentinels.is_empty());
+    }
+
+    #[test]
+    fn held_sentinels_stay_put_and_orbiting_ones_follow_the_ship() {
+        let mut sim = test_simulation(config(), 7);
+        let dropped_at = sim.pilots[0].ship.position;
+        deploy(&mut sim, 0.0);
+        sim.pilots[0].sentinel_cooldown = 0.0;
+        deploy(&mut sim, 1.0);
+        assert!(sim.sentinels[0].anchor == SentinelAnchor::Hold(dropped_at));
+        assert!(sim.sentinels[1].anchor == SentinelAnchor::Orbit);
+
+        let moved_to = dropped_at + vec2(200.0, -100.0);
+        sim.pilots[0].ship.position = moved_to;
+        for _ in 0..30 {
+            sim.update_sentinels();
+        }
+        assert_eq!(sim.sentinels[0].position, dropped_at);
+        let orbit = sim.sentinels[1].position.distance(moved_to);
+        assert!((orbit - SENTINEL_ORBIT_RADIUS).abs() < 1e-3, "orbit radius {orbit}");
+    }
+
+    #[test]
+    fn sentinels_expire_when_out_of_time_or_energy() {
+        let mut sim = test_simulation(config(), 7);
+        deploy(&mut sim, 0.0);
+        sim.sentinels[0].ttl = sim.dt / 2.0;
+        sim.update_sentinels();
+        assert!(sim.sentinels.is_empty(), "outlived its ttl");
+
+        sim.pilots[0].sentinel_cooldown = 0.0;
+        deploy(&mut sim, 0.0);
+        sim.sentinels[0].energy = 1;
+        let near = sim.pilots[0].ship.position + vec2(100.0, 0.0);
+        rock(&mut sim, AsteroidArchetype::Rock, near, Vec2::ZERO);
+        sim.update_sentinels();
+        assert_eq!(sim.bullets.len(), 1, "spent its last shot");
+        assert!(sim.sentinels.is_empty(), "kept flying with no energy");
+    }
+
+    #[test]
+    fn targets_favour_closing_threats_within_range() {
+        let mut sim = test_simulation(config(), 7);
+        let ship = sim.pilots[0].ship.position;
+        assert_eq!(sim.sentinel_target(ship, 0), None);
+
+        rock(&mut sim, AsteroidArchetype::Rock, ship + vec2(600.0, 0.0), Vec2::ZERO);
+        assert_eq!(sim.sentinel_target(ship, 0), None, "aimed out of range");
+
+        // Nearer but flying away, against further out but closing fast.
+        rock(&mut sim, AsteroidArchetype::Rock, ship + vec2(100.0, 0.0), vec2(50.0, 0.0));
+        let incoming = ship + vec2(-150.0, 0.0);
+        rock(&mut sim, AsteroidArchetype::Rock, incoming, vec2(200.0, 0.0));
+        let aim = sim.sentinel_target(ship, 0).expect("no target");
+        assert!(aim.x > incoming.x && aim.x < ship.x, "aimed at {aim}");
+        assert_eq!(aim.y, ship.y);
+    }
+
+    #[test]
+    fn a_lone_carrier_in_range_draws_fire() {
+        let mut sim = test_simulation(config(), 7);
+        deploy(&mut sim, 0.0);
+        let at = sim.pilots[0].ship.position + vec2(150.0, 0.0);
+        let hp = sim.tuning.carrier_hp;
+        sim.carriers.push(Carrier::new(0, at, Vec2::ZERO, hp, 60.0));
+        sim.update_sentinels();
+        let shot = sim.bullets.iter().next().expect("held fire on the carrier");
+        assert!(shot.source == BulletSource::Sentinel(0));
+        assert!(shot.velocity.x > 0.0 && shot.velocity.y.abs() < 1e-3);
+    }
+
+    #[test]
+    fn only_sentinel_kills_count_as_sentinel_kills() {
+        let mut sim = test_simulation(config(), 7);
+        let spots = [vec2(100.0, 100.0), vec2(700.0, 100.0), vec2(100.0, 500.0)];
+        rock(&mut sim, AsteroidArchetype::Rock, spots[0], Vec2::ZERO);
+        rock(&mut sim, AsteroidArchetype::Armoured, spots[1], Vec2::ZERO);
+        rock(&mut sim, AsteroidArchetype::Rock, spots[2], Vec2::ZERO);
+        let sources = [BulletSource::Sentinel(0), BulletSource::Sentinel(0), BulletSource::Player(0)];
+        for (spot, source) in spots.into_iter().zip(sources) {
+            sim.bullets.insert(Bullet::new(spot, Vec2::ZERO, source, 1.0));
+        }
+        sim.resolve_collisions();
+
+        // The armoured rock only took a dent, and the player's own kill is not the sentinel's.
+        assert_eq!(sim.asteroids.len(), 1);
+        assert_eq!(sim.pilots[0].run_stats.sentinel_kills, 1);
+    }
+}
@@ -1,58 +1,69 @@
-use macroquad::prelude::{Vec2, screen_height, screen_width, vec2};
-use macroquad::rand::gen_range;
+use crate::config::AsteroidArchetype;
+use crate::controllers::alien::controller_for;
+use macroquad::prelude::{Vec2, vec2};
 use std::f32::consts::PI;
 
-use super::super::{
-    ALIEN_SPAWN_INTERVAL, ALIEN_SPAWN_SCORE_THRESHOLD, ASTEROID_MAX_SPEED, ASTEROID_MIN_SPEED,
-    ASTEROID_SPAWN_INTERVAL, MAX_LARGE_ALIENS, MAX_SMALL_ALIENS, SHIP_SIZE,
-};
+use super::super::SHIP_SIZE;
 use super::super::model::{Alien, AlienSize, Asteroid, AsteroidSize};
-use super::super::Simulation;
+use super::super::{SimEvent, Simulation};
 
 impl Simulation {
     pub(in crate::simulation) fn tick_asteroid_spawns(&mut self) {
         self.spawn_acc += self.dt;
-        while self.spawn_acc >= ASTEROID_SPAWN_INTERVAL {
-            self.spawn_acc -= ASTEROID_SPAWN_INTERVAL;
+        let interval = self.tuning.asteroid_spawn_interval;
+        while self.spawn_acc >= interval {
+            self.spawn_acc -= interval;
             self.spawn_asteroid();
         }
     }
 
     pub(in crate::simulation) fn spawn_asteroid(&mut self) {
-        let width = screen_width();
-        let height = screen_height();
-        let side = gen_range(0, 4);
+        let (width, height) = (self.world.x, self.world.y);
+        let side = self.rng.gen_range(0, 4);
         let mut position = match side {
-            0 => vec2(gen_range(0.0, width), 0.0),
-            1 => vec2(width, gen_range(0.0, height)),
-            2 => vec2(gen_range(0.0, width), height),
-            _ => vec2(0.0, gen_range(0.0, height)),
+            0 => vec2(self.rng.gen_range(0.0, width), 0.0),
+            1 => vec2(width, self.rng.gen_range(0.0, height)),
+            2 => vec2(self.rng.gen_range(0.0, width), height),
+            _ => vec2(0.0, self.rng.gen_range(0.0, height)),
         };
 
-        // Avoid spawning too close to the ship
-        if position.distance(self.ship.position) < SHIP_SIZE * 2.0 {
-            let offset = Vec2::from_angle(gen_range(0.0, 2.0 * PI)) * (SHIP_SIZE * 3.0);
+        // Avoid spawning too close to a ship
+        let ships = self.ships_in_play();
+        if ships.iter().any(|ship| position.distance(*ship) < SHIP_SIZE * 2.0) {
+            let offset = Vec2::from_angle(self.rng.gen_range(0.0, 2.0 * PI)) * (SHIP_SIZE * 3.0);
             position += offset;
         }
 
-        let angle = gen_range(0.0, 2.0 * PI);
-        let speed = gen_range(ASTEROID_MIN_SPEED, ASTEROID_MAX_SPEED);
+        let angle = self.rng.gen_range(0.0, 2.0 * PI);
+        let speed = self.rng.gen_range(self.tuning.asteroid_min_speed, self.tuning.asteroid_max_speed);
         let velocity = Vec2::from_angle(angle) * speed;
-        self.asteroids
-            .push(Asteroid::new(AsteroidSize::Large, position, velocity));
+        let total = self.asteroid_weights.total();
+        let archetype = if total == 0 {
+            AsteroidArchetype::Rock
+        } else {
+            self.asteroid_weights.pick(self.rng.gen_range(0, total))
+        };
+        self.asteroids.insert(Asteroid::new(
+            AsteroidSize::Large,
+            archetype,
+            position,
+            velocity,
+            &mut self.rng,
+        ));
     }
 
     pub(in crate::simulation) fn tick_alien_spawns(&mut self) {
-        let width = screen_width();
+        let width = self.world.x;
         self.alien_spawn_acc += self.dt;
-        while self.alien_spawn_acc >= ALIEN_SPAWN_INTERVAL {
-            self.alien_spawn_acc -= ALIEN_SPAWN_INTERVAL;
+        let interval = self.tuning.alien_spawn_interval;
+        while self.alien_spawn_acc >= interval {
+            self.alien_spawn_acc -= interval;
             self.maybe_spawn_alien(width);
         }
     }
 
     pub(in crate::simulation) fn maybe_spawn_alien(&mut self, width: f32) {
-        if self.status.score < ALIEN_SPAWN_SCORE_THRESHOLD {
+        if self.status.score < self.tuning.alien_spawn_score_threshold {
             return;
         }
 
@@ -67,23 +78,35 @@ impl Simulation {
             .filter(|alien| matches!(alien.size, AlienSize::Large))
             .count();
 
-        if small_count >= MAX_SMALL_ALIENS && large_count >= MAX_LARGE_ALIENS {
+        let max_small = self.tuning.max_small_aliens;
+        let max_large = self.tuning.max_large_aliens;
+        if small_count >= max_small && large_count >= max_large {
             return;
         }
 
-        let spawn_small = (gen_range(0.0, 1.0) < 0.65 && small_count < MAX_SMALL_ALIENS)
-            || large_count >= MAX_LARGE_ALIENS;
+        let spawn_small = (self.rng.gen_range(0.0, 1.0) < 0.65 && small_count < max_small)
+            || large_count >= max_large;
         let size = if spawn_small {
             AlienSize::Small
-        } else if large_count < MAX_LARGE_ALIENS {
+        } else if large_count < max_large {
             AlienSize::Large
         } else {
             return;
         };
 
-        let direction = if gen_range(0.0, 1.0) < 0.5 { 1.0 } else { -1.0 };
+        let direction = if self.rng.gen_range(0.0, 1.0) < 0.5 { 1.0 } else { -1.0 };
         let start_x = if direction > 0.0 { 30.0 } else { width - 30.0 };
-        self.aliens
-            .push(Alien::new(size, direction, start_x, width));
+        let alien = Alien::new(
+            size,
+            direction,
+            start_x,
+            width,
+            controller_for(self.alien_behaviour),
+        );
+        self.events.push(SimEvent::AlienSpawned {
+            position: alien.position,
+            large: matches!(size, AlienSize::Large),
+        });
+        self.aliens.insert(alien);
     }
 }
@@ -0,0 +1,145 @@
+use macroquad::prelude::Vec2;
+
+use super::super::VERSUS_RESPAWN_POINTS;
+use super::super::model::{Ship, nearest, ring_point};
+use super::super::Simulation;
+
+impl Simulation {
+    /// Runs the match clock and brings fragged ships back once their delay is up.
+    pub(in crate::simulation) fn update_versus(&mut self) {
+        let Some(versus) = &mut self.versus else {
+            return;
+        };
+        versus.remaining = (versus.remaining - self.dt).max(0.0);
+
+        for seat in 0..self.pilots.len() {
+            let Some(timer) = &mut self.pilots[seat].respawn_timer else {
+                continue;
+            };
+            *timer -= self.dt;
+            if *timer <= 0.0 {
+                self.pilots[seat].respawn_timer = None;
+                self.respawn_away_from_rivals(seat);
+            }
+        }
+    }
+
+    /// Puts the ship back on the spawn ring at the point farthest from any rival still flying.
+    fn respawn_away_from_rivals(&mut self, seat: usize) {
+        let rivals: Vec<Vec2> = self
+            .pilots
+            .iter()
+            .enumerate()
+            .filter(|&(other, pilot)| other != seat && pilot.in_play())
+            .map(|(_, pilot)| pilot.ship.position)
+            .collect();
+        let clearance =
+            |point: Vec2| nearest(&rivals, point).map_or(f32::MAX, |rival| rival.distance(point));
+        let spawn = (0..VERSUS_RESPAWN_POINTS)
+            .map(|index| ring_point(index, VERSUS_RESPAWN_POINTS, self.world))
+            .max_by(|a, b| clearance(*a).total_cmp(&clearance(*b)))
+            .unwrap_or_else(|| ring_point(seat, self.pilots.len(), self.world));
+
+        let pilot = &mut self.pilots[seat];
+        pilot.ship = Ship::at(spawn);
+        pilot.last_seen = spawn;
+        pilot.invulnerability_timer = self.tuning.invulnerability_duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::model::{Asteroid, AsteroidSize, Bullet, BulletSource};
+    use super::super::super::test_simulation;
+    use super::*;
+    use crate::config::{AsteroidArchetype, GameConfig, VersusSettings};
+
+    fn versus(match_seconds: u32, respawn_seconds: f32) -> Simulation {
+        let config = GameConfig {
+            versus: VersusSettings {
+                enabled: true,
+                match_seconds,
+                respawn_seconds,
+                ..VersusSettings::off()
+            },
+            ..GameConfig::default()
+        };
+        let mut sim = test_simulation(config, 9);
+        for pilot in &mut sim.pilots {
+            pilot.invulnerability_timer = 0.0;
+        }
+        sim
+    }
+
+    fn shot_at(sim: &mut Simulation, seat: usize, source: BulletSource) {
+        let at = sim.pilots[seat].ship.position;
+        sim.bullets.insert(Bullet::new(at, Vec2::ZERO, source, 1.0));
+        sim.resolve_collisions();
+    }
+
+    #[test]
+    fn rival_shots_credit_the_shooter() {
+        let mut sim = versus(180, 2.0);
+        shot_at(&mut sim, 0, BulletSource::Player(1));
+        assert_eq!((sim.pilots[0].frags, sim.pilots[0].deaths), (0, 1));
+        assert_eq!((sim.pilots[1].frags, sim.pilots[1].deaths), (1, 0));
+        assert!(!sim.pilots[0].in_play());
+
+        // Rocks take ships without crediting anyone.
+        let at = sim.pilots[1].ship.position;
+        let (size, archetype) = (AsteroidSize::Large, AsteroidArchetype::Rock);
+        let asteroid = Asteroid::new(size, archetype, at, Vec2::ZERO, &mut sim.rng);
+        sim.asteroids.insert(asteroid);
+        sim.resolve_collisions();
+        assert_eq!(sim.pilots[1].deaths, 1);
+        assert_eq!(sim.pilots.iter().map(|pilot| pilot.frags).sum::<u32>(), 1);
+    }
+
+    #[test]
+    fn own_shots_earn_no_frags() {
+        let mut sim = versus(180, 2.0);
+        shot_at(&mut sim, 0, BulletSource::Player(0));
+        assert_eq!(sim.pilots[0].frags, 0);
+    }
+
+    #[test]
+    fn sentinel_shots_pass_through_rivals() {
+        let mut sim = versus(180, 2.0);
+        shot_at(&mut sim, 0, BulletSource::Sentinel(1));
+        assert!(sim.pilots[0].in_play());
+        assert_eq!((sim.pilots[0].deaths, sim.pilots[1].frags), (0, 0));
+        assert_eq!(sim.bullets.len(), 1, "the rival's ship stopped the shot");
+    }
+
+    #[test]
+    fn fragged_ships_respawn_after_the_delay() {
+        let mut sim = versus(180, 0.5);
+        shot_at(&mut sim, 0, BulletSource::Player(1));
+        assert_eq!(sim.pilots[0].respawn_timer, Some(0.5));
+
+        for _ in 0..29 {
+            sim.update_versus();
+        }
+        assert!(!sim.pilots[0].in_play(), "back before the respawn delay");
+        for _ in 0..2 {
+            sim.update_versus();
+        }
+        assert!(sim.pilots[0].in_play());
+        assert_eq!(sim.pilots[0].invulnerability_timer, sim.tuning.invulnerability_duration);
+    }
+
+    #[test]
+    fn matches_end_when_the_clock_runs_out() {
+        let mut sim = versus(1, 2.0);
+        for _ in 0..59 {
+            sim.step();
+        }
+        assert!(!sim.status().game_over, "match ended early");
+        for _ in 0..2 {
+            sim.step();
+        }
+        let status = sim.status();
+        assert!(status.game_over);
+        assert_eq!(status.match_remaining, Some(0.0));
+    }
+}
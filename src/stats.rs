@@ -7,6 +7,11 @@ pub struct RunStats {
     pub hits_small_asteroid: u32,
     pub hits_large_alien: u32,
     pub hits_small_alien: u32,
+    pub hits_carrier: u32,
+    pub hits_drone: u32,
+    /// Kills scored by deployed sentinels; kept apart from the player's own hit counts.
+    pub sentinel_kills: u32,
+    pub pickups_collected: u32,
 }
 
 impl RunStats {
@@ -18,19 +23,40 @@ impl RunStats {
         }
     }
 
+    /// Adds another pilot's counters into these, for a co-op team total.
+    pub fn merge(&mut self, other: &RunStats) {
+        self.shots_fired = self.shots_fired.saturating_add(other.shots_fired);
+        self.shots_hit = self.shots_hit.saturating_add(other.shots_hit);
+        self.hits_large_asteroid = self.hits_large_asteroid.saturating_add(other.hits_large_asteroid);
+        self.hits_medium_asteroid = self.hits_medium_asteroid.saturating_add(other.hits_medium_asteroid);
+        self.hits_small_asteroid = self.hits_small_asteroid.saturating_add(other.hits_small_asteroid);
+        self.hits_large_alien = self.hits_large_alien.saturating_add(other.hits_large_alien);
+        self.hits_small_alien = self.hits_small_alien.saturating_add(other.hits_small_alien);
+        self.hits_carrier = self.hits_carrier.saturating_add(other.hits_carrier);
+        self.hits_drone = self.hits_drone.saturating_add(other.hits_drone);
+        self.sentinel_kills = self.sentinel_kills.saturating_add(other.sentinel_kills);
+        self.pickups_collected = self.pickups_collected.saturating_add(other.pickups_collected);
+    }
+
     pub fn serialize(&self) -> String {
         format!(
-            "{},{},{},{},{},{},{}",
+            "{},{},{},{},{},{},{},{},{},{},{}",
             self.shots_fired,
             self.shots_hit,
             self.hits_large_asteroid,
             self.hits_medium_asteroid,
             self.hits_small_asteroid,
             self.hits_large_alien,
-            self.hits_small_alien
+            self.hits_small_alien,
+            self.hits_carrier,
+            self.hits_drone,
+            self.sentinel_kills,
+            self.pickups_collected
         )
     }
 
+    /// Parses a serialized record. Fields appended after the original seven are optional so
+    /// leaderboards written by older builds still load.
     pub fn parse(text: &str) -> Option<Self> {
         let mut parts = text.split(',');
         Some(Self {
@@ -41,6 +67,14 @@ impl RunStats {
             hits_small_asteroid: parts.next()?.parse().ok()?,
             hits_large_alien: parts.next()?.parse().ok()?,
             hits_small_alien: parts.next()?.parse().ok()?,
+            hits_carrier: optional_field(&mut parts),
+            hits_drone: optional_field(&mut parts),
+            sentinel_kills: optional_field(&mut parts),
+            pickups_collected: optional_field(&mut parts),
         })
     }
 }
+
+fn optional_field<'a>(parts: &mut impl Iterator<Item = &'a str>) -> u32 {
+    parts.next().and_then(|part| part.parse().ok()).unwrap_or(0)
+}
@@ -0,0 +1,656 @@
+mod json;
+mod report;
+
+pub use self::report::Report;
+
+use crate::simulation::{SimEvent, SimulationStatus};
+use crate::stats::RunStats;
+use self::json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The live log's name; rotated files are `runs.1.jsonl` (newest) to `runs.4.jsonl` (oldest).
+const LOG_NAME: &str = "runs";
+const KEPT_FILES: usize = 5;
+/// A log grows to about this many bytes before it is rotated.
+const ROTATE_BYTES: u64 = 1 << 20;
+
+/// How much `--telemetry` writes: a record per finished run, or those plus one sample a second
+/// of game time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Detail {
+    Runs,
+    Samples,
+}
+
+impl Detail {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "runs" => Some(Detail::Runs),
+            "samples" => Some(Detail::Samples),
+            _ => None,
+        }
+    }
+}
+
+/// A ship lost: which seat, how far into the run in seconds of game time, and what hit it, as
+/// `HitCause::label` spells it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Death {
+    pub seat: usize,
+    pub time: f32,
+    pub cause: String,
+}
+
+/// One finished run. Hot-seat players each get their own, sharing the `run` ID.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunRecord {
+    pub run: u64,
+    /// The hot-seat player, from 0; always 0 otherwise.
+    pub player: usize,
+    /// `None` for a continued run, whose seed was drawn before it was saved.
+    pub seed: Option<u64>,
+    pub preset: String,
+    pub controller: String,
+    pub score: u32,
+    pub survival_seconds: f32,
+    pub frames: u64,
+    pub assisted: bool,
+    pub stats: RunStats,
+    pub deaths: Vec<Death>,
+}
+
+/// The field once a second of game time, with `--telemetry samples`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub run: u64,
+    pub player: usize,
+    pub time: f32,
+    pub bodies: usize,
+    pub asteroids: usize,
+    pub bullets: usize,
+    pub enemies: usize,
+    pub fps: f32,
+    /// What player one's controller was doing, if it says; see `Controller::phase`.
+    pub ai_phase: Option<String>,
+}
+
+/// A line of the log.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Record {
+    Run(RunRecord),
+    Sample(Sample),
+}
+
+impl Record {
+    /// The record as one line of JSON, without the newline.
+    pub fn to_json(&self) -> String {
+        match self {
+            Record::Run(run) => run_value(run),
+            Record::Sample(sample) => sample_value(sample),
+        }
+        .to_string()
+    }
+
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let value = Value::parse(line)?;
+        match value.get("type").and_then(Value::as_str) {
+            Some("run") => parse_run(&value).map(Record::Run),
+            Some("sample") => parse_sample(&value).map(Record::Sample),
+            Some(other) => Err(format!("unknown record type `{other}`")),
+            None => Err("record has no type".to_string()),
+        }
+    }
+}
+
+/// Appends records to `runs.jsonl` in a directory, rotating it once it grows past a size.
+pub struct TelemetryLog {
+    dir: PathBuf,
+    detail: Detail,
+    rotate_bytes: u64,
+}
+
+impl TelemetryLog {
+    pub fn new(dir: PathBuf, detail: Detail) -> Self {
+        Self {
+            dir,
+            detail,
+            rotate_bytes: ROTATE_BYTES,
+        }
+    }
+
+    /// The log in the per-user data directory.
+    pub fn in_data_dir(detail: Detail) -> Self {
+        Self::new(data_dir(), detail)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn detail(&self) -> Detail {
+        self.detail
+    }
+
+    pub fn append(&self, record: &Record) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let line = record.to_json() + "\n";
+        let live = log_path(&self.dir, 0);
+        let size = fs::metadata(&live).map_or(0, |meta| meta.len());
+        if size > 0 && size + line.len() as u64 > self.rotate_bytes {
+            self.rotate()?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&live)?
+            .write_all(line.as_bytes())
+    }
+
+    /// Shifts each file one place older, dropping the oldest.
+    fn rotate(&self) -> io::Result<()> {
+        for index in (1..KEPT_FILES).rev() {
+            let from = log_path(&self.dir, index - 1);
+            if from.exists() {
+                fs::rename(from, log_path(&self.dir, index))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An ID for a new run: the wall clock in nanoseconds, so runs logged by separate sessions
+/// stay apart.
+pub fn run_id() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// Where `--telemetry` keeps its logs.
+pub fn data_dir() -> PathBuf {
+    crate::config::data_file("telemetry")
+}
+
+fn log_path(dir: &Path, index: usize) -> PathBuf {
+    match index {
+        0 => dir.join(format!("{LOG_NAME}.jsonl")),
+        _ => dir.join(format!("{LOG_NAME}.{index}.jsonl")),
+    }
+}
+
+/// Every record in a log directory, oldest first. A missing directory reads as empty; a line
+/// that does not parse is an error naming the file and line.
+pub fn read_dir(dir: &Path) -> Result<Vec<Record>, String> {
+    let mut records = Vec::new();
+    for index in (0..KEPT_FILES).rev() {
+        let path = log_path(dir, index);
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(format!("{}: {err}", path.display())),
+        };
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| format!("{}: {err}", path.display()))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record = Record::parse(&line)
+                .map_err(|err| format!("{}:{}: {err}", path.display(), number + 1))?;
+            records.push(record);
+        }
+    }
+    Ok(records)
+}
+
+/// What the log needs from a run while it is played: its deaths, and when the next sample is
+/// due.
+pub struct RunTrace {
+    pub run: u64,
+    pub player: usize,
+    seed: Option<u64>,
+    controller: String,
+    deaths: Vec<Death>,
+    next_sample: f32,
+}
+
+impl RunTrace {
+    pub fn new(run: u64, player: usize, seed: Option<u64>) -> Self {
+        Self {
+            run,
+            player,
+            seed,
+            controller: String::new(),
+            deaths: Vec::new(),
+            next_sample: 0.0,
+        }
+    }
+
+    /// Notes a ship lost at `time` seconds into the run; a shield soaking the hit is not one.
+    pub fn observe(&mut self, event: &SimEvent, time: f32) {
+        if let SimEvent::ShipHit {
+            seat,
+            shielded: false,
+            cause,
+            ..
+        } = event
+        {
+            self.deaths.push(Death {
+                seat: *seat,
+                time,
+                cause: cause.label().to_string(),
+            });
+        }
+    }
+
+    /// Whether a sample is due at `time`; if so the next one is a second later.
+    pub fn sample_due(&mut self, time: f32) -> bool {
+        if time < self.next_sample {
+            return false;
+        }
+        self.next_sample = time.floor() + 1.0;
+        true
+    }
+
+    /// Records who flies the ship. Before the run's first frame that replaces the last choice;
+    /// after it, a different controller is added, e.g. `human+ai-balanced`.
+    pub fn flown_by(&mut self, name: &str, started: bool) {
+        if !started || self.controller.is_empty() {
+            self.controller = name.to_string();
+        } else if !self.controller.split('+').any(|flown| flown == name) {
+            self.controller = format!("{}+{name}", self.controller);
+        }
+    }
+
+    /// Forgets deaths after `time`, when play resumes from a rewound point. Samples already
+    /// written stay in the log.
+    pub fn rewind_to(&mut self, time: f32) {
+        self.deaths.retain(|death| death.time <= time);
+    }
+
+    /// The finished record, from the run's final status.
+    pub fn finish(&self, preset: &str, status: &SimulationStatus) -> RunRecord {
+        RunRecord {
+            run: self.run,
+            player: self.player,
+            seed: self.seed,
+            preset: preset.to_string(),
+            controller: self.controller.clone(),
+            score: status.score,
+            survival_seconds: status.frame as f32 * status.frame_time,
+            frames: status.frame,
+            assisted: status.assisted,
+            stats: status.run_stats.clone(),
+            deaths: self.deaths.clone(),
+        }
+    }
+}
+
+fn stats_fields(stats: &RunStats) -> [(&'static str, u32); 11] {
+    [
+        ("shots_fired", stats.shots_fired),
+        ("shots_hit", stats.shots_hit),
+        ("hits_large_asteroid", stats.hits_large_asteroid),
+        ("hits_medium_asteroid", stats.hits_medium_asteroid),
+        ("hits_small_asteroid", stats.hits_small_asteroid),
+        ("hits_large_alien", stats.hits_large_alien),
+        ("hits_small_alien", stats.hits_small_alien),
+        ("hits_carrier", stats.hits_carrier),
+        ("hits_drone", stats.hits_drone),
+        ("sentinel_kills", stats.sentinel_kills),
+        ("pickups_collected", stats.pickups_collected),
+    ]
+}
+
+fn stat_mut<'a>(stats: &'a mut RunStats, name: &str) -> Option<&'a mut u32> {
+    Some(match name {
+        "shots_fired" => &mut stats.shots_fired,
+        "shots_hit" => &mut stats.shots_hit,
+        "hits_large_asteroid" => &mut stats.hits_large_asteroid,
+        "hits_medium_asteroid" => &mut stats.hits_medium_asteroid,
+        "hits_small_asteroid" => &mut stats.hits_small_asteroid,
+        "hits_large_alien" => &mut stats.hits_large_alien,
+        "hits_small_alien" => &mut stats.hits_small_alien,
+        "hits_carrier" => &mut stats.hits_carrier,
+        "hits_drone" => &mut stats.hits_drone,
+        "sentinel_kills" => &mut stats.sentinel_kills,
+        "pickups_collected" => &mut stats.pickups_collected,
+        _ => return None,
+    })
+}
+
+fn field(name: &str, value: Value) -> (String, Value) {
+    (name.to_string(), value)
+}
+
+fn run_value(run: &RunRecord) -> Value {
+    let stats = stats_fields(&run.stats)
+        .into_iter()
+        .map(|(name, count)| field(name, Value::int(count)))
+        .collect();
+    let deaths = run
+        .deaths
+        .iter()
+        .map(|death| {
+            Value::Object(vec![
+                field("seat", Value::int(death.seat as u64)),
+                field("time", Value::float(death.time)),
+                field("cause", Value::text(&death.cause)),
+            ])
+        })
+        .collect();
+    Value::Object(vec![
+        field("type", Value::text("run")),
+        field("run", Value::int(run.run)),
+        field("player", Value::int(run.player as u64)),
+        field("seed", run.seed.map_or(Value::Null, Value::int)),
+        field("preset", Value::text(&run.preset)),
+        field("controller", Value::text(&run.controller)),
+        field("score", Value::int(run.score)),
+        field("survival_seconds", Value::float(run.survival_seconds)),
+        field("frames", Value::int(run.frames)),
+        field("assisted", Value::Bool(run.assisted)),
+        field("stats", Value::Object(stats)),
+        field("deaths", Value::Array(deaths)),
+    ])
+}
+
+fn sample_value(sample: &Sample) -> Value {
+    Value::Object(vec![
+        field("type", Value::text("sample")),
+        field("run", Value::int(sample.run)),
+        field("player", Value::int(sample.player as u64)),
+        field("time", Value::float(sample.time)),
+        field("bodies", Value::int(sample.bodies as u64)),
+        field("asteroids", Value::int(sample.asteroids as u64)),
+        field("bullets", Value::int(sample.bullets as u64)),
+        field("enemies", Value::int(sample.enemies as u64)),
+        field("fps", Value::float(sample.fps)),
+        field("ai_phase", sample.ai_phase.as_deref().map_or(Value::Null, Value::text)),
+    ])
+}
+
+fn required<'a>(value: &'a Value, name: &str) -> Result<&'a Value, String> {
+    value.get(name).ok_or_else(|| format!("missing `{name}`"))
+}
+
+fn count(value: &Value, name: &str) -> Result<u64, String> {
+    required(value, name)?
+        .as_u64()
+        .ok_or_else(|| format!("`{name}` is not a whole number"))
+}
+
+/// A float field; `null` stands for a value that was not finite.
+fn float(value: &Value, name: &str) -> Result<f32, String> {
+    match required(value, name)? {
+        Value::Null => Ok(f32::NAN),
+        other => other.as_f32().ok_or_else(|| format!("`{name}` is not a number")),
+    }
+}
+
+fn text(value: &Value, name: &str) -> Result<String, String> {
+    required(value, name)?
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| format!("`{name}` is not a string"))
+}
+
+fn parse_run(value: &Value) -> Result<RunRecord, String> {
+    let mut stats = RunStats::default();
+    if let Value::Object(fields) = required(value, "stats")? {
+        for (name, count) in fields {
+            // Counters a later build adds are skipped rather than refused.
+            if let Some(stat) = stat_mut(&mut stats, name) {
+                *stat = count
+                    .as_u64()
+                    .ok_or_else(|| format!("`stats.{name}` is not a whole number"))?
+                    as u32;
+            }
+        }
+    } else {
+        return Err("`stats` is not an object".to_string());
+    }
+    let deaths = required(value, "deaths")?
+        .as_array()
+        .ok_or_else(|| "`deaths` is not an array".to_string())?
+        .iter()
+        .map(|death| {
+            Ok(Death {
+                seat: count(death, "seat")? as usize,
+                time: float(death, "time")?,
+                cause: text(death, "cause")?,
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    let seed = match required(value, "seed")? {
+        Value::Null => None,
+        seed => Some(seed.as_u64().ok_or("`seed` is not a whole number")?),
+    };
+    Ok(RunRecord {
+        run: count(value, "run")?,
+        player: count(value, "player")? as usize,
+        seed,
+        preset: text(value, "preset")?,
+        controller: text(value, "controller")?,
+        score: count(value, "score")? as u32,
+        survival_seconds: float(value, "survival_seconds")?,
+        frames: count(value, "frames")?,
+        assisted: required(value, "assisted")?
+            .as_bool()
+            .ok_or("`assisted` is not true or false")?,
+        stats,
+        deaths,
+    })
+}
+
+fn parse_sample(value: &Value) -> Result<Sample, String> {
+    let ai_phase = match required(value, "ai_phase")? {
+        Value::Null => None,
+        _ => Some(text(value, "ai_phase")?),
+    };
+    Ok(Sample {
+        run: count(value, "run")?,
+        player: count(value, "player")? as usize,
+        time: float(value, "time")?,
+        bodies: count(value, "bodies")? as usize,
+        asteroids: count(value, "asteroids")? as usize,
+        bullets: count(value, "bullets")? as usize,
+        enemies: count(value, "enemies")? as usize,
+        fps: float(value, "fps")?,
+        ai_phase,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::HitCause;
+    use macroquad::prelude::vec2;
+
+    fn run_record(run: u64) -> RunRecord {
+        RunRecord {
+            run,
+            player: 1,
+            seed: Some(u64::MAX),
+            preset: "Arcade \"Upgrades\"".to_string(),
+            controller: "ai-veteran".to_string(),
+            score: 12_340,
+            survival_seconds: 93.5,
+            frames: 5610,
+            assisted: true,
+            stats: RunStats {
+                shots_fired: 200,
+                shots_hit: 57,
+                hits_carrier: 2,
+                pickups_collected: 3,
+                ..RunStats::default()
+            },
+            deaths: vec![Death {
+                seat: 0,
+                time: 41.25,
+                cause: "saucer-shot".to_string(),
+            }],
+        }
+    }
+
+    fn sample(run: u64, time: f32) -> Sample {
+        Sample {
+            run,
+            player: 0,
+            time,
+            bodies: 40,
+            asteroids: 22,
+            bullets: 12,
+            enemies: 1,
+            fps: 59.8,
+            ai_phase: Some("evade".to_string()),
+        }
+    }
+
+    #[test]
+    fn records_round_trip_through_json() {
+        let records = [
+            Record::Run(run_record(7)),
+            Record::Run(RunRecord {
+                seed: None,
+                deaths: Vec::new(),
+                ..run_record(8)
+            }),
+            Record::Sample(sample(7, 3.0)),
+            Record::Sample(Sample {
+                ai_phase: None,
+                ..sample(7, 4.0)
+            }),
+        ];
+        for record in records {
+            let line = record.to_json();
+            assert!(!line.contains('\n'));
+            assert_eq!(Record::parse(&line), Ok(record));
+        }
+    }
+
+    #[test]
+    fn unknown_and_malformed_lines_are_refused() {
+        assert!(Record::parse(r#"{"type":"wave","run":1}"#).is_err());
+        assert!(Record::parse(r#"{"type":"sample","run":1}"#).is_err());
+        assert!(Record::parse(r#"{"type":"run""#).is_err());
+        assert!(Record::parse("[1, 2] 3").is_err());
+    }
+
+    #[test]
+    fn strings_with_escapes_parse() {
+        let value = Value::parse(r#"{"name": "tab\there \"q\" é 🚀"}"#).unwrap();
+        assert_eq!(value.get("name").and_then(Value::as_str), Some("tab\there \"q\" é 🚀"));
+    }
+
+    #[test]
+    fn trace_counts_unshielded_hits_as_deaths() {
+        let mut trace = RunTrace::new(1, 0, Some(3));
+        let hit = |shielded, cause| SimEvent::ShipHit {
+            seat: 1,
+            position: vec2(0.0, 0.0),
+            shielded,
+            cause,
+        };
+        trace.observe(&hit(true, HitCause::Asteroid), 2.0);
+        trace.observe(&hit(false, HitCause::GravityWell), 5.5);
+        trace.observe(&SimEvent::ExtraLife { seat: 0 }, 6.0);
+        assert_eq!(
+            trace.deaths,
+            [Death {
+                seat: 1,
+                time: 5.5,
+                cause: "gravity-well".to_string(),
+            }]
+        );
+
+        assert!(trace.sample_due(0.0));
+        assert!(!trace.sample_due(0.5));
+        assert!(trace.sample_due(1.02));
+        assert!(!trace.sample_due(1.9));
+
+        trace.rewind_to(5.0);
+        assert!(trace.deaths.is_empty());
+    }
+
+    #[test]
+    fn trace_names_every_controller_that_flew() {
+        let mut trace = RunTrace::new(1, 0, None);
+        trace.flown_by("human", false);
+        trace.flown_by("ai-casual", false);
+        trace.flown_by("ai-casual", true);
+        trace.flown_by("human", true);
+        trace.flown_by("ai-casual", true);
+        assert_eq!(trace.controller, "ai-casual+human");
+    }
+
+    #[test]
+    fn log_rotates_and_reads_back_oldest_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = TelemetryLog::new(dir.path().join("telemetry"), Detail::Runs);
+        assert_eq!(read_dir(log.dir()), Ok(Vec::new()));
+
+        // Small enough that every run record starts a new file.
+        log.rotate_bytes = 100;
+        for run in 0..8 {
+            log.append(&Record::Run(run_record(run))).unwrap();
+        }
+        for index in 0..KEPT_FILES {
+            assert!(log_path(log.dir(), index).exists());
+        }
+        assert!(!log_path(log.dir(), KEPT_FILES).exists());
+
+        let runs: Vec<u64> = read_dir(log.dir())
+            .unwrap()
+            .into_iter()
+            .map(|record| match record {
+                Record::Run(run) => run.run,
+                Record::Sample(sample) => sample.run,
+            })
+            .collect();
+        assert_eq!(runs, [3, 4, 5, 6, 7]);
+    }
+
+    #[test]
+    fn report_adds_up_runs_and_causes() {
+        let mut second = run_record(2);
+        second.score = 660;
+        second.assisted = false;
+        second.deaths = ["asteroid", "saucer-shot", "asteroid"]
+            .into_iter()
+            .map(|cause| Death {
+                seat: 0,
+                time: 10.0,
+                cause: cause.to_string(),
+            })
+            .collect();
+        let records = vec![
+            Record::Run(run_record(1)),
+            Record::Sample(sample(1, 0.0)),
+            Record::Run(second),
+        ];
+        let report = Report::new(records).to_string();
+        assert!(report.starts_with("2 runs (1 assisted), 1 samples\n"), "{report}");
+        assert!(report.contains("score 6500 average, 12340 best"), "{report}");
+        assert!(report.contains("4 ships lost\n  asteroid"), "{report}");
+        assert!(report.contains("saucer-shot        2   50.0%"), "{report}");
+        assert_eq!(Report::new(Vec::new()).to_string(), "no runs logged (0 samples)\n");
+    }
+
+    #[test]
+    fn reading_names_the_broken_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = TelemetryLog::new(dir.path().to_path_buf(), Detail::Samples);
+        log.append(&Record::Sample(sample(1, 0.0))).unwrap();
+        let live = log_path(dir.path(), 0);
+        let mut text = fs::read_to_string(&live).unwrap();
+        text.push_str("{not json}\n");
+        fs::write(&live, text).unwrap();
+
+        let err = read_dir(dir.path()).unwrap_err();
+        assert!(err.contains("runs.jsonl:2"), "{err}");
+    }
+}
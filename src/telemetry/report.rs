@@ -0,0 +1,71 @@
+use super::{Record, RunRecord};
+use std::fmt;
+
+/// What a telemetry log adds up to: how the logged runs scored and lasted, and what ended
+/// the ships in them.
+pub struct Report {
+    runs: Vec<RunRecord>,
+    samples: usize,
+    /// Each cause of death with how often it struck, most frequent first.
+    causes: Vec<(String, usize)>,
+}
+
+impl Report {
+    pub fn new(records: Vec<Record>) -> Self {
+        let mut runs = Vec::new();
+        let mut samples = 0;
+        for record in records {
+            match record {
+                Record::Run(run) => runs.push(run),
+                Record::Sample(_) => samples += 1,
+            }
+        }
+        let mut causes: Vec<(String, usize)> = Vec::new();
+        for death in runs.iter().flat_map(|run| &run.deaths) {
+            match causes.iter_mut().find(|(cause, _)| *cause == death.cause) {
+                Some((_, count)) => *count += 1,
+                None => causes.push((death.cause.clone(), 1)),
+            }
+        }
+        causes.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Self {
+            runs,
+            samples,
+            causes,
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.runs.is_empty() {
+            return writeln!(f, "no runs logged ({} samples)", self.samples);
+        }
+        let count = self.runs.len() as f32;
+        let mean_score = self.runs.iter().map(|run| run.score as f32).sum::<f32>() / count;
+        let best = self.runs.iter().map(|run| run.score).max().unwrap_or_default();
+        let survival = self.runs.iter().map(|run| run.survival_seconds).sum::<f32>() / count;
+        let assisted = self.runs.iter().filter(|run| run.assisted).count();
+        writeln!(
+            f,
+            "{} {} ({assisted} assisted), {} samples",
+            self.runs.len(),
+            if self.runs.len() == 1 { "run" } else { "runs" },
+            self.samples
+        )?;
+        writeln!(
+            f,
+            "score {mean_score:.0} average, {best} best; {survival:.1} s survived on average"
+        )?;
+        let deaths: usize = self.causes.iter().map(|(_, count)| count).sum();
+        writeln!(f, "{deaths} ships lost")?;
+        for (cause, lost) in &self.causes {
+            writeln!(
+                f,
+                "  {cause:<13} {lost:>6}  {:>5.1}%",
+                *lost as f32 * 100.0 / deaths as f32
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -1,20 +1,37 @@
 use crate::config::GameConfig;
+use crate::eval::{Profiler, Summary};
+use crate::netplay::NetStatus;
 use crate::render::shapes::{draw_shape, ship_lines};
-use crate::simulation::SimulationStatus;
-use macroquad::prelude::{Color, Vec2, WHITE, draw_rectangle, draw_text, screen_height, screen_width};
+use crate::simulation::{SimulationStatus, Stage, seat_color};
+use crate::ui::widgets::format_clock;
+use macroquad::prelude::{
+    Color, Vec2, WHITE, draw_line, draw_rectangle, draw_text, screen_height, screen_width,
+};
 use macroquad::text::measure_text;
 use std::f32::consts::PI;
 
 const OVERLAY_WIDTH: f32 = 240.0;
 const OVERLAY_MARGIN: f32 = 16.0;
+const REWIND_BAR_HEIGHT: f32 = 6.0;
+const PROFILER_WIDTH: f32 = 260.0;
+const PROFILER_TOP: f32 = 60.0;
+const PROFILER_GRAPH_HEIGHT: f32 = 48.0;
+/// Frame length at the top of the graph, in milliseconds: two 60 Hz frames.
+const PROFILER_GRAPH_MS: f32 = 33.3;
 const LIFE_ICON_SCALE: f32 = 10.0;
 const LIFE_ICON_SPACING: f32 = 28.0;
 const LIFE_ICON_MARGIN: f32 = 20.0;
 const LIFE_ICON_Y: f32 = 32.0;
 const LIFE_ICON_STROKE: f32 = 1.6;
 const SCORE_FONT_SIZE: f32 = 32.0;
+const PICKUP_FONT_SIZE: f32 = 20.0;
 
-pub fn draw_stats_overlay(config: &GameConfig, status: &SimulationStatus) {
+pub fn draw_stats_overlay(
+    config: &GameConfig,
+    status: &SimulationStatus,
+    time_scale: f32,
+    bullet_time: bool,
+) {
     use crate::config::{
         CollisionPolicy, FragmentationMode, LeaderboardMode, PhysicsMode, PlayerControllerMode,
     };
@@ -66,9 +83,16 @@ pub fn draw_stats_overlay(config: &GameConfig, status: &SimulationStatus) {
     let mut lines = vec![
         format!("FPS: {:.1}", status.fps),
         format!("Frame: {}", status.frame),
+        format!(
+            "Speed: {time_scale}x{}",
+            if bullet_time { " (bullet time)" } else { "" }
+        ),
         format!("Score: {}", status.score),
         format!("Asteroids: {}", status.asteroid_count),
         format!("Bullets: {}", status.bullet_count),
+        format!("Enemies: {}", status.enemy_count),
+        format!("Sentinels: {}", status.sentinel_count),
+        format!("Pickups: {}", status.pickup_count),
         format!("Bodies: {}", status.active_bodies),
         format!("Primary CD: {:.2}s", status.primary_cooldown),
         format!("Secondary CD: {:.2}s", status.secondary_cooldown),
@@ -89,12 +113,13 @@ pub fn draw_stats_overlay(config: &GameConfig, status: &SimulationStatus) {
         upgrade_label(config.upgrades_enabled)
     ));
     lines.push(format!(
-        "Budgets: max={} frag={} ttl={}ms radius={:.1} v_max={}",
+        "Budgets: max={} frag={} ttl={}ms radius={:.1} v_max={} enemies={}",
         config.budgets.max_bodies,
         config.budgets.frag_event_cap,
         config.budgets.debris_ttl_ms,
         config.budgets.big_collision_radius,
         config.budgets.v_max,
+        config.budgets.enemy_cap,
     ));
     lines.push(format!("Shots Fired: {}", status.run_stats.shots_fired));
     lines.push(format!("Shots Hit: {}", status.run_stats.shots_hit));
@@ -109,10 +134,19 @@ pub fn draw_stats_overlay(config: &GameConfig, status: &SimulationStatus) {
         "Hits (Aliens L/S): {}/{}",
         status.run_stats.hits_large_alien, status.run_stats.hits_small_alien,
     ));
+    lines.push(format!(
+        "Hits (Carrier/Drone): {}/{}",
+        status.run_stats.hits_carrier, status.run_stats.hits_drone,
+    ));
+    lines.push(format!("Sentinel Kills: {}", status.run_stats.sentinel_kills));
+    lines.push(format!("Pickups Collected: {}", status.run_stats.pickups_collected));
     lines.push(format!(
         "Invuln: {}",
         if status.invulnerability_enabled { "On" } else { "Off" }
     ));
+    if status.assisted {
+        lines.push("Assisted: slowed down (flagged on the leaderboard)".to_string());
+    }
 
     let height = 18.0 * (lines.len() as f32) + 16.0;
     let x = OVERLAY_MARGIN;
@@ -126,12 +160,140 @@ pub fn draw_stats_overlay(config: &GameConfig, status: &SimulationStatus) {
     }
 }
 
+/// Where the time goes, top right under the score: the frame, drawing and the step, then each
+/// stage of the step, as averages and p99 over the profiler's window, with a graph of recent
+/// frame lengths against the 60 Hz budget.
+pub fn draw_profiler(profiler: &Profiler) {
+    fn line(label: &str, summary: Summary) -> String {
+        format!("{label:<12} {:>6.2} ms  p99 {:>6.2}", summary.average, summary.p99)
+    }
+
+    let pairs = profiler.collision_pairs();
+    let mut lines = vec![
+        line("frame", profiler.frame()),
+        line("draw", profiler.draw()),
+        line("step", profiler.step()),
+    ];
+    lines.extend(Stage::ALL.iter().map(|stage| line(stage.label(), profiler.stage(*stage))));
+    lines.push(format!("{:<12} {:>6.0}     p99 {:>6.0}", "pairs", pairs.average, pairs.p99));
+
+    let height = 16.0 * lines.len() as f32 + PROFILER_GRAPH_HEIGHT + 24.0;
+    let x = screen_width() - OVERLAY_MARGIN - PROFILER_WIDTH;
+    draw_rectangle(x, PROFILER_TOP, PROFILER_WIDTH, height, Color::new(0.0, 0.0, 0.0, 0.6));
+    let mut offset = PROFILER_TOP + 16.0;
+    for text in &lines {
+        draw_text(text, x + 8.0, offset, 16.0, WHITE);
+        offset += 16.0;
+    }
+
+    let graph_x = x + 8.0;
+    let graph_width = PROFILER_WIDTH - 16.0;
+    let bottom = offset + PROFILER_GRAPH_HEIGHT;
+    let bar = graph_width / profiler.window() as f32;
+    for (index, ms) in profiler.frame_history().enumerate() {
+        let height = (ms / PROFILER_GRAPH_MS).min(1.0) * PROFILER_GRAPH_HEIGHT;
+        let color = if ms > 1000.0 / 60.0 + 1.0 {
+            Color::new(1.0, 0.4, 0.3, 0.9)
+        } else {
+            Color::new(0.5, 1.0, 0.4, 0.9)
+        };
+        draw_rectangle(graph_x + index as f32 * bar, bottom - height, bar.max(1.0), height, color);
+    }
+    let budget = bottom - PROFILER_GRAPH_HEIGHT * (1000.0 / 60.0) / PROFILER_GRAPH_MS;
+    draw_line(graph_x, budget, graph_x + graph_width, budget, 1.0, Color::new(1.0, 1.0, 1.0, 0.4));
+}
+
 pub fn draw_score_display(status: &SimulationStatus) {
-    let text = format!("{:06}", status.score);
-    let metrics = measure_text(&text, None, SCORE_FONT_SIZE as u16, 1.0);
-    let x = screen_width() - OVERLAY_MARGIN - metrics.width;
-    draw_text(&text, x, 42.0, SCORE_FONT_SIZE, WHITE);
-    draw_life_icons(status.lives);
+    if let Some(remaining) = status.match_remaining {
+        draw_versus_scores(status, remaining);
+    } else if status.pilots.len() > 1 {
+        draw_coop_scores(status);
+    } else {
+        let text = format!("{:06}", status.score);
+        let metrics = measure_text(&text, None, SCORE_FONT_SIZE as u16, 1.0);
+        let x = screen_width() - OVERLAY_MARGIN - metrics.width;
+        draw_text(&text, x, 42.0, SCORE_FONT_SIZE, WHITE);
+        draw_life_icons(status.lives);
+    }
+    draw_pickup_timers(status);
+}
+
+/// Co-op: player one top left, player two top right, each with their own score and lives.
+fn draw_coop_scores(status: &SimulationStatus) {
+    for (seat, pilot) in status.pilots.iter().enumerate() {
+        let color = seat_color(seat);
+        let text = if pilot.lives > 0 {
+            format!("P{} {:06}  x{}", seat + 1, pilot.score, pilot.lives)
+        } else {
+            format!("P{} {:06}  OUT", seat + 1, pilot.score)
+        };
+        let metrics = measure_text(&text, None, PICKUP_FONT_SIZE as u16, 1.0);
+        let x = if seat == 0 {
+            LIFE_ICON_MARGIN
+        } else {
+            screen_width() - OVERLAY_MARGIN - metrics.width
+        };
+        draw_text(&text, x, LIFE_ICON_Y, PICKUP_FONT_SIZE, color);
+    }
+    let total = format!("TEAM {:06}", status.score);
+    let metrics = measure_text(&total, None, SCORE_FONT_SIZE as u16, 1.0);
+    draw_text(
+        &total,
+        (screen_width() - metrics.width) / 2.0,
+        42.0,
+        SCORE_FONT_SIZE,
+        WHITE,
+    );
+}
+
+/// Versus: frags and deaths per seat down the top left, the match clock top centre.
+fn draw_versus_scores(status: &SimulationStatus, remaining: f32) {
+    for (seat, pilot) in status.pilots.iter().enumerate() {
+        let text = format!("P{}  {} / {}", seat + 1, pilot.frags, pilot.deaths);
+        let y = LIFE_ICON_Y + seat as f32 * PICKUP_FONT_SIZE;
+        draw_text(&text, LIFE_ICON_MARGIN, y, PICKUP_FONT_SIZE, seat_color(seat));
+    }
+    let clock = format_clock(remaining);
+    let metrics = measure_text(&clock, None, SCORE_FONT_SIZE as u16, 1.0);
+    draw_text(
+        &clock,
+        (screen_width() - metrics.width) / 2.0,
+        42.0,
+        SCORE_FONT_SIZE,
+        WHITE,
+    );
+}
+
+fn draw_pickup_timers(status: &SimulationStatus) {
+    let mut lines = Vec::new();
+    if status.rapid_fire_remaining > 0.0 {
+        lines.push(format!("RAPID {:.1}s", status.rapid_fire_remaining));
+    }
+    if status.score_multiplier_remaining > 0.0 {
+        lines.push(format!(
+            "SCORE x{} {:.1}s",
+            status.score_multiplier, status.score_multiplier_remaining
+        ));
+    }
+    if status.shield_charges > 0 {
+        lines.push(format!("SHIELD {}", "#".repeat(status.shield_charges as usize)));
+    }
+    if status.solar_flare_active {
+        lines.push("SOLAR FLARE - SHIELDS DOWN".to_string());
+    } else if status.solar_flare_incoming {
+        lines.push("FLARE INCOMING".to_string());
+    }
+    if status.ship_hidden {
+        lines.push("HIDDEN".to_string());
+    }
+
+    let mut y = 66.0;
+    for line in &lines {
+        let metrics = measure_text(line, None, PICKUP_FONT_SIZE as u16, 1.0);
+        let x = screen_width() - OVERLAY_MARGIN - metrics.width;
+        draw_text(line, x, y, PICKUP_FONT_SIZE, Color::new(1.0, 0.9, 0.4, 1.0));
+        y += PICKUP_FONT_SIZE;
+    }
 }
 
 fn draw_life_icons(lives: u32) {
@@ -143,6 +305,87 @@ fn draw_life_icons(lives: u32) {
     }
 }
 
+/// Netplay: which seat is ours and how the connection is coping, along the bottom edge.
+pub fn draw_netplay_status(net: &NetStatus, desync_note: Option<&str>) {
+    let stats = &net.stats;
+    let verified = stats
+        .verified
+        .map_or_else(|| "-".to_string(), |frame| frame.to_string());
+    let text = format!(
+        "NET P{}  delay {}  ahead {}  rollback {} ({} total)  stalls {}  verified {}",
+        net.local_seat + 1,
+        net.input_delay,
+        stats.prediction,
+        stats.last_rollback,
+        stats.rolled_back_frames,
+        stats.stalls,
+        verified,
+    );
+    let y = screen_height() - OVERLAY_MARGIN;
+    draw_text(&text, OVERLAY_MARGIN, y, PICKUP_FONT_SIZE, Color::new(0.6, 0.8, 1.0, 1.0));
+    if let Some(frame) = net.desync_frame {
+        let text = format!("DESYNC at frame {frame}: {}", desync_note.unwrap_or("peers disagree"));
+        draw_text(
+            &text,
+            OVERLAY_MARGIN,
+            y - PICKUP_FONT_SIZE,
+            PICKUP_FONT_SIZE,
+            Color::new(1.0, 0.3, 0.3, 1.0),
+        );
+    }
+}
+
+/// Paused with rewind history: a bar along the bottom spanning the frames kept, with a marker
+/// where the simulation is now, and how far back that is.
+pub fn draw_rewind_status(behind: usize, span: usize, frame_time: f32) {
+    if span == 0 {
+        return;
+    }
+    let width = screen_width() - 2.0 * OVERLAY_MARGIN;
+    let y = screen_height() - OVERLAY_MARGIN - REWIND_BAR_HEIGHT;
+    let color = Color::new(1.0, 0.85, 0.3, 1.0);
+    draw_rectangle(OVERLAY_MARGIN, y, width, REWIND_BAR_HEIGHT, Color::new(1.0, 1.0, 1.0, 0.15));
+    let marker = OVERLAY_MARGIN + width * (span - behind) as f32 / span as f32;
+    draw_rectangle(marker - 1.5, y - 4.0, 3.0, REWIND_BAR_HEIGHT + 8.0, color);
+    let text = if behind == 0 {
+        format!("REWIND  {:.1} s available", span as f32 * frame_time)
+    } else {
+        format!("REWIND  -{:.2} s ({behind} frames)", behind as f32 * frame_time)
+    };
+    draw_text(&text, OVERLAY_MARGIN, y - 8.0, PICKUP_FONT_SIZE, color);
+}
+
+/// Hot-seat: whose turn it is, under the score.
+pub fn draw_hot_seat_turn(player: usize) {
+    let text = format!("PLAYER {} UP", player + 1);
+    let metrics = measure_text(&text, None, PICKUP_FONT_SIZE as u16, 1.0);
+    draw_text(
+        &text,
+        (screen_width() - metrics.width) / 2.0,
+        LIFE_ICON_Y,
+        PICKUP_FONT_SIZE,
+        Color::new(0.5, 1.0, 0.4, 1.0),
+    );
+}
+
+/// Short callouts stacked in the upper middle of the field, fading out; each is coloured for
+/// the seat it is about.
+pub fn draw_callouts(callouts: &[(&str, Option<usize>, f32)]) {
+    let top = screen_height() * 0.28;
+    for (row, &(text, seat, alpha)) in callouts.iter().enumerate() {
+        let metrics = measure_text(text, None, PICKUP_FONT_SIZE as u16, 1.0);
+        let mut color = seat.map_or(Color::new(1.0, 0.85, 0.3, 1.0), seat_color);
+        color.a = alpha;
+        draw_text(
+            text,
+            (screen_width() - metrics.width) / 2.0,
+            top + row as f32 * (PICKUP_FONT_SIZE + 6.0),
+            PICKUP_FONT_SIZE,
+            color,
+        );
+    }
+}
+
 pub fn draw_autopilot_status(engaged: bool, profile: &str) {
     if engaged {
         draw_text(
@@ -4,9 +4,13 @@ pub mod widgets;
 
 // Backward-compatible facade: keep `crate::ui::menu::*` call sites stable.
 pub mod menu {
-    pub use crate::ui::hud::{draw_autopilot_status, draw_score_display, draw_stats_overlay};
+    pub use crate::ui::hud::{
+        draw_autopilot_status, draw_callouts, draw_hot_seat_turn, draw_netplay_status,
+        draw_profiler, draw_rewind_status, draw_score_display, draw_stats_overlay,
+    };
     pub use crate::ui::screens::{
-        draw_game_over, draw_leaderboard_menu, draw_main_menu, draw_options_menu,
+        draw_advanced_options, draw_controls, draw_game_over, draw_hot_seat_ready,
+        draw_leaderboard_menu, draw_main_menu, draw_match_results, draw_options_menu,
     };
 }
 
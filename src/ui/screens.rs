@@ -1,34 +1,66 @@
-use crate::config::{GameConfig};
+use crate::config::advanced::{self, AdvancedField};
+use crate::config::{GameConfig, PerformanceWarning, Preset};
+use crate::keymap::{self, Action, Context, Keymap};
 use crate::scoreboard::Leaderboard;
-use crate::ui::widgets::{draw_menu_box, format_name_with_cursor};
+use crate::simulation::SimulationStatus;
+use crate::ui::widgets::{draw_menu_box, format_clock, format_name_with_cursor};
 
-pub fn draw_main_menu() {
-    let lines = [
+/// Flight keys that only do something on the pause screen; the main menu lists them on one line.
+const PAUSED_ACTIONS: [Action; 5] = [
+    Action::SaveAndQuit,
+    Action::StepBack,
+    Action::StepForward,
+    Action::ScrubBack,
+    Action::ScrubForward,
+];
+
+/// `saved_run` adds the Continue entry; `message` reports the last save or continue.
+pub fn draw_main_menu(keymap: &Keymap, saved_run: bool, message: Option<&str>) {
+    let mut lines = vec![
         "ASTEROIDS — SYSTEMS".to_string(),
         "".to_string(),
-        "P - Play".to_string(),
-        "O - Options".to_string(),
-        "L - Leaderboard".to_string(),
+        format!("{} - Play", keymap.describe(Action::Play)),
+    ];
+    if saved_run {
+        lines.push(format!("{} - Continue saved run", keymap.describe(Action::ContinueRun)));
+    }
+    lines.extend([
+        format!("{} - Options", keymap.describe(Action::OpenOptions)),
+        format!("{} - Leaderboard", keymap.describe(Action::OpenLeaderboard)),
         "Esc - Quit".to_string(),
         "".to_string(),
+        message.unwrap_or_default().to_string(),
         "".to_string(),
         "Controls:".to_string(),
         "----------------".to_string(),
-        "Thrust - W/Up".to_string(),
-        "Rotate - Left/Right".to_string(),
-        "Fire   - Space -> primary fire".to_string(),
-        "Fire   - Shift -> secondary fire".to_string(),
-        "".to_string(),
-        "U - toggle autopilot".to_string(),
-        "P - cycle AI profile when autopilot is enabled / pause otherwise".to_string(),
-        "T - toggle stats".to_string(),
-        "I - toggle invulnerability".to_string(),
-    ];
+    ]);
+    let flight = Action::all()
+        .filter(|action| action.context() == Context::Flight && !PAUSED_ACTIONS.contains(action));
+    lines.extend(
+        flight.map(|action| format!("{:<24} {}", action.label(), keymap.describe(action))),
+    );
+    let paused: Vec<String> = PAUSED_ACTIONS.iter().map(|&action| keymap.describe(action)).collect();
+    lines.push(format!("{:<24} {}", "Paused: save, step, scrub", paused.join(" ")));
+    let partner: Vec<String> = Action::all()
+        .filter(|action| action.context() == Context::Partner)
+        .map(|action| keymap.describe(action))
+        .collect();
+    lines.push(format!("{:<24} {}", "2nd human (co-op/versus)", partner.join(" ")));
+    lines.push("".to_string());
+    lines.push("Rebind keys from Options > Controls".to_string());
     draw_menu_box(&lines);
 }
 
-pub fn draw_options_menu(config: &GameConfig, preset_label: &str) {
-    use crate::config::{CollisionPolicy, FragmentationMode, LeaderboardMode, PhysicsMode};
+pub fn draw_options_menu(
+    config: &GameConfig,
+    preset: Option<&Preset>,
+    notices: &[String],
+    keymap: &Keymap,
+) {
+    use crate::config::{
+        AiProfile, AimMode, AlienBehaviour, CollisionPolicy, FragmentationMode, LeaderboardMode,
+        PhysicsMode, PlayerControllerMode,
+    };
 
     fn fragmentation_label(mode: &FragmentationMode) -> &'static str {
         match mode {
@@ -67,27 +99,281 @@ pub fn draw_options_menu(config: &GameConfig, preset_label: &str) {
         if enabled { "On" } else { "Off" }
     }
 
-    let lines = [
+    fn aim_label(mode: AimMode) -> &'static str {
+        match mode {
+            AimMode::Keyboard => "Keyboard",
+            AimMode::Mouse => "Mouse",
+        }
+    }
+
+    fn partner_label(mode: &PlayerControllerMode) -> &'static str {
+        match mode {
+            PlayerControllerMode::Human => "Human",
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Casual,
+            } => "AI Casual",
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Balanced,
+            } => "AI Balanced",
+            PlayerControllerMode::Ai {
+                profile: AiProfile::Veteran,
+            } => "AI Veteran",
+        }
+    }
+
+    fn alien_label(behaviour: AlienBehaviour) -> &'static str {
+        match behaviour {
+            AlienBehaviour::Classic => "Classic",
+            AlienBehaviour::Hunter => "Hunter",
+            AlienBehaviour::Sniper => "Sniper",
+            AlienBehaviour::Kamikaze => "Kamikaze",
+        }
+    }
+
+    let key = |action| keymap.describe(action);
+    let mut lines = vec![
         "OPTIONS".to_string(),
         "".to_string(),
         format!(
-            "C - Collision Policy: {}",
+            "{} - Collision Policy: {}",
+            key(Action::CycleCollision),
             collision_label(&config.collision_policy)
         ),
-        format!("K - Physics Mode: {}", physics_label(&config.physics_mode)),
         format!(
-            "F - Fragmentation Mode: {}",
+            "{} - Physics Mode: {}",
+            key(Action::CyclePhysics),
+            physics_label(&config.physics_mode)
+        ),
+        format!(
+            "{} - Fragmentation Mode: {}",
+            key(Action::CycleFragmentation),
             fragmentation_label(&config.fragmentation_mode)
         ),
         format!(
-            "L - Leaderboard Mode: {}",
+            "{} - Leaderboard Mode: {}",
+            key(Action::CycleLeaderboard),
             leaderboard_label(&config.leaderboard_mode)
         ),
-        format!("G - Upgrades: {}", upgrade_label(config.upgrades_enabled)),
-        format!("Y - Preset: {}", preset_label),
-        "".to_string(),
-        "Enter / Esc - Back".to_string(),
+        format!(
+            "{} - Upgrades: {}",
+            key(Action::ToggleUpgrades),
+            upgrade_label(config.upgrades_enabled)
+        ),
+        format!(
+            "{} - Enemies: {}",
+            key(Action::ToggleEnemies),
+            upgrade_label(config.enemies_enabled)
+        ),
+        format!(
+            "{} - Sentinels: {}",
+            key(Action::ToggleSentinels),
+            upgrade_label(config.sentinels_enabled)
+        ),
+        format!(
+            "{} - Alien Behaviour: {}",
+            key(Action::CycleAlienBehaviour),
+            alien_label(config.alien_behaviour)
+        ),
+        format!(
+            "{} - Hazards: {}",
+            key(Action::ToggleHazards),
+            upgrade_label(config.hazards.any())
+        ),
+        format!(
+            "{} - Aim: {}",
+            key(Action::ToggleAimMode),
+            aim_label(config.aim_mode)
+        ),
+        format!(
+            "{} - Bullet time (slows near misses; runs are flagged): {}",
+            key(Action::ToggleBulletTime),
+            upgrade_label(config.bullet_time)
+        ),
+        format!(
+            "{} - Co-op: {}",
+            key(Action::ToggleCoop),
+            upgrade_label(config.coop.enabled)
+        ),
+        format!(
+            "{} - Co-op Partner: {}",
+            key(Action::CycleCoopPartner),
+            partner_label(&config.coop.partner)
+        ),
+        format!(
+            "{} - Friendly Fire: {}",
+            key(Action::ToggleFriendlyFire),
+            upgrade_label(config.coop.friendly_fire)
+        ),
+        format!(
+            "{} - Hot-seat (take turns): {}",
+            key(Action::ToggleHotSeat),
+            upgrade_label(config.hot_seat)
+        ),
+        format!(
+            "{} - Versus: {}",
+            key(Action::ToggleVersus),
+            upgrade_label(config.versus.enabled)
+        ),
+        format!(
+            "{} - Rivals: {}",
+            key(Action::CycleVersusLineup),
+            config
+                .versus
+                .rivals
+                .iter()
+                .map(partner_label)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        format!(
+            "{} - Match length: {}",
+            key(Action::CycleMatchLength),
+            format_clock(config.versus.match_seconds as f32)
+        ),
+        format!(
+            "{} - Preset: {}",
+            key(Action::NextPreset),
+            preset.map_or("Custom", |preset| &preset.name)
+        ),
     ];
+    if let Some(preset) = preset {
+        if !preset.description.is_empty() {
+            lines.push(format!("    {}", preset.description));
+        }
+        if preset.warning != PerformanceWarning::None {
+            lines.push(format!("    Performance: {} load", preset.warning.label()));
+        }
+    }
+    lines.extend([
+        format!("{} - Advanced settings", key(Action::AdvancedSettings)),
+        format!("{} - Controls", key(Action::Controls)),
+        format!(
+            "{} - Save as preset   {} - Delete preset",
+            key(Action::SavePreset),
+            key(Action::DeletePreset)
+        ),
+        format!("{} - Reset to defaults", key(Action::ResetOptions)),
+        "".to_string(),
+    ]);
+    if !notices.is_empty() {
+        lines.extend(notices.iter().cloned());
+        lines.push("".to_string());
+    }
+    lines.push("Enter / Esc - Save and back".to_string());
+    draw_menu_box(&lines);
+}
+
+/// Rows of the Advanced panel visible at once; the list scrolls to keep the cursor in view.
+const ADVANCED_VISIBLE_ROWS: usize = 12;
+
+pub fn draw_advanced_options(
+    config: &GameConfig,
+    fields: &[AdvancedField],
+    cursor: usize,
+    message: Option<&str>,
+) {
+    fn format_value(field: &AdvancedField, value: f64) -> String {
+        if field.integer {
+            format!("{value:.0}")
+        } else {
+            // Values are stored as f32; printing at that precision avoids 0.44999998.
+            format!("{}", value as f32)
+        }
+    }
+
+    let mut lines = vec!["ADVANCED SETTINGS".to_string(), "".to_string()];
+    let first = cursor
+        .saturating_sub(ADVANCED_VISIBLE_ROWS / 2)
+        .min(fields.len().saturating_sub(ADVANCED_VISIBLE_ROWS));
+    for (index, field) in fields
+        .iter()
+        .enumerate()
+        .skip(first)
+        .take(ADVANCED_VISIBLE_ROWS)
+    {
+        lines.push(format!(
+            "{} {:<36} {:>10}   [{} - {}]",
+            if index == cursor { ">" } else { " " },
+            field.key(),
+            format_value(field, field.value(config)),
+            format_value(field, field.min),
+            format_value(field, field.max),
+        ));
+    }
+    lines.push(format!("({}/{})", cursor + 1, fields.len()));
+    lines.push("".to_string());
+
+    if let Some(message) = message {
+        lines.push(message.to_string());
+    }
+    for warning in advanced::performance_warnings(config) {
+        lines.push(format!("Warning: {warning}"));
+    }
+    lines.push("".to_string());
+    lines.push("Up/Down - Select   Left/Right - Adjust (Shift x10)".to_string());
+    lines.push("Enter / Esc - Back to options".to_string());
+    draw_menu_box(&lines);
+}
+
+/// Rows of the Controls screen visible at once.
+const CONTROLS_VISIBLE_ROWS: usize = 14;
+
+pub fn draw_controls(
+    keymap: &Keymap,
+    cursor: usize,
+    slot: usize,
+    capturing: bool,
+    message: Option<&str>,
+) {
+    let actions: Vec<Action> = Action::all().collect();
+    let rows = actions.len() + 1;
+    let mut lines = vec!["CONTROLS".to_string(), "".to_string()];
+    let first = cursor
+        .saturating_sub(CONTROLS_VISIBLE_ROWS / 2)
+        .min(rows.saturating_sub(CONTROLS_VISIBLE_ROWS));
+    for index in (first..rows).take(CONTROLS_VISIBLE_ROWS) {
+        let marker = if index == cursor { ">" } else { " " };
+        let Some(&action) = actions.get(index) else {
+            lines.push(format!("{marker} Reset all to defaults"));
+            continue;
+        };
+        let slots: Vec<String> = keymap
+            .keys(action)
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let name = if capturing && index == cursor && i == slot {
+                    "..."
+                } else {
+                    key.map_or("-", keymap::key_name)
+                };
+                if index == cursor && i == slot {
+                    format!("[{name:^9}]")
+                } else {
+                    format!(" {name:^9} ")
+                }
+            })
+            .collect();
+        lines.push(format!(
+            "{marker} {:<10} {:<24} {}",
+            action.context().label(),
+            action.label(),
+            slots.join(" ")
+        ));
+    }
+    lines.push(format!("({}/{})", cursor + 1, rows));
+    lines.push("".to_string());
+
+    if let Some(message) = message {
+        lines.push(message.to_string());
+    }
+    for conflict in keymap.conflicts() {
+        lines.push(format!("Conflict: {conflict}"));
+    }
+    lines.extend(keymap.coop_gaps());
+    lines.push("".to_string());
+    lines.push("Up/Down - Select   Left/Right - Key slot".to_string());
+    lines.push("Enter - Rebind   Backspace - Clear slot   Esc - Save and back".to_string());
     draw_menu_box(&lines);
 }
 
@@ -98,10 +384,12 @@ pub fn draw_leaderboard_menu(leaderboard: &Leaderboard) {
     } else {
         for (idx, entry) in leaderboard.entries().iter().enumerate() {
             lines.push(format!(
-                "{:>2}. {:>8} pts - {}",
+                "{:>2}. {:>8} pts - {}{}{}",
                 idx + 1,
                 entry.score,
-                entry.name
+                entry.name,
+                if entry.team { " (team)" } else { "" },
+                if entry.assisted { " (assisted)" } else { "" }
             ));
             lines.push(format!(
                 "    Shots {}/{}  Acc:{:.1}%",
@@ -124,9 +412,45 @@ pub fn draw_leaderboard_menu(leaderboard: &Leaderboard) {
     draw_menu_box(&lines);
 }
 
-pub fn draw_game_over(score: u32, name_input: &str, cursor_pos: usize, name_max: usize) {
+/// Shown between hot-seat turns so the next player can take the controls.
+pub fn draw_hot_seat_ready(player: usize, finished: Option<usize>) {
+    let mut lines = vec![format!("PLAYER {} READY", player + 1), "".to_string()];
+    if let Some(finished) = finished {
+        lines.push(format!("Player {} is out of lives", finished + 1));
+        lines.push("".to_string());
+    }
+    lines.push("ENTER - Start turn".to_string());
+    lines.push("ESC   - End run".to_string());
+    draw_menu_box(&lines);
+}
+
+/// Versus standings, best first.
+pub fn draw_match_results(status: &SimulationStatus) {
+    let mut lines = vec!["MATCH OVER".to_string(), "".to_string()];
+    for (place, seat) in status.standings().into_iter().enumerate() {
+        let pilot = &status.pilots[seat];
+        lines.push(format!(
+            "{}. PLAYER {}   FRAGS {}   DEATHS {}",
+            place + 1,
+            seat + 1,
+            pilot.frags,
+            pilot.deaths
+        ));
+    }
+    lines.push("".to_string());
+    lines.push("ENTER - Main menu".to_string());
+    draw_menu_box(&lines);
+}
+
+pub fn draw_game_over(
+    heading: &str,
+    score: u32,
+    name_input: &str,
+    cursor_pos: usize,
+    name_max: usize,
+) {
     let lines = [
-        "GAME OVER".to_string(),
+        heading.to_string(),
         "".to_string(),
         format!("SCORE {:06}", score),
         "".to_string(),
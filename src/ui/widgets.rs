@@ -13,6 +13,12 @@ pub fn draw_menu_box(lines: &[String]) {
     }
 }
 
+/// `m:ss`, rounding up so a clock reads 0:00 only once it has run out.
+pub fn format_clock(seconds: f32) -> String {
+    let whole = seconds.max(0.0).ceil() as u32;
+    format!("{}:{:02}", whole / 60, whole % 60)
+}
+
 pub fn format_name_with_cursor(name: &str, cursor_pos: usize) -> String {
     let chars: Vec<char> = name.chars().collect();
     let cursor = cursor_pos.min(chars.len());